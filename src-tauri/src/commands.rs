@@ -6,15 +6,27 @@ use crate::cloud::{
     KubeSecretKey, ParsedConnection,
 };
 use crate::sql_analyzer;
-use crate::db::{ColumnInfo, ConnectionManager, ConstraintInfo, DatabaseUser, FunctionInfo, IndexInfo, QueryResult, UserGrant, ViewDependency};
+use crate::sql_completion::Completion;
+use crate::db::diagnostics::{self, ConnectionTestReport};
+use crate::db::driver::DriverCapabilities;
+use crate::db::query_plan::QueryPlan;
+use crate::db::redis_keyspace::{RedisBytes, RedisDatabaseInfo, RedisKeyPage, RedisNamespace};
+use crate::db::redis_value::{RedisEdit, RedisKeyValue, RedisScoredMember};
+use crate::db::result_set::ResultPage;
+use crate::db::row_changes::{AppliedRowChanges, TableChanges};
+use crate::db::ssh_config::{SshConfig, SshConfigHost};
+use crate::db::ssh_tunnel;
+use crate::db::{ColumnInfo, ConnectionManager, ConstraintInfo, DatabaseUser, FunctionInfo, IndexInfo, OnError, QueryColumns, StatementResult, TransactionStatus, UserGrant, ViewDependency};
 use crate::history::{HistoryManager, QueryHistoryEntry, QueryHistoryFilter};
-use crate::storage::{self, Category, ConnectionConfig, DatabaseType, SshTunnelConfig, SslMode};
+use crate::secrets::{SecretBackend, SecretManager, SecretStoreStatus};
+use crate::storage::{self, Category, ConnectionConfig, ConnectionOptions, DatabaseType, RedisTopology, SecretRefs, SshTunnelConfig, SslMode};
 use std::sync::OnceLock;
 use tauri::Manager;
 use tokio::sync::OnceCell;
 
 static CONNECTION_MANAGER: OnceLock<ConnectionManager> = OnceLock::new();
 static HISTORY_MANAGER: OnceCell<HistoryManager> = OnceCell::const_new();
+static SECRET_MANAGER: OnceLock<SecretManager> = OnceLock::new();
 
 fn get_manager() -> &'static ConnectionManager {
     CONNECTION_MANAGER.get_or_init(ConnectionManager::new)
@@ -33,6 +45,32 @@ async fn get_history_manager(app: &tauri::AppHandle) -> Result<&'static HistoryM
         .await
 }
 
+fn get_secret_manager(app: &tauri::AppHandle) -> Result<&'static SecretManager, String> {
+    if let Some(manager) = SECRET_MANAGER.get() {
+        return Ok(manager);
+    }
+    let config_dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to get config directory: {}", e))?;
+    Ok(SECRET_MANAGER.get_or_init(|| SecretManager::new(&config_dir)))
+}
+
+/// Loads a connection with its passwords filled in from the secret store.
+fn get_connection_with_secrets(
+    app: &tauri::AppHandle,
+    id: &str,
+) -> Result<ConnectionConfig, String> {
+    let config_dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to get config directory: {}", e))?;
+    let mut config = storage::get_connection(&config_dir, id)
+        .ok_or_else(|| format!("Connection '{}' not found", id))?;
+    storage::resolve_secrets(get_secret_manager(app)?, &mut config)?;
+    Ok(config)
+}
+
 #[derive(serde::Deserialize)]
 pub struct SaveConnectionInput {
     pub name: String,
@@ -47,6 +85,16 @@ pub struct SaveConnectionInput {
     #[serde(default)]
     pub ssl_mode: SslMode,
     #[serde(default)]
+    pub ssl_root_cert: Option<String>,
+    #[serde(default)]
+    pub ssl_client_cert: Option<String>,
+    #[serde(default)]
+    pub ssl_client_key: Option<String>,
+    #[serde(default)]
+    pub options: ConnectionOptions,
+    #[serde(default)]
+    pub redis_topology: RedisTopology,
+    #[serde(default)]
     pub ssh_tunnel: Option<SshTunnelConfig>,
 }
 
@@ -59,15 +107,41 @@ pub struct UpdateConnectionInput {
     pub port: u16,
     pub username: String,
     pub password: String,
+    /// Removes the saved main password. A blank `password` alone keeps it.
+    #[serde(default)]
+    pub clear_password: bool,
     pub database: Option<String>,
     pub category_id: Option<String>,
     pub visible_databases: Option<u16>,
     #[serde(default)]
     pub ssl_mode: SslMode,
     #[serde(default)]
+    pub ssl_root_cert: Option<String>,
+    #[serde(default)]
+    pub ssl_client_cert: Option<String>,
+    #[serde(default)]
+    pub ssl_client_key: Option<String>,
+    #[serde(default)]
+    pub options: ConnectionOptions,
+    #[serde(default)]
+    pub redis_topology: RedisTopology,
+    #[serde(default)]
     pub ssh_tunnel: Option<SshTunnelConfig>,
 }
 
+#[derive(serde::Deserialize)]
+pub struct TestConnectionInput {
+    /// The saved connection being edited, if any. Passwords the form left
+    /// blank are taken from it.
+    #[serde(default)]
+    pub id: Option<String>,
+    /// The form cleared the saved main password, so it isn't filled in.
+    #[serde(default)]
+    pub clear_password: bool,
+    #[serde(flatten)]
+    pub connection: SaveConnectionInput,
+}
+
 #[tauri::command]
 pub fn save_connection(
     app: tauri::AppHandle,
@@ -89,7 +163,13 @@ pub fn save_connection(
     );
     config.visible_databases = input.visible_databases;
     config.ssl_mode = input.ssl_mode;
+    config.ssl_root_cert = input.ssl_root_cert;
+    config.ssl_client_cert = input.ssl_client_cert;
+    config.ssl_client_key = input.ssl_client_key;
+    config.options = input.options;
+    config.redis_topology = input.redis_topology;
     config.ssh_tunnel = input.ssh_tunnel;
+    storage::store_secrets(get_secret_manager(&app)?, &mut config)?;
     storage::add_connection(&config_dir, config)
 }
 
@@ -99,7 +179,18 @@ pub fn list_connections(app: tauri::AppHandle) -> Result<Vec<ConnectionConfig>,
         .path()
         .app_config_dir()
         .map_err(|e| format!("Failed to get config directory: {}", e))?;
-    Ok(storage::load_connections(&config_dir))
+    // Move any plaintext passwords from older versions into the secret store
+    // as soon as it is available.
+    let secrets = get_secret_manager(&app)?;
+    if secrets.is_unlocked() {
+        storage::migrate_plaintext_secrets(&config_dir, secrets)?;
+    }
+    // While it is locked they stay on disk, but never reach the UI.
+    let mut connections = storage::load_connections(&config_dir);
+    for config in connections.iter_mut() {
+        config.strip_plaintext_secrets();
+    }
+    Ok(connections)
 }
 
 #[tauri::command]
@@ -108,6 +199,10 @@ pub fn delete_connection(app: tauri::AppHandle, id: String) -> Result<(), String
         .path()
         .app_config_dir()
         .map_err(|e| format!("Failed to get config directory: {}", e))?;
+    if let Some(config) = storage::get_connection(&config_dir, &id) {
+        // A locked store leaves the secrets orphaned, which is harmless.
+        storage::delete_secrets(get_secret_manager(&app)?, &config).ok();
+    }
     storage::remove_connection(&config_dir, &id)
 }
 
@@ -124,7 +219,9 @@ pub fn set_visible_databases(
     let mut config = storage::get_connection(&config_dir, &connection_id)
         .ok_or_else(|| format!("Connection '{}' not found", connection_id))?;
     config.visible_databases = Some(count);
-    storage::update_connection(&config_dir, config)
+    let mut config = storage::update_connection(&config_dir, config)?;
+    config.strip_plaintext_secrets();
+    Ok(config)
 }
 
 #[tauri::command]
@@ -136,9 +233,16 @@ pub fn update_connection(
         .path()
         .app_config_dir()
         .map_err(|e| format!("Failed to get config directory: {}", e))?;
-    // Preserve last_selected from the existing config
+    // The edit form never sees plaintext passwords from older versions, so
+    // move them into the secret store before the form's blanks replace them.
+    let secrets_store = get_secret_manager(&app)?;
+    storage::migrate_plaintext_secrets(&config_dir, secrets_store)?;
+    // Preserve last_selected and the secret references from the existing config
     let existing = storage::get_connection(&config_dir, &input.id);
-    let config = ConnectionConfig {
+    let (last_selected, secrets) = existing
+        .map(|c| (c.last_selected, c.secrets))
+        .unwrap_or((None, SecretRefs::default()));
+    let mut config = ConnectionConfig {
         id: input.id,
         name: input.name,
         db_type: input.db_type,
@@ -150,9 +254,19 @@ pub fn update_connection(
         category_id: input.category_id,
         visible_databases: input.visible_databases,
         ssl_mode: input.ssl_mode,
-        last_selected: existing.and_then(|c| c.last_selected),
+        ssl_root_cert: input.ssl_root_cert,
+        ssl_client_cert: input.ssl_client_cert,
+        ssl_client_key: input.ssl_client_key,
+        options: input.options,
+        redis_topology: input.redis_topology,
+        last_selected,
         ssh_tunnel: input.ssh_tunnel,
+        secrets,
     };
+    if input.clear_password {
+        storage::clear_password(secrets_store, &mut config)?;
+    }
+    storage::store_secrets(secrets_store, &mut config)?;
     storage::update_connection(&config_dir, config)
 }
 
@@ -174,30 +288,133 @@ pub fn save_last_selected(
 }
 
 #[tauri::command]
-pub async fn connect(app: tauri::AppHandle, id: String) -> Result<String, String> {
+pub fn secret_store_status(app: tauri::AppHandle) -> Result<SecretStoreStatus, String> {
+    Ok(get_secret_manager(&app)?.status())
+}
+
+#[tauri::command]
+pub fn unlock_secret_store(
+    app: tauri::AppHandle,
+    master_password: String,
+) -> Result<SecretStoreStatus, String> {
     let config_dir = app
         .path()
         .app_config_dir()
         .map_err(|e| format!("Failed to get config directory: {}", e))?;
-    let config = storage::get_connection(&config_dir, &id)
-        .ok_or_else(|| format!("Connection '{}' not found", id))?;
-    get_manager().connect(&config).await
+    let secrets = get_secret_manager(&app)?;
+    secrets.unlock(&master_password)?;
+    storage::migrate_plaintext_secrets(&config_dir, secrets)?;
+    Ok(secrets.status())
 }
 
 #[tauri::command]
-pub async fn disconnect(connection_id: String) -> Result<(), String> {
-    get_manager().disconnect(&connection_id).await
+pub fn lock_secret_store(app: tauri::AppHandle) -> Result<SecretStoreStatus, String> {
+    let secrets = get_secret_manager(&app)?;
+    secrets.lock();
+    Ok(secrets.status())
 }
 
 #[tauri::command]
-pub async fn switch_database(app: tauri::AppHandle, connection_id: String, database: String) -> Result<(), String> {
+pub fn change_master_password(
+    app: tauri::AppHandle,
+    current_password: String,
+    new_password: String,
+) -> Result<(), String> {
+    get_secret_manager(&app)?.change_master_password(&current_password, &new_password)
+}
+
+#[tauri::command]
+pub fn set_secret_backend(
+    app: tauri::AppHandle,
+    backend: SecretBackend,
+    master_password: Option<String>,
+) -> Result<SecretStoreStatus, String> {
     let config_dir = app
         .path()
         .app_config_dir()
         .map_err(|e| format!("Failed to get config directory: {}", e))?;
-    let config = storage::get_connection(&config_dir, &connection_id)
-        .ok_or_else(|| format!("Connection '{}' not found", connection_id))?;
-    get_manager().switch_database(&config, &database).await
+    let keys: Vec<String> = storage::load_connections(&config_dir)
+        .iter()
+        .flat_map(|c| c.secrets.keys().cloned().collect::<Vec<_>>())
+        .collect();
+    let secrets = get_secret_manager(&app)?;
+    secrets.set_backend(backend, master_password.as_deref(), &keys)?;
+    Ok(secrets.status())
+}
+
+#[tauri::command]
+pub async fn connect(app: tauri::AppHandle, id: String) -> Result<String, String> {
+    let config = get_connection_with_secrets(&app, &id)?;
+    get_manager().connect(&app, &config).await
+}
+
+/// Tries the connection described by the form without saving it or adding it
+/// to the open connections.
+#[tauri::command]
+pub async fn test_connection(
+    app: tauri::AppHandle,
+    input: TestConnectionInput,
+) -> Result<ConnectionTestReport, String> {
+    let form = input.connection;
+    let mut config = ConnectionConfig::new(
+        form.name,
+        form.db_type,
+        form.host,
+        form.port,
+        form.username,
+        form.password,
+        form.database,
+        form.category_id,
+    );
+    config.ssl_mode = form.ssl_mode;
+    config.ssl_root_cert = form.ssl_root_cert;
+    config.ssl_client_cert = form.ssl_client_cert;
+    config.ssl_client_key = form.ssl_client_key;
+    config.options = form.options;
+    config.redis_topology = form.redis_topology;
+    config.ssh_tunnel = form.ssh_tunnel;
+    if let Some(id) = input.id {
+        let config_dir = app
+            .path()
+            .app_config_dir()
+            .map_err(|e| format!("Failed to get config directory: {}", e))?;
+        if let Some(saved) = storage::get_connection(&config_dir, &id) {
+            config.secrets = saved.secrets;
+            if input.clear_password {
+                config.secrets.password = None;
+            }
+            storage::resolve_secrets(get_secret_manager(&app)?, &mut config)?;
+        }
+    }
+    Ok(diagnostics::test_connection(&app, &config).await)
+}
+
+#[tauri::command]
+pub fn list_ssh_config_hosts() -> Vec<SshConfigHost> {
+    SshConfig::load_user_config().hosts()
+}
+
+#[tauri::command]
+pub fn answer_host_key_prompt(request_id: String, trust: bool) -> Result<(), String> {
+    ssh_tunnel::answer_host_key_prompt(&request_id, trust)
+}
+
+#[tauri::command]
+pub async fn disconnect(connection_id: String, force: Option<bool>) -> Result<(), String> {
+    get_manager()
+        .disconnect(&connection_id, force.unwrap_or(false))
+        .await
+}
+
+#[tauri::command]
+pub async fn switch_database(app: tauri::AppHandle, connection_id: String, database: String) -> Result<(), String> {
+    let config = get_connection_with_secrets(&app, &connection_id)?;
+    get_manager().switch_database(&app, &config, &database).await
+}
+
+#[tauri::command]
+pub async fn get_capabilities(connection_id: String) -> Result<DriverCapabilities, String> {
+    get_manager().get_capabilities(&connection_id).await
 }
 
 #[tauri::command]
@@ -220,6 +437,20 @@ pub async fn list_schemas(connection_id: String, database: String) -> Result<Vec
     get_manager().list_schemas(&connection_id, &database).await
 }
 
+/// Drops cached schema metadata so the next listing reads the server again.
+/// Without `database`, everything cached for the connection goes.
+#[tauri::command]
+pub async fn refresh_metadata(
+    connection_id: String,
+    database: Option<String>,
+    schema: Option<String>,
+) -> Result<(), String> {
+    get_manager()
+        .refresh_metadata(&connection_id, database.as_deref(), schema.as_deref())
+        .await;
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn list_tables(
     connection_id: String,
@@ -368,22 +599,270 @@ pub async fn list_constraints(
         .await
 }
 
+/// Saves edits made in the results grid, to every table they touch at once.
+#[tauri::command]
+pub async fn apply_row_changes(
+    app: tauri::AppHandle,
+    connection_id: String,
+    session_id: Option<String>,
+    tables: Vec<TableChanges>,
+) -> Result<Vec<AppliedRowChanges>, String> {
+    get_manager()
+        .apply_row_changes(&app, &connection_id, session_id.as_deref(), tables)
+        .await
+}
+
+#[tauri::command]
+pub async fn redis_list_databases(
+    connection_id: String,
+) -> Result<Vec<RedisDatabaseInfo>, String> {
+    get_manager().redis_list_databases(&connection_id).await
+}
+
+#[tauri::command]
+pub async fn redis_list_namespaces(
+    connection_id: String,
+    database: String,
+) -> Result<Vec<RedisNamespace>, String> {
+    get_manager()
+        .redis_list_namespaces(&connection_id, &database)
+        .await
+}
+
+/// Pass `cursor` "0" for the first page, then the cursor of the previous one.
+#[tauri::command]
+pub async fn redis_scan_namespace(
+    connection_id: String,
+    database: String,
+    namespace: String,
+    cursor: String,
+    count: Option<usize>,
+) -> Result<RedisKeyPage, String> {
+    get_manager()
+        .redis_scan_namespace(&connection_id, &database, &namespace, &cursor, count.unwrap_or(100))
+        .await
+}
+
+/// One page of `key`'s value; pass back the returned cursor for the next.
+#[tauri::command]
+pub async fn redis_get_key(
+    connection_id: String,
+    database: String,
+    key: RedisBytes,
+    cursor: Option<String>,
+    count: Option<usize>,
+) -> Result<RedisKeyValue, String> {
+    get_manager()
+        .redis_get_key(&connection_id, &database, &key, cursor.as_deref(), count.unwrap_or(100))
+        .await
+}
+
+async fn redis_edit(
+    connection_id: String,
+    database: String,
+    key: RedisBytes,
+    edit: RedisEdit,
+) -> Result<(), String> {
+    get_manager()
+        .redis_edit_key(&connection_id, &database, &key, edit)
+        .await
+}
+
+#[tauri::command]
+pub async fn redis_set_string(
+    connection_id: String,
+    database: String,
+    key: RedisBytes,
+    value: String,
+) -> Result<(), String> {
+    redis_edit(connection_id, database, key, RedisEdit::String { value }).await
+}
+
+#[tauri::command]
+pub async fn redis_set_hash_fields(
+    connection_id: String,
+    database: String,
+    key: RedisBytes,
+    fields: Vec<(String, String)>,
+) -> Result<(), String> {
+    redis_edit(connection_id, database, key, RedisEdit::HashFields { fields }).await
+}
+
+#[tauri::command]
+pub async fn redis_set_list_item(
+    connection_id: String,
+    database: String,
+    key: RedisBytes,
+    index: i64,
+    value: String,
+) -> Result<(), String> {
+    redis_edit(connection_id, database, key, RedisEdit::ListItem { index, value }).await
+}
+
+#[tauri::command]
+pub async fn redis_push_list_items(
+    connection_id: String,
+    database: String,
+    key: RedisBytes,
+    values: Vec<String>,
+    head: bool,
+) -> Result<(), String> {
+    redis_edit(connection_id, database, key, RedisEdit::ListPush { values, head }).await
+}
+
+#[tauri::command]
+pub async fn redis_set_set_members(
+    connection_id: String,
+    database: String,
+    key: RedisBytes,
+    members: Vec<String>,
+) -> Result<(), String> {
+    redis_edit(connection_id, database, key, RedisEdit::SetMembers { members }).await
+}
+
+#[tauri::command]
+pub async fn redis_set_zset_members(
+    connection_id: String,
+    database: String,
+    key: RedisBytes,
+    members: Vec<RedisScoredMember>,
+) -> Result<(), String> {
+    redis_edit(connection_id, database, key, RedisEdit::ZsetMembers { members }).await
+}
+
+#[tauri::command]
+pub async fn redis_set_stream_entry(
+    connection_id: String,
+    database: String,
+    key: RedisBytes,
+    fields: Vec<(String, String)>,
+) -> Result<(), String> {
+    redis_edit(connection_id, database, key, RedisEdit::StreamEntry { fields }).await
+}
+
+#[tauri::command]
+pub async fn redis_set_json(
+    connection_id: String,
+    database: String,
+    key: RedisBytes,
+    path: Option<String>,
+    value: serde_json::Value,
+) -> Result<(), String> {
+    let path = path.unwrap_or_else(|| "$".to_string());
+    redis_edit(connection_id, database, key, RedisEdit::Json { path, value }).await
+}
+
+/// Expires `key` after `ttl_ms`, or makes it persistent when that is null.
+#[tauri::command]
+pub async fn redis_set_ttl(
+    connection_id: String,
+    database: String,
+    key: RedisBytes,
+    ttl_ms: Option<i64>,
+) -> Result<(), String> {
+    redis_edit(connection_id, database, key, RedisEdit::Ttl { ttl_ms }).await
+}
+
+#[tauri::command]
+pub async fn redis_delete_members(
+    connection_id: String,
+    database: String,
+    key: RedisBytes,
+    members: Vec<RedisBytes>,
+) -> Result<u64, String> {
+    get_manager()
+        .redis_delete_members(&connection_id, &database, &key, members)
+        .await
+}
+
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn execute_query(
     app: tauri::AppHandle,
     query_id: String,
     connection_id: String,
+    session_id: Option<String>,
     query: String,
     database: Option<String>,
-) -> Result<(QueryResult, u64), String> {
+    max_rows: Option<usize>,
+    on_error: Option<OnError>,
+) -> Result<(Vec<StatementResult>, u64), String> {
     let start = std::time::Instant::now();
     let result = get_manager()
-        .execute_query(&app, &query_id, &connection_id, &query, database.as_deref())
+        .execute_query(
+            &app,
+            &query_id,
+            &connection_id,
+            session_id.as_deref(),
+            &query,
+            database.as_deref(),
+            max_rows,
+            on_error.unwrap_or_default(),
+        )
         .await?;
     let elapsed_ms = start.elapsed().as_millis() as u64;
     Ok((result, elapsed_ms))
 }
 
+#[tauri::command]
+pub async fn begin_transaction(
+    app: tauri::AppHandle,
+    connection_id: String,
+    session_id: String,
+) -> Result<(), String> {
+    get_manager()
+        .begin_transaction(&app, &connection_id, &session_id)
+        .await
+}
+
+#[tauri::command]
+pub async fn commit(app: tauri::AppHandle, session_id: String) -> Result<(), String> {
+    get_manager().commit(&app, &session_id).await
+}
+
+#[tauri::command]
+pub async fn rollback(app: tauri::AppHandle, session_id: String) -> Result<(), String> {
+    get_manager().rollback(&app, &session_id).await
+}
+
+#[tauri::command]
+pub async fn transaction_status(session_id: String) -> Result<TransactionStatus, String> {
+    Ok(get_manager().transaction_status(&session_id).await)
+}
+
+#[tauri::command]
+pub async fn open_transaction_count(connection_id: String) -> Result<usize, String> {
+    Ok(get_manager().open_transaction_count(&connection_id).await)
+}
+
+#[tauri::command]
+pub async fn close_session(session_id: String, force: Option<bool>) -> Result<(), String> {
+    get_manager()
+        .close_session(&session_id, force.unwrap_or(false))
+        .await
+}
+
+#[tauri::command]
+pub async fn open_result(
+    connection_id: String,
+    query: String,
+    max_rows: Option<usize>,
+) -> Result<String, String> {
+    get_manager()
+        .open_result(&connection_id, &query, max_rows)
+        .await
+}
+
+#[tauri::command]
+pub async fn fetch_page(result_id: String, offset: usize, limit: usize) -> Result<ResultPage, String> {
+    get_manager().fetch_page(&result_id, offset, limit).await
+}
+
+#[tauri::command]
+pub async fn close_result(result_id: String) -> Result<(), String> {
+    get_manager().close_result(&result_id).await
+}
+
 #[tauri::command]
 pub async fn list_users(connection_id: String) -> Result<Vec<DatabaseUser>, String> {
     get_manager().list_users(&connection_id).await
@@ -401,8 +880,8 @@ pub async fn get_user_grants(
 }
 
 #[tauri::command]
-pub async fn cancel_queries(connection_id: String) -> Result<u64, String> {
-    get_manager().cancel_queries(&connection_id).await
+pub async fn cancel_query(connection_id: String, query_id: String) -> Result<bool, String> {
+    get_manager().cancel_query(&connection_id, &query_id).await
 }
 
 #[tauri::command]
@@ -594,3 +1073,40 @@ pub fn extract_query_table(
 ) -> Option<sql_analyzer::QueryTableInfo> {
     sql_analyzer::extract_single_table(&query, &db_type)
 }
+
+#[tauri::command]
+pub async fn analyze_query_columns(
+    connection_id: String,
+    database: String,
+    schema: String,
+    query: String,
+    db_type: String,
+) -> Result<Option<QueryColumns>, String> {
+    get_manager()
+        .analyze_query_columns(&connection_id, &database, &schema, &query, &db_type)
+        .await
+}
+
+#[tauri::command]
+pub async fn explain_query(
+    connection_id: String,
+    query: String,
+    analyze: bool,
+) -> Result<QueryPlan, String> {
+    get_manager()
+        .explain_query(&connection_id, &query, analyze)
+        .await
+}
+
+#[tauri::command]
+pub async fn complete_sql(
+    connection_id: String,
+    database: String,
+    schema: String,
+    text: String,
+    cursor_offset: usize,
+) -> Result<Completion, String> {
+    get_manager()
+        .complete_sql(&connection_id, &database, &schema, &text, cursor_offset)
+        .await
+}
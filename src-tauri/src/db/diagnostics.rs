@@ -0,0 +1,570 @@
+// ABOUTME: Tests a connection layer by layer without registering it: SSH tunnel, TCP, TLS,
+// ABOUTME: authentication and a round-trip query, reporting timings and the step that failed.
+
+use super::{mysql, postgres, redis_connection, sqlite, ssh_tunnel, CONNECT_TIMEOUT};
+use crate::storage::{ConnectionConfig, DatabaseType, SslMode};
+use serde::Serialize;
+use sqlx::{Connection, Executor, Row};
+use std::future::Future;
+use std::time::Instant;
+use tauri::AppHandle;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Postgres' SSLRequest: length 8, then the magic request code 80877103.
+const PG_SSL_REQUEST: [u8; 8] = [0, 0, 0, 8, 0x04, 0xd2, 0x16, 0x2f];
+/// CLIENT_SSL in the MySQL handshake's capability flags.
+const MYSQL_CLIENT_SSL: u16 = 0x0800;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestStepKind {
+    SshTunnel,
+    Tcp,
+    Tls,
+    Auth,
+    Query,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestStepStatus {
+    Ok,
+    Failed,
+    /// Not applicable to this connection, or not reached after a failure.
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TestStep {
+    pub kind: TestStepKind,
+    pub status: TestStepStatus,
+    pub duration_ms: u64,
+    /// What the step found, the error when it failed, or why it was skipped.
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionTestReport {
+    pub success: bool,
+    /// Every step for this kind of connection, in order.
+    pub steps: Vec<TestStep>,
+    pub server_version: Option<String>,
+    pub failed_step: Option<TestStepKind>,
+    pub error: Option<String>,
+    pub total_ms: u64,
+}
+
+/// A failure, and the step it belongs to when that isn't the step running.
+/// A failed TLS handshake only surfaces when the driver connects, for one.
+struct StepError {
+    kind: Option<TestStepKind>,
+    message: String,
+}
+
+impl From<String> for StepError {
+    fn from(message: String) -> Self {
+        Self {
+            kind: None,
+            message,
+        }
+    }
+}
+
+struct TestRun {
+    steps: Vec<TestStep>,
+    server_version: Option<String>,
+}
+
+impl TestRun {
+    /// Runs one step, recording its outcome. `Err` means the test stops here.
+    async fn step<T>(
+        &mut self,
+        kind: TestStepKind,
+        fut: impl Future<Output = Result<(T, String), StepError>>,
+    ) -> Result<T, ()> {
+        let started = Instant::now();
+        let result = fut.await;
+        let duration_ms = started.elapsed().as_millis() as u64;
+        match result {
+            Ok((value, detail)) => {
+                self.steps.push(TestStep {
+                    kind,
+                    status: TestStepStatus::Ok,
+                    duration_ms,
+                    detail: Some(detail),
+                });
+                Ok(value)
+            }
+            Err(e) => {
+                let kind = e.kind.unwrap_or(kind);
+                // The step the failure belongs to may already be recorded as
+                // passed; the failure replaces it.
+                self.steps.retain(|s| s.kind != kind);
+                self.steps.push(TestStep {
+                    kind,
+                    status: TestStepStatus::Failed,
+                    duration_ms,
+                    detail: Some(e.message),
+                });
+                Err(())
+            }
+        }
+    }
+
+    fn skip(&mut self, kind: TestStepKind, reason: &str) {
+        self.steps.push(TestStep {
+            kind,
+            status: TestStepStatus::Skipped,
+            duration_ms: 0,
+            detail: Some(reason.to_string()),
+        });
+    }
+
+    /// Orders the steps as `plan` does, marking the ones never reached.
+    fn finish(self, plan: &[TestStepKind], started: Instant) -> ConnectionTestReport {
+        let failed = self
+            .steps
+            .iter()
+            .find(|s| s.status == TestStepStatus::Failed)
+            .cloned();
+        let steps = plan
+            .iter()
+            .map(|kind| {
+                self.steps
+                    .iter()
+                    .find(|s| s.kind == *kind)
+                    .cloned()
+                    .unwrap_or_else(|| TestStep {
+                        kind: *kind,
+                        status: TestStepStatus::Skipped,
+                        duration_ms: 0,
+                        detail: Some("An earlier step failed".to_string()),
+                    })
+            })
+            .collect();
+        ConnectionTestReport {
+            success: failed.is_none(),
+            steps,
+            server_version: self.server_version,
+            failed_step: failed.as_ref().map(|s| s.kind),
+            error: failed.and_then(|s| s.detail),
+            total_ms: started.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+fn plan(db_type: &DatabaseType) -> &'static [TestStepKind] {
+    use TestStepKind::*;
+    match db_type {
+        DatabaseType::Sqlite => &[Auth, Query],
+        _ => &[SshTunnel, Tcp, Tls, Auth, Query],
+    }
+}
+
+/// Tests `config` one layer at a time. Nothing is kept open afterwards, and
+/// the report says which step failed rather than returning an error.
+pub async fn test_connection(app: &AppHandle, config: &ConnectionConfig) -> ConnectionTestReport {
+    let started = Instant::now();
+    let mut run = TestRun {
+        steps: Vec::new(),
+        server_version: None,
+    };
+    let _ = run_steps(app, config, &mut run).await;
+    run.finish(plan(&config.db_type), started)
+}
+
+async fn run_steps(
+    app: &AppHandle,
+    config: &ConnectionConfig,
+    run: &mut TestRun,
+) -> Result<(), ()> {
+    if matches!(config.db_type, DatabaseType::Sqlite) {
+        return test_sqlite(config, run).await;
+    }
+
+    // Held until the test is over; dropping it closes the tunnel.
+    let tunnel = match &config.ssh_tunnel {
+        Some(ssh_cfg) => {
+            let test_id = format!("test-{}", uuid::Uuid::new_v4());
+            let handle = run
+                .step(TestStepKind::SshTunnel, async {
+                    redis_connection::check_tunnel(config)?;
+                    config.check_tunnel_tls()?;
+                    let handle = ssh_tunnel::establish_tunnel(
+                        app,
+                        &test_id,
+                        ssh_cfg,
+                        config.host.clone(),
+                        config.port,
+                    )
+                    .await?;
+                    let detail = format!("Tunnel open on local port {}", handle.local_port);
+                    Ok((handle, detail))
+                })
+                .await?;
+            Some(handle)
+        }
+        None => {
+            run.skip(TestStepKind::SshTunnel, "No SSH tunnel configured");
+            None
+        }
+    };
+    let (host, port) = match &tunnel {
+        Some(handle) => ("127.0.0.1".to_string(), handle.local_port),
+        None => (config.host.clone(), config.port),
+    };
+
+    let mut socket = run
+        .step(TestStepKind::Tcp, async {
+            let socket =
+                tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect((host.as_str(), port)))
+                    .await
+                    .map_err(|_| {
+                        format!("Connecting to {}:{} timed out", config.host, config.port)
+                    })?
+                    .map_err(|e| {
+                        format!("Could not reach {}:{}: {}", config.host, config.port, e)
+                    })?;
+            let detail = match &tunnel {
+                Some(_) => format!("Reached {}:{} through the tunnel", config.host, config.port),
+                None => match socket.peer_addr() {
+                    Ok(addr) => format!("Connected to {}", addr),
+                    Err(_) => format!("Connected to {}:{}", config.host, config.port),
+                },
+            };
+            Ok((socket, detail))
+        })
+        .await?;
+
+    match config.db_type {
+        DatabaseType::Postgres => {
+            if matches!(config.ssl_mode, SslMode::Disable) {
+                run.skip(TestStepKind::Tls, "TLS is disabled for this connection");
+            } else {
+                run.step(
+                    TestStepKind::Tls,
+                    probe_postgres_tls(&mut socket, &config.ssl_mode),
+                )
+                .await?;
+            }
+            drop(socket);
+            let options = postgres::connect_options(config, &host, port);
+            test_sqlx::<sqlx::PgConnection>(run, options).await
+        }
+        DatabaseType::Mysql => {
+            let version = run
+                .step(
+                    TestStepKind::Tls,
+                    probe_mysql_tls(&mut socket, &config.ssl_mode),
+                )
+                .await?;
+            run.server_version = Some(version);
+            drop(socket);
+            let options = mysql::connect_options(config, &host, port);
+            test_sqlx::<sqlx::MySqlConnection>(run, options).await
+        }
+        DatabaseType::Redis => {
+            drop(socket);
+            let reason = if config.ssl_mode == SslMode::Disable {
+                "TLS is disabled for this connection"
+            } else {
+                "Redis has no TLS negotiation; the handshake is part of logging in"
+            };
+            run.skip(TestStepKind::Tls, reason);
+            test_redis(config, &host, port, run).await
+        }
+        DatabaseType::Sqlite => unreachable!("handled above"),
+    }
+}
+
+/// Asks the server whether it speaks TLS, before the driver tries to.
+async fn probe_postgres_tls(
+    socket: &mut TcpStream,
+    ssl_mode: &SslMode,
+) -> Result<((), String), StepError> {
+    let mut answer = [0u8; 1];
+    tokio::time::timeout(CONNECT_TIMEOUT, async {
+        socket.write_all(&PG_SSL_REQUEST).await?;
+        socket.read_exact(&mut answer).await
+    })
+    .await
+    .map_err(|_| "The server did not answer the TLS request".to_string())?
+    .map_err(|e| format!("The TLS request failed: {}", e))?;
+    match (answer[0], ssl_mode) {
+        (b'S', _) => Ok(((), "The server accepts TLS".to_string())),
+        (_, SslMode::Prefer) => Ok((
+            (),
+            "The server does not support TLS; connecting unencrypted".to_string(),
+        )),
+        _ => Err(
+            "The server does not support TLS, but the SSL mode requires it"
+                .to_string()
+                .into(),
+        ),
+    }
+}
+
+/// MySQL servers greet every client; the greeting carries the server
+/// version and whether TLS is on offer.
+async fn probe_mysql_tls(
+    socket: &mut TcpStream,
+    ssl_mode: &SslMode,
+) -> Result<(String, String), StepError> {
+    let greeting = tokio::time::timeout(CONNECT_TIMEOUT, async {
+        let mut header = [0u8; 4];
+        socket.read_exact(&mut header).await?;
+        let len = u32::from_le_bytes([header[0], header[1], header[2], 0]) as usize;
+        let mut payload = vec![0u8; len];
+        socket.read_exact(&mut payload).await?;
+        Ok::<_, std::io::Error>(payload)
+    })
+    .await
+    .map_err(|_| "The server did not send a MySQL greeting".to_string())?
+    .map_err(|e| format!("Reading the MySQL greeting failed: {}", e))?;
+    let (version, offers_tls) = parse_mysql_greeting(&greeting)?;
+    let detail = match (offers_tls, ssl_mode) {
+        (_, SslMode::Disable) => "TLS is disabled for this connection",
+        (true, _) => "The server accepts TLS",
+        (false, SslMode::Prefer) => "The server does not support TLS; connecting unencrypted",
+        (false, _) => {
+            return Err(
+                "The server does not support TLS, but the SSL mode requires it"
+                    .to_string()
+                    .into(),
+            )
+        }
+    };
+    Ok((version, detail.to_string()))
+}
+
+/// The server version and whether CLIENT_SSL is set, from a protocol 10
+/// handshake packet's payload. An error packet becomes its message.
+fn parse_mysql_greeting(payload: &[u8]) -> Result<(String, bool), String> {
+    match payload.first() {
+        Some(0xff) => {
+            let message = payload.get(3..).unwrap_or_default();
+            return Err(format!(
+                "The server refused the connection: {}",
+                String::from_utf8_lossy(message)
+            ));
+        }
+        Some(10) => {}
+        Some(other) => return Err(format!("Unsupported MySQL protocol version {}", other)),
+        None => return Err("The server sent an empty greeting".to_string()),
+    }
+    let version_end = payload[1..]
+        .iter()
+        .position(|&b| b == 0)
+        .ok_or("The MySQL greeting is truncated")?
+        + 1;
+    let version = String::from_utf8_lossy(&payload[1..version_end]).into_owned();
+    // Connection id (4), auth data part 1 (8) and a filler byte come next.
+    let flags_at = version_end + 1 + 4 + 8 + 1;
+    let flags = payload
+        .get(flags_at..flags_at + 2)
+        .ok_or("The MySQL greeting is truncated")?;
+    let capabilities = u16::from_le_bytes([flags[0], flags[1]]);
+    Ok((version, capabilities & MYSQL_CLIENT_SSL != 0))
+}
+
+/// Logs in with a single sqlx connection, which is where the TLS handshake
+/// happens, then runs `SELECT 1`.
+async fn test_sqlx<C>(run: &mut TestRun, options: Result<C::Options, String>) -> Result<(), ()>
+where
+    C: Connection,
+    for<'c> &'c mut C: Executor<'c, Database = C::Database>,
+    for<'q> <C::Database as sqlx::Database>::Arguments<'q>: sqlx::IntoArguments<'q, C::Database>,
+    String: for<'r> sqlx::Decode<'r, C::Database> + sqlx::Type<C::Database>,
+    usize: sqlx::ColumnIndex<<C::Database as sqlx::Database>::Row>,
+{
+    let mut conn = run
+        .step(TestStepKind::Auth, async {
+            let conn = tokio::time::timeout(CONNECT_TIMEOUT, C::connect_with(&options?))
+                .await
+                .map_err(|_| "Logging in timed out".to_string())?
+                .map_err(|e| match e {
+                    sqlx::Error::Tls(e) => StepError {
+                        kind: Some(TestStepKind::Tls),
+                        message: format!("TLS handshake failed: {}", e),
+                    },
+                    e => format!("Login failed: {}", e).into(),
+                })?;
+            Ok((conn, "Logged in".to_string()))
+        })
+        .await?;
+
+    run.step(TestStepKind::Query, async {
+        sqlx::query("SELECT 1")
+            .execute(&mut conn)
+            .await
+            .map_err(|e| format!("SELECT 1 failed: {}", e))?;
+        Ok(((), "SELECT 1 succeeded".to_string()))
+    })
+    .await?;
+
+    if let Ok(row) = sqlx::query("SELECT version()").fetch_one(&mut conn).await {
+        if let Ok(version) = row.try_get::<String, _>(0usize) {
+            run.server_version = Some(version);
+        }
+    }
+    let _ = conn.close().await;
+    Ok(())
+}
+
+async fn test_redis(
+    config: &ConnectionConfig,
+    host: &str,
+    port: u16,
+    run: &mut TestRun,
+) -> Result<(), ()> {
+    let mut conn = run
+        .step(TestStepKind::Auth, async {
+            let conn = tokio::time::timeout(
+                CONNECT_TIMEOUT,
+                redis_connection::connect(config, host, port, 0),
+            )
+            .await
+            .map_err(|_| "Logging in timed out".to_string())?
+            .map_err(|e| format!("Login failed: {}", e))?;
+            let detail = if config.password.is_empty() {
+                "Connected without a password"
+            } else {
+                "Logged in"
+            };
+            Ok((conn, detail.to_string()))
+        })
+        .await?;
+
+    run.step(TestStepKind::Query, async {
+        let pong: String = redis::cmd("PING")
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| format!("PING failed: {}", e))?;
+        Ok(((), format!("PING answered {}", pong)))
+    })
+    .await?;
+
+    let info: Result<String, _> = redis::cmd("INFO")
+        .arg("server")
+        .query_async(&mut conn)
+        .await;
+    run.server_version = info.ok().and_then(|info| {
+        info.lines()
+            .find_map(|line| line.strip_prefix("redis_version:"))
+            .map(|v| v.trim().to_string())
+    });
+    Ok(())
+}
+
+async fn test_sqlite(config: &ConnectionConfig, run: &mut TestRun) -> Result<(), ()> {
+    let options = sqlite::connect_options(config);
+    let mut conn = run
+        .step(TestStepKind::Auth, async {
+            let conn = sqlx::SqliteConnection::connect_with(&options)
+                .await
+                .map_err(|e| format!("Could not open {}: {}", config.host, e))?;
+            Ok((conn, format!("Opened {}", config.host)))
+        })
+        .await?;
+
+    run.step(TestStepKind::Query, async {
+        sqlx::query("SELECT 1")
+            .execute(&mut conn)
+            .await
+            .map_err(|e| format!("SELECT 1 failed: {}", e))?;
+        Ok(((), "SELECT 1 succeeded".to_string()))
+    })
+    .await?;
+
+    if let Ok(row) = sqlx::query("SELECT sqlite_version()")
+        .fetch_one(&mut conn)
+        .await
+    {
+        run.server_version = row.try_get::<String, _>(0).ok();
+    }
+    let _ = conn.close().await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn greeting(version: &str, capabilities: u16) -> Vec<u8> {
+        let mut payload = vec![10];
+        payload.extend_from_slice(version.as_bytes());
+        payload.push(0);
+        payload.extend_from_slice(&[1, 0, 0, 0]);
+        payload.extend_from_slice(b"abcdefgh");
+        payload.push(0);
+        payload.extend_from_slice(&capabilities.to_le_bytes());
+        payload.extend_from_slice(&[0; 16]);
+        payload
+    }
+
+    #[test]
+    fn mysql_greeting_version_and_tls() {
+        assert_eq!(
+            parse_mysql_greeting(&greeting("8.0.36", 0xffff)).unwrap(),
+            ("8.0.36".to_string(), true)
+        );
+        assert_eq!(
+            parse_mysql_greeting(&greeting("5.5.5-10.11.6-MariaDB", 0xf7ff)).unwrap(),
+            ("5.5.5-10.11.6-MariaDB".to_string(), false)
+        );
+    }
+
+    #[test]
+    fn mysql_error_and_truncated_greetings() {
+        let mut refused = vec![0xff, 0x6a, 0x04];
+        refused.extend_from_slice(b"Host '10.0.0.9' is not allowed to connect");
+        let err = parse_mysql_greeting(&refused).unwrap_err();
+        assert!(
+            err.ends_with("Host '10.0.0.9' is not allowed to connect"),
+            "{}",
+            err
+        );
+
+        let full = greeting("8.0.36", 0xffff);
+        assert!(parse_mysql_greeting(&full[..12]).is_err());
+        assert!(parse_mysql_greeting(&[9, b'x', 0]).is_err());
+        assert!(parse_mysql_greeting(&[]).is_err());
+    }
+
+    #[test]
+    fn unreached_steps_are_skipped_in_plan_order() {
+        let mut run = TestRun {
+            steps: Vec::new(),
+            server_version: None,
+        };
+        run.skip(TestStepKind::SshTunnel, "No SSH tunnel configured");
+        run.steps.push(TestStep {
+            kind: TestStepKind::Tls,
+            status: TestStepStatus::Failed,
+            duration_ms: 3,
+            detail: Some("TLS handshake failed".to_string()),
+        });
+        run.steps.push(TestStep {
+            kind: TestStepKind::Tcp,
+            status: TestStepStatus::Ok,
+            duration_ms: 1,
+            detail: None,
+        });
+        let report = run.finish(plan(&DatabaseType::Postgres), Instant::now());
+        assert!(!report.success);
+        assert_eq!(report.failed_step, Some(TestStepKind::Tls));
+        assert_eq!(report.error.as_deref(), Some("TLS handshake failed"));
+        let order: Vec<_> = report.steps.iter().map(|s| (s.kind, s.status)).collect();
+        assert_eq!(
+            order,
+            vec![
+                (TestStepKind::SshTunnel, TestStepStatus::Skipped),
+                (TestStepKind::Tcp, TestStepStatus::Ok),
+                (TestStepKind::Tls, TestStepStatus::Failed),
+                (TestStepKind::Auth, TestStepStatus::Skipped),
+                (TestStepKind::Query, TestStepStatus::Skipped),
+            ]
+        );
+    }
+}
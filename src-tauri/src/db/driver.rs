@@ -0,0 +1,252 @@
+// ABOUTME: Engine-agnostic driver trait implemented by each database backend.
+// ABOUTME: Lets ConnectionManager dispatch without matching on the engine type.
+
+use super::query_plan::QueryPlan;
+use super::redis_db::RedisDriver;
+use super::result_set::ResultCursor;
+use super::row_changes::{AppliedRowChanges, TableEdit};
+use super::{
+    ColumnInfo, ConstraintInfo, DatabaseUser, FunctionInfo, IndexInfo, QueryResult, UserGrant,
+    ViewDependency,
+};
+use async_trait::async_trait;
+use serde::Serialize;
+use std::collections::HashMap;
+use tauri::AppHandle;
+
+/// Describes which optional features an engine supports, so the UI can hide
+/// tabs and actions up front instead of surfacing "X does not support Y" errors.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DriverCapabilities {
+    pub engine: &'static str,
+    pub supports_schemas: bool,
+    pub supports_create_database: bool,
+    pub supports_create_schema: bool,
+    pub supports_users: bool,
+    pub supports_functions: bool,
+    pub supports_procedures: bool,
+    pub supports_materialized_views: bool,
+    pub supports_sequences: bool,
+    pub supports_triggers: bool,
+    pub supports_view_dependencies: bool,
+    pub supports_query_cancel: bool,
+    pub supports_paged_results: bool,
+    pub supports_transactions: bool,
+    pub supports_query_plans: bool,
+    /// Switching databases happens on the live connection (e.g. Redis SELECT)
+    /// rather than by reconnecting with a different default database.
+    pub switches_database_in_place: bool,
+}
+
+/// One pooled connection pinned for a transaction session, so consecutive
+/// statements see the same transaction and session state.
+#[async_trait]
+pub trait DriverSession: Send {
+    async fn execute_query(
+        &mut self,
+        app: &AppHandle,
+        query_id: &str,
+        query: &str,
+        max_rows: usize,
+    ) -> Result<QueryResult, String>;
+
+    /// Applies row edits inside the session's open transaction, so they
+    /// commit or roll back with it.
+    async fn apply_row_changes(
+        &mut self,
+        edits: &[TableEdit],
+    ) -> Result<Vec<AppliedRowChanges>, String>;
+
+    /// Closes the connection instead of returning it to the pool when the
+    /// session is dropped. Used when a transaction may still be open, since
+    /// the pool would otherwise hand that transaction to the next caller.
+    fn discard(&mut self);
+}
+
+/// A connected database backend. Only the introspection calls every engine can
+/// answer are required; optional features default to "empty" or "unsupported"
+/// and are advertised through [`DriverCapabilities`].
+#[async_trait]
+pub trait DatabaseDriver: Send + Sync {
+    fn capabilities(&self) -> DriverCapabilities;
+
+    fn unsupported(&self, what: &str) -> String {
+        format!("{} does not support {}", self.capabilities().engine, what)
+    }
+
+    async fn list_databases(&self) -> Result<Vec<String>, String>;
+
+    async fn list_schemas(&self, database: &str) -> Result<Vec<String>, String>;
+
+    async fn list_tables(&self, database: &str, schema: &str) -> Result<Vec<String>, String>;
+
+    async fn list_views(&self, database: &str, schema: &str) -> Result<Vec<String>, String>;
+
+    async fn list_columns(
+        &self,
+        database: &str,
+        schema: &str,
+        table: &str,
+    ) -> Result<Vec<ColumnInfo>, String>;
+
+    /// Columns of every table and view in a schema, by table name. Engines
+    /// override this with one catalog query; the default asks table by table.
+    async fn list_schema_columns(
+        &self,
+        database: &str,
+        schema: &str,
+    ) -> Result<HashMap<String, Vec<ColumnInfo>>, String> {
+        let mut tables = self.list_tables(database, schema).await?;
+        tables.extend(self.list_views(database, schema).await?);
+        let mut columns = HashMap::new();
+        for table in tables {
+            let table_columns = self.list_columns(database, schema, &table).await?;
+            columns.insert(table, table_columns);
+        }
+        Ok(columns)
+    }
+
+    async fn list_indexes(
+        &self,
+        database: &str,
+        schema: &str,
+        table: &str,
+    ) -> Result<Vec<IndexInfo>, String>;
+
+    async fn list_constraints(
+        &self,
+        database: &str,
+        schema: &str,
+        table: &str,
+    ) -> Result<Vec<ConstraintInfo>, String>;
+
+    /// Splits a script into the statements `execute_query` runs one at a time.
+    /// Engines without a statement separator run the input as-is.
+    fn split_script(&self, script: &str) -> Vec<String> {
+        vec![script.to_string()]
+    }
+
+    /// Runs `query` and collects at most `max_rows` rows, setting
+    /// `QueryResult::truncated` when more were available.
+    async fn execute_query(
+        &self,
+        app: &AppHandle,
+        query_id: &str,
+        query: &str,
+        database: Option<&str>,
+        max_rows: usize,
+    ) -> Result<QueryResult, String>;
+
+    /// Pins a connection for a transaction session.
+    async fn open_session(&self) -> Result<Box<dyn DriverSession>, String> {
+        Err(self.unsupported("transactions"))
+    }
+
+    /// Opens `query` as a paged result that is read on demand.
+    async fn open_result(&self, _query: &str) -> Result<Box<dyn ResultCursor>, String> {
+        Err(self.unsupported("paged results"))
+    }
+
+    async fn switch_database(&self, _database: &str) -> Result<(), String> {
+        Err(self.unsupported("switching databases on a live connection"))
+    }
+
+    async fn create_database(&self, _name: &str) -> Result<(), String> {
+        Err(self.unsupported("CREATE DATABASE"))
+    }
+
+    async fn create_schema(&self, _name: &str) -> Result<(), String> {
+        Err(self.unsupported("CREATE SCHEMA"))
+    }
+
+    async fn list_functions(&self, _database: &str, _schema: &str) -> Result<Vec<String>, String> {
+        Ok(vec![])
+    }
+
+    async fn list_procedures(&self, _database: &str, _schema: &str) -> Result<Vec<String>, String> {
+        Ok(vec![])
+    }
+
+    async fn list_materialized_views(
+        &self,
+        _database: &str,
+        _schema: &str,
+    ) -> Result<Vec<String>, String> {
+        Ok(vec![])
+    }
+
+    async fn list_sequences(&self, _database: &str, _schema: &str) -> Result<Vec<String>, String> {
+        Ok(vec![])
+    }
+
+    async fn list_triggers(&self, _database: &str, _schema: &str) -> Result<Vec<String>, String> {
+        Ok(vec![])
+    }
+
+    async fn get_function_definition(
+        &self,
+        _database: &str,
+        _schema: &str,
+        _function_name: &str,
+    ) -> Result<FunctionInfo, String> {
+        Err(self.unsupported("user-defined functions"))
+    }
+
+    async fn get_view_definition(
+        &self,
+        _database: &str,
+        _schema: &str,
+        _view_name: &str,
+    ) -> Result<FunctionInfo, String> {
+        Err(self.unsupported("views"))
+    }
+
+    async fn get_view_dependencies(
+        &self,
+        _database: &str,
+        _schema: &str,
+    ) -> Result<Vec<ViewDependency>, String> {
+        Ok(vec![])
+    }
+
+    async fn list_users(&self) -> Result<Vec<DatabaseUser>, String> {
+        Err(self.unsupported("user management"))
+    }
+
+    async fn get_user_grants(
+        &self,
+        _username: &str,
+        _host: Option<&str>,
+    ) -> Result<Vec<UserGrant>, String> {
+        Err(self.unsupported("user management"))
+    }
+
+    /// Applies every table's changes in one transaction, matching rows on
+    /// each table's key. Any statement that affects other than one row rolls
+    /// back all of them.
+    async fn apply_row_changes(
+        &self,
+        _edits: &[TableEdit],
+    ) -> Result<Vec<AppliedRowChanges>, String> {
+        Err(self.unsupported("row editing"))
+    }
+
+    /// Captures the plan for `query`. With `analyze` the query runs so the
+    /// plan carries actual rows and timings; engines that can't measure a
+    /// run return the estimated plan, with `QueryPlan::analyzed` unset.
+    async fn explain_query(&self, _query: &str, _analyze: bool) -> Result<QueryPlan, String> {
+        Err(self.unsupported("query plans"))
+    }
+
+    /// Cancels the in-flight query started by `execute_query` with
+    /// `query_id`. Returns false if no such query is running.
+    async fn cancel_query(&self, _query_id: &str) -> Result<bool, String> {
+        Ok(false)
+    }
+
+    /// The Redis driver, for the key browser commands that have no SQL
+    /// counterpart.
+    fn as_redis(&self) -> Option<&RedisDriver> {
+        None
+    }
+}
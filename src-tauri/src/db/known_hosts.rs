@@ -0,0 +1,362 @@
+// ABOUTME: Parses OpenSSH known_hosts files and checks SSH server host keys against them.
+// ABOUTME: Handles hashed host names, wildcard and negated patterns, non-default ports and markers.
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use russh::keys::ssh_key::{HashAlg, PublicKey};
+use serde::Serialize;
+use sha1::Sha1;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Marker {
+    /// The key is a CA that signs host certificates for the matching hosts.
+    CertAuthority,
+    /// The key must never be accepted.
+    Revoked,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    marker: Option<Marker>,
+    patterns: String,
+    key: PublicKey,
+    source: String,
+    line: usize,
+}
+
+/// A key recorded for a host, for error messages and the trust prompt.
+#[derive(Debug, Clone, Serialize)]
+pub struct KnownKey {
+    pub key_type: String,
+    pub fingerprint: String,
+    pub source: String,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone)]
+pub enum HostKeyStatus {
+    Trusted,
+    /// No key is recorded for the host.
+    Unknown,
+    /// Keys are recorded for the host, and the server's key is not one of them.
+    Changed {
+        expected: Vec<KnownKey>,
+    },
+    Revoked {
+        source: String,
+        line: usize,
+    },
+}
+
+/// Every entry from the known_hosts files that were loaded, in load order.
+#[derive(Debug, Default)]
+pub struct KnownHosts {
+    entries: Vec<Entry>,
+}
+
+impl KnownHosts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the entries of the file at `path`. A missing or unreadable file
+    /// adds nothing, like ssh does.
+    pub fn load(&mut self, path: &Path) {
+        if let Ok(content) = std::fs::read_to_string(path) {
+            self.parse(&path.display().to_string(), &content);
+        }
+    }
+
+    /// Adds the entries in `content`. Lines that don't parse (unknown key
+    /// types, unknown markers, truncated lines) are skipped.
+    pub fn parse(&mut self, source: &str, content: &str) {
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let mut first = fields.next();
+            let marker = match first {
+                Some("@cert-authority") => Some(Marker::CertAuthority),
+                Some("@revoked") => Some(Marker::Revoked),
+                Some(m) if m.starts_with('@') => continue,
+                _ => None,
+            };
+            if marker.is_some() {
+                first = fields.next();
+            }
+            let (Some(patterns), Some(key_type), Some(key_data)) =
+                (first, fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let Ok(key) = PublicKey::from_openssh(&format!("{} {}", key_type, key_data)) else {
+                continue;
+            };
+            self.entries.push(Entry {
+                marker,
+                patterns: patterns.to_string(),
+                key,
+                source: source.to_string(),
+                line: index + 1,
+            });
+        }
+    }
+
+    /// Checks the key `host:port` presented. Host certificates aren't
+    /// negotiated, so `@cert-authority` entries never vouch for a plain key:
+    /// as with ssh, a host only covered by a CA is unknown when it presents one.
+    pub fn check(&self, host: &str, port: u16, key: &PublicKey) -> HostKeyStatus {
+        let name = host_name(host, port);
+        let matching: Vec<&Entry> = self
+            .entries
+            .iter()
+            .filter(|e| host_matches(&name, &e.patterns))
+            .collect();
+
+        if let Some(revoked) = matching
+            .iter()
+            .find(|e| e.marker == Some(Marker::Revoked) && keys_equal(&e.key, key))
+        {
+            return HostKeyStatus::Revoked {
+                source: revoked.source.clone(),
+                line: revoked.line,
+            };
+        }
+
+        let recorded: Vec<&Entry> = matching
+            .into_iter()
+            .filter(|e| e.marker.is_none())
+            .collect();
+        if recorded.iter().any(|e| keys_equal(&e.key, key)) {
+            HostKeyStatus::Trusted
+        } else if recorded.is_empty() {
+            HostKeyStatus::Unknown
+        } else {
+            HostKeyStatus::Changed {
+                expected: recorded
+                    .iter()
+                    .map(|e| KnownKey {
+                        key_type: e.key.algorithm().to_string(),
+                        fingerprint: fingerprint(&e.key),
+                        source: e.source.clone(),
+                        line: e.line,
+                    })
+                    .collect(),
+            }
+        }
+    }
+}
+
+/// Comments are not part of the key.
+fn keys_equal(a: &PublicKey, b: &PublicKey) -> bool {
+    a.key_data() == b.key_data()
+}
+
+/// The name ssh looks a host up by: `host`, or `[host]:port` off port 22.
+pub fn host_name(host: &str, port: u16) -> String {
+    if port == 22 {
+        host.to_string()
+    } else {
+        format!("[{}]:{}", host, port)
+    }
+}
+
+/// `SHA256:...`, as ssh prints it.
+pub fn fingerprint(key: &PublicKey) -> String {
+    key.fingerprint(HashAlg::Sha256).to_string()
+}
+
+/// A known_hosts line recording `key` for `host:port`.
+pub fn format_entry(host: &str, port: u16, key: &PublicKey) -> Result<String, String> {
+    let key = key
+        .to_openssh()
+        .map_err(|e| format!("Failed to encode host key: {}", e))?;
+    // Drop any comment the key carried; it isn't ours.
+    let key: Vec<&str> = key.split_whitespace().take(2).collect();
+    Ok(format!("{} {}", host_name(host, port), key.join(" ")))
+}
+
+/// Whether `name` matches a comma-separated pattern list. A matching negated
+/// pattern (`!pattern`) excludes the host even if another pattern matches.
+fn host_matches(name: &str, patterns: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns.split(',') {
+        if let Some(negated) = pattern.strip_prefix('!') {
+            if pattern_matches(name, negated) {
+                return false;
+            }
+        } else if pattern_matches(name, pattern) {
+            matched = true;
+        }
+    }
+    matched
+}
+
+fn pattern_matches(name: &str, pattern: &str) -> bool {
+    if let Some(hashed) = pattern.strip_prefix("|1|") {
+        return hashed_matches(name, hashed);
+    }
+    wildcard_matches(
+        name.to_ascii_lowercase().as_bytes(),
+        pattern.to_ascii_lowercase().as_bytes(),
+    )
+}
+
+/// `HashKnownHosts` entries are `|1|base64(salt)|base64(HMAC-SHA1(salt, name))`.
+fn hashed_matches(name: &str, hashed: &str) -> bool {
+    let Some((salt, hash)) = hashed.split_once('|') else {
+        return false;
+    };
+    let (Ok(salt), Ok(hash)) = (BASE64.decode(salt), BASE64.decode(hash)) else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha1>::new_from_slice(&salt) else {
+        return false;
+    };
+    mac.update(name.as_bytes());
+    mac.verify_slice(&hash).is_ok()
+}
+
+/// `*` matches any run of characters and `?` any single character.
+pub(super) fn wildcard_matches(name: &[u8], pattern: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|i| wildcard_matches(&name[i..], rest)),
+        Some((b'?', rest)) => !name.is_empty() && wildcard_matches(&name[1..], rest),
+        Some((c, rest)) => name.first() == Some(c) && wildcard_matches(&name[1..], rest),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY_A: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAILIG2T/B0l0gaqj3puu510tu9N1OkQ4znY3LYuEm5zCF";
+    const KEY_B: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIJdD7y3aLq454yWBdwLWbieU1ebz9/cu7/QEXn9OIeZJ";
+
+    fn key(s: &str) -> PublicKey {
+        PublicKey::from_openssh(s).unwrap()
+    }
+
+    fn known(content: &str) -> KnownHosts {
+        let mut hosts = KnownHosts::new();
+        hosts.parse("known_hosts", content);
+        hosts
+    }
+
+    #[test]
+    fn plain_entries_with_port_and_comments() {
+        let hosts = known(&format!(
+            "# comment\n\ndb.example.com,10.0.0.5 {}\n[bastion]:2222 {} me@laptop\n",
+            KEY_A, KEY_B
+        ));
+        assert!(matches!(
+            hosts.check("db.example.com", 22, &key(KEY_A)),
+            HostKeyStatus::Trusted
+        ));
+        assert!(matches!(
+            hosts.check("10.0.0.5", 22, &key(KEY_A)),
+            HostKeyStatus::Trusted
+        ));
+        assert!(matches!(
+            hosts.check("bastion", 2222, &key(KEY_B)),
+            HostKeyStatus::Trusted
+        ));
+        // The port is part of the name.
+        assert!(matches!(
+            hosts.check("bastion", 22, &key(KEY_B)),
+            HostKeyStatus::Unknown
+        ));
+    }
+
+    #[test]
+    fn hashed_entries_match() {
+        let hosts = known(&format!(
+            "|1|O33ESRMWPVkMYIwJ1Uw+n877jTo=|nuuC5vEqXlEZ/8BXQR7m619W6Ak= {}\n",
+            KEY_A
+        ));
+        assert!(matches!(
+            hosts.check("example.com", 22, &key(KEY_A)),
+            HostKeyStatus::Trusted
+        ));
+        assert!(matches!(
+            hosts.check("example.org", 22, &key(KEY_A)),
+            HostKeyStatus::Unknown
+        ));
+    }
+
+    #[test]
+    fn changed_key_reports_the_recorded_fingerprint() {
+        let hosts = known(&format!("db.example.com {}\n", KEY_A));
+        match hosts.check("db.example.com", 22, &key(KEY_B)) {
+            HostKeyStatus::Changed { expected } => {
+                assert_eq!(expected.len(), 1);
+                assert_eq!(expected[0].line, 1);
+                assert_eq!(expected[0].fingerprint, fingerprint(&key(KEY_A)));
+                assert!(expected[0].fingerprint.starts_with("SHA256:"));
+            }
+            other => panic!("expected a changed key, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn wildcards_and_negation() {
+        let hosts = known(&format!(
+            "*.example.com,!secret.example.com,db?.lan {}\n",
+            KEY_A
+        ));
+        assert!(matches!(
+            hosts.check("a.example.com", 22, &key(KEY_A)),
+            HostKeyStatus::Trusted
+        ));
+        assert!(matches!(
+            hosts.check("DB1.lan", 22, &key(KEY_A)),
+            HostKeyStatus::Trusted
+        ));
+        assert!(matches!(
+            hosts.check("secret.example.com", 22, &key(KEY_A)),
+            HostKeyStatus::Unknown
+        ));
+        assert!(matches!(
+            hosts.check("db10.lan", 22, &key(KEY_A)),
+            HostKeyStatus::Unknown
+        ));
+    }
+
+    #[test]
+    fn markers() {
+        let hosts = known(&format!(
+            "@revoked * {}\n@cert-authority *.example.com {}\n@unknown-marker host {}\n",
+            KEY_B, KEY_A, KEY_A
+        ));
+        assert!(matches!(
+            hosts.check("anything", 22, &key(KEY_B)),
+            HostKeyStatus::Revoked { line: 1, .. }
+        ));
+        // A CA key doesn't vouch for the same plain key presented by a host.
+        assert!(matches!(
+            hosts.check("db.example.com", 22, &key(KEY_A)),
+            HostKeyStatus::Unknown
+        ));
+        assert!(matches!(
+            hosts.check("host", 22, &key(KEY_A)),
+            HostKeyStatus::Unknown
+        ));
+    }
+
+    #[test]
+    fn formatted_entries_read_back() {
+        let line = format_entry("db.internal", 2200, &key(&format!("{} comment", KEY_A))).unwrap();
+        assert_eq!(line, format!("[db.internal]:2200 {}", KEY_A));
+        assert!(matches!(
+            known(&line).check("db.internal", 2200, &key(KEY_A)),
+            HostKeyStatus::Trusted
+        ));
+    }
+}
@@ -0,0 +1,218 @@
+// ABOUTME: Per-connection cache of schema metadata (object lists, columns, indexes, constraints).
+// ABOUTME: Entries expire after a TTL and are dropped on refresh or when a statement changes the schema.
+
+use super::driver::DatabaseDriver;
+use super::{ColumnInfo, ConstraintInfo, IndexInfo};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// How long an entry is served before it is loaded again. DDL run through
+/// the app invalidates sooner; this bounds staleness from changes made
+/// elsewhere.
+const METADATA_TTL: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MetadataObject {
+    Tables,
+    Views,
+    Functions,
+    Procedures,
+    MaterializedViews,
+    Sequences,
+    Triggers,
+    Columns(String),
+    Indexes(String),
+    Constraints(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MetadataKey {
+    pub connection_id: String,
+    pub database: String,
+    pub schema: String,
+    pub object: MetadataObject,
+}
+
+impl MetadataKey {
+    pub fn new(connection_id: &str, database: &str, schema: &str, object: MetadataObject) -> Self {
+        Self {
+            connection_id: connection_id.to_string(),
+            database: database.to_string(),
+            schema: schema.to_string(),
+            object,
+        }
+    }
+
+    fn with_object(&self, object: MetadataObject) -> Self {
+        Self {
+            object,
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Metadata {
+    Names(Vec<String>),
+    Columns(Vec<ColumnInfo>),
+    Indexes(Vec<IndexInfo>),
+    Constraints(Vec<ConstraintInfo>),
+}
+
+impl Metadata {
+    pub fn into_names(self) -> Vec<String> {
+        match self {
+            Metadata::Names(names) => names,
+            Metadata::Columns(columns) => columns.into_iter().map(|c| c.name).collect(),
+            Metadata::Indexes(indexes) => indexes.into_iter().map(|i| i.name).collect(),
+            Metadata::Constraints(constraints) => constraints.into_iter().map(|c| c.name).collect(),
+        }
+    }
+
+    pub fn into_columns(self) -> Vec<ColumnInfo> {
+        match self {
+            Metadata::Columns(columns) => columns,
+            _ => vec![],
+        }
+    }
+
+    pub fn into_indexes(self) -> Vec<IndexInfo> {
+        match self {
+            Metadata::Indexes(indexes) => indexes,
+            _ => vec![],
+        }
+    }
+
+    pub fn into_constraints(self) -> Vec<ConstraintInfo> {
+        match self {
+            Metadata::Constraints(constraints) => constraints,
+            _ => vec![],
+        }
+    }
+}
+
+struct Entry {
+    value: Metadata,
+    loaded_at: Instant,
+}
+
+#[derive(Default)]
+pub struct MetadataCache {
+    entries: RwLock<HashMap<MetadataKey, Entry>>,
+}
+
+impl MetadataCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the cached entry for `key`, loading it from `driver` when it
+    /// is missing or older than the TTL. Failed loads are not cached.
+    pub async fn get_or_load(
+        &self,
+        driver: &dyn DatabaseDriver,
+        key: MetadataKey,
+    ) -> Result<Metadata, String> {
+        if let Some(hit) = self.entries.read().await.get(&key) {
+            if hit.loaded_at.elapsed() < METADATA_TTL {
+                return Ok(hit.value.clone());
+            }
+        }
+        let (database, schema) = (key.database.as_str(), key.schema.as_str());
+        let value = match &key.object {
+            MetadataObject::Tables => Metadata::Names(driver.list_tables(database, schema).await?),
+            MetadataObject::Views => Metadata::Names(driver.list_views(database, schema).await?),
+            MetadataObject::Functions => {
+                Metadata::Names(driver.list_functions(database, schema).await?)
+            }
+            MetadataObject::Procedures => {
+                Metadata::Names(driver.list_procedures(database, schema).await?)
+            }
+            MetadataObject::MaterializedViews => {
+                Metadata::Names(driver.list_materialized_views(database, schema).await?)
+            }
+            MetadataObject::Sequences => {
+                Metadata::Names(driver.list_sequences(database, schema).await?)
+            }
+            MetadataObject::Triggers => {
+                Metadata::Names(driver.list_triggers(database, schema).await?)
+            }
+            MetadataObject::Columns(table) => {
+                return self.load_columns(driver, &key, table).await;
+            }
+            MetadataObject::Indexes(table) => {
+                Metadata::Indexes(driver.list_indexes(database, schema, table).await?)
+            }
+            MetadataObject::Constraints(table) => {
+                Metadata::Constraints(driver.list_constraints(database, schema, table).await?)
+            }
+        };
+        self.entries.write().await.insert(
+            key,
+            Entry {
+                value: value.clone(),
+                loaded_at: Instant::now(),
+            },
+        );
+        Ok(value)
+    }
+
+    /// Loads the columns of every table in the schema in one catalog query
+    /// and caches them all, since a caller that wants one table's columns
+    /// usually wants its neighbours' next. Tables the bulk query misses
+    /// (e.g. a name typed in a different case) are loaded on their own.
+    async fn load_columns(
+        &self,
+        driver: &dyn DatabaseDriver,
+        key: &MetadataKey,
+        table: &str,
+    ) -> Result<Metadata, String> {
+        let mut by_table = driver
+            .list_schema_columns(&key.database, &key.schema)
+            .await?;
+        let columns = match by_table.remove(table) {
+            Some(columns) => columns,
+            None => {
+                driver
+                    .list_columns(&key.database, &key.schema, table)
+                    .await?
+            }
+        };
+
+        let loaded_at = Instant::now();
+        let mut entries = self.entries.write().await;
+        for (other, other_columns) in by_table {
+            entries.insert(
+                key.with_object(MetadataObject::Columns(other)),
+                Entry {
+                    value: Metadata::Columns(other_columns),
+                    loaded_at,
+                },
+            );
+        }
+        entries.insert(
+            key.clone(),
+            Entry {
+                value: Metadata::Columns(columns.clone()),
+                loaded_at,
+            },
+        );
+        Ok(Metadata::Columns(columns))
+    }
+
+    /// Drops cached entries for a connection, optionally narrowed to one
+    /// database and schema.
+    pub async fn invalidate(
+        &self,
+        connection_id: &str,
+        database: Option<&str>,
+        schema: Option<&str>,
+    ) {
+        self.entries.write().await.retain(|key, _| {
+            key.connection_id != connection_id
+                || database.is_some_and(|d| key.database != d)
+                || schema.is_some_and(|s| key.schema != s)
+        });
+    }
+}
@@ -1,28 +1,71 @@
 // ABOUTME: Database connection management and query execution.
-// ABOUTME: Supports PostgreSQL, MySQL, SQLite, and Redis with runtime driver selection.
+// ABOUTME: Supports PostgreSQL, MySQL, SQLite, and Redis via the DatabaseDriver trait.
 
+pub mod diagnostics;
+pub mod driver;
+pub mod known_hosts;
+pub mod metadata_cache;
 pub mod mysql;
+pub mod mysql_types;
+pub mod pg_types;
 pub mod postgres;
+pub mod query_plan;
+pub mod redis_connection;
 pub mod redis_db;
+pub mod redis_keyspace;
+pub mod redis_value;
+pub mod result_set;
+pub mod row_changes;
+pub mod running;
 pub mod sqlite;
+pub mod ssh_config;
 pub mod ssh_tunnel;
-
-use crate::storage::{ConnectionConfig, DatabaseType, SslMode};
+pub mod value;
+
+use crate::sql_analyzer::{self, ColumnProvenance, SourceTable};
+use crate::sql_completion::{self, Clause, Completion, CompletionCatalog};
+use crate::storage::{ConnectionConfig, DatabaseType};
+use chrono::{DateTime, Utc};
+use driver::{DatabaseDriver, DriverCapabilities, DriverSession};
+use metadata_cache::{Metadata, MetadataCache, MetadataKey, MetadataObject};
+use redis_keyspace::{RedisBytes, RedisDatabaseInfo, RedisKeyPage, RedisNamespace};
+use redis_value::{RedisEdit, RedisKeyValue};
+use result_set::{read_capped, ResultCursor, ResultPage};
+use row_changes::{AppliedRowChanges, TableChanges, TableEdit};
 use ssh_tunnel::TunnelHandle;
+use pg_types::PgTypeCatalog;
+use query_plan::QueryPlan;
+use value::{ColumnMeta, LogicalType};
 use serde::{Deserialize, Serialize};
+use sqlx::pool::PoolOptions;
 use sqlx::Column;
 use sqlx::Row;
 use sqlx::TypeInfo;
-use sqlx::pool::PoolOptions;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter};
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 
 const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
 const QUERY_PROGRESS_EVENT: &str = "query-progress";
 const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+/// Row cap for `execute_query` and paged results when the caller sets none.
+/// Bigger results should be browsed through a result session instead.
+pub const DEFAULT_MAX_ROWS: usize = 100_000;
+
+/// Pool settings for `config`'s options, on top of the connect timeout.
+fn pool_options<DB: sqlx::Database>(config: &ConnectionConfig) -> PoolOptions<DB> {
+    let mut pool = PoolOptions::new().acquire_timeout(CONNECT_TIMEOUT);
+    if let Some(max) = config.options.max_connections {
+        pool = pool.max_connections(max.max(1));
+    }
+    if let Some(secs) = config.options.idle_timeout_secs {
+        pool = pool.idle_timeout(Duration::from_secs(secs));
+    }
+    pool
+}
 
 fn emit_progress(
     app: &AppHandle,
@@ -65,6 +108,26 @@ pub struct IndexInfo {
     pub is_primary: bool,
 }
 
+/// A table behind some of a query's result columns, with the database and
+/// schema its rows are edited through.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryColumnTable {
+    pub database: String,
+    pub schema: String,
+    pub table: String,
+    /// Result positions of the table's primary key, or of a unique NOT NULL
+    /// key. Empty when the result doesn't identify its rows, so none of its
+    /// columns are editable.
+    pub key_columns: Vec<usize>,
+}
+
+/// Where each result column of a SELECT comes from.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryColumns {
+    pub tables: Vec<QueryColumnTable>,
+    pub columns: Vec<ColumnProvenance>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConstraintInfo {
     pub name: String,
@@ -92,6 +155,9 @@ pub struct ViewDependency {
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct QueryResult {
     pub columns: Vec<String>,
+    /// Type information for each entry of `columns`, in the same order.
+    #[serde(default)]
+    pub column_meta: Vec<ColumnMeta>,
     pub rows: Vec<Vec<serde_json::Value>>,
     pub row_count: usize,
     pub message: Option<String>,
@@ -105,6 +171,34 @@ pub struct QueryResult {
     /// higher, but for fat rows this dominates.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub bytes_transferred: Option<u64>,
+    /// The row cap was hit and more rows were left unread on the server.
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+/// What a script does when one of its statements fails.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnError {
+    #[default]
+    Stop,
+    Continue,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TransactionStatus {
+    pub active: bool,
+    pub connection_id: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+}
+
+/// Outcome of one statement of a script; exactly one of `result` and `error` is set.
+#[derive(Debug, Clone, Serialize)]
+pub struct StatementResult {
+    pub statement: String,
+    pub result: Option<QueryResult>,
+    pub error: Option<String>,
+    pub elapsed_ms: u64,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -149,34 +243,93 @@ pub struct UserGrant {
     pub inherited_from: Option<String>,
 }
 
-pub enum ConnectionPool {
-    Postgres(sqlx::PgPool),
-    Mysql(sqlx::MySqlPool),
-    Sqlite(sqlx::SqlitePool),
-    Redis(redis::aio::ConnectionManager),
-}
-
-/// A connected pool plus any resources whose lifetime must match the pool
-/// (e.g., an SSH tunnel). Field order matters: the pool is dropped before the
-/// tunnel so in-flight TCP traffic can still flow during pool teardown.
+/// A connected driver plus any resources whose lifetime must match it
+/// (e.g., an SSH tunnel). Field order matters: the driver (and its pool) is
+/// dropped before the tunnel so in-flight TCP traffic can still flow during
+/// pool teardown.
 pub struct ActiveConnection {
-    pub pool: Arc<ConnectionPool>,
+    pub driver: Arc<dyn DatabaseDriver>,
     _tunnel: Option<TunnelHandle>,
 }
 
+/// An open paged result. The cursor pins whatever server resources the
+/// engine needs (a connection, a cursor, a stream) until it is closed.
+struct ResultSession {
+    connection_id: String,
+    max_rows: usize,
+    cursor: Arc<Mutex<Box<dyn ResultCursor>>>,
+}
+
+/// A tab's pinned connection. It only exists while a transaction is open:
+/// ending the transaction drops it and returns the connection to the pool.
+struct TransactionSession {
+    connection_id: String,
+    started_at: DateTime<Utc>,
+    session: Mutex<Box<dyn DriverSession>>,
+    /// DDL ran in the transaction. Until it commits, metadata read on other
+    /// connections still shows the old schema and may refill the cache with
+    /// it, so the cache is dropped again when the transaction ends.
+    changed_schema: AtomicBool,
+}
+
+fn redis_driver(driver: &dyn DatabaseDriver) -> Result<&redis_db::RedisDriver, String> {
+    driver
+        .as_redis()
+        .ok_or_else(|| driver.unsupported("key browsing"))
+}
+
+/// The sql_analyzer dialect name for a driver's engine.
+fn sql_dialect(engine: &str) -> &'static str {
+    match engine {
+        "PostgreSQL" => "postgres",
+        "MySQL" => "mysql",
+        "SQLite" => "sqlite",
+        _ => "generic",
+    }
+}
+
+/// The (database, schema) a `qualifier.table` reference points at. Without
+/// schemas (MySQL), the qualifier names the database.
+fn qualified_location(
+    database: &str,
+    schema: &str,
+    qualifier: Option<&str>,
+    supports_schemas: bool,
+) -> (String, String) {
+    match (qualifier, supports_schemas) {
+        (Some(qualifier), true) => (database.to_string(), qualifier.to_string()),
+        (Some(qualifier), false) => (qualifier.to_string(), schema.to_string()),
+        (None, _) => (database.to_string(), schema.to_string()),
+    }
+}
+
 pub struct ConnectionManager {
     pools: RwLock<HashMap<String, ActiveConnection>>,
+    results: RwLock<HashMap<String, ResultSession>>,
+    /// Open transactions by session id (one per editor tab).
+    sessions: RwLock<HashMap<String, Arc<TransactionSession>>>,
+    /// Scripts being executed, by query_id, with whether a cancel was requested.
+    /// Checked between statements so a cancel also stops the rest of the script.
+    scripts: std::sync::Mutex<HashMap<String, bool>>,
+    /// Table, view, function and column names for autocomplete.
+    metadata: MetadataCache,
 }
 
 impl ConnectionManager {
     pub fn new() -> Self {
         Self {
             pools: RwLock::new(HashMap::new()),
+            results: RwLock::new(HashMap::new()),
+            sessions: RwLock::new(HashMap::new()),
+            scripts: std::sync::Mutex::new(HashMap::new()),
+            metadata: MetadataCache::new(),
         }
     }
 
-    pub async fn connect(&self, config: &ConnectionConfig) -> Result<String, String> {
+    pub async fn connect(&self, app: &AppHandle, config: &ConnectionConfig) -> Result<String, String> {
         let connection_id = config.id.clone();
+        redis_connection::check_tunnel(config)?;
+        config.check_tunnel_tls()?;
 
         // SQLite has no network connection so SSH tunneling does not apply.
         // For other database types, if an SSH tunnel is configured, establish
@@ -188,6 +341,8 @@ impl ConnectionManager {
             (config.host.clone(), config.port, None)
         } else if let Some(ssh_cfg) = &config.ssh_tunnel {
             let handle = ssh_tunnel::establish_tunnel(
+                app,
+                &connection_id,
                 ssh_cfg,
                 config.host.clone(),
                 config.port,
@@ -202,75 +357,21 @@ impl ConnectionManager {
             (config.host.clone(), config.port, None)
         };
 
-        let pool = match config.db_type {
-            DatabaseType::Postgres => {
-                let ssl_param = match config.ssl_mode {
-                    SslMode::Disable => "sslmode=disable",
-                    SslMode::Prefer => "sslmode=prefer",
-                    SslMode::Require => "sslmode=require",
-                };
-                let url = format!(
-                    "postgres://{}:{}@{}:{}/{}?{}",
-                    config.username,
-                    config.password,
-                    effective_host,
-                    effective_port,
-                    config.database.as_deref().unwrap_or("postgres"),
-                    ssl_param,
-                );
-                let pool = PoolOptions::new()
-                    .acquire_timeout(CONNECT_TIMEOUT)
-                    .connect(&url)
-                    .await
-                    .map_err(|e| format!("Failed to connect to PostgreSQL: {}", e))?;
-                ConnectionPool::Postgres(pool)
-            }
-            DatabaseType::Mysql => {
-                let ssl_param = match config.ssl_mode {
-                    SslMode::Disable => "ssl-mode=DISABLED",
-                    SslMode::Prefer => "ssl-mode=PREFERRED",
-                    SslMode::Require => "ssl-mode=REQUIRED",
-                };
-                let url = format!(
-                    "mysql://{}:{}@{}:{}/{}?{}",
-                    config.username,
-                    config.password,
-                    effective_host,
-                    effective_port,
-                    config.database.as_deref().unwrap_or("mysql"),
-                    ssl_param,
-                );
-                let pool = PoolOptions::new()
-                    .acquire_timeout(CONNECT_TIMEOUT)
-                    .connect(&url)
-                    .await
-                    .map_err(|e| format!("Failed to connect to MySQL: {}", e))?;
-                ConnectionPool::Mysql(pool)
-            }
-            DatabaseType::Sqlite => {
-                // For SQLite, host field contains the file path
-                let url = format!("sqlite:{}", config.host);
-                let pool = PoolOptions::new()
-                    .acquire_timeout(CONNECT_TIMEOUT)
-                    .connect(&url)
-                    .await
-                    .map_err(|e| format!("Failed to connect to SQLite: {}", e))?;
-                ConnectionPool::Sqlite(pool)
-            }
-            DatabaseType::Redis => {
-                let manager = redis_db::connect(
-                    &effective_host,
-                    effective_port,
-                    &config.username,
-                    &config.password,
-                )
-                .await?;
-                ConnectionPool::Redis(manager)
-            }
+        let driver: Arc<dyn DatabaseDriver> = match config.db_type {
+            DatabaseType::Postgres => Arc::new(
+                postgres::PostgresDriver::connect(config, &effective_host, effective_port).await?,
+            ),
+            DatabaseType::Mysql => Arc::new(
+                mysql::MysqlDriver::connect(config, &effective_host, effective_port).await?,
+            ),
+            DatabaseType::Sqlite => Arc::new(sqlite::SqliteDriver::connect(config).await?),
+            DatabaseType::Redis => Arc::new(
+                redis_db::RedisDriver::connect(config, &effective_host, effective_port).await?,
+            ),
         };
 
         let active = ActiveConnection {
-            pool: Arc::new(pool),
+            driver,
             _tunnel: tunnel,
         };
         let mut pools = self.pools.write().await;
@@ -278,7 +379,33 @@ impl ConnectionManager {
         Ok(connection_id)
     }
 
-    pub async fn disconnect(&self, connection_id: &str) -> Result<(), String> {
+    /// Closes the connection and drops its cached metadata. Open transactions
+    /// on it are rolled back, but only with `force`; otherwise this fails so
+    /// the UI can ask first.
+    pub async fn disconnect(&self, connection_id: &str, force: bool) -> Result<(), String> {
+        self.close_connection(connection_id, force).await?;
+        self.metadata.invalidate(connection_id, None, None).await;
+        Ok(())
+    }
+
+    /// Closes the connection but keeps its cached metadata, which is keyed by
+    /// database and so stays valid when reconnecting to another one.
+    async fn close_connection(&self, connection_id: &str, force: bool) -> Result<(), String> {
+        let open = self.open_transaction_count(connection_id).await;
+        if open > 0 && !force {
+            return Err(format!(
+                "{} open transaction(s) on this connection would be rolled back",
+                open
+            ));
+        }
+        self.discard_sessions(connection_id).await;
+
+        // Drop open result sessions first so their pinned connections are released.
+        self.results
+            .write()
+            .await
+            .retain(|_, session| session.connection_id != connection_id);
+
         let mut pools = self.pools.write().await;
         if pools.remove(connection_id).is_none() {
             return Err(format!("Connection '{}' not found", connection_id));
@@ -286,69 +413,58 @@ impl ConnectionManager {
         Ok(())
     }
 
-    pub async fn switch_database(&self, config: &ConnectionConfig, database: &str) -> Result<(), String> {
-        // For Redis, switch database using SELECT command instead of reconnecting
-        if matches!(config.db_type, DatabaseType::Redis) {
-            let pool = self.get_pool(&config.id).await?;
-            if let ConnectionPool::Redis(c) = pool.as_ref() {
-                return redis_db::switch_database(&mut c.clone(), database).await;
+    pub async fn switch_database(
+        &self,
+        app: &AppHandle,
+        config: &ConnectionConfig,
+        database: &str,
+    ) -> Result<(), String> {
+        // Engines like Redis switch on the live connection (SELECT) instead of reconnecting
+        if let Ok(driver) = self.get_driver(&config.id).await {
+            if driver.capabilities().switches_database_in_place {
+                return driver.switch_database(database).await;
             }
         }
 
         // For SQL databases, disconnect and reconnect with new database
-        let _ = self.disconnect(&config.id).await;
+        if self.open_transaction_count(&config.id).await > 0 {
+            return Err(
+                "Commit or roll back open transactions before switching databases".to_string(),
+            );
+        }
+        let _ = self.close_connection(&config.id, false).await;
 
         // Create new config with the specified database
         let mut new_config = config.clone();
         new_config.database = Some(database.to_string());
 
         // Connect to the new database
-        self.connect(&new_config).await?;
+        self.connect(app, &new_config).await?;
         Ok(())
     }
 
-    pub async fn get_pool(&self, connection_id: &str) -> Result<Arc<ConnectionPool>, String> {
+    pub async fn get_driver(&self, connection_id: &str) -> Result<Arc<dyn DatabaseDriver>, String> {
         let pools = self.pools.read().await;
         pools
             .get(connection_id)
-            .map(|a| a.pool.clone())
+            .map(|a| a.driver.clone())
             .ok_or_else(|| format!("Connection '{}' not found or not connected", connection_id))
     }
 
+    pub async fn get_capabilities(&self, connection_id: &str) -> Result<DriverCapabilities, String> {
+        Ok(self.get_driver(connection_id).await?.capabilities())
+    }
+
     pub async fn create_database(&self, connection_id: &str, name: &str) -> Result<(), String> {
-        let pool = self.get_pool(connection_id).await?;
-        match pool.as_ref() {
-            ConnectionPool::Postgres(p) => postgres::create_database(p, name).await,
-            ConnectionPool::Mysql(p) => mysql::create_database(p, name).await,
-            ConnectionPool::Sqlite(_) => Err("SQLite does not support CREATE DATABASE".to_string()),
-            ConnectionPool::Redis(_) => Err("Redis does not support CREATE DATABASE".to_string()),
-        }
+        self.get_driver(connection_id).await?.create_database(name).await
     }
 
     pub async fn create_schema(&self, connection_id: &str, name: &str) -> Result<(), String> {
-        let pool = self.get_pool(connection_id).await?;
-        match pool.as_ref() {
-            ConnectionPool::Postgres(p) => postgres::create_schema(p, name).await,
-            ConnectionPool::Mysql(_) => {
-                Err("MySQL does not support CREATE SCHEMA separately from CREATE DATABASE".to_string())
-            }
-            ConnectionPool::Sqlite(_) => {
-                Err("SQLite does not support CREATE SCHEMA".to_string())
-            }
-            ConnectionPool::Redis(_) => {
-                Err("Redis does not support CREATE SCHEMA".to_string())
-            }
-        }
+        self.get_driver(connection_id).await?.create_schema(name).await
     }
 
     pub async fn list_databases(&self, connection_id: &str) -> Result<Vec<String>, String> {
-        let pool = self.get_pool(connection_id).await?;
-        match pool.as_ref() {
-            ConnectionPool::Postgres(p) => postgres::list_databases(p).await,
-            ConnectionPool::Mysql(p) => mysql::list_databases(p).await,
-            ConnectionPool::Sqlite(p) => sqlite::list_databases(p).await,
-            ConnectionPool::Redis(c) => redis_db::list_databases(&mut c.clone()).await,
-        }
+        self.get_driver(connection_id).await?.list_databases().await
     }
 
     pub async fn list_schemas(
@@ -356,13 +472,42 @@ impl ConnectionManager {
         connection_id: &str,
         database: &str,
     ) -> Result<Vec<String>, String> {
-        let pool = self.get_pool(connection_id).await?;
-        match pool.as_ref() {
-            ConnectionPool::Postgres(p) => postgres::list_schemas(p, database).await,
-            ConnectionPool::Mysql(p) => mysql::list_schemas(p, database).await,
-            ConnectionPool::Sqlite(p) => sqlite::list_schemas(p, database).await,
-            ConnectionPool::Redis(c) => redis_db::list_schemas(&mut c.clone(), database).await,
+        self.get_driver(connection_id).await?.list_schemas(database).await
+    }
+
+    /// Schema metadata through the cache, loading it from the driver on a
+    /// miss or once it has expired.
+    async fn cached_metadata(
+        &self,
+        connection_id: &str,
+        database: &str,
+        schema: &str,
+        object: MetadataObject,
+    ) -> Result<Metadata, String> {
+        let driver = self.get_driver(connection_id).await?;
+        // Redis namespaces change with every key written or expired, here or
+        // by other clients, so they are listed fresh rather than cached.
+        if object == MetadataObject::Tables && driver.as_redis().is_some() {
+            return driver
+                .list_tables(database, schema)
+                .await
+                .map(Metadata::Names);
         }
+        let key = MetadataKey::new(connection_id, database, schema, object);
+        self.metadata.get_or_load(driver.as_ref(), key).await
+    }
+
+    /// Drops cached metadata so the next listing reads the server again:
+    /// for one schema, one database, or the whole connection.
+    pub async fn refresh_metadata(
+        &self,
+        connection_id: &str,
+        database: Option<&str>,
+        schema: Option<&str>,
+    ) {
+        self.metadata
+            .invalidate(connection_id, database, schema)
+            .await;
     }
 
     pub async fn list_tables(
@@ -371,13 +516,9 @@ impl ConnectionManager {
         database: &str,
         schema: &str,
     ) -> Result<Vec<String>, String> {
-        let pool = self.get_pool(connection_id).await?;
-        match pool.as_ref() {
-            ConnectionPool::Postgres(p) => postgres::list_tables(p, database, schema).await,
-            ConnectionPool::Mysql(p) => mysql::list_tables(p, database, schema).await,
-            ConnectionPool::Sqlite(p) => sqlite::list_tables(p, database, schema).await,
-            ConnectionPool::Redis(c) => redis_db::list_tables(&mut c.clone(), database, schema).await,
-        }
+        self.cached_metadata(connection_id, database, schema, MetadataObject::Tables)
+            .await
+            .map(Metadata::into_names)
     }
 
     pub async fn list_views(
@@ -386,13 +527,9 @@ impl ConnectionManager {
         database: &str,
         schema: &str,
     ) -> Result<Vec<String>, String> {
-        let pool = self.get_pool(connection_id).await?;
-        match pool.as_ref() {
-            ConnectionPool::Postgres(p) => postgres::list_views(p, database, schema).await,
-            ConnectionPool::Mysql(p) => mysql::list_views(p, database, schema).await,
-            ConnectionPool::Sqlite(p) => sqlite::list_views(p, database, schema).await,
-            ConnectionPool::Redis(c) => redis_db::list_views(&mut c.clone(), database, schema).await,
-        }
+        self.cached_metadata(connection_id, database, schema, MetadataObject::Views)
+            .await
+            .map(Metadata::into_names)
     }
 
     pub async fn list_functions(
@@ -401,13 +538,9 @@ impl ConnectionManager {
         database: &str,
         schema: &str,
     ) -> Result<Vec<String>, String> {
-        let pool = self.get_pool(connection_id).await?;
-        match pool.as_ref() {
-            ConnectionPool::Postgres(p) => postgres::list_functions(p, database, schema).await,
-            ConnectionPool::Mysql(p) => mysql::list_functions(p, database, schema).await,
-            ConnectionPool::Sqlite(p) => sqlite::list_functions(p, database, schema).await,
-            ConnectionPool::Redis(c) => redis_db::list_functions(&mut c.clone(), database, schema).await,
-        }
+        self.cached_metadata(connection_id, database, schema, MetadataObject::Functions)
+            .await
+            .map(Metadata::into_names)
     }
 
     pub async fn list_materialized_views(
@@ -416,13 +549,14 @@ impl ConnectionManager {
         database: &str,
         schema: &str,
     ) -> Result<Vec<String>, String> {
-        let pool = self.get_pool(connection_id).await?;
-        match pool.as_ref() {
-            ConnectionPool::Postgres(p) => postgres::list_materialized_views(p, database, schema).await,
-            ConnectionPool::Mysql(p) => mysql::list_materialized_views(p, database, schema).await,
-            ConnectionPool::Sqlite(p) => sqlite::list_materialized_views(p, database, schema).await,
-            ConnectionPool::Redis(c) => redis_db::list_materialized_views(&mut c.clone(), database, schema).await,
-        }
+        self.cached_metadata(
+            connection_id,
+            database,
+            schema,
+            MetadataObject::MaterializedViews,
+        )
+        .await
+        .map(Metadata::into_names)
     }
 
     pub async fn list_sequences(
@@ -431,13 +565,9 @@ impl ConnectionManager {
         database: &str,
         schema: &str,
     ) -> Result<Vec<String>, String> {
-        let pool = self.get_pool(connection_id).await?;
-        match pool.as_ref() {
-            ConnectionPool::Postgres(p) => postgres::list_sequences(p, database, schema).await,
-            ConnectionPool::Mysql(p) => mysql::list_sequences(p, database, schema).await,
-            ConnectionPool::Sqlite(p) => sqlite::list_sequences(p, database, schema).await,
-            ConnectionPool::Redis(c) => redis_db::list_sequences(&mut c.clone(), database, schema).await,
-        }
+        self.cached_metadata(connection_id, database, schema, MetadataObject::Sequences)
+            .await
+            .map(Metadata::into_names)
     }
 
     pub async fn list_triggers(
@@ -446,13 +576,9 @@ impl ConnectionManager {
         database: &str,
         schema: &str,
     ) -> Result<Vec<String>, String> {
-        let pool = self.get_pool(connection_id).await?;
-        match pool.as_ref() {
-            ConnectionPool::Postgres(p) => postgres::list_triggers(p, database, schema).await,
-            ConnectionPool::Mysql(p) => mysql::list_triggers(p, database, schema).await,
-            ConnectionPool::Sqlite(p) => sqlite::list_triggers(p, database, schema).await,
-            ConnectionPool::Redis(c) => redis_db::list_triggers(&mut c.clone(), database, schema).await,
-        }
+        self.cached_metadata(connection_id, database, schema, MetadataObject::Triggers)
+            .await
+            .map(Metadata::into_names)
     }
 
     pub async fn list_procedures(
@@ -461,13 +587,9 @@ impl ConnectionManager {
         database: &str,
         schema: &str,
     ) -> Result<Vec<String>, String> {
-        let pool = self.get_pool(connection_id).await?;
-        match pool.as_ref() {
-            ConnectionPool::Postgres(p) => postgres::list_procedures(p, database, schema).await,
-            ConnectionPool::Mysql(p) => mysql::list_procedures(p, database, schema).await,
-            ConnectionPool::Sqlite(p) => sqlite::list_procedures(p, database, schema).await,
-            ConnectionPool::Redis(c) => redis_db::list_procedures(&mut c.clone(), database, schema).await,
-        }
+        self.cached_metadata(connection_id, database, schema, MetadataObject::Procedures)
+            .await
+            .map(Metadata::into_names)
     }
 
     pub async fn get_function_definition(
@@ -477,21 +599,10 @@ impl ConnectionManager {
         schema: &str,
         function_name: &str,
     ) -> Result<FunctionInfo, String> {
-        let pool = self.get_pool(connection_id).await?;
-        match pool.as_ref() {
-            ConnectionPool::Postgres(p) => {
-                postgres::get_function_definition(p, database, schema, function_name).await
-            }
-            ConnectionPool::Mysql(p) => {
-                mysql::get_function_definition(p, database, schema, function_name).await
-            }
-            ConnectionPool::Sqlite(p) => {
-                sqlite::get_function_definition(p, database, schema, function_name).await
-            }
-            ConnectionPool::Redis(c) => {
-                redis_db::get_function_definition(&mut c.clone(), database, schema, function_name).await
-            }
-        }
+        self.get_driver(connection_id)
+            .await?
+            .get_function_definition(database, schema, function_name)
+            .await
     }
 
     pub async fn get_view_dependencies(
@@ -500,13 +611,10 @@ impl ConnectionManager {
         database: &str,
         schema: &str,
     ) -> Result<Vec<ViewDependency>, String> {
-        let pool = self.get_pool(connection_id).await?;
-        match pool.as_ref() {
-            ConnectionPool::Postgres(p) => {
-                postgres::get_view_dependencies(p, database, schema).await
-            }
-            _ => Ok(vec![]),
-        }
+        self.get_driver(connection_id)
+            .await?
+            .get_view_dependencies(database, schema)
+            .await
     }
 
     pub async fn get_view_definition(
@@ -516,77 +624,30 @@ impl ConnectionManager {
         schema: &str,
         view_name: &str,
     ) -> Result<FunctionInfo, String> {
-        let pool = self.get_pool(connection_id).await?;
-        match pool.as_ref() {
-            ConnectionPool::Postgres(p) => {
-                postgres::get_view_definition(p, database, schema, view_name).await
-            }
-            ConnectionPool::Mysql(p) => {
-                mysql::get_view_definition(p, database, schema, view_name).await
-            }
-            ConnectionPool::Sqlite(p) => {
-                sqlite::get_view_definition(p, database, schema, view_name).await
-            }
-            ConnectionPool::Redis(c) => {
-                redis_db::get_view_definition(&mut c.clone(), database, schema, view_name).await
-            }
-        }
+        self.get_driver(connection_id)
+            .await?
+            .get_view_definition(database, schema, view_name)
+            .await
     }
 
-    pub async fn cancel_queries(&self, connection_id: &str) -> Result<u64, String> {
-        let pool = self.get_pool(connection_id).await?;
-        match pool.as_ref() {
-            ConnectionPool::Postgres(p) => {
-                // Cancel all active queries from this application's connections
-                let row = sqlx::query(
-                    "SELECT count(*) as cnt FROM (
-                        SELECT pg_cancel_backend(pid)
-                        FROM pg_stat_activity
-                        WHERE pid != pg_backend_pid()
-                          AND leader_pid IS NULL
-                          AND state = 'active'
-                          AND query NOT LIKE '%pg_cancel_backend%'
-                    ) t"
-                )
-                .fetch_one(p)
-                .await
-                .map_err(|e| format!("Failed to cancel queries: {}", e))?;
-                let count: i64 = sqlx::Row::get(&row, "cnt");
-                Ok(count as u64)
+    pub async fn cancel_query(&self, connection_id: &str, query_id: &str) -> Result<bool, String> {
+        let script_running = match self.scripts.lock().unwrap().get_mut(query_id) {
+            Some(cancelled) => {
+                *cancelled = true;
+                true
             }
-            ConnectionPool::Mysql(p) => {
-                // Kill active queries from this connection's user
-                let rows = sqlx::query(
-                    "SELECT id FROM information_schema.processlist WHERE id != CONNECTION_ID() AND command != 'Sleep'"
-                )
-                .fetch_all(p)
-                .await
-                .map_err(|e| format!("Failed to list queries: {}", e))?;
-                let mut killed = 0u64;
-                for row in &rows {
-                    let id: u64 = sqlx::Row::get(row, "id");
-                    if sqlx::query(&format!("KILL QUERY {}", id))
-                        .execute(p)
-                        .await
-                        .is_ok()
-                    {
-                        killed += 1;
-                    }
-                }
-                Ok(killed)
-            }
-            _ => Ok(0),
-        }
+            None => false,
+        };
+        let statement_cancelled = self
+            .get_driver(connection_id)
+            .await?
+            .cancel_query(query_id)
+            .await?;
+        Ok(script_running || statement_cancelled)
     }
 
     pub async fn list_users(&self, connection_id: &str) -> Result<Vec<DatabaseUser>, String> {
-        let pool = self.get_pool(connection_id).await?;
-        match pool.as_ref() {
-            ConnectionPool::Postgres(p) => postgres::list_users(p).await,
-            ConnectionPool::Mysql(p) => mysql::list_users(p).await,
-            ConnectionPool::Sqlite(_) => Err("SQLite does not have a user management system".to_string()),
-            ConnectionPool::Redis(_) => Err("Redis user management is not yet supported".to_string()),
-        }
+        self.get_driver(connection_id).await?.list_users().await
     }
 
     pub async fn get_user_grants(
@@ -595,13 +656,10 @@ impl ConnectionManager {
         username: &str,
         host: Option<&str>,
     ) -> Result<Vec<UserGrant>, String> {
-        let pool = self.get_pool(connection_id).await?;
-        match pool.as_ref() {
-            ConnectionPool::Postgres(p) => postgres::get_user_grants(p, username).await,
-            ConnectionPool::Mysql(p) => mysql::get_user_grants(p, username, host).await,
-            ConnectionPool::Sqlite(_) => Err("SQLite does not have a user management system".to_string()),
-            ConnectionPool::Redis(_) => Err("Redis user management is not yet supported".to_string()),
-        }
+        self.get_driver(connection_id)
+            .await?
+            .get_user_grants(username, host)
+            .await
     }
 
     pub async fn list_columns(
@@ -611,13 +669,10 @@ impl ConnectionManager {
         schema: &str,
         table: &str,
     ) -> Result<Vec<ColumnInfo>, String> {
-        let pool = self.get_pool(connection_id).await?;
-        match pool.as_ref() {
-            ConnectionPool::Postgres(p) => postgres::list_columns(p, database, schema, table).await,
-            ConnectionPool::Mysql(p) => mysql::list_columns(p, database, schema, table).await,
-            ConnectionPool::Sqlite(p) => sqlite::list_columns(p, database, schema, table).await,
-            ConnectionPool::Redis(c) => redis_db::list_columns(&mut c.clone(), database, schema, table).await,
-        }
+        let object = MetadataObject::Columns(table.to_string());
+        self.cached_metadata(connection_id, database, schema, object)
+            .await
+            .map(Metadata::into_columns)
     }
 
     pub async fn list_indexes(
@@ -627,13 +682,10 @@ impl ConnectionManager {
         schema: &str,
         table: &str,
     ) -> Result<Vec<IndexInfo>, String> {
-        let pool = self.get_pool(connection_id).await?;
-        match pool.as_ref() {
-            ConnectionPool::Postgres(p) => postgres::list_indexes(p, database, schema, table).await,
-            ConnectionPool::Mysql(p) => mysql::list_indexes(p, database, schema, table).await,
-            ConnectionPool::Sqlite(p) => sqlite::list_indexes(p, database, schema, table).await,
-            ConnectionPool::Redis(c) => redis_db::list_indexes(&mut c.clone(), database, schema, table).await,
-        }
+        let object = MetadataObject::Indexes(table.to_string());
+        self.cached_metadata(connection_id, database, schema, object)
+            .await
+            .map(Metadata::into_indexes)
     }
 
     pub async fn list_constraints(
@@ -643,57 +695,644 @@ impl ConnectionManager {
         schema: &str,
         table: &str,
     ) -> Result<Vec<ConstraintInfo>, String> {
-        let pool = self.get_pool(connection_id).await?;
-        match pool.as_ref() {
-            ConnectionPool::Postgres(p) => {
-                postgres::list_constraints(p, database, schema, table).await
+        let object = MetadataObject::Constraints(table.to_string());
+        self.cached_metadata(connection_id, database, schema, object)
+            .await
+            .map(Metadata::into_constraints)
+    }
+
+    /// Applies row edits to one or more tables in a single transaction, so an
+    /// edit to a join result saves all of its tables or none. Each table's key
+    /// is checked against its primary and unique keys first, so nothing starts
+    /// when a statement could match more than one row.
+    ///
+    /// When the tab `session_id` has a transaction open, the edits run inside
+    /// it under a savepoint: they commit or roll back with it, and a failed
+    /// edit leaves the transaction as it was.
+    pub async fn apply_row_changes(
+        &self,
+        app: &AppHandle,
+        connection_id: &str,
+        session_id: Option<&str>,
+        tables: Vec<TableChanges>,
+    ) -> Result<Vec<AppliedRowChanges>, String> {
+        let driver = self.get_driver(connection_id).await?;
+        let mut edits = Vec::with_capacity(tables.len());
+        for target in tables {
+            let (database, schema, table) = (&target.database, &target.schema, &target.table);
+            let columns = driver.list_columns(database, schema, table).await?;
+            if columns.is_empty() {
+                return Err(format!("Table {} not found", table));
+            }
+            let constraints = driver.list_constraints(database, schema, table).await?;
+            let key = row_changes::choose_key(table, &columns, &constraints, &target.key_columns)?;
+            edits.push(TableEdit {
+                target,
+                columns,
+                key,
+            });
+        }
+
+        let Some(pinned) = self.pinned_session(session_id).await else {
+            return driver.apply_row_changes(&edits).await;
+        };
+        let mut session = pinned.session.lock().await;
+        let query_id = uuid::Uuid::new_v4().to_string();
+        session
+            .execute_query(app, &query_id, "SAVEPOINT dbui_row_changes", 0)
+            .await?;
+        let applied = session.apply_row_changes(&edits).await;
+        let end = match applied {
+            Ok(_) => "RELEASE SAVEPOINT dbui_row_changes",
+            Err(_) => "ROLLBACK TO SAVEPOINT dbui_row_changes",
+        };
+        session.execute_query(app, &query_id, end, 0).await?;
+        applied
+    }
+
+    /// Captures the plan for one statement; see [`DatabaseDriver::explain_query`].
+    pub async fn explain_query(
+        &self,
+        connection_id: &str,
+        query: &str,
+        analyze: bool,
+    ) -> Result<QueryPlan, String> {
+        let query = query.trim().trim_end_matches(';').trim_end();
+        if query.is_empty() {
+            return Err("Nothing to explain".to_string());
+        }
+        self.get_driver(connection_id)
+            .await?
+            .explain_query(query, analyze)
+            .await
+    }
+
+    /// Traces the result columns of `query` to the tables they read, using
+    /// each table's live columns to expand `*` and place unqualified names.
+    /// Returns None for queries that aren't a single SELECT.
+    pub async fn analyze_query_columns(
+        &self,
+        connection_id: &str,
+        database: &str,
+        schema: &str,
+        query: &str,
+        db_type: &str,
+    ) -> Result<Option<QueryColumns>, String> {
+        let Some(analysis) = sql_analyzer::analyze_select(query, db_type) else {
+            return Ok(None);
+        };
+        let driver = self.get_driver(connection_id).await?;
+        let supports_schemas = driver.capabilities().supports_schemas;
+
+        let mut tables = Vec::new();
+        let mut sources = Vec::new();
+        for info in &analysis.tables {
+            let (database, schema) =
+                qualified_location(database, schema, info.schema.as_deref(), supports_schemas);
+            // A table that can't be listed just leaves its columns untraced.
+            let object = MetadataObject::Columns(info.table.clone());
+            let columns = self
+                .cached_metadata(connection_id, &database, &schema, object)
+                .await
+                .map(Metadata::into_columns)
+                .unwrap_or_default();
+            let object = MetadataObject::Constraints(info.table.clone());
+            let constraints = self
+                .cached_metadata(connection_id, &database, &schema, object)
+                .await
+                .map(Metadata::into_constraints)
+                .unwrap_or_default();
+            sources.push(SourceTable {
+                keys: row_changes::candidate_keys(&columns, &constraints),
+                columns,
+            });
+            tables.push(QueryColumnTable {
+                database,
+                schema,
+                table: info.table.clone(),
+                key_columns: vec![],
+            });
+        }
+
+        let Some(resolved) = analysis.resolve(&sources) else {
+            return Ok(None);
+        };
+        for (table, key) in tables.iter_mut().zip(resolved.keys) {
+            table.key_columns = key;
+        }
+        Ok(Some(QueryColumns {
+            tables,
+            columns: resolved.columns,
+        }))
+    }
+
+    /// Completion suggestions for the editor at `cursor_offset` (UTF-16
+    /// units) in `text`. Names come from the metadata cache; a catalog that
+    /// can't be listed just contributes no suggestions.
+    pub async fn complete_sql(
+        &self,
+        connection_id: &str,
+        database: &str,
+        schema: &str,
+        text: &str,
+        cursor_offset: usize,
+    ) -> Result<Completion, String> {
+        let driver = self.get_driver(connection_id).await?;
+        let capabilities = driver.capabilities();
+        let db_type = sql_dialect(capabilities.engine);
+        let cursor = sql_completion::utf16_to_byte(text, cursor_offset);
+        let context = sql_completion::analyze(text, cursor, db_type);
+
+        let load = |database: &str, schema: &str, object: MetadataObject| {
+            let key = MetadataKey::new(connection_id, database, schema, object);
+            let driver = driver.clone();
+            async move {
+                self.metadata
+                    .get_or_load(driver.as_ref(), key)
+                    .await
+                    .unwrap_or(Metadata::Names(vec![]))
             }
-            ConnectionPool::Mysql(p) => mysql::list_constraints(p, database, schema, table).await,
-            ConnectionPool::Sqlite(p) => sqlite::list_constraints(p, database, schema, table).await,
-            ConnectionPool::Redis(c) => redis_db::list_constraints(&mut c.clone(), database, schema, table).await,
+        };
+        let mut catalog = CompletionCatalog::default();
+
+        // After `x.`, x is either a table in scope or a schema to list.
+        let (scope, listing_schema): (Vec<_>, Option<&str>) = match &context.clause {
+            Clause::Qualified(qualifier) => {
+                let scope: Vec<_> = context
+                    .scope
+                    .iter()
+                    .filter(|t| t.matches(qualifier))
+                    .collect();
+                let listing = scope.is_empty().then_some(qualifier.as_str());
+                (scope, listing)
+            }
+            Clause::Expression => (context.scope.iter().collect(), None),
+            _ => (vec![], None),
+        };
+        for table in scope {
+            let (database, schema) = qualified_location(
+                database,
+                schema,
+                table.schema.as_deref(),
+                capabilities.supports_schemas,
+            );
+            let object = MetadataObject::Columns(table.table.clone());
+            let columns = load(&database, &schema, object).await.into_columns();
+            catalog.columns.push((table.label().to_string(), columns));
+        }
+
+        let names_at = match (&context.clause, listing_schema) {
+            (Clause::Table, _) => Some((database.to_string(), schema.to_string())),
+            (_, Some(qualifier)) => Some(qualified_location(
+                database,
+                schema,
+                Some(qualifier),
+                capabilities.supports_schemas,
+            )),
+            _ => None,
+        };
+        if let Some((database, schema)) = names_at {
+            catalog.tables = load(&database, &schema, MetadataObject::Tables)
+                .await
+                .into_names();
+            catalog.views = load(&database, &schema, MetadataObject::Views)
+                .await
+                .into_names();
+        }
+        if context.clause == Clause::Expression && capabilities.supports_functions {
+            catalog.functions = load(database, schema, MetadataObject::Functions)
+                .await
+                .into_names();
         }
+
+        Ok(Completion {
+            from: sql_completion::byte_to_utf16(text, context.prefix_start),
+            suggestions: sql_completion::suggest(&context, &catalog, db_type),
+        })
     }
 
+    pub async fn redis_list_databases(
+        &self,
+        connection_id: &str,
+    ) -> Result<Vec<RedisDatabaseInfo>, String> {
+        let driver = self.get_driver(connection_id).await?;
+        redis_driver(driver.as_ref())?.list_database_info().await
+    }
+
+    pub async fn redis_list_namespaces(
+        &self,
+        connection_id: &str,
+        database: &str,
+    ) -> Result<Vec<RedisNamespace>, String> {
+        let driver = self.get_driver(connection_id).await?;
+        redis_driver(driver.as_ref())?.list_namespaces(database).await
+    }
+
+    pub async fn redis_scan_namespace(
+        &self,
+        connection_id: &str,
+        database: &str,
+        namespace: &str,
+        cursor: &str,
+        count: usize,
+    ) -> Result<RedisKeyPage, String> {
+        let driver = self.get_driver(connection_id).await?;
+        redis_driver(driver.as_ref())?
+            .scan_namespace(database, namespace, cursor, count)
+            .await
+    }
+
+    pub async fn redis_get_key(
+        &self,
+        connection_id: &str,
+        database: &str,
+        key: &RedisBytes,
+        cursor: Option<&str>,
+        count: usize,
+    ) -> Result<RedisKeyValue, String> {
+        let driver = self.get_driver(connection_id).await?;
+        redis_driver(driver.as_ref())?
+            .get_key(database, key, cursor, count)
+            .await
+    }
+
+    pub async fn redis_edit_key(
+        &self,
+        connection_id: &str,
+        database: &str,
+        key: &RedisBytes,
+        edit: RedisEdit,
+    ) -> Result<(), String> {
+        let driver = self.get_driver(connection_id).await?;
+        redis_driver(driver.as_ref())?.edit_key(database, key, edit).await
+    }
+
+    pub async fn redis_delete_members(
+        &self,
+        connection_id: &str,
+        database: &str,
+        key: &RedisBytes,
+        members: Vec<RedisBytes>,
+    ) -> Result<u64, String> {
+        let driver = self.get_driver(connection_id).await?;
+        redis_driver(driver.as_ref())?
+            .delete_members(database, key, members)
+            .await
+    }
+
+    /// Runs `query` as a script: each statement executes in order and gets
+    /// its own result or error. All statements share `query_id`, so progress
+    /// events and cancellation follow whichever statement is running.
+    /// With a `session_id`, a `BEGIN` pins a connection for that session and
+    /// later statements run on it until `COMMIT` or `ROLLBACK`.
+    #[allow(clippy::too_many_arguments)]
     pub async fn execute_query(
         &self,
         app: &AppHandle,
         query_id: &str,
         connection_id: &str,
+        session_id: Option<&str>,
         query: &str,
         database: Option<&str>,
-    ) -> Result<QueryResult, String> {
-        let pool = self.get_pool(connection_id).await?;
-        match pool.as_ref() {
-            ConnectionPool::Postgres(p) => execute_query_pg(app, query_id, p, query).await,
-            ConnectionPool::Mysql(p) => execute_query_mysql(app, query_id, p, query).await,
-            ConnectionPool::Sqlite(p) => execute_query_sqlite(app, query_id, p, query).await,
-            ConnectionPool::Redis(c) => {
-                let mut conn = c.clone();
-                if let Some(db) = database {
-                    redis_db::switch_database(&mut conn, db).await?;
+        max_rows: Option<usize>,
+        on_error: OnError,
+    ) -> Result<Vec<StatementResult>, String> {
+        let driver = self.get_driver(connection_id).await?;
+        let dialect = sql_dialect(driver.capabilities().engine);
+        let max_rows = max_rows.unwrap_or(DEFAULT_MAX_ROWS);
+        let mut statements = driver.split_script(query);
+        if statements.is_empty() {
+            statements.push(query.to_string());
+        }
+
+        self.scripts
+            .lock()
+            .unwrap()
+            .insert(query_id.to_string(), false);
+        let mut results = Vec::with_capacity(statements.len());
+        for statement in statements {
+            if self.scripts.lock().unwrap().get(query_id) == Some(&true) {
+                break;
+            }
+            let start = Instant::now();
+            let outcome = match session_id {
+                Some(session_id) => {
+                    self.execute_in_session(
+                        app,
+                        query_id,
+                        &driver,
+                        connection_id,
+                        session_id,
+                        &statement,
+                        max_rows,
+                    )
+                    .await
                 }
-                let start = Instant::now();
-                emit_progress(app, query_id, "executing", 0, 0, None, None, None);
-                let result = redis_db::execute_query(&mut conn, query).await?;
-                let total_ms = start.elapsed().as_millis() as u64;
-                emit_progress(
-                    app,
-                    query_id,
-                    "done",
-                    result.row_count,
-                    total_ms,
-                    Some(total_ms),
-                    Some(0),
-                    None,
+                None => {
+                    driver
+                        .execute_query(app, query_id, &statement, database, max_rows)
+                        .await
+                }
+            };
+            let elapsed_ms = start.elapsed().as_millis() as u64;
+            // Checked whatever the outcome: a failed or cancelled statement
+            // may still have changed the schema.
+            if sql_analyzer::changes_schema(&statement, dialect) {
+                self.metadata.invalidate(connection_id, None, None).await;
+                if let Some(pinned) = self.pinned_session(session_id).await {
+                    pinned.changed_schema.store(true, Ordering::Relaxed);
+                }
+            }
+            let failed = outcome.is_err();
+            let (result, error) = match outcome {
+                Ok(result) => (Some(result), None),
+                Err(e) => (None, Some(e)),
+            };
+            results.push(StatementResult {
+                statement,
+                result,
+                error,
+                elapsed_ms,
+            });
+            if failed && on_error == OnError::Stop {
+                break;
+            }
+        }
+        self.scripts.lock().unwrap().remove(query_id);
+
+        Ok(results)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_in_session(
+        &self,
+        app: &AppHandle,
+        query_id: &str,
+        driver: &Arc<dyn DatabaseDriver>,
+        connection_id: &str,
+        session_id: &str,
+        statement: &str,
+        max_rows: usize,
+    ) -> Result<QueryResult, String> {
+        let control = transaction_control(statement);
+        let pinned = self.sessions.read().await.get(session_id).cloned();
+
+        let Some(pinned) = pinned else {
+            if control != Some(TransactionControl::Begin) {
+                return driver
+                    .execute_query(app, query_id, statement, None, max_rows)
+                    .await;
+            }
+            let mut session = driver.open_session().await?;
+            let result = session
+                .execute_query(app, query_id, statement, max_rows)
+                .await?;
+            self.sessions.write().await.insert(
+                session_id.to_string(),
+                Arc::new(TransactionSession {
+                    connection_id: connection_id.to_string(),
+                    started_at: Utc::now(),
+                    session: Mutex::new(session),
+                    changed_schema: AtomicBool::new(false),
+                }),
+            );
+            return Ok(result);
+        };
+
+        if pinned.connection_id != connection_id {
+            return Err(
+                "This tab has an open transaction on another connection; commit or roll it back first"
+                    .to_string(),
+            );
+        }
+        let mut session = pinned.session.lock().await;
+        let outcome = session
+            .execute_query(app, query_id, statement, max_rows)
+            .await;
+        if control == Some(TransactionControl::End) {
+            // A failed COMMIT leaves the transaction state unknown; close the
+            // connection rather than reuse it.
+            if outcome.is_err() {
+                session.discard();
+            }
+            drop(session);
+            self.sessions.write().await.remove(session_id);
+            self.ended(&pinned).await;
+        }
+        outcome
+    }
+
+    async fn pinned_session(&self, session_id: Option<&str>) -> Option<Arc<TransactionSession>> {
+        self.sessions.read().await.get(session_id?).cloned()
+    }
+
+    /// Drops metadata cached while the transaction's DDL was uncommitted.
+    async fn ended(&self, pinned: &TransactionSession) {
+        if pinned.changed_schema.load(Ordering::Relaxed) {
+            self.metadata
+                .invalidate(&pinned.connection_id, None, None)
+                .await;
+        }
+    }
+
+    pub async fn begin_transaction(
+        &self,
+        app: &AppHandle,
+        connection_id: &str,
+        session_id: &str,
+    ) -> Result<(), String> {
+        if self.sessions.read().await.contains_key(session_id) {
+            return Err("A transaction is already open in this tab".to_string());
+        }
+        let driver = self.get_driver(connection_id).await?;
+        self.execute_in_session(
+            app,
+            &uuid::Uuid::new_v4().to_string(),
+            &driver,
+            connection_id,
+            session_id,
+            "BEGIN",
+            0,
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn commit(&self, app: &AppHandle, session_id: &str) -> Result<(), String> {
+        self.end_transaction(app, session_id, "COMMIT").await
+    }
+
+    pub async fn rollback(&self, app: &AppHandle, session_id: &str) -> Result<(), String> {
+        self.end_transaction(app, session_id, "ROLLBACK").await
+    }
+
+    async fn end_transaction(
+        &self,
+        app: &AppHandle,
+        session_id: &str,
+        statement: &str,
+    ) -> Result<(), String> {
+        let pinned = self
+            .sessions
+            .write()
+            .await
+            .remove(session_id)
+            .ok_or_else(|| "No open transaction in this tab".to_string())?;
+        let mut session = pinned.session.lock().await;
+        let outcome = session
+            .execute_query(app, &uuid::Uuid::new_v4().to_string(), statement, 0)
+            .await;
+        if outcome.is_err() {
+            session.discard();
+        }
+        drop(session);
+        self.ended(&pinned).await;
+        outcome.map(|_| ())
+    }
+
+    pub async fn transaction_status(&self, session_id: &str) -> TransactionStatus {
+        match self.sessions.read().await.get(session_id) {
+            Some(pinned) => TransactionStatus {
+                active: true,
+                connection_id: Some(pinned.connection_id.clone()),
+                started_at: Some(pinned.started_at),
+            },
+            None => TransactionStatus {
+                active: false,
+                connection_id: None,
+                started_at: None,
+            },
+        }
+    }
+
+    pub async fn open_transaction_count(&self, connection_id: &str) -> usize {
+        self.sessions
+            .read()
+            .await
+            .values()
+            .filter(|pinned| pinned.connection_id == connection_id)
+            .count()
+    }
+
+    /// Called when a tab closes. An open transaction is rolled back only with
+    /// `force`; otherwise this fails so the UI can ask first.
+    pub async fn close_session(&self, session_id: &str, force: bool) -> Result<(), String> {
+        let pinned = {
+            let mut sessions = self.sessions.write().await;
+            if !sessions.contains_key(session_id) {
+                return Ok(());
+            }
+            if !force {
+                return Err(
+                    "This tab has an open transaction that would be rolled back".to_string(),
                 );
-                Ok(QueryResult {
-                    server_time_ms: Some(total_ms),
-                    transfer_time_ms: Some(0),
-                    ..result
-                })
             }
+            sessions.remove(session_id)
+        };
+        if let Some(pinned) = pinned {
+            pinned.session.lock().await.discard();
+        }
+        Ok(())
+    }
+
+    async fn discard_sessions(&self, connection_id: &str) {
+        let doomed: Vec<_> = {
+            let mut sessions = self.sessions.write().await;
+            let ids: Vec<String> = sessions
+                .iter()
+                .filter(|(_, pinned)| pinned.connection_id == connection_id)
+                .map(|(id, _)| id.clone())
+                .collect();
+            ids.iter().filter_map(|id| sessions.remove(id)).collect()
+        };
+        // Closing the connection makes the server roll the transaction back.
+        for pinned in doomed {
+            pinned.session.lock().await.discard();
         }
     }
+
+    /// Opens `query` as a paged result and returns its id for `fetch_page`.
+    pub async fn open_result(
+        &self,
+        connection_id: &str,
+        query: &str,
+        max_rows: Option<usize>,
+    ) -> Result<String, String> {
+        let cursor = self.get_driver(connection_id).await?.open_result(query).await?;
+        let result_id = uuid::Uuid::new_v4().to_string();
+        self.results.write().await.insert(
+            result_id.clone(),
+            ResultSession {
+                connection_id: connection_id.to_string(),
+                max_rows: max_rows.unwrap_or(DEFAULT_MAX_ROWS),
+                cursor: Arc::new(Mutex::new(cursor)),
+            },
+        );
+        Ok(result_id)
+    }
+
+    pub async fn fetch_page(
+        &self,
+        result_id: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<ResultPage, String> {
+        let (cursor, max_rows) = {
+            let results = self.results.read().await;
+            let session = results
+                .get(result_id)
+                .ok_or_else(|| format!("Result '{}' not found or already closed", result_id))?;
+            (session.cursor.clone(), session.max_rows)
+        };
+
+        // Never read past the session's row cap, and report when it cut the page short.
+        let end = offset.saturating_add(limit).min(max_rows);
+        let mut page = cursor
+            .lock()
+            .await
+            .fetch(offset, end.saturating_sub(offset))
+            .await?;
+        if page.has_more && end == max_rows {
+            page.has_more = false;
+            page.truncated = true;
+        }
+        Ok(page)
+    }
+
+    pub async fn close_result(&self, result_id: &str) -> Result<(), String> {
+        let session = self
+            .results
+            .write()
+            .await
+            .remove(result_id)
+            .ok_or_else(|| format!("Result '{}' not found or already closed", result_id))?;
+        let mut cursor = session.cursor.lock().await;
+        cursor.close().await
+    }
+}
+
+/// Whether a statement opens or closes a session transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TransactionControl {
+    Begin,
+    End,
+}
+
+/// Recognizes statements that open or end a transaction. Savepoint rollbacks
+/// and `COMMIT AND CHAIN` keep the transaction open, so they are not `End`.
+fn transaction_control(query: &str) -> Option<TransactionControl> {
+    let upper = query.trim().trim_end_matches(';').to_uppercase();
+    let words: Vec<&str> = upper.split_whitespace().collect();
+    match words.first().copied()? {
+        "BEGIN" => Some(TransactionControl::Begin),
+        "START" if words.get(1) == Some(&"TRANSACTION") => Some(TransactionControl::Begin),
+        "COMMIT" | "END" | "ROLLBACK" | "ABORT" => {
+            let keeps_open = words.contains(&"TO")
+                || words.windows(2).any(|w| w == ["AND", "CHAIN"]);
+            (!keeps_open).then_some(TransactionControl::End)
+        }
+        _ => None,
+    }
 }
 
 /// Returns true if the query modifies data and won't return rows.
@@ -728,19 +1367,21 @@ fn returns_rows(query: &str) -> bool {
 async fn execute_query_pg(
     app: &AppHandle,
     query_id: &str,
-    pool: &sqlx::PgPool,
+    conn: &mut sqlx::PgConnection,
     query: &str,
+    max_rows: usize,
 ) -> Result<QueryResult, String> {
-    use futures::StreamExt;
+    use sqlx::Executor;
 
     let start = Instant::now();
     emit_progress(app, query_id, "executing", 0, 0, None, None, None);
 
-    // Use raw_sql to avoid prepared statements — the PREPARE + DESCRIBE
+    // Pass the query as a bare string so it goes over the simple-query
+    // protocol and skips prepared statements — the PREPARE + DESCRIBE
     // round-trip can hang on system catalogs and connection poolers.
     if !returns_rows(query) {
-        let result = sqlx::raw_sql(query)
-            .execute(pool)
+        let result = conn
+            .execute(query)
             .await
             .map_err(|e| format!("Query failed: {}", e))?;
 
@@ -758,28 +1399,46 @@ async fn execute_query_pg(
         );
         return Ok(QueryResult {
             columns: vec![],
+            column_meta: vec![],
             rows: vec![],
             row_count: rows_affected as usize,
             message: Some(format!("{} row(s) affected.", rows_affected)),
             server_time_ms: Some(total_ms),
             transfer_time_ms: Some(0),
             bytes_transferred: Some(0),
+            truncated: false,
         });
     }
 
-    let mut stream = sqlx::raw_sql(query).fetch(pool);
+    let mut stream = conn.fetch(query);
     let mut columns: Vec<String> = Vec::new();
-    let mut result_rows: Vec<Vec<serde_json::Value>> = Vec::new();
+    let mut column_meta: Vec<ColumnMeta> = Vec::new();
+    let mut column_sources: Vec<Option<(u32, i16)>> = Vec::new();
+    // Rows are decoded after the stream ends: user-defined types need a
+    // pg_type lookup, which can't run while the connection is mid-result.
+    let mut raw_rows: Vec<sqlx::postgres::PgRow> = Vec::new();
     let mut first_row_at: Option<Instant> = None;
     let mut last_emit = start;
     let mut bytes_total: u64 = 0;
 
-    while let Some(row_res) = stream.next().await {
-        let row = row_res.map_err(|e| format!("Query failed: {}", e))?;
+    // The rest of a truncated result is left unread. Outside a transaction the
+    // driver then closes the connection rather than let sqlx drain it before
+    // the next query; a pinned session drains it to keep its transaction.
+    let truncated = read_capped(&mut stream, max_rows, |row: sqlx::postgres::PgRow| {
         if first_row_at.is_none() {
             let now = Instant::now();
             first_row_at = Some(now);
-            columns = row.columns().iter().map(|c| c.name().to_string()).collect();
+            column_meta = row
+                .columns()
+                .iter()
+                .map(|c| column_meta_for(c, pg_logical_type))
+                .collect();
+            columns = column_meta.iter().map(|c| c.name.clone()).collect();
+            column_sources = row
+                .columns()
+                .iter()
+                .map(|c| c.relation_id().zip(c.relation_attribute_no()).map(|(rel, num)| (rel.0, num)))
+                .collect();
             let server_ms = now.duration_since(start).as_millis() as u64;
             emit_progress(
                 app,
@@ -793,13 +1452,8 @@ async fn execute_query_pg(
             );
             last_emit = now;
         }
-        let mut row_values = Vec::with_capacity(row.columns().len());
-        for (i, col) in row.columns().iter().enumerate() {
-            let v = pg_value_to_json(&row, i, col.type_info().name());
-            bytes_total += approx_value_bytes(&v) as u64;
-            row_values.push(v);
-        }
-        result_rows.push(row_values);
+        bytes_total += pg_wire_bytes(&row) as u64;
+        raw_rows.push(row);
 
         if last_emit.elapsed() >= PROGRESS_INTERVAL {
             let now = Instant::now();
@@ -809,7 +1463,7 @@ async fn execute_query_pg(
                 app,
                 query_id,
                 "transferring",
-                result_rows.len(),
+                raw_rows.len(),
                 elapsed,
                 server_ms,
                 None,
@@ -817,7 +1471,11 @@ async fn execute_query_pg(
             );
             last_emit = now;
         }
-    }
+    })
+    .await
+    .map_err(|e| format!("Query failed: {}", e))?;
+
+    drop(stream);
 
     let end = Instant::now();
     let total_ms = end.duration_since(start).as_millis() as u64;
@@ -833,36 +1491,238 @@ async fn execute_query_pg(
         app,
         query_id,
         "done",
-        result_rows.len(),
+        raw_rows.len(),
         total_ms,
         Some(server_ms),
         Some(transfer_ms),
         Some(bytes_total),
     );
 
+    pg_fill_nullability(conn, &column_sources, &mut column_meta).await;
+    let types = match raw_rows.first() {
+        Some(row) => pg_load_types(conn, row, &mut column_meta).await,
+        None => PgTypeCatalog::new(),
+    };
+    let result_rows: Vec<Vec<serde_json::Value>> = raw_rows
+        .into_iter()
+        .map(|row| pg_row_to_json(&row, &types))
+        .collect();
+
     if result_rows.is_empty() {
         return Ok(QueryResult {
             columns: vec![],
+            column_meta: vec![],
             rows: vec![],
             row_count: 0,
             message: Some("0 row(s) affected.".to_string()),
             server_time_ms: Some(server_ms),
             transfer_time_ms: Some(transfer_ms),
             bytes_transferred: Some(bytes_total),
+            truncated: false,
         });
     }
 
     Ok(QueryResult {
         columns,
+        column_meta,
         row_count: result_rows.len(),
         rows: result_rows,
         message: None,
         server_time_ms: Some(server_ms),
         transfer_time_ms: Some(transfer_ms),
         bytes_transferred: Some(bytes_total),
+        truncated,
     })
 }
 
+/// Fills `nullable` from pg_attribute for columns that come straight from a
+/// table column. Best effort: if the lookup fails nullability stays unknown.
+async fn pg_fill_nullability(
+    conn: &mut sqlx::PgConnection,
+    sources: &[Option<(u32, i16)>],
+    meta: &mut [ColumnMeta],
+) {
+    use sqlx::Executor;
+
+    let pairs: Vec<String> = sources
+        .iter()
+        .flatten()
+        .map(|(rel, num)| format!("({}, {})", rel, num))
+        .collect();
+    if pairs.is_empty() {
+        return;
+    }
+    // Only integers are interpolated, so building the IN list is safe.
+    let sql = format!(
+        "SELECT attrelid::int8, attnum::int4, attnotnull FROM pg_attribute
+         WHERE (attrelid::int8, attnum::int4) IN ({})",
+        pairs.join(", ")
+    );
+    let Ok(rows) = conn.fetch_all(sql.as_str()).await else {
+        return;
+    };
+    let not_null: HashMap<(i64, i32), bool> = rows
+        .iter()
+        .filter_map(|r| {
+            Some((
+                (r.try_get::<i64, _>(0).ok()?, r.try_get::<i32, _>(1).ok()?),
+                r.try_get::<bool, _>(2).ok()?,
+            ))
+        })
+        .collect();
+    for (column, source) in meta.iter_mut().zip(sources) {
+        if let Some((rel, num)) = source {
+            column.nullable = not_null.get(&(*rel as i64, *num as i32)).map(|nn| !nn);
+        }
+    }
+}
+
+/// Resolves the user-defined types in a result's columns through pg_type and
+/// fills in the metadata sqlx could not name. Best effort: on failure those
+/// values are shown as text.
+async fn pg_load_types(
+    conn: &mut sqlx::PgConnection,
+    row: &sqlx::postgres::PgRow,
+    meta: &mut [ColumnMeta],
+) -> PgTypeCatalog {
+    let oids: Vec<u32> = row
+        .columns()
+        .iter()
+        .map(|c| c.type_info().oid().map_or(0, |o| o.0))
+        .collect();
+    let types = pg_load_type_catalog(conn, &oids).await;
+    for (column, type_oid) in meta.iter_mut().zip(&oids) {
+        let Some(name) = types.name(*type_oid) else {
+            continue;
+        };
+        column.type_name = name.to_string();
+        column.logical_type = match types.kind(*type_oid) {
+            pg_types::PgKind::Enum => LogicalType::Enum,
+            pg_types::PgKind::Array(_) => LogicalType::Array,
+            _ => column.logical_type,
+        };
+    }
+    types
+}
+
+/// Looks up `oids`, and every user-defined type they reference, in pg_type.
+async fn pg_load_type_catalog(conn: &mut sqlx::PgConnection, oids: &[u32]) -> PgTypeCatalog {
+    use pg_types::PgKind;
+    use sqlx::Executor;
+
+    let mut types = PgTypeCatalog::new();
+    // Each round resolves one level of nesting (array -> element -> field
+    // type ...); real schemas rarely go more than a few deep.
+    for _ in 0..8 {
+        let missing = types.unresolved(oids);
+        if missing.is_empty() {
+            break;
+        }
+        let list = missing.iter().map(u32::to_string).collect::<Vec<_>>().join(", ");
+        // Only integers are interpolated, so building the IN lists is safe.
+        let type_sql = format!(
+            "SELECT t.oid::int8, t.typname::text, t.typtype::text, t.typcategory::text,
+                    t.typelem::int8, t.typbasetype::int8, t.typrelid::int8,
+                    COALESCE(r.rngsubtype, 0)::int8
+             FROM pg_type t LEFT JOIN pg_range r ON r.rngtypid = t.oid
+             WHERE t.oid IN ({})",
+            list
+        );
+        let Ok(rows) = conn.fetch_all(type_sql.as_str()).await else {
+            break;
+        };
+        let mut composites = Vec::new();
+        for r in &rows {
+            let (Ok(type_oid), Ok(name), Ok(typtype), Ok(category)) = (
+                r.try_get::<i64, _>(0),
+                r.try_get::<String, _>(1),
+                r.try_get::<String, _>(2),
+                r.try_get::<String, _>(3),
+            ) else {
+                continue;
+            };
+            let related = |i: usize| r.try_get::<i64, _>(i).unwrap_or(0) as u32;
+            let kind = match (typtype.as_str(), category.as_str()) {
+                ("e", _) => PgKind::Enum,
+                ("r", _) => PgKind::Range(related(7)),
+                ("d", _) => PgKind::Domain(related(5)),
+                ("c", _) => {
+                    composites.push((type_oid as u32, related(6)));
+                    PgKind::Composite(Vec::new())
+                }
+                (_, "A") if related(4) != 0 => PgKind::Array(related(4)),
+                _ => PgKind::Base,
+            };
+            types.insert(type_oid as u32, &name, kind);
+        }
+        // Anything pg_type did not return is treated as text from now on.
+        for type_oid in &missing {
+            if types.name(*type_oid).is_none() {
+                types.insert(*type_oid, "?", PgKind::Base);
+            }
+        }
+
+        if composites.is_empty() {
+            continue;
+        }
+        let relations = composites
+            .iter()
+            .map(|(_, rel)| rel.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let field_sql = format!(
+            "SELECT attrelid::int8, attname::text, atttypid::int8 FROM pg_attribute
+             WHERE attrelid IN ({}) AND attnum > 0 AND NOT attisdropped
+             ORDER BY attrelid, attnum",
+            relations
+        );
+        let Ok(field_rows) = conn.fetch_all(field_sql.as_str()).await else {
+            continue;
+        };
+        for (type_oid, rel) in composites {
+            let fields = field_rows
+                .iter()
+                .filter(|r| r.try_get::<i64, _>(0).ok() == Some(rel as i64))
+                .filter_map(|r| {
+                    Some((
+                        r.try_get::<String, _>(1).ok()?,
+                        r.try_get::<i64, _>(2).ok()? as u32,
+                    ))
+                })
+                .collect();
+            let name = types.name(type_oid).unwrap_or("?").to_string();
+            types.insert(type_oid, &name, PgKind::Composite(fields));
+        }
+    }
+    types
+}
+
+/// Wire size of a row's values, for progress reporting.
+fn pg_wire_bytes(row: &sqlx::postgres::PgRow) -> usize {
+    use sqlx::ValueRef;
+
+    (0..row.len())
+        .filter_map(|i| row.try_get_raw(i).ok())
+        .filter(|raw| !raw.is_null())
+        .filter_map(|raw| raw.as_bytes().ok().map(<[u8]>::len))
+        .sum()
+}
+
+fn pg_row_to_json(row: &sqlx::postgres::PgRow, types: &PgTypeCatalog) -> Vec<serde_json::Value> {
+    row.columns()
+        .iter()
+        .enumerate()
+        .map(|(i, col)| pg_value_to_json(row, i, col.type_info().name(), types))
+        .collect()
+}
+
+/// Builds a column's metadata from its engine type name. Nullability is left
+/// unknown; engines that can tell fill it in afterwards.
+fn column_meta_for<C: Column>(column: &C, logical_type: fn(&str) -> LogicalType) -> ColumnMeta {
+    let type_name = column.type_info().name();
+    ColumnMeta::new(column.name(), type_name, logical_type(type_name))
+}
+
 /// Approximate the payload size of a decoded value for progress reporting.
 /// Serialized JSON length is a reasonable proxy — strings and numbers are
 /// 1:1 with their wire form; binary blobs are inflated by base64/escape but
@@ -881,6 +1741,7 @@ fn pg_value_to_json(
     row: &sqlx::postgres::PgRow,
     index: usize,
     type_name: &str,
+    types: &PgTypeCatalog,
 ) -> serde_json::Value {
     use sqlx::{Row, ValueRef};
 
@@ -902,22 +1763,13 @@ fn pg_value_to_json(
         "INT8" => row
             .try_get::<i64, _>(index)
             .map(|v| serde_json::Value::Number(v.into())),
-        "FLOAT4" | "FLOAT8" => row
-            .try_get::<f64, _>(index)
-            .map(|v| {
-                serde_json::Number::from_f64(v)
-                    .map(serde_json::Value::Number)
-                    .unwrap_or(serde_json::Value::String(v.to_string()))
-            }),
-        "TIMESTAMP" => row
-            .try_get::<chrono::NaiveDateTime, _>(index)
-            .map(|v| serde_json::Value::String(v.to_string())),
-        "TIMESTAMPTZ" => row
-            .try_get::<chrono::DateTime<chrono::Utc>, _>(index)
-            .map(|v| serde_json::Value::String(v.to_rfc3339())),
-        "DATE" => row
-            .try_get::<chrono::NaiveDate, _>(index)
-            .map(|v| serde_json::Value::String(v.to_string())),
+        "FLOAT4" | "FLOAT8" => row.try_get::<f64, _>(index).map(value::float),
+        "BYTEA" => row
+            .try_get::<Vec<u8>, _>(index)
+            .map(|bytes| value::binary(&bytes)),
+        // Not decoded through sqlx's chrono types: they panic on dates
+        // Postgres allows but chrono can't hold, and on infinity.
+        "TIMESTAMP" | "TIMESTAMPTZ" | "DATE" => pg_decode_raw(row, index, types),
         "TIME" => row
             .try_get::<chrono::NaiveTime, _>(index)
             .map(|v| serde_json::Value::String(v.to_string())),
@@ -927,9 +1779,9 @@ fn pg_value_to_json(
         "JSON" | "JSONB" => row
             .try_get::<serde_json::Value, _>(index)
             .map(|v| v),
-        _ => pg_try_decode_unknown(row, index, type_name),
+        _ => pg_decode_raw(row, index, types),
     }
-    .or_else(|_| pg_try_decode_unknown(row, index, type_name))
+    .or_else(|_| pg_decode_raw(row, index, types))
     .unwrap_or_else(|_| {
         // Non-null value we couldn't decode — try raw bytes, then show type name
         row.try_get::<Vec<u8>, _>(index)
@@ -940,68 +1792,75 @@ fn pg_value_to_json(
     })
 }
 
-/// Brute-force decode for columns where the typed match arm failed.
-/// Uses raw value access to bypass sqlx's type checking, then decodes
-/// based on the PostgreSQL wire format (OID).
-fn pg_try_decode_unknown(
+fn pg_logical_type(type_name: &str) -> LogicalType {
+    if type_name.ends_with("[]") {
+        return LogicalType::Array;
+    }
+    match type_name {
+        "BOOL" => LogicalType::Boolean,
+        "INT2" | "INT4" | "INT8" | "OID" => LogicalType::Integer,
+        "FLOAT4" | "FLOAT8" => LogicalType::Float,
+        "NUMERIC" => LogicalType::Decimal,
+        "TEXT" | "VARCHAR" | "BPCHAR" | "NAME" | "CHAR" => LogicalType::Text,
+        "BYTEA" => LogicalType::Binary,
+        "DATE" => LogicalType::Date,
+        "TIME" | "TIMETZ" => LogicalType::Time,
+        "TIMESTAMP" => LogicalType::Timestamp,
+        "TIMESTAMPTZ" => LogicalType::TimestampTz,
+        "INTERVAL" => LogicalType::Interval,
+        "UUID" => LogicalType::Uuid,
+        "JSON" | "JSONB" => LogicalType::Json,
+        "INET" | "CIDR" | "MACADDR" | "MACADDR8" => LogicalType::Network,
+        "POINT" | "LINE" | "LSEG" | "BOX" | "PATH" | "POLYGON" | "CIRCLE" => {
+            LogicalType::Geometry
+        }
+        "BIT" | "VARBIT" => LogicalType::Bit,
+        _ => LogicalType::Unknown,
+    }
+}
+
+/// Decodes a value sqlx has no typed mapping for straight from its wire bytes,
+/// in whichever format the server sent it.
+fn pg_decode_raw(
     row: &sqlx::postgres::PgRow,
     index: usize,
-    type_name: &str,
+    types: &PgTypeCatalog,
 ) -> Result<serde_json::Value, sqlx::Error> {
-    use sqlx::{Row, ValueRef, TypeInfo};
-    use sqlx::postgres::PgValueRef;
-
-    let raw: PgValueRef<'_> = row.try_get_raw(index)?;
-    let oid_type = raw.type_info();
-    let oid_name = oid_type.name();
-
-    // Try decoding based on the actual OID-resolved type name
-    // This handles cases where the match arm's type name is correct but
-    // sqlx's type validation rejected the decode anyway.
-    let result = match oid_name {
-        "BOOL" | "bool" => row.try_get::<bool, _>(index).map(serde_json::Value::Bool),
-        "INT2" | "int2" => row.try_get::<i16, _>(index).map(|v| serde_json::Value::Number(v.into())),
-        "INT4" | "int4" | "OID" | "oid" => row.try_get::<i32, _>(index).map(|v| serde_json::Value::Number(v.into())),
-        "INT8" | "int8" => row.try_get::<i64, _>(index).map(|v| serde_json::Value::Number(v.into())),
-        "FLOAT4" | "float4" | "FLOAT8" | "float8" => row.try_get::<f64, _>(index).map(|v| {
-            serde_json::Number::from_f64(v)
-                .map(serde_json::Value::Number)
-                .unwrap_or(serde_json::Value::String(v.to_string()))
-        }),
-        "TIMESTAMPTZ" | "timestamptz" => row.try_get::<chrono::DateTime<chrono::Utc>, _>(index).map(|v| serde_json::Value::String(v.to_rfc3339())),
-        "TIMESTAMP" | "timestamp" => row.try_get::<chrono::NaiveDateTime, _>(index).map(|v| serde_json::Value::String(v.to_string())),
-        "DATE" | "date" => row.try_get::<chrono::NaiveDate, _>(index).map(|v| serde_json::Value::String(v.to_string())),
-        "TIME" | "time" | "TIMETZ" | "timetz" => row.try_get::<chrono::NaiveTime, _>(index).map(|v| serde_json::Value::String(v.to_string())),
-        "UUID" | "uuid" => row.try_get::<uuid::Uuid, _>(index).map(|v| serde_json::Value::String(v.to_string())),
-        "JSON" | "json" | "JSONB" | "jsonb" => row.try_get::<serde_json::Value, _>(index),
-        _ => row.try_get::<String, _>(index).map(serde_json::Value::String),
+    use pg_types::WireFormat;
+    use sqlx::postgres::PgValueFormat;
+    use sqlx::ValueRef;
+
+    let raw = row.try_get_raw(index)?;
+    let type_oid = raw.type_info().oid().map_or(0, |o| o.0);
+    let format = match raw.format() {
+        PgValueFormat::Text => WireFormat::Text,
+        PgValueFormat::Binary => WireFormat::Binary,
     };
+    let bytes = raw.as_bytes().map_err(sqlx::Error::Decode)?;
 
-    // If typed decode failed, try reading raw bytes as a UTF-8 string.
-    // This bypasses sqlx's type validation (which rejects String for non-text types).
-    result.or_else(|_| {
-        let raw: PgValueRef<'_> = row.try_get_raw(index)?;
-        let bytes = raw.as_bytes().map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
-        Ok(serde_json::Value::String(String::from_utf8_lossy(bytes).into_owned()))
-    })
+    // If the typed decode failed, show the bytes as text rather than nothing.
+    Ok(pg_types::decode(bytes, format, type_oid, types)
+        .unwrap_or_else(|_| serde_json::Value::String(String::from_utf8_lossy(bytes).into_owned())))
 }
 
 async fn execute_query_mysql(
     app: &AppHandle,
     query_id: &str,
-    pool: &sqlx::MySqlPool,
+    conn: &mut sqlx::MySqlConnection,
     query: &str,
+    max_rows: usize,
 ) -> Result<QueryResult, String> {
-    use futures::StreamExt;
+    use sqlx::Executor;
 
     let start = Instant::now();
     emit_progress(app, query_id, "executing", 0, 0, None, None, None);
 
-    // Use raw_sql to avoid prepared statements — MySQL's prepared statement
+    // Pass the query as a bare string so it goes over the text protocol and
+    // skips prepared statements — MySQL's prepared statement
     // protocol doesn't support many statement types (SET, KILL, etc.).
     if !returns_rows(query) {
-        let result = sqlx::raw_sql(query)
-            .execute(pool)
+        let result = conn
+            .execute(query)
             .await
             .map_err(|e| format!("Query failed: {}", e))?;
 
@@ -1019,28 +1878,38 @@ async fn execute_query_mysql(
         );
         return Ok(QueryResult {
             columns: vec![],
+            column_meta: vec![],
             rows: vec![],
             row_count: rows_affected as usize,
             message: Some(format!("{} row(s) affected.", rows_affected)),
             server_time_ms: Some(total_ms),
             transfer_time_ms: Some(0),
             bytes_transferred: Some(0),
+            truncated: false,
         });
     }
 
-    let mut stream = sqlx::raw_sql(query).fetch(pool);
+    let mut stream = conn.fetch(query);
     let mut columns: Vec<String> = Vec::new();
+    let mut column_meta: Vec<ColumnMeta> = Vec::new();
     let mut result_rows: Vec<Vec<serde_json::Value>> = Vec::new();
     let mut first_row_at: Option<Instant> = None;
     let mut last_emit = start;
     let mut bytes_total: u64 = 0;
 
-    while let Some(row_res) = stream.next().await {
-        let row = row_res.map_err(|e| format!("Query failed: {}", e))?;
+    // The rest of a truncated result is left unread. Outside a transaction the
+    // driver then closes the connection rather than let sqlx drain it before
+    // the next query; a pinned session drains it to keep its transaction.
+    let truncated = read_capped(&mut stream, max_rows, |row: sqlx::mysql::MySqlRow| {
         if first_row_at.is_none() {
             let now = Instant::now();
             first_row_at = Some(now);
-            columns = row.columns().iter().map(|c| c.name().to_string()).collect();
+            column_meta = row
+                .columns()
+                .iter()
+                .map(|c| column_meta_for(c, mysql_logical_type))
+                .collect();
+            columns = column_meta.iter().map(|c| c.name.clone()).collect();
             let server_ms = now.duration_since(start).as_millis() as u64;
             emit_progress(
                 app,
@@ -1078,7 +1947,10 @@ async fn execute_query_mysql(
             );
             last_emit = now;
         }
-    }
+    })
+    .await
+    .map_err(|e| format!("Query failed: {}", e))?;
+    drop(stream);
 
     let end = Instant::now();
     let total_ms = end.duration_since(start).as_millis() as u64;
@@ -1104,23 +1976,27 @@ async fn execute_query_mysql(
     if result_rows.is_empty() {
         return Ok(QueryResult {
             columns: vec![],
+            column_meta: vec![],
             rows: vec![],
             row_count: 0,
             message: Some("0 row(s) affected.".to_string()),
             server_time_ms: Some(server_ms),
             transfer_time_ms: Some(transfer_ms),
             bytes_transferred: Some(bytes_total),
+            truncated: false,
         });
     }
 
     Ok(QueryResult {
         columns,
+        column_meta,
         row_count: result_rows.len(),
         rows: result_rows,
         message: None,
         server_time_ms: Some(server_ms),
         transfer_time_ms: Some(transfer_ms),
         bytes_transferred: Some(bytes_total),
+        truncated,
     })
 }
 
@@ -1135,56 +2011,50 @@ fn mysql_value_to_json(
         return serde_json::Value::Null;
     }
 
-    match type_name {
-        "BOOLEAN" | "TINYINT(1)" => row
-            .try_get::<bool, _>(index)
-            .map(serde_json::Value::Bool),
-        "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "TINYINT UNSIGNED"
-        | "SMALLINT UNSIGNED" | "MEDIUMINT UNSIGNED" | "INT UNSIGNED" => row
-            .try_get::<i32, _>(index)
-            .map(|v| serde_json::Value::Number(v.into())),
-        "BIGINT" | "BIGINT UNSIGNED" => row
-            .try_get::<i64, _>(index)
-            .map(|v| serde_json::Value::Number(v.into())),
-        "FLOAT" | "DOUBLE" | "DECIMAL" => row
-            .try_get::<f64, _>(index)
-            .map(|v| {
-                serde_json::Number::from_f64(v)
-                    .map(serde_json::Value::Number)
-                    .unwrap_or(serde_json::Value::String(v.to_string()))
-            }),
-        "JSON" => row
-            .try_get::<serde_json::Value, _>(index)
-            .map(|v| v),
-        _ => row
-            .try_get::<String, _>(index)
-            .or_else(|_| {
-                // MySQL over TLS may return string columns as VARBINARY
-                row.try_get::<Vec<u8>, _>(index)
-                    .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
-            })
-            .map(serde_json::Value::String),
+    row.try_get_unchecked::<&[u8], _>(index)
+        .map(|bytes| mysql_types::text_value(type_name, bytes))
+        .unwrap_or_else(|_| {
+            serde_json::Value::String(format!("<unsupported type: {}>", type_name))
+        })
+}
+
+fn mysql_logical_type(type_name: &str) -> LogicalType {
+    // Integer types carry an " UNSIGNED" suffix; the base name decides.
+    match type_name.split_whitespace().next().unwrap_or("") {
+        "BOOLEAN" => LogicalType::Boolean,
+        "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "BIGINT" | "YEAR" => LogicalType::Integer,
+        "FLOAT" | "DOUBLE" => LogicalType::Float,
+        "DECIMAL" => LogicalType::Decimal,
+        "CHAR" | "VARCHAR" | "TEXT" | "TINYTEXT" | "MEDIUMTEXT" | "LONGTEXT" | "SET" => {
+            LogicalType::Text
+        }
+        "BINARY" | "VARBINARY" | "BLOB" | "TINYBLOB" | "MEDIUMBLOB" | "LONGBLOB" => {
+            LogicalType::Binary
+        }
+        "DATE" => LogicalType::Date,
+        "TIME" => LogicalType::Time,
+        "DATETIME" | "TIMESTAMP" => LogicalType::Timestamp,
+        "JSON" => LogicalType::Json,
+        "GEOMETRY" => LogicalType::Geometry,
+        "ENUM" => LogicalType::Enum,
+        // Decoded as a number; see mysql_value_to_json.
+        "BIT" => LogicalType::Integer,
+        _ => LogicalType::Unknown,
     }
-    .unwrap_or_else(|_| {
-        row.try_get::<Vec<u8>, _>(index)
-            .map(|bytes| serde_json::Value::String(String::from_utf8_lossy(&bytes).into_owned()))
-            .unwrap_or_else(|_| {
-                serde_json::Value::String(format!("<unsupported type: {}>", type_name))
-            })
-    })
 }
 
 async fn execute_query_sqlite(
     app: &AppHandle,
     query_id: &str,
-    pool: &sqlx::SqlitePool,
+    conn: &mut sqlx::SqliteConnection,
     query: &str,
+    max_rows: usize,
 ) -> Result<QueryResult, String> {
     let start = Instant::now();
     emit_progress(app, query_id, "executing", 0, 0, None, None, None);
     if !returns_rows(query) {
         let result = sqlx::query(query)
-            .execute(pool)
+            .execute(&mut *conn)
             .await
             .map_err(|e| format!("Query failed: {}", e))?;
 
@@ -1202,50 +2072,58 @@ async fn execute_query_sqlite(
         );
         return Ok(QueryResult {
             columns: vec![],
+            column_meta: vec![],
             rows: vec![],
             row_count: rows_affected as usize,
             message: Some(format!("{} row(s) affected.", rows_affected)),
             server_time_ms: Some(total_ms),
             transfer_time_ms: Some(0),
             bytes_transferred: Some(0),
+            truncated: false,
         });
     }
 
-    let rows = sqlx::query(query)
-        .fetch_all(pool)
-        .await
-        .map_err(|e| format!("Query failed: {}", e))?;
+    let mut stream = sqlx::query(query).fetch(&mut *conn);
+    let mut columns: Vec<String> = Vec::new();
+    let mut column_meta: Vec<ColumnMeta> = Vec::new();
+    let mut result_rows = Vec::new();
+
+    let truncated = read_capped(&mut stream, max_rows, |row: sqlx::sqlite::SqliteRow| {
+        if columns.is_empty() {
+            column_meta = row
+                .columns()
+                .iter()
+                .map(|c| column_meta_for(c, sqlite_logical_type))
+                .collect();
+            columns = column_meta.iter().map(|c| c.name.clone()).collect();
+        }
+        let mut row_values = Vec::new();
+        for (i, col) in row.columns().iter().enumerate() {
+            let value = sqlite_value_to_json(&row, i, col.type_info().name());
+            row_values.push(value);
+        }
+        result_rows.push(row_values);
+    })
+    .await
+    .map_err(|e| format!("Query failed: {}", e))?;
+    drop(stream);
 
-    if rows.is_empty() {
+    if result_rows.is_empty() {
         let total_ms = start.elapsed().as_millis() as u64;
         emit_progress(app, query_id, "done", 0, total_ms, Some(total_ms), Some(0), Some(0));
         return Ok(QueryResult {
             columns: vec![],
+            column_meta: vec![],
             rows: vec![],
             row_count: 0,
             message: Some("0 row(s) affected.".to_string()),
             server_time_ms: Some(total_ms),
             transfer_time_ms: Some(0),
             bytes_transferred: Some(0),
+            truncated: false,
         });
     }
 
-    let columns: Vec<String> = rows[0]
-        .columns()
-        .iter()
-        .map(|c| c.name().to_string())
-        .collect();
-
-    let mut result_rows = Vec::new();
-    for row in &rows {
-        let mut row_values = Vec::new();
-        for (i, col) in row.columns().iter().enumerate() {
-            let value = sqlite_value_to_json(&row, i, col.type_info().name());
-            row_values.push(value);
-        }
-        result_rows.push(row_values);
-    }
-
     let total_ms = start.elapsed().as_millis() as u64;
     emit_progress(
         app,
@@ -1259,12 +2137,14 @@ async fn execute_query_sqlite(
     );
     Ok(QueryResult {
         columns,
+        column_meta,
         row_count: result_rows.len(),
         rows: result_rows,
         message: None,
         server_time_ms: Some(total_ms),
         transfer_time_ms: Some(0),
         bytes_transferred: None,
+        truncated,
     })
 }
 
@@ -1286,13 +2166,15 @@ fn sqlite_value_to_json(
         "INTEGER" => row
             .try_get::<i64, _>(index)
             .map(|v| serde_json::Value::Number(v.into())),
-        "REAL" => row
-            .try_get::<f64, _>(index)
-            .map(|v| {
-                serde_json::Number::from_f64(v)
-                    .map(serde_json::Value::Number)
-                    .unwrap_or(serde_json::Value::String(v.to_string()))
-            }),
+        "REAL" => row.try_get::<f64, _>(index).map(value::float),
+        // NUMERIC affinity may store an integer, a real or text; SQLite's text
+        // conversion of whichever it is keeps the stored digits.
+        "NUMERIC" => row
+            .try_get_unchecked::<String, _>(index)
+            .map(|v| value::decimal(&v)),
+        "BLOB" => row
+            .try_get::<Vec<u8>, _>(index)
+            .map(|bytes| value::binary(&bytes)),
         _ => row
             .try_get::<String, _>(index)
             .map(serde_json::Value::String),
@@ -1305,3 +2187,18 @@ fn sqlite_value_to_json(
             })
     })
 }
+
+fn sqlite_logical_type(type_name: &str) -> LogicalType {
+    match type_name {
+        "BOOLEAN" => LogicalType::Boolean,
+        "INTEGER" => LogicalType::Integer,
+        "REAL" => LogicalType::Float,
+        "NUMERIC" => LogicalType::Decimal,
+        "TEXT" => LogicalType::Text,
+        "BLOB" => LogicalType::Binary,
+        "DATE" => LogicalType::Date,
+        "TIME" => LogicalType::Time,
+        "DATETIME" => LogicalType::Timestamp,
+        _ => LogicalType::Unknown,
+    }
+}
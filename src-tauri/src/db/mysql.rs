@@ -1,8 +1,295 @@
 // ABOUTME: MySQL-specific database introspection queries.
 // ABOUTME: Provides schema, table, column, index, and constraint information.
 
-use super::{ColumnInfo, ConstraintInfo, DatabaseUser, FunctionInfo, IndexInfo, UserGrant};
-use sqlx::Row;
+use super::driver::{DatabaseDriver, DriverCapabilities, DriverSession};
+use super::query_plan::{self, QueryPlan};
+use super::result_set::{open_pool_stream, ResultCursor};
+use super::row_changes::{bind_param, AppliedRowChanges, Dialect, TableEdit};
+use super::running::{IdleSession, RunningQueries};
+use super::{
+    pool_options, ColumnInfo, ConstraintInfo, DatabaseUser, FunctionInfo, IndexInfo, QueryResult,
+    UserGrant,
+};
+use crate::sql_analyzer;
+use crate::storage::{ConnectionConfig, SslMode};
+use async_trait::async_trait;
+use sqlx::mysql::{MySqlConnectOptions, MySqlConnection, MySqlSslMode};
+use sqlx::pool::{PoolConnection, PoolOptions};
+use sqlx::{Connection, Executor, MySql, Row};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::AppHandle;
+
+pub struct MysqlDriver {
+    pool: sqlx::MySqlPool,
+    /// For the one-off connections that cancel queries.
+    options: MySqlConnectOptions,
+    /// CONNECTION_ID() of the connection running each in-flight query.
+    running: Arc<RunningQueries<u64>>,
+    idle: IdleSession<MysqlSession>,
+}
+
+impl MysqlDriver {
+    pub async fn connect(config: &ConnectionConfig, host: &str, port: u16) -> Result<Self, String> {
+        let options = connect_options(config, host, port)?;
+        let pool = pool_with_timeout(config)
+            .connect_with(options.clone())
+            .await
+            .map_err(|e| format!("Failed to connect to MySQL: {}", e))?;
+        Ok(Self {
+            idle: IdleSession::new(pool.options().get_max_connections()),
+            pool,
+            options,
+            running: Arc::new(RunningQueries::new()),
+        })
+    }
+
+    async fn session(&self) -> Result<MysqlSession, String> {
+        match self.idle.take() {
+            Some(session) => Ok(session),
+            None => MysqlSession::open(&self.pool, self.running.clone()).await,
+        }
+    }
+}
+
+/// The sqlx options for `config`, reached at `host:port` (the tunnel's local
+/// end when there is one).
+pub(super) fn connect_options(
+    config: &ConnectionConfig,
+    host: &str,
+    port: u16,
+) -> Result<MySqlConnectOptions, String> {
+    let ssl_mode = match config.ssl_mode {
+        SslMode::Disable => MySqlSslMode::Disabled,
+        SslMode::Prefer => MySqlSslMode::Preferred,
+        SslMode::Require => MySqlSslMode::Required,
+        SslMode::VerifyCa => MySqlSslMode::VerifyCa,
+        SslMode::VerifyFull => MySqlSslMode::VerifyIdentity,
+    };
+    let mut options = MySqlConnectOptions::new()
+        .host(host)
+        .port(port)
+        .username(&config.username)
+        .database(config.database.as_deref().unwrap_or("mysql"))
+        .ssl_mode(ssl_mode);
+    if !config.password.is_empty() {
+        options = options.password(&config.password);
+    }
+    if let Some(ca) = config.ssl_root_cert.as_deref().filter(|p| !p.is_empty()) {
+        options = options.ssl_ca(ca);
+    }
+    if let Some((cert, key)) = config.ssl_client_identity()? {
+        options = options.ssl_client_cert(cert).ssl_client_key(key);
+    }
+    if let Some(charset) = config.options.charset.as_deref().filter(|c| !c.is_empty()) {
+        options = options.charset(charset);
+    }
+    Ok(options)
+}
+
+/// The pool for `config`, with its statement timeout set on every connection.
+fn pool_with_timeout(config: &ConnectionConfig) -> PoolOptions<MySql> {
+    let pool = pool_options(config);
+    let Some(ms) = config.options.statement_timeout_ms else {
+        return pool;
+    };
+    pool.after_connect(move |conn, _| {
+        Box::pin(async move {
+            let mysql = format!("SET SESSION max_execution_time = {}", ms);
+            if conn.execute(mysql.as_str()).await.is_err() {
+                // MariaDB names it differently, and counts in seconds.
+                let mariadb = format!("SET SESSION max_statement_time = {}", ms as f64 / 1000.0);
+                conn.execute(mariadb.as_str()).await?;
+            }
+            Ok(())
+        })
+    })
+}
+
+#[async_trait]
+impl DatabaseDriver for MysqlDriver {
+    fn capabilities(&self) -> DriverCapabilities {
+        DriverCapabilities {
+            engine: "MySQL",
+            supports_schemas: false,
+            supports_create_database: true,
+            supports_create_schema: false,
+            supports_users: true,
+            supports_functions: true,
+            supports_procedures: true,
+            supports_materialized_views: false,
+            supports_sequences: false,
+            supports_triggers: true,
+            supports_view_dependencies: false,
+            supports_query_cancel: true,
+            supports_paged_results: true,
+            supports_transactions: true,
+            supports_query_plans: true,
+            switches_database_in_place: false,
+        }
+    }
+
+    async fn list_databases(&self) -> Result<Vec<String>, String> {
+        list_databases(&self.pool).await
+    }
+
+    async fn list_schemas(&self, database: &str) -> Result<Vec<String>, String> {
+        list_schemas(&self.pool, database).await
+    }
+
+    async fn list_tables(&self, database: &str, schema: &str) -> Result<Vec<String>, String> {
+        list_tables(&self.pool, database, schema).await
+    }
+
+    async fn list_views(&self, database: &str, schema: &str) -> Result<Vec<String>, String> {
+        list_views(&self.pool, database, schema).await
+    }
+
+    async fn list_columns(
+        &self,
+        database: &str,
+        schema: &str,
+        table: &str,
+    ) -> Result<Vec<ColumnInfo>, String> {
+        list_columns(&self.pool, database, schema, table).await
+    }
+
+    async fn list_schema_columns(
+        &self,
+        database: &str,
+        _schema: &str,
+    ) -> Result<HashMap<String, Vec<ColumnInfo>>, String> {
+        list_schema_columns(&self.pool, database).await
+    }
+
+    async fn list_indexes(
+        &self,
+        database: &str,
+        schema: &str,
+        table: &str,
+    ) -> Result<Vec<IndexInfo>, String> {
+        list_indexes(&self.pool, database, schema, table).await
+    }
+
+    async fn list_constraints(
+        &self,
+        database: &str,
+        schema: &str,
+        table: &str,
+    ) -> Result<Vec<ConstraintInfo>, String> {
+        list_constraints(&self.pool, database, schema, table).await
+    }
+
+    fn split_script(&self, script: &str) -> Vec<String> {
+        sql_analyzer::split_statements(script, "mysql")
+    }
+
+    async fn execute_query(
+        &self,
+        app: &AppHandle,
+        query_id: &str,
+        query: &str,
+        _database: Option<&str>,
+        max_rows: usize,
+    ) -> Result<QueryResult, String> {
+        let mut session = self.session().await?;
+        let result = session.execute_query(app, query_id, query, max_rows).await;
+        // Only a clean connection outside any transaction is kept for reuse;
+        // any other is closed, since the pool would hand its transaction on.
+        // A truncated result is still in flight; closing beats draining it.
+        let reusable = matches!(&result, Ok(r) if !r.truncated);
+        if reusable && super::transaction_control(query).is_none() {
+            self.idle.park(session);
+        } else {
+            session.discard();
+        }
+        result
+    }
+
+    async fn open_session(&self) -> Result<Box<dyn DriverSession>, String> {
+        Ok(Box::new(self.session().await?))
+    }
+
+    async fn open_result(&self, query: &str) -> Result<Box<dyn ResultCursor>, String> {
+        Ok(Box::new(
+            open_pool_stream(
+                self.pool.clone(),
+                query.to_string(),
+                super::mysql_value_to_json,
+                super::mysql_logical_type,
+            )
+            .await?,
+        ))
+    }
+
+    async fn create_database(&self, name: &str) -> Result<(), String> {
+        create_database(&self.pool, name).await
+    }
+
+    async fn create_schema(&self, _name: &str) -> Result<(), String> {
+        Err("MySQL does not support CREATE SCHEMA separately from CREATE DATABASE".to_string())
+    }
+
+    async fn list_functions(&self, database: &str, schema: &str) -> Result<Vec<String>, String> {
+        list_functions(&self.pool, database, schema).await
+    }
+
+    async fn list_procedures(&self, database: &str, schema: &str) -> Result<Vec<String>, String> {
+        list_procedures(&self.pool, database, schema).await
+    }
+
+    async fn list_triggers(&self, database: &str, schema: &str) -> Result<Vec<String>, String> {
+        list_triggers(&self.pool, database, schema).await
+    }
+
+    async fn get_function_definition(
+        &self,
+        database: &str,
+        schema: &str,
+        function_name: &str,
+    ) -> Result<FunctionInfo, String> {
+        get_function_definition(&self.pool, database, schema, function_name).await
+    }
+
+    async fn get_view_definition(
+        &self,
+        database: &str,
+        schema: &str,
+        view_name: &str,
+    ) -> Result<FunctionInfo, String> {
+        get_view_definition(&self.pool, database, schema, view_name).await
+    }
+
+    async fn list_users(&self) -> Result<Vec<DatabaseUser>, String> {
+        list_users(&self.pool).await
+    }
+
+    async fn get_user_grants(
+        &self,
+        username: &str,
+        host: Option<&str>,
+    ) -> Result<Vec<UserGrant>, String> {
+        get_user_grants(&self.pool, username, host).await
+    }
+
+    async fn apply_row_changes(
+        &self,
+        edits: &[TableEdit],
+    ) -> Result<Vec<AppliedRowChanges>, String> {
+        apply_row_changes(&self.pool, edits).await
+    }
+
+    async fn explain_query(&self, query: &str, analyze: bool) -> Result<QueryPlan, String> {
+        explain_query(&self.pool, query, analyze).await
+    }
+
+    async fn cancel_query(&self, query_id: &str) -> Result<bool, String> {
+        match self.running.get(query_id) {
+            Some(connection_id) => kill_query(&self.options, connection_id).await.map(|_| true),
+            None => Ok(false),
+        }
+    }
+}
 
 /// MySQL over TLS may return information_schema strings as VARBINARY instead of VARCHAR.
 /// This helper tries String first, then falls back to reading raw bytes.
@@ -25,6 +312,60 @@ fn get_opt_str(row: &sqlx::mysql::MySqlRow, index: usize) -> Option<String> {
         })
 }
 
+/// A pooled connection with its CONNECTION_ID(), so queries on it can be
+/// killed. The id is read once, when the connection is acquired.
+pub struct MysqlSession {
+    conn: PoolConnection<MySql>,
+    connection_id: u64,
+    running: Arc<RunningQueries<u64>>,
+}
+
+impl MysqlSession {
+    async fn open(
+        pool: &sqlx::MySqlPool,
+        running: Arc<RunningQueries<u64>>,
+    ) -> Result<Self, String> {
+        let mut conn = pool
+            .acquire()
+            .await
+            .map_err(|e| format!("Failed to acquire connection: {}", e))?;
+        let connection_id: u64 = sqlx::query_scalar("SELECT CONNECTION_ID()")
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(|e| format!("Query failed: {}", e))?;
+        Ok(Self {
+            conn,
+            connection_id,
+            running,
+        })
+    }
+}
+
+#[async_trait]
+impl DriverSession for MysqlSession {
+    async fn execute_query(
+        &mut self,
+        app: &AppHandle,
+        query_id: &str,
+        query: &str,
+        max_rows: usize,
+    ) -> Result<QueryResult, String> {
+        let _running = self.running.register(query_id, self.connection_id);
+        super::execute_query_mysql(app, query_id, &mut self.conn, query, max_rows).await
+    }
+
+    async fn apply_row_changes(
+        &mut self,
+        edits: &[TableEdit],
+    ) -> Result<Vec<AppliedRowChanges>, String> {
+        apply_edits(&mut self.conn, edits).await
+    }
+
+    fn discard(&mut self) {
+        self.conn.close_on_drop();
+    }
+}
+
 pub async fn list_databases(pool: &sqlx::MySqlPool) -> Result<Vec<String>, String> {
     let rows = sqlx::query("SHOW DATABASES")
         .fetch_all(pool)
@@ -88,7 +429,7 @@ pub async fn get_view_definition(
     database: &str,
     _schema: &str,
     view_name: &str,
-) -> Result<super::FunctionInfo, String> {
+) -> Result<FunctionInfo, String> {
     let query = format!("SHOW CREATE VIEW `{}`.`{}`", database, view_name);
     let row = sqlx::query(&query)
         .fetch_one(pool)
@@ -97,7 +438,7 @@ pub async fn get_view_definition(
 
     let definition: String = get_str(&row, 1);
 
-    Ok(super::FunctionInfo {
+    Ok(FunctionInfo {
         name: view_name.to_string(),
         definition,
         return_type: None,
@@ -158,24 +499,6 @@ pub async fn get_function_definition(
     })
 }
 
-pub async fn list_materialized_views(
-    _pool: &sqlx::MySqlPool,
-    _database: &str,
-    _schema: &str,
-) -> Result<Vec<String>, String> {
-    // MySQL does not support materialized views
-    Ok(vec![])
-}
-
-pub async fn list_sequences(
-    _pool: &sqlx::MySqlPool,
-    _database: &str,
-    _schema: &str,
-) -> Result<Vec<String>, String> {
-    // MySQL does not have standalone sequences
-    Ok(vec![])
-}
-
 pub async fn list_triggers(
     pool: &sqlx::MySqlPool,
     database: &str,
@@ -238,16 +561,52 @@ pub async fn list_columns(
     .await
     .map_err(|e| format!("Failed to list columns: {}", e))?;
 
-    Ok(rows
-        .iter()
-        .map(|r| ColumnInfo {
-            name: get_str(r, 0),
-            data_type: get_str(r, 1),
-            is_nullable: get_str(r, 2) == "YES",
-            column_default: get_opt_str(r, 3),
-            is_primary_key: get_str(r, 4) == "PRI",
-        })
-        .collect())
+    Ok(rows.iter().map(column_info).collect())
+}
+
+/// Columns of every table and view in `database`, in one query.
+pub async fn list_schema_columns(
+    pool: &sqlx::MySqlPool,
+    database: &str,
+) -> Result<HashMap<String, Vec<ColumnInfo>>, String> {
+    // Same columns as list_columns, then table_name(5)
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            column_name,
+            column_type,
+            is_nullable,
+            column_default,
+            column_key,
+            table_name
+        FROM information_schema.columns
+        WHERE table_schema = ?
+        ORDER BY table_name, ordinal_position
+        "#,
+    )
+    .bind(database)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to list columns: {}", e))?;
+
+    let mut columns: HashMap<String, Vec<ColumnInfo>> = HashMap::new();
+    for row in &rows {
+        columns
+            .entry(get_str(row, 5))
+            .or_default()
+            .push(column_info(row));
+    }
+    Ok(columns)
+}
+
+fn column_info(r: &sqlx::mysql::MySqlRow) -> ColumnInfo {
+    ColumnInfo {
+        name: get_str(r, 0),
+        data_type: get_str(r, 1),
+        is_nullable: get_str(r, 2) == "YES",
+        column_default: get_opt_str(r, 3),
+        is_primary_key: get_str(r, 4) == "PRI",
+    }
 }
 
 pub async fn list_indexes(
@@ -352,6 +711,113 @@ pub async fn list_constraints(
         .collect())
 }
 
+/// Relies on the driver's FOUND_ROWS flag: an UPDATE that rewrites a row with
+/// its current values still counts as affecting it.
+/// `EXPLAIN ANALYZE` (MySQL 8.0.18+) only prints its text tree, so the
+/// estimated plan comes from `FORMAT=JSON` instead.
+pub async fn explain_query(
+    pool: &sqlx::MySqlPool,
+    query: &str,
+    analyze: bool,
+) -> Result<QueryPlan, String> {
+    let sql = if analyze {
+        format!("EXPLAIN ANALYZE {}", query)
+    } else {
+        format!("EXPLAIN FORMAT=JSON {}", query)
+    };
+    // EXPLAIN ANALYZE executes the statement; roll back whatever it wrote.
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let row = (&mut *tx)
+        .fetch_one(sql.as_str())
+        .await
+        .map_err(|e| format!("Failed to explain query: {}", e))?;
+    tx.rollback()
+        .await
+        .map_err(|e| format!("Failed to roll back: {}", e))?;
+    let output = get_str(&row, 0);
+    if analyze {
+        query_plan::parse_mysql_tree(&output)
+    } else {
+        query_plan::parse_mysql_json(&output)
+    }
+}
+
+/// Applies `edits` in a transaction of their own.
+pub async fn apply_row_changes(
+    pool: &sqlx::MySqlPool,
+    edits: &[TableEdit],
+) -> Result<Vec<AppliedRowChanges>, String> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let results = apply_edits(&mut tx, edits).await?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit changes: {}", e))?;
+    Ok(results)
+}
+
+/// Runs `edits` on `conn`, inside whatever transaction the caller opened.
+async fn apply_edits(
+    conn: &mut MySqlConnection,
+    edits: &[TableEdit],
+) -> Result<Vec<AppliedRowChanges>, String> {
+    let dialect = Dialect::Mysql;
+    let batches = edits
+        .iter()
+        .map(|edit| {
+            let (database, table) = (&edit.target.database, &edit.target.table);
+            let table_ref = if database.is_empty() {
+                dialect.quote(table)
+            } else {
+                format!("{}.{}", dialect.quote(database), dialect.quote(table))
+            };
+            edit.statements(dialect, &table_ref, &HashMap::new())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut results = Vec::with_capacity(edits.len());
+    for (edit, statements) in edits.iter().zip(&batches) {
+        let mut applied = edit.applied();
+        for statement in statements {
+            let query = statement
+                .params
+                .iter()
+                .fold(sqlx::query(&statement.sql), bind_param);
+            let result = query
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| edit.in_table(statement.failed(e)))?;
+            applied
+                .record(statement, result.rows_affected())
+                .map_err(|e| edit.in_table(e))?;
+        }
+        results.push(applied);
+    }
+    Ok(results)
+}
+
+/// Aborts the statement running on `connection_id`, leaving the connection
+/// open. KILL goes out on a connection of its own, since the pool may have no
+/// free one while that statement runs.
+pub async fn kill_query(options: &MySqlConnectOptions, connection_id: u64) -> Result<(), String> {
+    let mut conn = MySqlConnection::connect_with(options)
+        .await
+        .map_err(|e| format!("Failed to cancel query: {}", e))?;
+    let kill = format!("KILL QUERY {}", connection_id);
+    let killed = conn
+        .execute(kill.as_str())
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("Failed to cancel query: {}", e));
+    let _ = conn.close().await;
+    killed
+}
+
 pub async fn list_users(pool: &sqlx::MySqlPool) -> Result<Vec<DatabaseUser>, String> {
     // SELECT user(0), host(1), super_priv(2), create_user_priv(3), create_priv(4),
     //        repl_slave_priv(5), account_locked(6), password_lifetime(7)
@@ -0,0 +1,310 @@
+// ABOUTME: Maps MySQL result values, as sent over the text protocol, to the grid's cell encoding.
+// ABOUTME: Pure functions over wire bytes so the type mapping can be tested without a server.
+
+use super::value;
+use serde_json::Value;
+
+/// Decodes one non-NULL value. Results are always read with unprepared
+/// statements, so every value arrives in the text protocol: numbers as their
+/// decimal text, BIT and binary strings as raw bytes. `type_name` is sqlx's
+/// name for the column type. Values that don't parse as their type are shown
+/// as text rather than dropped.
+pub fn text_value(type_name: &str, bytes: &[u8]) -> Value {
+    let text = || String::from_utf8_lossy(bytes).into_owned();
+    let parsed = match type_name {
+        "BOOLEAN" | "TINYINT(1)" => text().parse::<i64>().ok().map(|v| Value::Bool(v != 0)),
+        "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "BIGINT" => {
+            text().parse::<i64>().ok().map(Value::from)
+        }
+        // YEAR is flagged unsigned on some servers and not others.
+        "TINYINT UNSIGNED" | "SMALLINT UNSIGNED" | "MEDIUMINT UNSIGNED" | "INT UNSIGNED"
+        | "BIGINT UNSIGNED" | "YEAR" => text().parse::<u64>().ok().map(Value::from),
+        // A number rather than a bit string, so an exported value inserts
+        // back into a BIT column unchanged.
+        "BIT" => bits(bytes).ok().map(Value::from),
+        "FLOAT" | "DOUBLE" => text().parse::<f64>().ok().map(value::float),
+        "DECIMAL" => Some(value::decimal(&text())),
+        // Always tagged, even when the bytes happen to be valid UTF-8, so the
+        // cell matches the column's Binary logical type. sqlx can't tell a
+        // real binary column from a text column with a `_bin` collation.
+        "BINARY" | "VARBINARY" | "BLOB" | "TINYBLOB" | "MEDIUMBLOB" | "LONGBLOB" => {
+            Some(value::binary(bytes))
+        }
+        "GEOMETRY" => Some(
+            geometry(bytes)
+                .map(Value::String)
+                .unwrap_or_else(|_| value::binary(bytes)),
+        ),
+        "JSON" => serde_json::from_slice(bytes).ok(),
+        // SET stays MySQL's comma-separated form, which is also its input form.
+        _ => None,
+    };
+    parsed.unwrap_or_else(|| Value::String(text()))
+}
+
+/// BIT(M) values are big-endian bytes in both the text and binary protocol.
+/// M is at most 64, so every value fits a u64.
+pub fn bits(bytes: &[u8]) -> Result<u64, String> {
+    if bytes.len() > 8 {
+        return Err(format!(
+            "BIT value of {} bytes is wider than 64 bits",
+            bytes.len()
+        ));
+    }
+    Ok(bytes.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64))
+}
+
+/// GEOMETRY values are a little-endian SRID followed by WKB. Returns WKT as
+/// `ST_AsText` would, prefixed with `SRID=n;` when the SRID is not 0.
+pub fn geometry(bytes: &[u8]) -> Result<String, String> {
+    if bytes.len() < 4 {
+        return Err("GEOMETRY value is too short".to_string());
+    }
+    let srid = u32::from_le_bytes(bytes[..4].try_into().unwrap());
+    let mut wkb = Wkb {
+        bytes: &bytes[4..],
+        little_endian: true,
+    };
+    let wkt = wkb.geometry()?;
+    Ok(if srid == 0 {
+        wkt
+    } else {
+        format!("SRID={};{}", srid, wkt)
+    })
+}
+
+struct Wkb<'a> {
+    bytes: &'a [u8],
+    little_endian: bool,
+}
+
+impl Wkb<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        if self.bytes.len() < N {
+            return Err("WKB value is truncated".to_string());
+        }
+        let (head, rest) = self.bytes.split_at(N);
+        self.bytes = rest;
+        Ok(head.try_into().unwrap())
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.take::<4>()?;
+        Ok(if self.little_endian {
+            u32::from_le_bytes(b)
+        } else {
+            u32::from_be_bytes(b)
+        })
+    }
+
+    fn f64(&mut self) -> Result<f64, String> {
+        let b = self.take::<8>()?;
+        Ok(if self.little_endian {
+            f64::from_le_bytes(b)
+        } else {
+            f64::from_be_bytes(b)
+        })
+    }
+
+    fn point(&mut self) -> Result<String, String> {
+        Ok(format!("{} {}", self.f64()?, self.f64()?))
+    }
+
+    fn points(&mut self) -> Result<String, String> {
+        let n = self.u32()?;
+        Ok((0..n)
+            .map(|_| self.point())
+            .collect::<Result<Vec<_>, _>>()?
+            .join(","))
+    }
+
+    fn rings(&mut self) -> Result<String, String> {
+        let n = self.u32()?;
+        Ok((0..n)
+            .map(|_| Ok(format!("({})", self.points()?)))
+            .collect::<Result<Vec<_>, String>>()?
+            .join(","))
+    }
+
+    /// Members of a MULTI* or collection, each a complete WKB geometry.
+    /// `strip` removes the member's own type name, as WKT writes
+    /// `MULTIPOINT((1 2),(3 4))` rather than repeating `POINT`.
+    fn members(&mut self, strip: Option<&str>) -> Result<Vec<String>, String> {
+        let n = self.u32()?;
+        (0..n)
+            .map(|_| {
+                let member = self.geometry()?;
+                Ok(match strip {
+                    Some(name) => member.strip_prefix(name).unwrap_or(&member).to_string(),
+                    None => member,
+                })
+            })
+            .collect()
+    }
+
+    fn geometry(&mut self) -> Result<String, String> {
+        self.little_endian = self.take::<1>()?[0] == 1;
+        let (name, body) = match self.u32()? {
+            1 => ("POINT", self.point()?),
+            2 => ("LINESTRING", self.points()?),
+            3 => ("POLYGON", self.rings()?),
+            4 => ("MULTIPOINT", self.members(Some("POINT"))?.join(",")),
+            5 => (
+                "MULTILINESTRING",
+                self.members(Some("LINESTRING"))?.join(","),
+            ),
+            6 => ("MULTIPOLYGON", self.members(Some("POLYGON"))?.join(",")),
+            7 => ("GEOMETRYCOLLECTION", self.members(None)?.join(",")),
+            other => return Err(format!("unknown WKB geometry type {}", other)),
+        };
+        Ok(if body.is_empty() {
+            format!("{} EMPTY", name)
+        } else {
+            format!("{}({})", name, body)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wkb_point(x: f64, y: f64) -> Vec<u8> {
+        let mut b = vec![1];
+        b.extend(1u32.to_le_bytes());
+        b.extend(x.to_le_bytes());
+        b.extend(y.to_le_bytes());
+        b
+    }
+
+    #[test]
+    fn bits_cover_the_full_width() {
+        assert_eq!(bits(&[0x01]), Ok(1));
+        assert_eq!(bits(&[0x00]), Ok(0));
+        assert_eq!(bits(&[0x01, 0x00]), Ok(256));
+        assert_eq!(bits(&[0xff; 8]), Ok(u64::MAX));
+        assert!(bits(&[0xff; 9]).is_err());
+    }
+
+    #[test]
+    fn integer_boundaries_stay_exact() {
+        // Extremes must come out as exact JSON integers, not via f64.
+        let cases = [
+            ("BIGINT UNSIGNED", "18446744073709551615"),
+            ("INT UNSIGNED", "4294967295"),
+            ("BIGINT", "-9223372036854775808"),
+            ("BIGINT", "9223372036854775807"),
+            ("TINYINT", "-128"),
+            ("YEAR", "2155"),
+        ];
+        for (type_name, text) in cases {
+            let v = text_value(type_name, text.as_bytes());
+            assert!(v.is_number(), "{} {}", type_name, text);
+            assert_eq!(v.to_string(), text);
+        }
+        assert_eq!(text_value("BOOLEAN", b"1"), Value::Bool(true));
+        assert_eq!(text_value("BIT", &[0x01, 0x00]), Value::from(256));
+    }
+
+    #[test]
+    fn decimal_boundaries_stay_exact() {
+        let max = "99999999999999999999999999999999999.999999999999999999999999999999";
+        assert_eq!(
+            text_value("DECIMAL", max.as_bytes()),
+            Value::String(max.into())
+        );
+        assert_eq!(
+            text_value("DECIMAL", b"-0.000000000000000000000000000001"),
+            "-0.000000000000000000000000000001"
+        );
+        assert_eq!(text_value("DOUBLE", b"1.5"), Value::from(1.5));
+    }
+
+    #[test]
+    fn binary_columns_are_always_tagged() {
+        for type_name in ["BINARY", "VARBINARY", "BLOB", "LONGBLOB"] {
+            assert_eq!(
+                super::super::mysql_logical_type(type_name),
+                value::LogicalType::Binary
+            );
+            // Valid UTF-8 is still binary: the column says so.
+            assert_eq!(text_value(type_name, b"hello")["hex"], "68656c6c6f");
+        }
+        assert_eq!(
+            text_value("VARBINARY", &[0xde, 0xad, 0xbe, 0xef])["hex"],
+            "deadbeef"
+        );
+        // BINARY(4) pads with zero bytes
+        assert_eq!(text_value("BINARY", b"ab\0\0")["hex"], "61620000");
+    }
+
+    #[test]
+    fn text_like_types_stay_text() {
+        assert_eq!(text_value("SET", b"a,b"), "a,b");
+        assert_eq!(text_value("VARCHAR", "żółw".as_bytes()), "żółw");
+        assert_eq!(
+            text_value("DATETIME", b"2024-01-02 03:04:05"),
+            "2024-01-02 03:04:05"
+        );
+        assert_eq!(text_value("JSON", br#"{"a":1}"#)["a"], 1);
+        // A value that doesn't parse as its type is shown rather than lost.
+        assert_eq!(text_value("INT", b"n/a"), "n/a");
+    }
+
+    #[test]
+    fn geometry_point_with_and_without_srid() {
+        let mut b = 0u32.to_le_bytes().to_vec();
+        b.extend(wkb_point(1.0, -2.5));
+        assert_eq!(geometry(&b).unwrap(), "POINT(1 -2.5)");
+
+        let mut b = 4326u32.to_le_bytes().to_vec();
+        b.extend(wkb_point(13.4, 52.5));
+        assert_eq!(geometry(&b).unwrap(), "SRID=4326;POINT(13.4 52.5)");
+    }
+
+    #[test]
+    fn geometry_polygon_and_multipoint() {
+        let mut b = 0u32.to_le_bytes().to_vec();
+        b.push(1);
+        b.extend(3u32.to_le_bytes());
+        b.extend(1u32.to_le_bytes());
+        b.extend(4u32.to_le_bytes());
+        for (x, y) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 0.0)] {
+            b.extend(f64::to_le_bytes(x));
+            b.extend(f64::to_le_bytes(y));
+        }
+        assert_eq!(geometry(&b).unwrap(), "POLYGON((0 0,1 0,1 1,0 0))");
+
+        let mut b = 0u32.to_le_bytes().to_vec();
+        b.push(1);
+        b.extend(4u32.to_le_bytes());
+        b.extend(2u32.to_le_bytes());
+        b.extend(wkb_point(1.0, 2.0));
+        b.extend(wkb_point(3.0, 4.0));
+        assert_eq!(geometry(&b).unwrap(), "MULTIPOINT((1 2),(3 4))");
+    }
+
+    #[test]
+    fn geometry_big_endian_and_empty_collection() {
+        let mut b = 0u32.to_le_bytes().to_vec();
+        b.push(0);
+        b.extend(1u32.to_be_bytes());
+        b.extend(5.0f64.to_be_bytes());
+        b.extend(6.0f64.to_be_bytes());
+        assert_eq!(geometry(&b).unwrap(), "POINT(5 6)");
+
+        let mut b = 0u32.to_le_bytes().to_vec();
+        b.push(1);
+        b.extend(7u32.to_le_bytes());
+        b.extend(0u32.to_le_bytes());
+        assert_eq!(geometry(&b).unwrap(), "GEOMETRYCOLLECTION EMPTY");
+    }
+
+    #[test]
+    fn truncated_geometry_is_an_error() {
+        assert!(geometry(&[0, 0]).is_err());
+        let mut b = 0u32.to_le_bytes().to_vec();
+        b.extend(&wkb_point(1.0, 2.0)[..10]);
+        assert!(geometry(&b).is_err());
+    }
+}
@@ -0,0 +1,1255 @@
+// ABOUTME: Decodes Postgres wire values, in binary or text format, for types sqlx has no JSON mapping for.
+// ABOUTME: Covers arrays, ranges, interval, network, geometric, money, numeric and pg_type-resolved enums/composites.
+
+use super::value::{self, TYPE_TAG};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+
+/// OIDs from the default pg_type catalog, which are the same on every server.
+pub mod oid {
+    pub const BOOL: u32 = 16;
+    pub const BYTEA: u32 = 17;
+    pub const CHAR: u32 = 18;
+    pub const NAME: u32 = 19;
+    pub const INT8: u32 = 20;
+    pub const INT2: u32 = 21;
+    pub const INT4: u32 = 23;
+    pub const TEXT: u32 = 25;
+    pub const OID: u32 = 26;
+    pub const JSON: u32 = 114;
+    pub const XML: u32 = 142;
+    pub const POINT: u32 = 600;
+    pub const LSEG: u32 = 601;
+    pub const PATH: u32 = 602;
+    pub const BOX: u32 = 603;
+    pub const POLYGON: u32 = 604;
+    pub const LINE: u32 = 628;
+    pub const CIDR: u32 = 650;
+    pub const FLOAT4: u32 = 700;
+    pub const FLOAT8: u32 = 701;
+    pub const UNKNOWN: u32 = 705;
+    pub const CIRCLE: u32 = 718;
+    pub const MACADDR8: u32 = 774;
+    pub const MONEY: u32 = 790;
+    pub const MACADDR: u32 = 829;
+    pub const INET: u32 = 869;
+    pub const BPCHAR: u32 = 1042;
+    pub const VARCHAR: u32 = 1043;
+    pub const DATE: u32 = 1082;
+    pub const TIME: u32 = 1083;
+    pub const TIMESTAMP: u32 = 1114;
+    pub const TIMESTAMPTZ: u32 = 1184;
+    pub const INTERVAL: u32 = 1186;
+    pub const TIMETZ: u32 = 1266;
+    pub const BIT: u32 = 1560;
+    pub const VARBIT: u32 = 1562;
+    pub const NUMERIC: u32 = 1700;
+    pub const RECORD: u32 = 2249;
+    pub const UUID: u32 = 2950;
+    pub const JSONB: u32 = 3802;
+    pub const INT4RANGE: u32 = 3904;
+    pub const NUMRANGE: u32 = 3906;
+    pub const TSRANGE: u32 = 3908;
+    pub const TSTZRANGE: u32 = 3910;
+    pub const DATERANGE: u32 = 3912;
+    pub const INT8RANGE: u32 = 3926;
+}
+
+/// OIDs below this are assigned by initdb; anything at or above it was
+/// created later (extensions, CREATE TYPE) and must be looked up in pg_type.
+const FIRST_NORMAL_OID: u32 = 16384;
+
+/// Built-in array types and their element type.
+const BUILTIN_ARRAYS: &[(u32, u32)] = &[
+    (1000, oid::BOOL),
+    (1001, oid::BYTEA),
+    (1002, oid::CHAR),
+    (1003, oid::NAME),
+    (1005, oid::INT2),
+    (1007, oid::INT4),
+    (1009, oid::TEXT),
+    (1014, oid::BPCHAR),
+    (1015, oid::VARCHAR),
+    (1016, oid::INT8),
+    (1017, oid::POINT),
+    (1018, oid::LSEG),
+    (1019, oid::PATH),
+    (1020, oid::BOX),
+    (1021, oid::FLOAT4),
+    (1022, oid::FLOAT8),
+    (1027, oid::POLYGON),
+    (1028, oid::OID),
+    (1040, oid::MACADDR),
+    (1041, oid::INET),
+    (1115, oid::TIMESTAMP),
+    (1182, oid::DATE),
+    (1183, oid::TIME),
+    (1185, oid::TIMESTAMPTZ),
+    (1187, oid::INTERVAL),
+    (1231, oid::NUMERIC),
+    (1270, oid::TIMETZ),
+    (1561, oid::BIT),
+    (1563, oid::VARBIT),
+    (143, oid::XML),
+    (199, oid::JSON),
+    (629, oid::LINE),
+    (651, oid::CIDR),
+    (719, oid::CIRCLE),
+    (775, oid::MACADDR8),
+    (791, oid::MONEY),
+    (2951, oid::UUID),
+    (3807, oid::JSONB),
+    (3905, oid::INT4RANGE),
+    (3907, oid::NUMRANGE),
+    (3909, oid::TSRANGE),
+    (3911, oid::TSTZRANGE),
+    (3913, oid::DATERANGE),
+    (3927, oid::INT8RANGE),
+];
+
+/// Built-in range types and their subtype.
+const BUILTIN_RANGES: &[(u32, u32)] = &[
+    (oid::INT4RANGE, oid::INT4),
+    (oid::INT8RANGE, oid::INT8),
+    (oid::NUMRANGE, oid::NUMERIC),
+    (oid::TSRANGE, oid::TIMESTAMP),
+    (oid::TSTZRANGE, oid::TIMESTAMPTZ),
+    (oid::DATERANGE, oid::DATE),
+];
+
+/// How a type's values are laid out, as far as decoding is concerned.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PgKind {
+    /// A scalar, decoded by its OID.
+    Base,
+    /// Array of the given element type.
+    Array(u32),
+    /// Range over the given subtype.
+    Range(u32),
+    Enum,
+    /// Named fields in declaration order. Empty for anonymous records.
+    Composite(Vec<(String, u32)>),
+    /// Domain over the given base type.
+    Domain(u32),
+}
+
+/// Wire format of a single value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireFormat {
+    Text,
+    Binary,
+}
+
+/// User-defined types seen in a result, resolved from pg_type. Built-in
+/// types never need an entry.
+#[derive(Debug, Clone, Default)]
+pub struct PgTypeCatalog {
+    types: HashMap<u32, (String, PgKind)>,
+}
+
+impl PgTypeCatalog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, oid: u32, name: &str, kind: PgKind) {
+        self.types.insert(oid, (name.to_string(), kind));
+    }
+
+    pub fn kind(&self, type_oid: u32) -> PgKind {
+        if type_oid == oid::RECORD {
+            return PgKind::Composite(Vec::new());
+        }
+        if let Some((_, elem)) = BUILTIN_ARRAYS.iter().find(|(a, _)| *a == type_oid) {
+            return PgKind::Array(*elem);
+        }
+        if let Some((_, sub)) = BUILTIN_RANGES.iter().find(|(r, _)| *r == type_oid) {
+            return PgKind::Range(*sub);
+        }
+        self.types
+            .get(&type_oid)
+            .map(|(_, kind)| kind.clone())
+            .unwrap_or(PgKind::Base)
+    }
+
+    /// The pg_type name of a catalog-resolved type.
+    pub fn name(&self, type_oid: u32) -> Option<&str> {
+        self.types.get(&type_oid).map(|(name, _)| name.as_str())
+    }
+
+    /// User-defined OIDs reachable from `oids` (through array elements, range
+    /// subtypes, composite fields and domain bases) that still need a pg_type
+    /// lookup.
+    pub fn unresolved(&self, oids: &[u32]) -> Vec<u32> {
+        let mut pending: Vec<u32> = oids.to_vec();
+        let mut seen = HashSet::new();
+        let mut missing = Vec::new();
+        while let Some(type_oid) = pending.pop() {
+            if !seen.insert(type_oid) {
+                continue;
+            }
+            if type_oid >= FIRST_NORMAL_OID && !self.types.contains_key(&type_oid) {
+                missing.push(type_oid);
+                continue;
+            }
+            match self.kind(type_oid) {
+                PgKind::Array(inner) | PgKind::Range(inner) | PgKind::Domain(inner) => {
+                    pending.push(inner)
+                }
+                PgKind::Composite(fields) => pending.extend(fields.iter().map(|(_, f)| *f)),
+                PgKind::Base | PgKind::Enum => {}
+            }
+        }
+        missing.sort_unstable();
+        missing
+    }
+}
+
+/// Decodes one non-NULL value. Errors mean the bytes did not match the type
+/// and the caller should fall back to showing them as text.
+pub fn decode(
+    bytes: &[u8],
+    format: WireFormat,
+    type_oid: u32,
+    catalog: &PgTypeCatalog,
+) -> Result<Value, String> {
+    match (catalog.kind(type_oid), format) {
+        (PgKind::Domain(base), _) => decode(bytes, format, base, catalog),
+        (PgKind::Enum, _) => utf8(bytes).map(Value::String),
+        (PgKind::Array(_), WireFormat::Binary) => decode_array_binary(bytes, catalog),
+        (PgKind::Array(elem), WireFormat::Text) => {
+            let delimiter = if elem == oid::BOX { ';' } else { ',' };
+            decode_array_text(&utf8(bytes)?, elem, delimiter, catalog)
+        }
+        (PgKind::Range(sub), WireFormat::Binary) => decode_range_binary(bytes, sub, catalog),
+        (PgKind::Range(sub), WireFormat::Text) => decode_range_text(&utf8(bytes)?, sub, catalog),
+        (PgKind::Composite(fields), WireFormat::Binary) => {
+            decode_record_binary(bytes, &fields, catalog)
+        }
+        (PgKind::Composite(fields), WireFormat::Text) => {
+            decode_record_text(&utf8(bytes)?, &fields, catalog)
+        }
+        (PgKind::Base, WireFormat::Binary) => decode_scalar_binary(bytes, type_oid),
+        (PgKind::Base, WireFormat::Text) => Ok(decode_scalar_text(&utf8(bytes)?, type_oid)),
+    }
+}
+
+fn utf8(bytes: &[u8]) -> Result<String, String> {
+    String::from_utf8(bytes.to_vec()).map_err(|e| e.to_string())
+}
+
+/// Big-endian reader over a binary-format value.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        if self.bytes.len() < n {
+            return Err("value is shorter than its type requires".to_string());
+        }
+        let (head, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn i16(&mut self) -> Result<i16, String> {
+        Ok(i16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn i64(&mut self) -> Result<i64, String> {
+        Ok(i64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn f64(&mut self) -> Result<f64, String> {
+        Ok(f64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    /// A length-prefixed value as used inside arrays, ranges and records;
+    /// a length of -1 is NULL.
+    fn value(&mut self) -> Result<Option<&'a [u8]>, String> {
+        let len = self.i32()?;
+        if len < 0 {
+            return Ok(None);
+        }
+        self.take(len as usize).map(Some)
+    }
+}
+
+fn decode_scalar_binary(bytes: &[u8], type_oid: u32) -> Result<Value, String> {
+    let mut r = Reader::new(bytes);
+    Ok(match type_oid {
+        oid::BOOL => Value::Bool(r.u8()? != 0),
+        oid::INT2 => r.i16()?.into(),
+        oid::INT4 => r.i32()?.into(),
+        oid::INT8 => r.i64()?.into(),
+        oid::OID => r.u32()?.into(),
+        oid::FLOAT4 => value::float(f32::from_be_bytes(r.take(4)?.try_into().unwrap()) as f64),
+        oid::FLOAT8 => value::float(r.f64()?),
+        oid::NUMERIC => value::decimal(&numeric(&mut r)?),
+        oid::MONEY => Value::String(money(r.i64()?)),
+        oid::TEXT
+        | oid::VARCHAR
+        | oid::BPCHAR
+        | oid::NAME
+        | oid::CHAR
+        | oid::UNKNOWN
+        | oid::XML => Value::String(utf8(bytes)?),
+        oid::BYTEA => value::binary(bytes),
+        oid::UUID => Value::String(
+            uuid::Uuid::from_slice(bytes)
+                .map_err(|e| e.to_string())?
+                .to_string(),
+        ),
+        oid::JSON => json_text(&utf8(bytes)?),
+        oid::JSONB => {
+            // JSONB is a version byte (always 1) followed by the JSON text.
+            r.u8()?;
+            json_text(&utf8(r.bytes)?)
+        }
+        oid::DATE => Value::String(date(r.i32()?)),
+        oid::TIME => Value::String(time_of_day(r.i64()?)),
+        oid::TIMETZ => {
+            let micros = r.i64()?;
+            // The zone is stored as seconds *west* of UTC.
+            let offset = -r.i32()?;
+            Value::String(format!("{}{}", time_of_day(micros), utc_offset(offset)))
+        }
+        oid::TIMESTAMP => Value::String(timestamp(r.i64()?, false)),
+        oid::TIMESTAMPTZ => Value::String(timestamp(r.i64()?, true)),
+        oid::INTERVAL => {
+            let micros = r.i64()?;
+            let days = r.i32()?;
+            let months = r.i32()?;
+            Value::String(interval(months, days, micros))
+        }
+        oid::INET | oid::CIDR => Value::String(inet(&mut r)?),
+        oid::MACADDR | oid::MACADDR8 => Value::String(
+            bytes
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<Vec<_>>()
+                .join(":"),
+        ),
+        oid::POINT => Value::String(point(&mut r)?),
+        oid::LSEG => Value::String(format!("[{},{}]", point(&mut r)?, point(&mut r)?)),
+        oid::BOX => Value::String(format!("{},{}", point(&mut r)?, point(&mut r)?)),
+        oid::LINE => Value::String(format!("{{{},{},{}}}", r.f64()?, r.f64()?, r.f64()?)),
+        oid::CIRCLE => Value::String(format!("<{},{}>", point(&mut r)?, r.f64()?)),
+        oid::PATH => {
+            let closed = r.u8()? != 0;
+            let points = points(&mut r)?;
+            Value::String(if closed {
+                format!("({})", points)
+            } else {
+                format!("[{}]", points)
+            })
+        }
+        oid::POLYGON => Value::String(format!("({})", points(&mut r)?)),
+        oid::BIT | oid::VARBIT => {
+            let len = r.i32()?.max(0) as usize;
+            let bits = r.take(len.div_ceil(8))?;
+            Value::String(
+                (0..len)
+                    .map(|i| {
+                        if bits[i / 8] & (0x80 >> (i % 8)) != 0 {
+                            '1'
+                        } else {
+                            '0'
+                        }
+                    })
+                    .collect(),
+            )
+        }
+        _ => return Err(format!("no binary decoder for type OID {}", type_oid)),
+    })
+}
+
+/// Text format is already what psql would show; only types with a natural
+/// JSON form are converted.
+fn decode_scalar_text(text: &str, type_oid: u32) -> Value {
+    let converted = match type_oid {
+        oid::BOOL => match text {
+            "t" => Some(Value::Bool(true)),
+            "f" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        oid::INT2 | oid::INT4 | oid::INT8 | oid::OID => text.parse::<i64>().ok().map(Value::from),
+        oid::FLOAT4 | oid::FLOAT8 => text.parse::<f64>().ok().map(value::float),
+        oid::NUMERIC => Some(value::decimal(text)),
+        oid::BYTEA => text
+            .strip_prefix("\\x")
+            .and_then(value::unhex)
+            .map(|bytes| value::binary(&bytes)),
+        oid::JSON | oid::JSONB => Some(json_text(text)),
+        _ => None,
+    };
+    converted.unwrap_or_else(|| Value::String(text.to_string()))
+}
+
+fn json_text(text: &str) -> Value {
+    serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()))
+}
+
+/// NUMERIC is base-10000 digits with a weight (position of the first digit
+/// group relative to the decimal point) and a display scale.
+fn numeric(r: &mut Reader) -> Result<String, String> {
+    let ndigits = r.i16()?.max(0) as usize;
+    let weight = r.i16()? as i32;
+    let sign = r.u16()?;
+    let dscale = r.u16()? as usize;
+    match sign {
+        0xC000 => return Ok("NaN".to_string()),
+        0xD000 => return Ok("Infinity".to_string()),
+        0xF000 => return Ok("-Infinity".to_string()),
+        _ => {}
+    }
+    let digits = (0..ndigits)
+        .map(|_| r.i16())
+        .collect::<Result<Vec<_>, _>>()?;
+    let group = |i: i32| -> i16 {
+        if i >= 0 && (i as usize) < digits.len() {
+            digits[i as usize]
+        } else {
+            0
+        }
+    };
+
+    let mut out = String::new();
+    if sign == 0x4000 && digits.iter().any(|d| *d != 0) {
+        out.push('-');
+    }
+    if weight < 0 {
+        out.push('0');
+    } else {
+        out.push_str(&group(0).to_string());
+        for i in 1..=weight {
+            out.push_str(&format!("{:04}", group(i)));
+        }
+    }
+    if dscale > 0 {
+        let mut fraction = String::new();
+        let groups = dscale.div_ceil(4) as i32;
+        for k in 0..groups {
+            fraction.push_str(&format!("{:04}", group(weight + 1 + k)));
+        }
+        fraction.truncate(dscale);
+        out.push('.');
+        out.push_str(&fraction);
+    }
+    Ok(out)
+}
+
+/// MONEY is an integer count of the currency's minor unit. The number of
+/// fraction digits depends on lc_monetary; two is by far the most common.
+fn money(minor_units: i64) -> String {
+    let sign = if minor_units < 0 { "-" } else { "" };
+    let abs = minor_units.unsigned_abs();
+    format!("{}{}.{:02}", sign, abs / 100, abs % 100)
+}
+
+fn pg_epoch() -> chrono::NaiveDateTime {
+    chrono::NaiveDate::from_ymd_opt(2000, 1, 1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+}
+
+fn date(days: i32) -> String {
+    match days {
+        i32::MAX => "infinity".to_string(),
+        i32::MIN => "-infinity".to_string(),
+        _ => pg_epoch()
+            .date()
+            .checked_add_signed(chrono::Duration::days(days as i64))
+            .map_or_else(|| date_text(days as i64), |d| d.to_string()),
+    }
+}
+
+fn time_of_day(micros: i64) -> String {
+    (chrono::NaiveTime::MIN + chrono::Duration::microseconds(micros)).to_string()
+}
+
+/// Naive timestamps in chrono's `Display` form, zoned ones as RFC 3339 in
+/// UTC; values outside chrono's range fall back to Postgres's text form.
+fn timestamp(micros: i64, zoned: bool) -> String {
+    match micros {
+        i64::MAX => "infinity".to_string(),
+        i64::MIN => "-infinity".to_string(),
+        _ => match pg_epoch().checked_add_signed(chrono::Duration::microseconds(micros)) {
+            Some(naive) if zoned => naive.and_utc().to_rfc3339(),
+            Some(naive) => naive.to_string(),
+            None => timestamp_text(micros, zoned),
+        },
+    }
+}
+
+const MICROS_PER_DAY: i64 = 86_400_000_000;
+
+/// Postgres's own ISO output for the date `days` after 2000-01-01. Postgres
+/// accepts years past chrono's range (dates run to 5874897 AD), so those are
+/// formatted here. Postgres's earliest dates are still within chrono's range.
+fn date_text(days: i64) -> String {
+    // Days to civil date, counting from 0000-03-01 so leap days fall last.
+    let z = days + 10_957 + 719_468;
+    let (era, day_of_era) = (z.div_euclid(146_097), z.rem_euclid(146_097));
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn timestamp_text(micros: i64, zoned: bool) -> String {
+    let date = date_text(micros.div_euclid(MICROS_PER_DAY));
+    let of_day = micros.rem_euclid(MICROS_PER_DAY);
+    let secs = of_day / 1_000_000;
+    let mut out = format!(
+        "{} {:02}:{:02}:{:02}",
+        date,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    );
+    let fraction = of_day % 1_000_000;
+    if fraction != 0 {
+        out.push_str(format!(".{:06}", fraction).trim_end_matches('0'));
+    }
+    if zoned {
+        out.push_str("+00");
+    }
+    out
+}
+
+fn utc_offset(seconds_east: i32) -> String {
+    let sign = if seconds_east < 0 { '-' } else { '+' };
+    let abs = seconds_east.unsigned_abs();
+    let (hours, minutes, seconds) = (abs / 3600, abs / 60 % 60, abs % 60);
+    let mut out = format!("{}{:02}", sign, hours);
+    if minutes != 0 || seconds != 0 {
+        out.push_str(&format!(":{:02}", minutes));
+    }
+    if seconds != 0 {
+        out.push_str(&format!(":{:02}", seconds));
+    }
+    out
+}
+
+/// Formats an interval the way Postgres' default `postgres` IntervalStyle
+/// does, e.g. `1 year 2 mons 3 days 04:05:06.5`.
+fn interval(months: i32, days: i32, micros: i64) -> String {
+    let mut parts = Vec::new();
+    let mut unit = |n: i64, singular: &str, plural: &str| {
+        if n != 0 {
+            parts.push(format!("{} {}", n, if n == 1 { singular } else { plural }));
+        }
+    };
+    unit((months / 12) as i64, "year", "years");
+    unit((months % 12) as i64, "mon", "mons");
+    unit(days as i64, "day", "days");
+
+    if micros != 0 || parts.is_empty() {
+        let sign = if micros < 0 { "-" } else { "" };
+        let abs = micros.unsigned_abs();
+        let secs = abs / 1_000_000;
+        let mut time = format!(
+            "{}{:02}:{:02}:{:02}",
+            sign,
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60
+        );
+        let fraction = abs % 1_000_000;
+        if fraction != 0 {
+            time.push_str(format!(".{:06}", fraction).trim_end_matches('0'));
+        }
+        parts.push(time);
+    }
+    parts.join(" ")
+}
+
+/// INET / CIDR: family, netmask bits, is-cidr flag, address length, address.
+fn inet(r: &mut Reader) -> Result<String, String> {
+    let family = r.u8()?;
+    let bits = r.u8()?;
+    let is_cidr = r.u8()? != 0;
+    let len = r.u8()? as usize;
+    let addr = r.take(len)?;
+    let (text, max_bits) = match (family, len) {
+        (2, 4) => (
+            std::net::Ipv4Addr::new(addr[0], addr[1], addr[2], addr[3]).to_string(),
+            32,
+        ),
+        (3, 16) => {
+            let octets: [u8; 16] = addr.try_into().unwrap();
+            (std::net::Ipv6Addr::from(octets).to_string(), 128)
+        }
+        _ => return Err(format!("unknown inet address family {}", family)),
+    };
+    // Like Postgres, a host address omits its full-length netmask; CIDR never does.
+    Ok(if is_cidr || bits != max_bits {
+        format!("{}/{}", text, bits)
+    } else {
+        text
+    })
+}
+
+fn point(r: &mut Reader) -> Result<String, String> {
+    Ok(format!("({},{})", r.f64()?, r.f64()?))
+}
+
+fn points(r: &mut Reader) -> Result<String, String> {
+    let count = r.i32()?.max(0);
+    Ok((0..count)
+        .map(|_| point(r))
+        .collect::<Result<Vec<_>, _>>()?
+        .join(","))
+}
+
+/// Binary arrays: dimension count, a has-nulls flag, the element OID, a
+/// (length, lower bound) pair per dimension, then every element in row-major
+/// order. Multi-dimensional arrays become nested JSON arrays.
+fn decode_array_binary(bytes: &[u8], catalog: &PgTypeCatalog) -> Result<Value, String> {
+    let mut r = Reader::new(bytes);
+    let ndim = r.i32()?.max(0) as usize;
+    let _has_nulls = r.i32()?;
+    let elem = r.u32()?;
+    let mut dims = Vec::with_capacity(ndim);
+    for _ in 0..ndim {
+        dims.push(r.i32()?.max(0) as usize);
+        let _lower_bound = r.i32()?;
+    }
+    if dims.is_empty() {
+        return Ok(Value::Array(Vec::new()));
+    }
+
+    fn level(
+        r: &mut Reader,
+        dims: &[usize],
+        elem: u32,
+        catalog: &PgTypeCatalog,
+    ) -> Result<Value, String> {
+        let mut items = Vec::with_capacity(dims[0]);
+        for _ in 0..dims[0] {
+            items.push(if dims.len() > 1 {
+                level(r, &dims[1..], elem, catalog)?
+            } else {
+                match r.value()? {
+                    Some(bytes) => decode(bytes, WireFormat::Binary, elem, catalog)?,
+                    None => Value::Null,
+                }
+            });
+        }
+        Ok(Value::Array(items))
+    }
+    level(&mut r, &dims, elem, catalog)
+}
+
+/// Text arrays: `{1,2,NULL}`, `{{1,2},{3,4}}`, `{"a b","c\"d"}`, optionally
+/// prefixed by explicit bounds such as `[0:1]=`.
+fn decode_array_text(
+    text: &str,
+    elem: u32,
+    delimiter: char,
+    catalog: &PgTypeCatalog,
+) -> Result<Value, String> {
+    let start = text.find('{').ok_or("array literal has no '{'")?;
+    let mut chars = text[start..].chars().peekable();
+
+    fn level(
+        chars: &mut std::iter::Peekable<std::str::Chars>,
+        elem: u32,
+        delimiter: char,
+        catalog: &PgTypeCatalog,
+    ) -> Result<Value, String> {
+        if chars.next() != Some('{') {
+            return Err("expected '{' in array literal".to_string());
+        }
+        let mut items = Vec::new();
+        if chars.peek() == Some(&'}') {
+            chars.next();
+            return Ok(Value::Array(items));
+        }
+        loop {
+            while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                chars.next();
+            }
+            let item = match chars.peek() {
+                Some('{') => level(chars, elem, delimiter, catalog)?,
+                Some('"') => {
+                    chars.next();
+                    let mut s = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('\\') => s.extend(chars.next()),
+                            Some('"') => break,
+                            Some(c) => s.push(c),
+                            None => return Err("unterminated quoted array element".to_string()),
+                        }
+                    }
+                    decode(s.as_bytes(), WireFormat::Text, elem, catalog)?
+                }
+                _ => {
+                    let mut s = String::new();
+                    while let Some(&c) = chars.peek() {
+                        if c == delimiter || c == '}' {
+                            break;
+                        }
+                        s.push(c);
+                        chars.next();
+                    }
+                    let s = s.trim();
+                    if s.eq_ignore_ascii_case("NULL") {
+                        Value::Null
+                    } else {
+                        decode(s.as_bytes(), WireFormat::Text, elem, catalog)?
+                    }
+                }
+            };
+            items.push(item);
+            while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                chars.next();
+            }
+            match chars.next() {
+                Some(c) if c == delimiter => continue,
+                Some('}') => return Ok(Value::Array(items)),
+                _ => return Err("malformed array literal".to_string()),
+            }
+        }
+    }
+    level(&mut chars, elem, delimiter, catalog)
+}
+
+const RANGE_EMPTY: u8 = 0x01;
+const RANGE_LB_INC: u8 = 0x02;
+const RANGE_UB_INC: u8 = 0x04;
+const RANGE_LB_INF: u8 = 0x08;
+const RANGE_UB_INF: u8 = 0x10;
+
+/// Encodes a range as `{"$type": "range", "empty", "lower", "upper",
+/// "lower_inclusive", "upper_inclusive"}`; an unbounded side is null.
+fn range(empty: bool, lower: Value, upper: Value, lower_inc: bool, upper_inc: bool) -> Value {
+    serde_json::json!({
+        TYPE_TAG: "range",
+        "empty": empty,
+        "lower": lower,
+        "upper": upper,
+        "lower_inclusive": lower_inc,
+        "upper_inclusive": upper_inc,
+    })
+}
+
+fn decode_range_binary(bytes: &[u8], sub: u32, catalog: &PgTypeCatalog) -> Result<Value, String> {
+    let mut r = Reader::new(bytes);
+    let flags = r.u8()?;
+    if flags & RANGE_EMPTY != 0 {
+        return Ok(range(true, Value::Null, Value::Null, false, false));
+    }
+    let mut bound = |infinite: bool| -> Result<Value, String> {
+        if infinite {
+            return Ok(Value::Null);
+        }
+        match r.value()? {
+            Some(bytes) => decode(bytes, WireFormat::Binary, sub, catalog),
+            None => Ok(Value::Null),
+        }
+    };
+    let lower = bound(flags & RANGE_LB_INF != 0)?;
+    let upper = bound(flags & RANGE_UB_INF != 0)?;
+    Ok(range(
+        false,
+        lower,
+        upper,
+        flags & RANGE_LB_INC != 0,
+        flags & RANGE_UB_INC != 0,
+    ))
+}
+
+/// Text ranges: `empty`, `[1,10)`, `(,5]`, `["2024-01-01 00:00:00+00",)`.
+fn decode_range_text(text: &str, sub: u32, catalog: &PgTypeCatalog) -> Result<Value, String> {
+    let text = text.trim();
+    if text.eq_ignore_ascii_case("empty") {
+        return Ok(range(true, Value::Null, Value::Null, false, false));
+    }
+    let lower_inc = text.starts_with('[');
+    let upper_inc = text.ends_with(']');
+    let body = text
+        .get(1..text.len().saturating_sub(1))
+        .ok_or("malformed range literal")?;
+    let bounds = split_fields(body);
+    let [lower, upper] = bounds.as_slice() else {
+        return Err("range literal needs exactly two bounds".to_string());
+    };
+    let decode_bound = |bound: &Option<String>| match bound {
+        Some(s) => decode(s.as_bytes(), WireFormat::Text, sub, catalog),
+        None => Ok(Value::Null),
+    };
+    Ok(range(
+        false,
+        decode_bound(lower)?,
+        decode_bound(upper)?,
+        lower_inc,
+        upper_inc,
+    ))
+}
+
+fn field_name(fields: &[(String, u32)], index: usize) -> String {
+    fields
+        .get(index)
+        .map(|(name, _)| name.clone())
+        .unwrap_or_else(|| format!("f{}", index + 1))
+}
+
+/// Binary records: field count, then (type OID, length-prefixed value) per
+/// field. Field names come from the catalog; anonymous records get Postgres'
+/// own `f1`, `f2`, ... names.
+fn decode_record_binary(
+    bytes: &[u8],
+    fields: &[(String, u32)],
+    catalog: &PgTypeCatalog,
+) -> Result<Value, String> {
+    let mut r = Reader::new(bytes);
+    let count = r.i32()?.max(0) as usize;
+    let mut out = Map::new();
+    for index in 0..count {
+        let field_oid = r.u32()?;
+        let v = match r.value()? {
+            Some(bytes) => decode(bytes, WireFormat::Binary, field_oid, catalog)?,
+            None => Value::Null,
+        };
+        out.insert(field_name(fields, index), v);
+    }
+    Ok(Value::Object(out))
+}
+
+/// Text records: `(1,"a b",)`, where an empty unquoted field is NULL.
+fn decode_record_text(
+    text: &str,
+    fields: &[(String, u32)],
+    catalog: &PgTypeCatalog,
+) -> Result<Value, String> {
+    let body = text
+        .trim()
+        .strip_prefix('(')
+        .and_then(|t| t.strip_suffix(')'))
+        .ok_or("malformed record literal")?;
+    let mut out = Map::new();
+    for (index, field) in split_fields(body).into_iter().enumerate() {
+        let field_oid = fields.get(index).map_or(oid::UNKNOWN, |(_, f)| *f);
+        let v = match field {
+            Some(s) => decode(s.as_bytes(), WireFormat::Text, field_oid, catalog)?,
+            None => Value::Null,
+        };
+        out.insert(field_name(fields, index), v);
+    }
+    Ok(Value::Object(out))
+}
+
+/// Splits the inside of a record or range literal on top-level commas and
+/// unquotes each field. `None` is a field that was empty and unquoted.
+fn split_fields(body: &str) -> Vec<Option<String>> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut in_quotes = false;
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                current.push('"');
+            }
+            '"' => {
+                in_quotes = !in_quotes;
+                quoted = true;
+            }
+            '\\' => current.extend(chars.next()),
+            ',' if !in_quotes => {
+                fields.push((quoted || !current.is_empty()).then(|| std::mem::take(&mut current)));
+                quoted = false;
+            }
+            _ => current.push(c),
+        }
+    }
+    fields.push((quoted || !current.is_empty()).then_some(current));
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn binary(bytes: &[u8], type_oid: u32) -> Value {
+        decode(bytes, WireFormat::Binary, type_oid, &PgTypeCatalog::new()).unwrap()
+    }
+
+    fn text(s: &str, type_oid: u32) -> Value {
+        decode(
+            s.as_bytes(),
+            WireFormat::Text,
+            type_oid,
+            &PgTypeCatalog::new(),
+        )
+        .unwrap()
+    }
+
+    /// Builds a binary numeric from its header and base-10000 digit groups.
+    fn numeric_bytes(weight: i16, sign: u16, dscale: u16, digits: &[i16]) -> Vec<u8> {
+        let mut b = Vec::new();
+        b.extend((digits.len() as i16).to_be_bytes());
+        b.extend(weight.to_be_bytes());
+        b.extend(sign.to_be_bytes());
+        b.extend(dscale.to_be_bytes());
+        for d in digits {
+            b.extend(d.to_be_bytes());
+        }
+        b
+    }
+
+    fn array_bytes(elem: u32, dims: &[i32], elements: &[Option<Vec<u8>>]) -> Vec<u8> {
+        let mut b = Vec::new();
+        b.extend((dims.len() as i32).to_be_bytes());
+        b.extend((elements.iter().any(Option::is_none) as i32).to_be_bytes());
+        b.extend(elem.to_be_bytes());
+        for d in dims {
+            b.extend(d.to_be_bytes());
+            b.extend(1i32.to_be_bytes());
+        }
+        for e in elements {
+            match e {
+                Some(v) => {
+                    b.extend((v.len() as i32).to_be_bytes());
+                    b.extend(v);
+                }
+                None => b.extend((-1i32).to_be_bytes()),
+            }
+        }
+        b
+    }
+
+    #[test]
+    fn numeric_binary_keeps_every_digit() {
+        // 12345.678 = groups [1, 2345, 6780], weight 1, scale 3
+        let v = binary(&numeric_bytes(1, 0, 3, &[1, 2345, 6780]), oid::NUMERIC);
+        assert_eq!(v, json!("12345.678"));
+        // -0.00001 = groups [1000] at weight -2
+        let v = binary(&numeric_bytes(-2, 0x4000, 5, &[1000]), oid::NUMERIC);
+        assert_eq!(v, json!("-0.00001"));
+        // 10000000 = group [1000] at weight 1, trailing zero group omitted
+        let v = binary(&numeric_bytes(1, 0, 0, &[1000]), oid::NUMERIC);
+        assert_eq!(v, json!("10000000"));
+        let v = binary(&numeric_bytes(0, 0xC000, 0, &[]), oid::NUMERIC);
+        assert_eq!(v, json!("NaN"));
+    }
+
+    #[test]
+    fn int4_array_binary() {
+        let elements: Vec<_> = [Some(1i32), None, Some(-3)]
+            .iter()
+            .map(|v| v.map(|n| n.to_be_bytes().to_vec()))
+            .collect();
+        let v = binary(&array_bytes(oid::INT4, &[3], &elements), 1007);
+        assert_eq!(v, json!([1, null, -3]));
+    }
+
+    #[test]
+    fn nested_array_binary() {
+        let elements: Vec<_> = (1i16..=4).map(|n| Some(n.to_be_bytes().to_vec())).collect();
+        let v = binary(&array_bytes(oid::INT2, &[2, 2], &elements), 1005);
+        assert_eq!(v, json!([[1, 2], [3, 4]]));
+    }
+
+    #[test]
+    fn empty_array_binary() {
+        assert_eq!(binary(&array_bytes(oid::INT4, &[], &[]), 1007), json!([]));
+    }
+
+    #[test]
+    fn array_text_handles_quotes_nulls_and_nesting() {
+        assert_eq!(text("{1,NULL,3}", 1007), json!([1, null, 3]));
+        assert_eq!(text("{{1,2},{3,4}}", 1007), json!([[1, 2], [3, 4]]));
+        assert_eq!(
+            text(r#"{plain,"with space","quote\"d","NULL"}"#, 1009),
+            json!(["plain", "with space", "quote\"d", "NULL"])
+        );
+        assert_eq!(text("[0:1]={t,f}", 1000), json!([true, false]));
+        assert_eq!(text("{}", 1009), json!([]));
+    }
+
+    #[test]
+    fn box_array_text_uses_semicolons() {
+        assert_eq!(
+            text("{(1,1),(0,0);(3,3),(2,2)}", 1020),
+            json!(["(1,1),(0,0)", "(3,3),(2,2)"])
+        );
+    }
+
+    #[test]
+    fn tstzrange_binary() {
+        // [2000-01-01 00:00:00+00, 2000-01-02 00:00:00+00)
+        let mut b = vec![RANGE_LB_INC];
+        b.extend(8i32.to_be_bytes());
+        b.extend(0i64.to_be_bytes());
+        b.extend(8i32.to_be_bytes());
+        b.extend(86_400_000_000i64.to_be_bytes());
+        let v = binary(&b, oid::TSTZRANGE);
+        assert_eq!(v[TYPE_TAG], "range");
+        assert_eq!(v["lower"], "2000-01-01T00:00:00+00:00");
+        assert_eq!(v["upper"], "2000-01-02T00:00:00+00:00");
+        assert_eq!(v["lower_inclusive"], true);
+        assert_eq!(v["upper_inclusive"], false);
+    }
+
+    #[test]
+    fn unbounded_and_empty_ranges() {
+        let mut b = vec![RANGE_LB_INF];
+        b.extend(4i32.to_be_bytes());
+        b.extend(10i32.to_be_bytes());
+        let v = binary(&b, oid::INT4RANGE);
+        assert_eq!(v["lower"], Value::Null);
+        assert_eq!(v["upper"], 10);
+
+        assert_eq!(binary(&[RANGE_EMPTY], oid::INT4RANGE)["empty"], true);
+        assert_eq!(text("empty", oid::INT4RANGE)["empty"], true);
+    }
+
+    #[test]
+    fn range_text() {
+        let v = text("[1,10)", oid::INT4RANGE);
+        assert_eq!(
+            (v["lower"].clone(), v["upper"].clone()),
+            (json!(1), json!(10))
+        );
+        assert_eq!(v["lower_inclusive"], true);
+        assert_eq!(v["upper_inclusive"], false);
+
+        let v = text(r#"["2024-01-01 00:00:00+00",)"#, oid::TSTZRANGE);
+        assert_eq!(v["lower"], "2024-01-01 00:00:00+00");
+        assert_eq!(v["upper"], Value::Null);
+    }
+
+    #[test]
+    fn interval_binary() {
+        // 1 year 2 mons 3 days 04:05:06.5
+        let mut b = Vec::new();
+        b.extend(14_706_500_000i64.to_be_bytes());
+        b.extend(3i32.to_be_bytes());
+        b.extend(14i32.to_be_bytes());
+        assert_eq!(
+            binary(&b, oid::INTERVAL),
+            json!("1 year 2 mons 3 days 04:05:06.5")
+        );
+
+        let mut b = Vec::new();
+        b.extend((-3_600_000_000i64).to_be_bytes());
+        b.extend(0i32.to_be_bytes());
+        b.extend(0i32.to_be_bytes());
+        assert_eq!(binary(&b, oid::INTERVAL), json!("-01:00:00"));
+
+        assert_eq!(binary(&[0; 16], oid::INTERVAL), json!("00:00:00"));
+    }
+
+    #[test]
+    fn inet_and_cidr_binary() {
+        assert_eq!(
+            binary(&[2, 32, 0, 4, 192, 168, 0, 1], oid::INET),
+            json!("192.168.0.1")
+        );
+        assert_eq!(
+            binary(&[2, 24, 0, 4, 10, 0, 0, 7], oid::INET),
+            json!("10.0.0.7/24")
+        );
+        assert_eq!(
+            binary(&[2, 16, 1, 4, 172, 16, 0, 0], oid::CIDR),
+            json!("172.16.0.0/16")
+        );
+
+        let mut v6 = vec![3, 64, 1, 16];
+        v6.extend([0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(binary(&v6, oid::CIDR), json!("2001:db8::/64"));
+    }
+
+    #[test]
+    fn macaddr_binary() {
+        assert_eq!(
+            binary(&[0x08, 0x00, 0x2b, 0x01, 0x02, 0x03], oid::MACADDR),
+            json!("08:00:2b:01:02:03")
+        );
+    }
+
+    #[test]
+    fn geometric_binary() {
+        let mut p = Vec::new();
+        p.extend(1.5f64.to_be_bytes());
+        p.extend((-2.0f64).to_be_bytes());
+        assert_eq!(binary(&p, oid::POINT), json!("(1.5,-2)"));
+
+        let mut circle = p.clone();
+        circle.extend(3.0f64.to_be_bytes());
+        assert_eq!(binary(&circle, oid::CIRCLE), json!("<(1.5,-2),3>"));
+
+        let mut path = vec![0];
+        path.extend(2i32.to_be_bytes());
+        path.extend(&p);
+        path.extend(&p);
+        assert_eq!(binary(&path, oid::PATH), json!("[(1.5,-2),(1.5,-2)]"));
+    }
+
+    #[test]
+    fn money_binary() {
+        assert_eq!(
+            binary(&123_456i64.to_be_bytes(), oid::MONEY),
+            json!("1234.56")
+        );
+        assert_eq!(binary(&(-5i64).to_be_bytes(), oid::MONEY), json!("-0.05"));
+    }
+
+    #[test]
+    fn temporal_binary() {
+        assert_eq!(
+            binary(&(-1i32).to_be_bytes(), oid::DATE),
+            json!("1999-12-31")
+        );
+        assert_eq!(
+            binary(&i32::MAX.to_be_bytes(), oid::DATE),
+            json!("infinity")
+        );
+        assert_eq!(
+            binary(&45_296_000_000i64.to_be_bytes(), oid::TIME),
+            json!("12:34:56")
+        );
+        assert_eq!(
+            binary(&(-1i64).to_be_bytes(), oid::TIMESTAMP),
+            json!("1999-12-31 23:59:59.999999")
+        );
+        let mut timetz = 45_296_000_000i64.to_be_bytes().to_vec();
+        timetz.extend((-7200i32).to_be_bytes());
+        assert_eq!(binary(&timetz, oid::TIMETZ), json!("12:34:56+02"));
+    }
+
+    #[test]
+    fn temporal_outside_chrono_range() {
+        let chrono_max = (chrono::NaiveDate::MAX - pg_epoch().date()).num_days() as i32;
+        assert_eq!(
+            binary(&chrono_max.to_be_bytes(), oid::DATE),
+            json!("+262142-12-31")
+        );
+        assert_eq!(
+            binary(&(chrono_max + 1).to_be_bytes(), oid::DATE),
+            json!("262143-01-01")
+        );
+        // The last and first values Postgres accepts.
+        assert_eq!(
+            binary(&2_145_031_948i32.to_be_bytes(), oid::DATE),
+            json!("5874897-12-31")
+        );
+        assert_eq!(
+            binary(&(-2_451_545i32).to_be_bytes(), oid::DATE),
+            json!("-4713-11-24")
+        );
+        assert_eq!(
+            binary(&9_223_371_331_199_999_999i64.to_be_bytes(), oid::TIMESTAMP),
+            json!("294276-12-31 23:59:59.999999")
+        );
+        assert_eq!(
+            binary(
+                &9_223_371_331_199_999_999i64.to_be_bytes(),
+                oid::TIMESTAMPTZ
+            ),
+            json!("294276-12-31 23:59:59.999999+00")
+        );
+        assert_eq!(
+            binary(
+                &(-211_813_488_000_000_000i64).to_be_bytes(),
+                oid::TIMESTAMPTZ
+            ),
+            json!("-4713-11-24T00:00:00+00:00")
+        );
+    }
+
+    #[test]
+    fn bit_string_binary() {
+        let mut b = 10i32.to_be_bytes().to_vec();
+        b.extend([0b1010_0000, 0b1100_0000]);
+        assert_eq!(binary(&b, oid::VARBIT), json!("1010000011"));
+    }
+
+    #[test]
+    fn enums_and_composites_resolve_through_the_catalog() {
+        let mut catalog = PgTypeCatalog::new();
+        catalog.insert(16400, "mood", PgKind::Enum);
+        catalog.insert(16410, "mood[]", PgKind::Array(16400));
+        catalog.insert(
+            16420,
+            "address",
+            PgKind::Composite(vec![
+                ("street".into(), oid::TEXT),
+                ("zip".into(), oid::INT4),
+            ]),
+        );
+
+        let v = decode(b"happy", WireFormat::Binary, 16400, &catalog).unwrap();
+        assert_eq!(v, json!("happy"));
+        let v = decode(b"{happy,sad}", WireFormat::Text, 16410, &catalog).unwrap();
+        assert_eq!(v, json!(["happy", "sad"]));
+
+        let mut record = 2i32.to_be_bytes().to_vec();
+        record.extend(oid::TEXT.to_be_bytes());
+        record.extend(4i32.to_be_bytes());
+        record.extend(b"Main");
+        record.extend(oid::INT4.to_be_bytes());
+        record.extend((-1i32).to_be_bytes());
+        let v = decode(&record, WireFormat::Binary, 16420, &catalog).unwrap();
+        assert_eq!(v, json!({"street": "Main", "zip": null}));
+
+        let v = decode(br#"("1 Main St",90210)"#, WireFormat::Text, 16420, &catalog).unwrap();
+        assert_eq!(v, json!({"street": "1 Main St", "zip": 90210}));
+    }
+
+    #[test]
+    fn anonymous_records_use_positional_names() {
+        assert_eq!(
+            text(r#"(1,"say ""hi""",)"#, oid::RECORD),
+            json!({"f1": "1", "f2": "say \"hi\"", "f3": null})
+        );
+    }
+
+    #[test]
+    fn unresolved_walks_referenced_types() {
+        let mut catalog = PgTypeCatalog::new();
+        assert_eq!(catalog.unresolved(&[oid::INT4, 1007, 16410]), vec![16410]);
+        catalog.insert(16410, "mood[]", PgKind::Array(16400));
+        assert_eq!(catalog.unresolved(&[16410]), vec![16400]);
+        catalog.insert(16400, "mood", PgKind::Enum);
+        assert!(catalog.unresolved(&[16410]).is_empty());
+    }
+
+    #[test]
+    fn jsonb_binary_skips_version_byte() {
+        assert_eq!(binary(b"\x01{\"a\":1}", oid::JSONB), json!({"a": 1}));
+    }
+}
@@ -1,8 +1,475 @@
 // ABOUTME: PostgreSQL-specific database introspection queries.
 // ABOUTME: Provides schema, table, column, index, and constraint information.
 
-use super::{ColumnInfo, ConstraintInfo, DatabaseUser, FunctionInfo, IndexInfo, UserGrant};
-use sqlx::Row;
+use super::driver::{DatabaseDriver, DriverCapabilities, DriverSession};
+use super::result_set::{ResultCursor, ResultPage};
+use super::row_changes::{param_text, AppliedRowChanges, Dialect, TableEdit};
+use super::running::{IdleSession, RunningQueries};
+use super::pg_types::PgTypeCatalog;
+use super::query_plan::{self, QueryPlan};
+use super::value::{self, ColumnMeta};
+use super::{
+    pool_options, ColumnInfo, ConstraintInfo, DatabaseUser, FunctionInfo, IndexInfo, QueryResult,
+    UserGrant, ViewDependency,
+};
+use crate::sql_analyzer;
+use crate::storage::{ConnectionConfig, SslMode};
+use async_trait::async_trait;
+use sqlx::pool::PoolConnection;
+use sqlx::postgres::{PgArguments, PgConnectOptions, PgConnection, PgSslMode};
+use sqlx::{Connection, Postgres, Row};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::AppHandle;
+
+pub struct PostgresDriver {
+    pool: sqlx::PgPool,
+    /// For the one-off connections that cancel queries.
+    options: PgConnectOptions,
+    /// Backend PID of the connection running each in-flight query.
+    running: Arc<RunningQueries<i32>>,
+    idle: IdleSession<PgSession>,
+}
+
+impl PostgresDriver {
+    pub async fn connect(config: &ConnectionConfig, host: &str, port: u16) -> Result<Self, String> {
+        let options = connect_options(config, host, port)?;
+        let pool = pool_options(config)
+            .connect_with(options.clone())
+            .await
+            .map_err(|e| format!("Failed to connect to PostgreSQL: {}", e))?;
+        Ok(Self {
+            idle: IdleSession::new(pool.options().get_max_connections()),
+            pool,
+            options,
+            running: Arc::new(RunningQueries::new()),
+        })
+    }
+
+    async fn session(&self) -> Result<PgSession, String> {
+        match self.idle.take() {
+            Some(session) => Ok(session),
+            None => PgSession::open(&self.pool, self.running.clone()).await,
+        }
+    }
+}
+
+/// The sqlx options for `config`, reached at `host:port` (the tunnel's local
+/// end when there is one).
+pub(super) fn connect_options(
+    config: &ConnectionConfig,
+    host: &str,
+    port: u16,
+) -> Result<PgConnectOptions, String> {
+    let ssl_mode = match config.ssl_mode {
+        SslMode::Disable => PgSslMode::Disable,
+        SslMode::Prefer => PgSslMode::Prefer,
+        SslMode::Require => PgSslMode::Require,
+        SslMode::VerifyCa => PgSslMode::VerifyCa,
+        SslMode::VerifyFull => PgSslMode::VerifyFull,
+    };
+    let mut options = PgConnectOptions::new_without_pgpass()
+        .host(host)
+        .port(port)
+        .username(&config.username)
+        .database(config.database.as_deref().unwrap_or("postgres"))
+        .ssl_mode(ssl_mode);
+    if !config.password.is_empty() {
+        options = options.password(&config.password);
+    }
+    if let Some(ca) = config.ssl_root_cert.as_deref().filter(|p| !p.is_empty()) {
+        options = options.ssl_root_cert(ca);
+    }
+    if let Some((cert, key)) = config.ssl_client_identity()? {
+        options = options.ssl_client_cert(cert).ssl_client_key(key);
+    }
+
+    let settings = &config.options;
+    if let Some(name) = settings.application_name.as_deref().filter(|n| !n.is_empty()) {
+        options = options.application_name(name);
+    }
+    let mut startup = Vec::new();
+    if let Some(ms) = settings.statement_timeout_ms {
+        startup.push(("statement_timeout", ms.to_string()));
+    }
+    if let Some(path) = settings.search_path.as_deref().filter(|p| !p.is_empty()) {
+        startup.push(("search_path", escape_startup_option(path)));
+    }
+    if !startup.is_empty() {
+        options = options.options(startup);
+    }
+    Ok(options)
+}
+
+/// Startup options travel as one space-separated `-c name=value` string, so
+/// spaces and backslashes in a value are escaped with a backslash.
+fn escape_startup_option(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c == ' ' || c == '\\' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[async_trait]
+impl DatabaseDriver for PostgresDriver {
+    fn capabilities(&self) -> DriverCapabilities {
+        DriverCapabilities {
+            engine: "PostgreSQL",
+            supports_schemas: true,
+            supports_create_database: true,
+            supports_create_schema: true,
+            supports_users: true,
+            supports_functions: true,
+            supports_procedures: true,
+            supports_materialized_views: true,
+            supports_sequences: true,
+            supports_triggers: true,
+            supports_view_dependencies: true,
+            supports_query_cancel: true,
+            supports_paged_results: true,
+            supports_transactions: true,
+            supports_query_plans: true,
+            switches_database_in_place: false,
+        }
+    }
+
+    async fn list_databases(&self) -> Result<Vec<String>, String> {
+        list_databases(&self.pool).await
+    }
+
+    async fn list_schemas(&self, database: &str) -> Result<Vec<String>, String> {
+        list_schemas(&self.pool, database).await
+    }
+
+    async fn list_tables(&self, database: &str, schema: &str) -> Result<Vec<String>, String> {
+        list_tables(&self.pool, database, schema).await
+    }
+
+    async fn list_views(&self, database: &str, schema: &str) -> Result<Vec<String>, String> {
+        list_views(&self.pool, database, schema).await
+    }
+
+    async fn list_columns(
+        &self,
+        database: &str,
+        schema: &str,
+        table: &str,
+    ) -> Result<Vec<ColumnInfo>, String> {
+        list_columns(&self.pool, database, schema, table).await
+    }
+
+    async fn list_schema_columns(
+        &self,
+        _database: &str,
+        schema: &str,
+    ) -> Result<HashMap<String, Vec<ColumnInfo>>, String> {
+        list_schema_columns(&self.pool, schema).await
+    }
+
+    async fn list_indexes(
+        &self,
+        database: &str,
+        schema: &str,
+        table: &str,
+    ) -> Result<Vec<IndexInfo>, String> {
+        list_indexes(&self.pool, database, schema, table).await
+    }
+
+    async fn list_constraints(
+        &self,
+        database: &str,
+        schema: &str,
+        table: &str,
+    ) -> Result<Vec<ConstraintInfo>, String> {
+        list_constraints(&self.pool, database, schema, table).await
+    }
+
+    fn split_script(&self, script: &str) -> Vec<String> {
+        sql_analyzer::split_statements(script, "postgres")
+    }
+
+    async fn execute_query(
+        &self,
+        app: &AppHandle,
+        query_id: &str,
+        query: &str,
+        _database: Option<&str>,
+        max_rows: usize,
+    ) -> Result<QueryResult, String> {
+        let mut session = self.session().await?;
+        let result = session.execute_query(app, query_id, query, max_rows).await;
+        // A failed query may have broken the connection, and one that opened a
+        // transaction must not leak it into the next query. A truncated result
+        // is still in flight; closing is cheaper than draining it.
+        let reusable = matches!(&result, Ok(r) if !r.truncated);
+        if reusable && super::transaction_control(query).is_none() {
+            self.idle.park(session);
+        } else {
+            session.discard();
+        }
+        result
+    }
+
+    async fn open_session(&self) -> Result<Box<dyn DriverSession>, String> {
+        Ok(Box::new(self.session().await?))
+    }
+
+    async fn open_result(&self, query: &str) -> Result<Box<dyn ResultCursor>, String> {
+        Ok(Box::new(PgCursor::open(&self.pool, query).await?))
+    }
+
+    async fn create_database(&self, name: &str) -> Result<(), String> {
+        create_database(&self.pool, name).await
+    }
+
+    async fn create_schema(&self, name: &str) -> Result<(), String> {
+        create_schema(&self.pool, name).await
+    }
+
+    async fn list_functions(&self, database: &str, schema: &str) -> Result<Vec<String>, String> {
+        list_functions(&self.pool, database, schema).await
+    }
+
+    async fn list_procedures(&self, database: &str, schema: &str) -> Result<Vec<String>, String> {
+        list_procedures(&self.pool, database, schema).await
+    }
+
+    async fn list_materialized_views(
+        &self,
+        database: &str,
+        schema: &str,
+    ) -> Result<Vec<String>, String> {
+        list_materialized_views(&self.pool, database, schema).await
+    }
+
+    async fn list_sequences(&self, database: &str, schema: &str) -> Result<Vec<String>, String> {
+        list_sequences(&self.pool, database, schema).await
+    }
+
+    async fn list_triggers(&self, database: &str, schema: &str) -> Result<Vec<String>, String> {
+        list_triggers(&self.pool, database, schema).await
+    }
+
+    async fn get_function_definition(
+        &self,
+        database: &str,
+        schema: &str,
+        function_name: &str,
+    ) -> Result<FunctionInfo, String> {
+        get_function_definition(&self.pool, database, schema, function_name).await
+    }
+
+    async fn get_view_definition(
+        &self,
+        database: &str,
+        schema: &str,
+        view_name: &str,
+    ) -> Result<FunctionInfo, String> {
+        get_view_definition(&self.pool, database, schema, view_name).await
+    }
+
+    async fn get_view_dependencies(
+        &self,
+        database: &str,
+        schema: &str,
+    ) -> Result<Vec<ViewDependency>, String> {
+        get_view_dependencies(&self.pool, database, schema).await
+    }
+
+    async fn list_users(&self) -> Result<Vec<DatabaseUser>, String> {
+        list_users(&self.pool).await
+    }
+
+    async fn get_user_grants(
+        &self,
+        username: &str,
+        _host: Option<&str>,
+    ) -> Result<Vec<UserGrant>, String> {
+        get_user_grants(&self.pool, username).await
+    }
+
+    async fn apply_row_changes(
+        &self,
+        edits: &[TableEdit],
+    ) -> Result<Vec<AppliedRowChanges>, String> {
+        apply_row_changes(&self.pool, edits).await
+    }
+
+    async fn explain_query(&self, query: &str, analyze: bool) -> Result<QueryPlan, String> {
+        explain_query(&self.pool, query, analyze).await
+    }
+
+    async fn cancel_query(&self, query_id: &str) -> Result<bool, String> {
+        match self.running.get(query_id) {
+            Some(pid) => cancel_backend(&self.options, pid).await,
+            None => Ok(false),
+        }
+    }
+}
+
+/// A pooled connection with its backend PID, so queries on it can be
+/// cancelled. The PID is looked up once, when the connection is acquired.
+pub struct PgSession {
+    conn: PoolConnection<Postgres>,
+    pid: i32,
+    running: Arc<RunningQueries<i32>>,
+}
+
+impl PgSession {
+    async fn open(pool: &sqlx::PgPool, running: Arc<RunningQueries<i32>>) -> Result<Self, String> {
+        let mut conn = pool
+            .acquire()
+            .await
+            .map_err(|e| format!("Failed to acquire connection: {}", e))?;
+        let pid: i32 = sqlx::query_scalar("SELECT pg_backend_pid()")
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(|e| format!("Query failed: {}", e))?;
+        Ok(Self { conn, pid, running })
+    }
+}
+
+#[async_trait]
+impl DriverSession for PgSession {
+    async fn execute_query(
+        &mut self,
+        app: &AppHandle,
+        query_id: &str,
+        query: &str,
+        max_rows: usize,
+    ) -> Result<QueryResult, String> {
+        let _running = self.running.register(query_id, self.pid);
+        super::execute_query_pg(app, query_id, &mut self.conn, query, max_rows).await
+    }
+
+    async fn apply_row_changes(
+        &mut self,
+        edits: &[TableEdit],
+    ) -> Result<Vec<AppliedRowChanges>, String> {
+        apply_edits(&mut self.conn, edits).await
+    }
+
+    fn discard(&mut self) {
+        self.conn.close_on_drop();
+    }
+}
+
+const RESULT_CURSOR: &str = "dbui_result";
+
+/// Paged result backed by a server-side `SCROLL` cursor inside a transaction
+/// pinned to one pooled connection, so any page can be fetched without the
+/// client ever holding more than that page.
+pub struct PgCursor {
+    conn: PoolConnection<Postgres>,
+    column_meta: Vec<ColumnMeta>,
+    /// User-defined types in the result, resolved on the first fetch.
+    types: Option<PgTypeCatalog>,
+    closed: bool,
+}
+
+impl PgCursor {
+    pub async fn open(pool: &sqlx::PgPool, query: &str) -> Result<Self, String> {
+        let mut conn = pool
+            .acquire()
+            .await
+            .map_err(|e| format!("Failed to acquire connection: {}", e))?;
+        sqlx::query("BEGIN")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to open result: {}", e))?;
+
+        let declare = format!(
+            "DECLARE {} SCROLL CURSOR FOR {}",
+            RESULT_CURSOR,
+            query.trim().trim_end_matches(';')
+        );
+        if let Err(e) = sqlx::query(&declare).persistent(false).execute(&mut *conn).await {
+            let _ = sqlx::query("ROLLBACK").execute(&mut *conn).await;
+            return Err(format!("Query failed: {}", e));
+        }
+
+        Ok(Self {
+            conn,
+            column_meta: Vec::new(),
+            types: None,
+            closed: false,
+        })
+    }
+}
+
+impl Drop for PgCursor {
+    fn drop(&mut self) {
+        // Never hand a connection with an open transaction back to the pool.
+        if !self.closed {
+            self.conn.close_on_drop();
+        }
+    }
+}
+
+#[async_trait]
+impl ResultCursor for PgCursor {
+    async fn fetch(&mut self, offset: usize, limit: usize) -> Result<ResultPage, String> {
+        // MOVE ABSOLUTE n leaves the cursor on row n (1-based), so the next
+        // FETCH starts at zero-based row n. One extra row tells us has_more.
+        sqlx::query(&format!("MOVE ABSOLUTE {} IN {}", offset, RESULT_CURSOR))
+            .persistent(false)
+            .execute(&mut *self.conn)
+            .await
+            .map_err(|e| format!("Failed to position cursor: {}", e))?;
+        let fetched = sqlx::query(&format!(
+            "FETCH FORWARD {} FROM {}",
+            limit.saturating_add(1),
+            RESULT_CURSOR
+        ))
+        .persistent(false)
+        .fetch_all(&mut *self.conn)
+        .await
+        .map_err(|e| format!("Failed to fetch rows: {}", e))?;
+
+        if let (None, Some(first)) = (&self.types, fetched.first()) {
+            self.column_meta = first
+                .columns()
+                .iter()
+                .map(|c| super::column_meta_for(c, super::pg_logical_type))
+                .collect();
+            self.types =
+                Some(super::pg_load_types(&mut self.conn, first, &mut self.column_meta).await);
+        }
+        let no_types = PgTypeCatalog::new();
+        let types = self.types.as_ref().unwrap_or(&no_types);
+        let has_more = fetched.len() > limit;
+        let rows = fetched
+            .iter()
+            .take(limit)
+            .map(|row| super::pg_row_to_json(row, types))
+            .collect();
+
+        Ok(ResultPage {
+            columns: self.column_meta.iter().map(|c| c.name.clone()).collect(),
+            column_meta: self.column_meta.clone(),
+            rows,
+            offset,
+            has_more,
+            truncated: false,
+        })
+    }
+
+    async fn close(&mut self) -> Result<(), String> {
+        if self.closed {
+            return Ok(());
+        }
+        sqlx::query("ROLLBACK")
+            .execute(&mut *self.conn)
+            .await
+            .map_err(|e| format!("Failed to close result: {}", e))?;
+        self.closed = true;
+        Ok(())
+    }
+}
 
 pub async fn list_databases(pool: &sqlx::PgPool) -> Result<Vec<String>, String> {
     let rows =
@@ -73,7 +540,7 @@ pub async fn get_view_definition(
     _database: &str,
     schema: &str,
     view_name: &str,
-) -> Result<super::FunctionInfo, String> {
+) -> Result<FunctionInfo, String> {
     let row = sqlx::query(
         "SELECT pg_get_viewdef(c.oid, true) as definition
          FROM pg_class c
@@ -89,7 +556,7 @@ pub async fn get_view_definition(
 
     let definition: String = row.get("definition");
 
-    Ok(super::FunctionInfo {
+    Ok(FunctionInfo {
         name: view_name.to_string(),
         definition: format!("CREATE OR REPLACE VIEW {}.{} AS\n{}", schema, view_name, definition),
         return_type: None,
@@ -259,35 +726,80 @@ pub async fn list_columns(
     .await
     .map_err(|e| format!("Failed to list columns: {}", e))?;
 
-    Ok(rows
-        .iter()
-        .map(|r| {
-            let base_type: String = r.get("data_type");
-            let char_max_len: Option<i32> = r.get("character_maximum_length");
-            let num_precision: Option<i32> = r.get("numeric_precision");
-            let num_scale: Option<i32> = r.get("numeric_scale");
-
-            let data_type = if let Some(len) = char_max_len {
-                format!("{}({})", base_type, len)
-            } else if base_type == "numeric" || base_type == "decimal" {
-                match (num_precision, num_scale) {
-                    (Some(p), Some(s)) if s > 0 => format!("{}({},{})", base_type, p, s),
-                    (Some(p), _) => format!("{}({})", base_type, p),
-                    _ => base_type,
-                }
-            } else {
-                base_type
-            };
-
-            ColumnInfo {
-                name: r.get("column_name"),
-                data_type,
-                is_nullable: r.get::<String, _>("is_nullable") == "YES",
-                column_default: r.get("column_default"),
-                is_primary_key: r.get("is_primary_key"),
-            }
-        })
-        .collect())
+    Ok(rows.iter().map(column_info).collect())
+}
+
+/// Columns of every table and view in `schema`, in one query.
+pub async fn list_schema_columns(
+    pool: &sqlx::PgPool,
+    schema: &str,
+) -> Result<HashMap<String, Vec<ColumnInfo>>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            c.table_name,
+            c.column_name,
+            c.data_type,
+            c.character_maximum_length,
+            c.numeric_precision,
+            c.numeric_scale,
+            c.is_nullable,
+            c.column_default,
+            CASE WHEN pk.column_name IS NOT NULL THEN true ELSE false END as is_primary_key
+        FROM information_schema.columns c
+        LEFT JOIN (
+            SELECT kcu.table_name, kcu.column_name
+            FROM information_schema.table_constraints tc
+            JOIN information_schema.key_column_usage kcu
+                ON tc.constraint_name = kcu.constraint_name
+                AND tc.table_schema = kcu.table_schema
+            WHERE tc.constraint_type = 'PRIMARY KEY'
+                AND tc.table_schema = $1
+        ) pk ON c.table_name = pk.table_name AND c.column_name = pk.column_name
+        WHERE c.table_schema = $1
+        ORDER BY c.table_name, c.ordinal_position
+        "#,
+    )
+    .bind(schema)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to list columns: {}", e))?;
+
+    let mut columns: HashMap<String, Vec<ColumnInfo>> = HashMap::new();
+    for row in &rows {
+        columns
+            .entry(row.get("table_name"))
+            .or_default()
+            .push(column_info(row));
+    }
+    Ok(columns)
+}
+
+fn column_info(r: &sqlx::postgres::PgRow) -> ColumnInfo {
+    let base_type: String = r.get("data_type");
+    let char_max_len: Option<i32> = r.get("character_maximum_length");
+    let num_precision: Option<i32> = r.get("numeric_precision");
+    let num_scale: Option<i32> = r.get("numeric_scale");
+
+    let data_type = if let Some(len) = char_max_len {
+        format!("{}({})", base_type, len)
+    } else if base_type == "numeric" || base_type == "decimal" {
+        match (num_precision, num_scale) {
+            (Some(p), Some(s)) if s > 0 => format!("{}({},{})", base_type, p, s),
+            (Some(p), _) => format!("{}({})", base_type, p),
+            _ => base_type,
+        }
+    } else {
+        base_type
+    };
+
+    ColumnInfo {
+        name: r.get("column_name"),
+        data_type,
+        is_nullable: r.get::<String, _>("is_nullable") == "YES",
+        column_default: r.get("column_default"),
+        is_primary_key: r.get("is_primary_key"),
+    }
 }
 
 pub async fn list_indexes(
@@ -358,7 +870,7 @@ pub async fn get_view_dependencies(
     pool: &sqlx::PgPool,
     _database: &str,
     schema: &str,
-) -> Result<Vec<super::ViewDependency>, String> {
+) -> Result<Vec<ViewDependency>, String> {
     let rows = sqlx::query(
         r#"
         SELECT DISTINCT
@@ -389,7 +901,7 @@ pub async fn get_view_dependencies(
 
     Ok(rows
         .iter()
-        .map(|r| super::ViewDependency {
+        .map(|r| ViewDependency {
             view_name: r.get("view_name"),
             depends_on: r.get("depends_on"),
             depends_on_type: r.get("depends_on_type"),
@@ -442,6 +954,139 @@ pub async fn list_constraints(
         .collect())
 }
 
+/// Column types as `format_type` spells them, so text parameters can be cast
+/// back to each column's exact type (including typmods and domains).
+async fn column_types(
+    conn: &mut PgConnection,
+    schema: &str,
+    table: &str,
+) -> Result<HashMap<String, String>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT a.attname::TEXT AS name, format_type(a.atttypid, a.atttypmod) AS type_name
+        FROM pg_attribute a
+        JOIN pg_class c ON c.oid = a.attrelid
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE n.nspname = $1 AND c.relname = $2 AND a.attnum > 0 AND NOT a.attisdropped
+        "#,
+    )
+    .bind(schema)
+    .bind(table)
+    .fetch_all(conn)
+    .await
+    .map_err(|e| format!("Failed to load column types: {}", e))?;
+
+    Ok(rows
+        .iter()
+        .map(|r| (r.get("name"), r.get("type_name")))
+        .collect())
+}
+
+pub async fn explain_query(
+    pool: &sqlx::PgPool,
+    query: &str,
+    analyze: bool,
+) -> Result<QueryPlan, String> {
+    // BUFFERS only means something for a measured run, and needs ANALYZE
+    // before Postgres 13.
+    let sql = if analyze {
+        format!("EXPLAIN (ANALYZE, BUFFERS, FORMAT JSON) {}", query)
+    } else {
+        format!("EXPLAIN (FORMAT JSON) {}", query)
+    };
+    // ANALYZE executes the statement; rolling back keeps any writes from sticking.
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let output: serde_json::Value = sqlx::query_scalar(&sql)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to explain query: {}", e))?;
+    tx.rollback()
+        .await
+        .map_err(|e| format!("Failed to roll back: {}", e))?;
+    query_plan::parse_postgres(&output, analyze)
+}
+
+/// Binds text for the statement to cast, or the bytes of a binary cell.
+fn bind_param<'q>(
+    query: sqlx::query::Query<'q, Postgres, PgArguments>,
+    param: &serde_json::Value,
+) -> sqlx::query::Query<'q, Postgres, PgArguments> {
+    match value::binary_bytes(param) {
+        Some(bytes) => query.bind(bytes),
+        None => query.bind(param_text(param)),
+    }
+}
+
+/// Applies `edits` in a transaction of their own.
+pub async fn apply_row_changes(
+    pool: &sqlx::PgPool,
+    edits: &[TableEdit],
+) -> Result<Vec<AppliedRowChanges>, String> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let results = apply_edits(&mut tx, edits).await?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit changes: {}", e))?;
+    Ok(results)
+}
+
+/// Runs `edits` on `conn`, inside whatever transaction the caller opened.
+async fn apply_edits(
+    conn: &mut PgConnection,
+    edits: &[TableEdit],
+) -> Result<Vec<AppliedRowChanges>, String> {
+    let dialect = Dialect::Postgres;
+    let mut batches = Vec::with_capacity(edits.len());
+    for edit in edits {
+        let (schema, table) = (&edit.target.schema, &edit.target.table);
+        let casts = column_types(conn, schema, table).await?;
+        let table_ref = format!("{}.{}", dialect.quote(schema), dialect.quote(table));
+        batches.push(edit.statements(dialect, &table_ref, &casts)?);
+    }
+
+    let mut results = Vec::with_capacity(edits.len());
+    for (edit, statements) in edits.iter().zip(&batches) {
+        let mut applied = edit.applied();
+        for statement in statements {
+            let query = statement
+                .params
+                .iter()
+                .fold(sqlx::query(&statement.sql), bind_param);
+            let result = query
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| edit.in_table(statement.failed(e)))?;
+            applied
+                .record(statement, result.rows_affected())
+                .map_err(|e| edit.in_table(e))?;
+        }
+        results.push(applied);
+    }
+    Ok(results)
+}
+
+/// Cancels whatever the backend with `pid` is running. The cancel request goes
+/// out on a connection of its own: the target one is busy, and the pool may
+/// have no other free.
+pub async fn cancel_backend(options: &PgConnectOptions, pid: i32) -> Result<bool, String> {
+    let mut conn = PgConnection::connect_with(options)
+        .await
+        .map_err(|e| format!("Failed to cancel query: {}", e))?;
+    let cancelled = sqlx::query_scalar("SELECT pg_cancel_backend($1)")
+        .bind(pid)
+        .fetch_one(&mut conn)
+        .await
+        .map_err(|e| format!("Failed to cancel query: {}", e));
+    let _ = conn.close().await;
+    cancelled
+}
+
 pub async fn list_users(pool: &sqlx::PgPool) -> Result<Vec<DatabaseUser>, String> {
     let rows = sqlx::query(
         r#"
@@ -706,3 +1351,45 @@ pub async fn get_user_grants(
 
     Ok(grants)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::postgres::PgPoolOptions;
+    use std::time::Duration;
+
+    #[tokio::test]
+    #[ignore = "needs a PostgreSQL server at DBUI_TEST_POSTGRES_URL"]
+    async fn queries_cancel_on_a_one_connection_pool() {
+        let url = std::env::var("DBUI_TEST_POSTGRES_URL").unwrap();
+        let options: PgConnectOptions = url.parse().unwrap();
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .acquire_timeout(Duration::from_secs(2))
+            .connect_with(options.clone())
+            .await
+            .unwrap();
+        let driver = PostgresDriver {
+            idle: IdleSession::new(pool.options().get_max_connections()),
+            pool,
+            options,
+            running: Arc::new(RunningQueries::new()),
+        };
+
+        let mut session = driver.session().await.unwrap();
+        let _running = driver.running.register("sleep", session.pid);
+        let sleep = sqlx::query("SELECT pg_sleep(30)").execute(&mut *session.conn);
+        let cancel = async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            driver.cancel_query("sleep").await
+        };
+        let (slept, cancelled) = tokio::time::timeout(Duration::from_secs(10), async {
+            tokio::join!(sleep, cancel)
+        })
+        .await
+        .unwrap();
+        assert_eq!(cancelled, Ok(true));
+        let error = slept.unwrap_err().to_string();
+        assert!(error.contains("canceling statement"), "{}", error);
+    }
+}
@@ -0,0 +1,647 @@
+// ABOUTME: Parses EXPLAIN output from each engine into one PlanNode tree.
+// ABOUTME: Flags likely problems such as large full scans and bad row estimates.
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// A full scan that reads at least this many rows is worth an index.
+const LARGE_SCAN_ROWS: f64 = 10_000.0;
+/// Estimates off by this factor or more, either way, are called out.
+const MISESTIMATE_FACTOR: f64 = 10.0;
+/// Misestimates where both counts are this small don't matter.
+const MISESTIMATE_MIN_ROWS: f64 = 100.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QueryPlan {
+    pub root: PlanNode,
+    /// The query actually ran, so nodes carry actual rows and timings.
+    pub analyzed: bool,
+    pub planning_time_ms: Option<f64>,
+    pub execution_time_ms: Option<f64>,
+    /// The engine's output as returned, for copying or a closer look.
+    pub raw: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PlanNode {
+    pub operation: String,
+    pub relation: Option<String>,
+    pub index: Option<String>,
+    /// Conditions, sort keys and the like, as the engine prints them.
+    pub detail: Option<String>,
+    /// Rows per loop; compare with `actual_rows`.
+    pub estimated_rows: Option<f64>,
+    pub actual_rows: Option<f64>,
+    pub loops: Option<f64>,
+    /// The engine's cost estimate for this node and its children.
+    pub cost: Option<f64>,
+    /// Time spent in this node and its children across all loops.
+    pub time_ms: Option<f64>,
+    pub buffers: Option<PlanBuffers>,
+    pub warnings: Vec<PlanWarning>,
+    pub children: Vec<PlanNode>,
+}
+
+/// Postgres shared buffer blocks found in cache versus read in.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlanBuffers {
+    pub hit: u64,
+    pub read: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlanWarning {
+    pub kind: PlanWarningKind,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanWarningKind {
+    FullScan,
+    RowEstimate,
+}
+
+/// How many rows a full table scan reads, when the engine says.
+enum FullScan {
+    Rows(f64),
+    Unknown,
+}
+
+impl PlanNode {
+    fn new(operation: impl Into<String>) -> Self {
+        Self {
+            operation: operation.into(),
+            ..Default::default()
+        }
+    }
+
+    fn annotate(&mut self, full_scan: Option<FullScan>) {
+        let table = self.relation.as_deref().unwrap_or("the table");
+        match full_scan {
+            Some(FullScan::Rows(rows)) if rows >= LARGE_SCAN_ROWS => {
+                self.warnings.push(PlanWarning {
+                    kind: PlanWarningKind::FullScan,
+                    message: format!(
+                        "Full scan reads about {} rows of {}; an index on the filtered columns may help",
+                        rows.round(),
+                        table
+                    ),
+                });
+            }
+            Some(FullScan::Unknown) => self.warnings.push(PlanWarning {
+                kind: PlanWarningKind::FullScan,
+                message: format!("Full scan of {} without an index", table),
+            }),
+            _ => {}
+        }
+
+        if let (Some(estimated), Some(actual)) = (self.estimated_rows, self.actual_rows) {
+            let (e, a) = (estimated.max(1.0), actual.max(1.0));
+            if (e / a).max(a / e) >= MISESTIMATE_FACTOR && e.max(a) >= MISESTIMATE_MIN_ROWS {
+                self.warnings.push(PlanWarning {
+                    kind: PlanWarningKind::RowEstimate,
+                    message: format!(
+                        "Estimated {} rows but got {}; table statistics may be out of date",
+                        estimated.round(),
+                        actual.round()
+                    ),
+                });
+            }
+        }
+    }
+}
+
+/// Joins several top-level nodes under one root, or returns the only one.
+fn single_root(mut roots: Vec<PlanNode>) -> PlanNode {
+    if roots.len() == 1 {
+        return roots.remove(0);
+    }
+    PlanNode {
+        children: roots,
+        ..PlanNode::new("Query plan")
+    }
+}
+
+/// Parses `EXPLAIN (FORMAT JSON)` output, with or without ANALYZE.
+pub fn parse_postgres(output: &Value, analyzed: bool) -> Result<QueryPlan, String> {
+    let top = output
+        .get(0)
+        .or(Some(output).filter(|v| v.is_object()))
+        .ok_or("Unexpected EXPLAIN output")?;
+    let plan = top.get("Plan").ok_or("EXPLAIN output has no plan")?;
+    Ok(QueryPlan {
+        root: postgres_node(plan),
+        analyzed,
+        planning_time_ms: top.get("Planning Time").and_then(Value::as_f64),
+        execution_time_ms: top.get("Execution Time").and_then(Value::as_f64),
+        raw: serde_json::to_string_pretty(output).unwrap_or_default(),
+    })
+}
+
+fn postgres_node(plan: &Value) -> PlanNode {
+    let number = |key: &str| plan.get(key).and_then(Value::as_f64);
+    let text = |key: &str| plan.get(key).and_then(Value::as_str).map(str::to_string);
+
+    let node_type = text("Node Type").unwrap_or_else(|| "Unknown".to_string());
+    let operation = match text("Join Type") {
+        Some(join) if join != "Inner" => format!("{} ({})", node_type, join),
+        _ => node_type.clone(),
+    };
+
+    let mut details = Vec::new();
+    for key in [
+        "Index Cond",
+        "Hash Cond",
+        "Merge Cond",
+        "Join Filter",
+        "Filter",
+        "Sort Key",
+        "Group Key",
+    ] {
+        let value = match plan.get(key) {
+            Some(Value::String(s)) => s.clone(),
+            Some(Value::Array(items)) => items
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join(", "),
+            _ => continue,
+        };
+        details.push(format!("{}: {}", key, value));
+    }
+
+    // Nodes under a branch that never ran report zero loops; their zero
+    // rows and times say nothing about the estimate.
+    let loops = number("Actual Loops");
+    let ran = loops.is_some_and(|l| l > 0.0);
+    let actual_rows = number("Actual Rows").filter(|_| ran);
+    let buffers = match (
+        plan.get("Shared Hit Blocks").and_then(Value::as_u64),
+        plan.get("Shared Read Blocks").and_then(Value::as_u64),
+    ) {
+        (None, None) => None,
+        (hit, read) => Some(PlanBuffers {
+            hit: hit.unwrap_or(0),
+            read: read.unwrap_or(0),
+        }),
+    };
+
+    let mut node = PlanNode {
+        relation: text("Relation Name")
+            .or_else(|| text("CTE Name"))
+            .or_else(|| text("Function Name")),
+        index: text("Index Name"),
+        detail: (!details.is_empty()).then(|| details.join("; ")),
+        estimated_rows: number("Plan Rows"),
+        actual_rows,
+        loops,
+        cost: number("Total Cost"),
+        time_ms: number("Actual Total Time")
+            .zip(loops)
+            .filter(|_| ran)
+            .map(|(t, l)| t * l),
+        buffers,
+        children: plan
+            .get("Plans")
+            .and_then(Value::as_array)
+            .map(|plans| plans.iter().map(postgres_node).collect())
+            .unwrap_or_default(),
+        ..PlanNode::new(operation)
+    };
+
+    // Rows read per scan: what came out plus what the filter threw away.
+    let full_scan = (node_type == "Seq Scan").then(|| {
+        let scanned = match actual_rows {
+            Some(rows) => Some(rows + number("Rows Removed by Filter").unwrap_or(0.0)),
+            None => node.estimated_rows,
+        };
+        scanned.map_or(FullScan::Unknown, FullScan::Rows)
+    });
+    node.annotate(full_scan);
+    node
+}
+
+/// Parses MySQL's `EXPLAIN FORMAT=JSON` output.
+pub fn parse_mysql_json(output: &str) -> Result<QueryPlan, String> {
+    let value: Value =
+        serde_json::from_str(output).map_err(|e| format!("Unexpected EXPLAIN output: {}", e))?;
+    Ok(QueryPlan {
+        root: single_root(mysql_nodes(&value)),
+        analyzed: false,
+        planning_time_ms: None,
+        execution_time_ms: None,
+        raw: output.to_string(),
+    })
+}
+
+/// Nodes for the plan operations nested anywhere in `value`. Keys that are
+/// not operations (cost_info, used_columns, query_specifications, ...) are
+/// looked through.
+fn mysql_nodes(value: &Value) -> Vec<PlanNode> {
+    match value {
+        Value::Array(items) => items.iter().flat_map(mysql_nodes).collect(),
+        Value::Object(map) => map
+            .iter()
+            .filter(|(_, v)| v.is_object() || v.is_array())
+            .flat_map(|(key, v)| mysql_operation(key, v))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn mysql_operation(key: &str, value: &Value) -> Vec<PlanNode> {
+    let operation = match key {
+        "table" => return vec![mysql_table(value)],
+        "query_block" => "Query block",
+        "nested_loop" => "Nested loop",
+        "ordering_operation" if value.get("using_filesort") == Some(&Value::Bool(true)) => {
+            "Sort (filesort)"
+        }
+        "ordering_operation" => "Sort",
+        "grouping_operation" => "Group",
+        "duplicates_removal" => "Remove duplicates",
+        "union_result" => "Union",
+        "windowing" => "Window",
+        _ => return mysql_nodes(value),
+    };
+    let cost = value
+        .pointer("/cost_info/query_cost")
+        .and_then(mysql_number);
+    let mut node = PlanNode {
+        cost,
+        children: mysql_nodes(value),
+        ..PlanNode::new(operation)
+    };
+    node.annotate(None);
+    vec![node]
+}
+
+fn mysql_table(table: &Value) -> PlanNode {
+    let text = |key: &str| table.get(key).and_then(Value::as_str).map(str::to_string);
+    let access = text("access_type").unwrap_or_default();
+    let operation = match access.as_str() {
+        "ALL" => "Full table scan".to_string(),
+        "index" => "Full index scan".to_string(),
+        "range" => "Index range scan".to_string(),
+        "ref" | "eq_ref" | "ref_or_null" => "Index lookup".to_string(),
+        "const" | "system" => "Constant row".to_string(),
+        "" => "Table".to_string(),
+        other => format!("Access ({})", other),
+    };
+    let mut node = PlanNode {
+        relation: text("table_name"),
+        index: text("key"),
+        detail: text("attached_condition"),
+        estimated_rows: table.get("rows_examined_per_scan").and_then(mysql_number),
+        cost: table
+            .pointer("/cost_info/prefix_cost")
+            .and_then(mysql_number),
+        children: mysql_nodes(table),
+        ..PlanNode::new(operation)
+    };
+    let full_scan = (access == "ALL").then(|| {
+        node.estimated_rows
+            .map_or(FullScan::Unknown, FullScan::Rows)
+    });
+    node.annotate(full_scan);
+    node
+}
+
+/// MySQL's JSON plans give costs as strings and row counts as numbers.
+fn mysql_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::String(s) => s.parse().ok(),
+        other => other.as_f64(),
+    }
+}
+
+/// Parses the indented tree that MySQL's `EXPLAIN ANALYZE` prints, e.g.
+/// `-> Table scan on t  (cost=0.35 rows=1) (actual time=0.02..0.03 rows=1 loops=1)`.
+pub fn parse_mysql_tree(output: &str) -> Result<QueryPlan, String> {
+    // Open nodes by depth; a line closes every open node at its depth or deeper.
+    let mut open: Vec<(usize, PlanNode)> = Vec::new();
+    let mut roots = Vec::new();
+    let close = |open: &mut Vec<(usize, PlanNode)>, roots: &mut Vec<PlanNode>| {
+        let (_, node) = open.pop().expect("only called with open nodes");
+        match open.last_mut() {
+            Some((_, parent)) => parent.children.push(node),
+            None => roots.push(node),
+        }
+    };
+
+    for line in output.lines() {
+        let trimmed = line.trim_start();
+        let Some(text) = trimmed.strip_prefix("-> ") else {
+            continue;
+        };
+        let depth = line.len() - trimmed.len();
+        while open.last().is_some_and(|(d, _)| *d >= depth) {
+            close(&mut open, &mut roots);
+        }
+        open.push((depth, mysql_tree_node(text)));
+    }
+    while !open.is_empty() {
+        close(&mut open, &mut roots);
+    }
+    if roots.is_empty() {
+        return Err("EXPLAIN ANALYZE output has no plan".to_string());
+    }
+
+    let root = single_root(roots);
+    Ok(QueryPlan {
+        execution_time_ms: root.time_ms,
+        root,
+        analyzed: true,
+        planning_time_ms: None,
+        raw: output.to_string(),
+    })
+}
+
+fn mysql_tree_node(text: &str) -> PlanNode {
+    let operation_end = ["  (cost=", " (cost=", " (actual time=", " (never executed)"]
+        .iter()
+        .filter_map(|marker| text.find(marker))
+        .min()
+        .unwrap_or(text.len());
+    let operation = text[..operation_end].trim();
+    let estimate = segment(text, "(cost=");
+    let actual = segment(text, "(actual time=");
+
+    // "Table scan on t", "Index lookup on t using idx (a=1)", ...
+    let (relation, index) = match operation.split_once(" on ") {
+        Some((_, rest)) => {
+            let mut words = rest.split_whitespace();
+            let relation = words.next().map(str::to_string);
+            let index = match (words.next(), words.next()) {
+                (Some("using"), Some(index)) => Some(index.to_string()),
+                _ => None,
+            };
+            (relation, index)
+        }
+        None => (None, None),
+    };
+
+    let loops = actual.and_then(|s| field(s, "loops"));
+    let mut node = PlanNode {
+        relation,
+        index,
+        estimated_rows: estimate.and_then(|s| field(s, "rows")),
+        cost: estimate.and_then(|s| field(s, "cost")),
+        actual_rows: actual.and_then(|s| field(s, "rows")),
+        loops,
+        time_ms: actual
+            .and_then(|s| field(s, "time"))
+            .map(|t| t * loops.unwrap_or(1.0)),
+        ..PlanNode::new(operation)
+    };
+    let full_scan = operation.starts_with("Table scan on").then(|| {
+        node.actual_rows
+            .or(node.estimated_rows)
+            .map_or(FullScan::Unknown, FullScan::Rows)
+    });
+    node.annotate(full_scan);
+    node
+}
+
+/// The parenthesized group starting with `start`, e.g. `(cost=1.2 rows=3)`.
+fn segment<'a>(text: &'a str, start: &str) -> Option<&'a str> {
+    let from = text.find(start)?;
+    let len = text[from..].find(')')?;
+    Some(&text[from + 1..from + len])
+}
+
+/// `name=value` inside a segment. For ranges like `time=0.02..0.03` this is
+/// the end of the range.
+fn field(segment: &str, name: &str) -> Option<f64> {
+    let value = segment
+        .split_whitespace()
+        .find_map(|part| part.strip_prefix(name)?.strip_prefix('='))?;
+    let value = value.rsplit("..").next()?;
+    value.parse().ok()
+}
+
+/// One row of SQLite's `EXPLAIN QUERY PLAN` output.
+pub struct SqlitePlanRow {
+    pub id: i64,
+    pub parent: i64,
+    pub detail: String,
+}
+
+/// Builds the tree from `EXPLAIN QUERY PLAN` rows, which link to their
+/// parent by id. SQLite reports no row counts or costs.
+pub fn parse_sqlite(rows: &[SqlitePlanRow]) -> QueryPlan {
+    fn children_of(rows: &[SqlitePlanRow], parent: i64) -> Vec<PlanNode> {
+        rows.iter()
+            .filter(|r| r.parent == parent)
+            .map(|r| {
+                let mut node = sqlite_node(&r.detail);
+                node.children = children_of(rows, r.id);
+                node
+            })
+            .collect()
+    }
+
+    let raw = rows
+        .iter()
+        .map(|r| format!("{}|{}|{}", r.id, r.parent, r.detail))
+        .collect::<Vec<_>>()
+        .join("\n");
+    QueryPlan {
+        root: single_root(children_of(rows, 0)),
+        analyzed: false,
+        planning_time_ms: None,
+        execution_time_ms: None,
+        raw,
+    }
+}
+
+/// `SCAN t`, `SCAN TABLE t USING COVERING INDEX i`, `SEARCH t USING INDEX i (a=?)`;
+/// anything else (temp B-trees, subqueries, compound parts) is kept as is.
+fn sqlite_node(detail: &str) -> PlanNode {
+    let (operation, rest) = match detail.split_once(' ') {
+        Some(("SCAN", rest)) => ("Scan", rest),
+        Some(("SEARCH", rest)) => ("Search", rest),
+        _ => return PlanNode::new(detail),
+    };
+    if rest == "CONSTANT ROW" {
+        return PlanNode::new(detail);
+    }
+    let rest = rest.strip_prefix("TABLE ").unwrap_or(rest);
+    let (relation, using) = match rest.split_once(" USING ") {
+        Some((relation, using)) => (relation, Some(using)),
+        None => (rest, None),
+    };
+    let index = using.map(|using| {
+        let name = using.split_once("INDEX ").map_or(using, |(_, name)| name);
+        name.split(" (").next().unwrap_or(name).to_string()
+    });
+
+    let mut node = PlanNode {
+        relation: Some(relation.to_string()),
+        detail: Some(detail.to_string()),
+        ..PlanNode::new(operation)
+    };
+    let full_scan = (operation == "Scan" && index.is_none()).then_some(FullScan::Unknown);
+    node.index = index;
+    node.annotate(full_scan);
+    node
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn warning_kinds(node: &PlanNode) -> Vec<PlanWarningKind> {
+        node.warnings.iter().map(|w| w.kind).collect()
+    }
+
+    #[test]
+    fn postgres_plan_flags_large_seq_scan_and_misestimate() {
+        let output = json!([{
+            "Plan": {
+                "Node Type": "Hash Join",
+                "Join Type": "Left",
+                "Total Cost": 950.5,
+                "Plan Rows": 10,
+                "Actual Rows": 4000,
+                "Actual Loops": 1,
+                "Actual Total Time": 12.5,
+                "Hash Cond": "(o.user_id = u.id)",
+                "Plans": [
+                    {
+                        "Node Type": "Seq Scan",
+                        "Relation Name": "orders",
+                        "Plan Rows": 4000,
+                        "Actual Rows": 4000,
+                        "Actual Loops": 1,
+                        "Rows Removed by Filter": 96000,
+                        "Filter": "(status = 'open')",
+                        "Shared Hit Blocks": 12,
+                        "Shared Read Blocks": 300
+                    },
+                    {
+                        "Node Type": "Index Scan",
+                        "Relation Name": "users",
+                        "Index Name": "users_pkey",
+                        "Plan Rows": 1,
+                        "Actual Rows": 0,
+                        "Actual Loops": 0
+                    }
+                ]
+            },
+            "Planning Time": 0.2,
+            "Execution Time": 13.1
+        }]);
+        let plan = parse_postgres(&output, true).unwrap();
+        assert_eq!(plan.execution_time_ms, Some(13.1));
+
+        let join = &plan.root;
+        assert_eq!(join.operation, "Hash Join (Left)");
+        assert_eq!(
+            join.detail.as_deref(),
+            Some("Hash Cond: (o.user_id = u.id)")
+        );
+        assert_eq!(warning_kinds(join), vec![PlanWarningKind::RowEstimate]);
+
+        let scan = &join.children[0];
+        assert_eq!(scan.relation.as_deref(), Some("orders"));
+        assert_eq!(scan.buffers, Some(PlanBuffers { hit: 12, read: 300 }));
+        assert_eq!(warning_kinds(scan), vec![PlanWarningKind::FullScan]);
+
+        let never_ran = &join.children[1];
+        assert_eq!(never_ran.index.as_deref(), Some("users_pkey"));
+        assert_eq!(never_ran.actual_rows, None);
+        assert!(never_ran.warnings.is_empty());
+    }
+
+    #[test]
+    fn mysql_json_plan_nests_tables_under_operations() {
+        let output = r#"{
+          "query_block": {
+            "select_id": 1,
+            "cost_info": {"query_cost": "20250.50"},
+            "ordering_operation": {
+              "using_filesort": true,
+              "nested_loop": [
+                {"table": {"table_name": "o", "access_type": "ALL",
+                           "rows_examined_per_scan": 50000,
+                           "cost_info": {"prefix_cost": "5000.00"},
+                           "attached_condition": "(`o`.`status` = 'open')"}},
+                {"table": {"table_name": "u", "access_type": "eq_ref", "key": "PRIMARY",
+                           "rows_examined_per_scan": 1,
+                           "cost_info": {"prefix_cost": "20250.50"}}}
+              ]
+            }
+          }
+        }"#;
+        let plan = parse_mysql_json(output).unwrap();
+        assert_eq!(plan.root.operation, "Query block");
+        assert_eq!(plan.root.cost, Some(20250.5));
+
+        let sort = &plan.root.children[0];
+        assert_eq!(sort.operation, "Sort (filesort)");
+        let tables = &sort.children[0].children;
+        assert_eq!(tables[0].operation, "Full table scan");
+        assert_eq!(warning_kinds(&tables[0]), vec![PlanWarningKind::FullScan]);
+        assert_eq!(tables[1].operation, "Index lookup");
+        assert_eq!(tables[1].index.as_deref(), Some("PRIMARY"));
+    }
+
+    #[test]
+    fn mysql_tree_plan_reads_indentation_and_timings() {
+        let output = "\
+-> Nested loop inner join  (cost=1.10 rows=2) (actual time=0.05..0.09 rows=2 loops=1)
+    -> Filter: (t.a > 1)  (cost=0.45 rows=2) (actual time=0.03..0.04 rows=2 loops=1)
+        -> Table scan on t  (cost=0.45 rows=20000) (actual time=0.02..0.03 rows=20000 loops=1)
+    -> Single-row index lookup on u using PRIMARY (id=t.u_id)  (cost=0.30 rows=1) (actual time=0.01..0.01 rows=1 loops=2)
+";
+        let plan = parse_mysql_tree(output).unwrap();
+        assert!(plan.analyzed);
+        assert_eq!(plan.execution_time_ms, Some(0.09));
+
+        let join = &plan.root;
+        assert_eq!(join.operation, "Nested loop inner join");
+        assert_eq!(join.children.len(), 2);
+
+        let scan = &join.children[0].children[0];
+        assert_eq!(scan.relation.as_deref(), Some("t"));
+        assert_eq!(warning_kinds(scan), vec![PlanWarningKind::FullScan]);
+
+        let lookup = &join.children[1];
+        assert_eq!(lookup.relation.as_deref(), Some("u"));
+        assert_eq!(lookup.index.as_deref(), Some("PRIMARY"));
+        assert_eq!(lookup.time_ms, Some(0.02));
+    }
+
+    #[test]
+    fn sqlite_plan_links_rows_by_parent() {
+        let row = |id, parent, detail: &str| SqlitePlanRow {
+            id,
+            parent,
+            detail: detail.to_string(),
+        };
+        let plan = parse_sqlite(&[
+            row(2, 0, "SCAN orders"),
+            row(4, 0, "SEARCH users USING INTEGER PRIMARY KEY (rowid=?)"),
+            row(7, 0, "USE TEMP B-TREE FOR ORDER BY"),
+            row(9, 2, "SCAN TABLE items USING COVERING INDEX items_order"),
+        ]);
+        let root = &plan.root;
+        assert_eq!(root.operation, "Query plan");
+        assert_eq!(root.children.len(), 3);
+
+        let scan = &root.children[0];
+        assert_eq!(scan.relation.as_deref(), Some("orders"));
+        assert_eq!(warning_kinds(scan), vec![PlanWarningKind::FullScan]);
+        assert_eq!(scan.children[0].index.as_deref(), Some("items_order"));
+        assert!(scan.children[0].warnings.is_empty());
+
+        assert_eq!(
+            root.children[1].index.as_deref(),
+            Some("INTEGER PRIMARY KEY")
+        );
+        assert_eq!(root.children[2].operation, "USE TEMP B-TREE FOR ORDER BY");
+    }
+}
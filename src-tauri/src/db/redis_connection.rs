@@ -0,0 +1,401 @@
+// ABOUTME: Redis connections to a single server, a Sentinel-monitored primary or a cluster.
+// ABOUTME: RedisConnection hides which one it is, so commands and pipelines run unchanged.
+
+use super::redis_db;
+use crate::storage::{ConnectionConfig, DatabaseType, RedisTopology};
+use futures::future::try_join_all;
+use redis::aio::{ConnectionLike, ConnectionManager};
+use redis::cluster::ClusterClientBuilder;
+use redis::cluster_async::ClusterConnection;
+use redis::cluster_routing::{RoutingInfo, SingleNodeRoutingInfo};
+use redis::sentinel::{Sentinel, SentinelNodeConnectionInfo};
+use redis::{
+    Cmd, ConnectionInfo, ErrorKind, FromRedisValue, Pipeline, RedisConnectionInfo, RedisError,
+    RedisFuture, RedisResult, TlsCertificates, Value,
+};
+use std::sync::{Arc, RwLock};
+use tokio::sync::Mutex;
+
+const DEFAULT_PORT: u16 = 6379;
+
+/// A server's host and port.
+pub type NodeAddr = (String, u16);
+
+/// All three kinds multiplex commands over shared connections, so clones are
+/// cheap and share them. Clones also share the selected database, so nothing
+/// here sends SELECT: each database gets a connection of its own.
+#[derive(Clone)]
+pub enum RedisConnection {
+    Node(ConnectionManager),
+    Cluster(ClusterConnection),
+    Sentinel(SentinelConnection),
+}
+
+/// The primary that the sentinels currently name. After a failover the old
+/// primary goes away or turns read-only; the first command to notice asks
+/// the sentinels again, and is retried once on the new primary if it cannot
+/// have run on the old one.
+#[derive(Clone)]
+pub struct SentinelConnection {
+    sentinel: Arc<Mutex<Sentinel>>,
+    master_name: String,
+    node_info: SentinelNodeConnectionInfo,
+    certificates: Option<TlsCertificates>,
+    primary: Arc<RwLock<ConnectionManager>>,
+}
+
+/// Connects as `config.redis_topology` says, with `host:port` as the first
+/// seed node or sentinel. A single server or Sentinel primary starts in
+/// database `db`, and returns to it on every reconnect; a cluster only has
+/// database 0.
+pub async fn connect(
+    config: &ConnectionConfig,
+    host: &str,
+    port: u16,
+    db: i64,
+) -> Result<RedisConnection, String> {
+    let failed = |e: RedisError| format!("Failed to connect to Redis: {}", e);
+    match &config.redis_topology {
+        RedisTopology::Standalone => {
+            let client = redis_db::client(config, host, port, db)?;
+            let manager = ConnectionManager::new(client).await.map_err(failed)?;
+            Ok(RedisConnection::Node(manager))
+        }
+        RedisTopology::Cluster { seeds } => {
+            let mut nodes = vec![redis_db::connection_info(config, host, port)];
+            for (host, port) in parse_nodes(seeds)? {
+                nodes.push(redis_db::connection_info(config, &host, port));
+            }
+            let mut builder = ClusterClientBuilder::new(nodes);
+            if let Some(certificates) = redis_db::tls_certificates(config)? {
+                // Without an explicit mode, `certs` would make Require
+                // verify the server.
+                if let Some(mode) = redis_db::tls_mode(&config.ssl_mode) {
+                    builder = builder.tls(mode);
+                }
+                builder = builder.certs(certificates);
+            }
+            let client = builder
+                .build()
+                .map_err(|e| format!("Failed to create Redis client: {}", e))?;
+            let conn = client.get_async_connection().await.map_err(failed)?;
+            Ok(RedisConnection::Cluster(conn))
+        }
+        RedisTopology::Sentinel {
+            master_name,
+            sentinels,
+        } => {
+            if master_name.trim().is_empty() {
+                return Err("A Sentinel connection needs the master name".to_string());
+            }
+            let mut nodes = vec![sentinel_info(config, host, port)];
+            for (host, port) in parse_nodes(sentinels)? {
+                nodes.push(sentinel_info(config, &host, port));
+            }
+            let mut sentinel = Sentinel::build(nodes)
+                .map_err(|e| format!("Failed to create Redis client: {}", e))?;
+            let master_name = master_name.trim().to_string();
+            let node_info = SentinelNodeConnectionInfo {
+                tls_mode: redis_db::tls_mode(&config.ssl_mode),
+                redis_connection_info: Some(RedisConnectionInfo {
+                    db,
+                    ..redis_db::credentials(config)
+                }),
+            };
+            let certificates = redis_db::tls_certificates(config)?;
+            let primary = find_primary(
+                &mut sentinel,
+                &master_name,
+                &node_info,
+                certificates.as_ref(),
+            )
+            .await
+            .map_err(failed)?;
+            let conn = SentinelConnection {
+                sentinel: Arc::new(Mutex::new(sentinel)),
+                master_name,
+                node_info,
+                certificates,
+                primary: Arc::new(RwLock::new(primary)),
+            };
+            Ok(RedisConnection::Sentinel(conn))
+        }
+    }
+}
+
+/// Cluster and Sentinel clients go on to connect to the addresses the servers
+/// report, which a tunnel to one of them does not reach.
+pub fn check_tunnel(config: &ConnectionConfig) -> Result<(), String> {
+    if matches!(config.db_type, DatabaseType::Redis)
+        && config.ssh_tunnel.is_some()
+        && !config.redis_topology.is_standalone()
+    {
+        return Err("Redis Cluster and Sentinel connections cannot use an SSH tunnel".to_string());
+    }
+    Ok(())
+}
+
+/// Sentinels are reached like the primary, but without its credentials:
+/// they have their own, and usually none.
+fn sentinel_info(config: &ConnectionConfig, host: &str, port: u16) -> ConnectionInfo {
+    ConnectionInfo {
+        redis: Default::default(),
+        ..redis_db::connection_info(config, host, port)
+    }
+}
+
+fn parse_nodes(nodes: &[String]) -> Result<Vec<NodeAddr>, String> {
+    nodes
+        .iter()
+        .map(|node| node.trim())
+        .filter(|node| !node.is_empty())
+        .map(parse_node)
+        .collect()
+}
+
+/// `host:port`, `[v6]:port`, or a bare host on the default port.
+fn parse_node(node: &str) -> Result<NodeAddr, String> {
+    let (host, port) = match node.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') || host.ends_with(']') => (host, Some(port)),
+        _ => (node, None),
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if host.is_empty() {
+        return Err(format!("Invalid Redis node address: {}", node));
+    }
+    let port = match port {
+        Some(port) => port
+            .parse()
+            .map_err(|_| format!("Invalid port in Redis node address: {}", node))?,
+        None => DEFAULT_PORT,
+    };
+    Ok((host.to_string(), port))
+}
+
+/// Primaries that serve slots and are not failing, sorted, from the output
+/// of `CLUSTER NODES`.
+fn cluster_primaries(nodes: &str) -> Vec<NodeAddr> {
+    let mut primaries: Vec<NodeAddr> = nodes
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let flags: Vec<&str> = fields.get(2)?.split(',').collect();
+            let usable = flags.contains(&"master")
+                && !flags
+                    .iter()
+                    .any(|f| f.starts_with("fail") || matches!(*f, "noaddr" | "handshake"));
+            if !usable || fields.len() <= 8 {
+                return None;
+            }
+            // ip:port@cport, optionally followed by ,hostname
+            let addr = fields[1].split(['@', ',']).next()?;
+            let (host, port) = addr.rsplit_once(':')?;
+            Some((host.to_string(), port.parse().ok()?))
+        })
+        .collect();
+    primaries.sort();
+    primaries
+}
+
+/// Errors after which the primary may have moved.
+fn primary_lost(err: &RedisError) -> bool {
+    err.is_io_error()
+        || err.is_connection_dropped()
+        || err.is_connection_refusal()
+        || err.kind() == ErrorKind::ReadOnly
+}
+
+impl SentinelConnection {
+    fn current(&self) -> ConnectionManager {
+        self.primary
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Switches to the new primary after `err`, and returns it if the failed
+    /// request can run again there.
+    async fn retry_after(&self, err: RedisError) -> RedisResult<ConnectionManager> {
+        let primary = self.fail_over().await;
+        // A refused connection or a READONLY reply means the request did not
+        // run. Any other failure may come after the server ran it, and a
+        // write applied twice is worse than an error.
+        if err.is_connection_refusal() || err.kind() == ErrorKind::ReadOnly {
+            primary
+        } else {
+            Err(err)
+        }
+    }
+
+    /// Asks the sentinels where the primary is now and switches to it.
+    async fn fail_over(&self) -> RedisResult<ConnectionManager> {
+        let mut sentinel = self.sentinel.lock().await;
+        let primary = find_primary(
+            &mut sentinel,
+            &self.master_name,
+            &self.node_info,
+            self.certificates.as_ref(),
+        )
+        .await?;
+        *self.primary.write().unwrap_or_else(|e| e.into_inner()) = primary.clone();
+        Ok(primary)
+    }
+}
+
+/// A connection to the primary, in `node_info`'s database. The connection
+/// manager selects that database again whenever it reconnects.
+async fn find_primary(
+    sentinel: &mut Sentinel,
+    master_name: &str,
+    node_info: &SentinelNodeConnectionInfo,
+    certificates: Option<&TlsCertificates>,
+) -> RedisResult<ConnectionManager> {
+    let client = sentinel
+        .async_master_for(master_name, Some(node_info))
+        .await?;
+    let client = match certificates {
+        Some(certificates) => redis::Client::build_with_tls(
+            client.get_connection_info().clone(),
+            certificates.clone(),
+        )?,
+        None => client,
+    };
+    ConnectionManager::new(client).await
+}
+
+impl RedisConnection {
+    pub fn is_cluster(&self) -> bool {
+        matches!(self, Self::Cluster(_))
+    }
+
+    /// A cluster's primaries, in a stable order. Empty for a single server.
+    pub async fn primaries(&mut self) -> Result<Vec<NodeAddr>, String> {
+        let Self::Cluster(conn) = self else {
+            return Ok(vec![]);
+        };
+        let nodes: String = redis::cmd("CLUSTER")
+            .arg("NODES")
+            .query_async(conn)
+            .await
+            .map_err(|e| format!("Failed to list cluster nodes: {}", e))?;
+        Ok(cluster_primaries(&nodes))
+    }
+
+    /// Runs `cmd` on one cluster node, or as usual when `node` is None.
+    pub async fn query_node<T: FromRedisValue>(
+        &mut self,
+        node: Option<&NodeAddr>,
+        cmd: &Cmd,
+    ) -> RedisResult<T> {
+        let value = match (self, node) {
+            (Self::Cluster(conn), Some((host, port))) => {
+                let routing = RoutingInfo::SingleNode(SingleNodeRoutingInfo::ByAddress {
+                    host: host.clone(),
+                    port: *port,
+                });
+                conn.route_command(cmd, routing).await?
+            }
+            (conn, _) => conn.req_packed_command(cmd).await?,
+        };
+        T::from_owned_redis_value(value)
+    }
+}
+
+impl ConnectionLike for RedisConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        match self {
+            Self::Node(conn) => conn.req_packed_command(cmd),
+            Self::Cluster(conn) => conn.req_packed_command(cmd),
+            Self::Sentinel(conn) => Box::pin(async move {
+                match conn.current().req_packed_command(cmd).await {
+                    Err(e) if primary_lost(&e) => {
+                        conn.retry_after(e).await?.req_packed_command(cmd).await
+                    }
+                    result => result,
+                }
+            }),
+        }
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        pipeline: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        match self {
+            Self::Node(conn) => conn.req_packed_commands(pipeline, offset, count),
+            // A cluster pipeline has to stay within one hash slot, and ours
+            // (TYPE and PTTL for a page of keys) do not. Sending the commands
+            // concurrently instead costs about the same single round trip,
+            // but means pipelines here are never atomic.
+            Self::Cluster(conn) => {
+                let conn = conn.clone();
+                Box::pin(async move {
+                    let replies = try_join_all(pipeline.cmd_iter().map(|cmd| {
+                        let mut conn = conn.clone();
+                        async move { conn.req_packed_command(cmd).await }
+                    }))
+                    .await?;
+                    Ok(replies.into_iter().skip(offset).take(count).collect())
+                })
+            }
+            Self::Sentinel(conn) => Box::pin(async move {
+                match conn
+                    .current()
+                    .req_packed_commands(pipeline, offset, count)
+                    .await
+                {
+                    Err(e) if primary_lost(&e) => {
+                        conn.retry_after(e)
+                            .await?
+                            .req_packed_commands(pipeline, offset, count)
+                            .await
+                    }
+                    result => result,
+                }
+            }),
+        }
+    }
+
+    fn get_db(&self) -> i64 {
+        match self {
+            Self::Node(conn) => conn.get_db(),
+            Self::Cluster(_) => 0,
+            Self::Sentinel(conn) => conn.current().get_db(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn node_addresses_default_the_port_and_accept_ipv6() {
+        let parse = |s: &str| parse_node(s).unwrap();
+        assert_eq!(parse("cache-1:7000"), ("cache-1".to_string(), 7000));
+        assert_eq!(parse("cache-1"), ("cache-1".to_string(), 6379));
+        assert_eq!(parse("[::1]:7001"), ("::1".to_string(), 7001));
+        assert_eq!(parse("::1"), ("::1".to_string(), 6379));
+        assert!(parse_node("cache-1:port").is_err());
+    }
+
+    #[test]
+    fn cluster_primaries_skip_replicas_and_failed_nodes() {
+        let nodes = "\
+07c3 10.0.0.2:6379@16379 master - 0 1426238317239 2 connected 5461-10922
+67ed 10.0.0.1:6379@16379,cache-a myself,master - 0 0 1 connected 0-5460
+292f 10.0.0.4:6379@16379 slave 67ed 0 1426238316232 1 connected
+824f 10.0.0.3:6379@16379 master,fail - 1426238316232 1426238316232 3 disconnected 10923-16383
+e7d1 10.0.0.5:6379@16379 master - 0 1426238316232 4 connected
+";
+        assert_eq!(
+            cluster_primaries(nodes),
+            vec![
+                ("10.0.0.1".to_string(), 6379),
+                ("10.0.0.2".to_string(), 6379)
+            ]
+        );
+    }
+}
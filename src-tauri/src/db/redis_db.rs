@@ -1,129 +1,411 @@
 // ABOUTME: Redis database operations for connection and command execution.
 // ABOUTME: Handles Redis-specific logic including command parsing and response formatting.
 
-use super::{ColumnInfo, ConstraintInfo, FunctionInfo, IndexInfo, QueryResult};
-use redis::aio::ConnectionManager;
-use redis::{RedisResult, Value};
-
-pub async fn connect(
-    host: &str,
+use super::driver::{DatabaseDriver, DriverCapabilities};
+use super::redis_connection::{self, RedisConnection};
+use super::redis_keyspace::{
+    self, KeyScan, RedisBytes, RedisDatabaseInfo, RedisKeyPage, RedisNamespace,
+};
+use super::redis_value::{self, RedisEdit, RedisKeyValue};
+use super::running::RunningQueries;
+use super::{emit_progress, ColumnInfo, ConstraintInfo, IndexInfo, QueryResult};
+use async_trait::async_trait;
+use crate::storage::{ConnectionConfig, SslMode};
+use redis::{
+    ClientTlsConfig, ConnectionAddr, ConnectionInfo, RedisConnectionInfo, RedisResult,
+    TlsCertificates, TlsMode, Value,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tauri::AppHandle;
+use tokio::sync::Notify;
+
+pub struct RedisDriver {
+    /// Database 0, for commands that aren't about any one database.
+    conn: RedisConnection,
+    /// How to open the connection for another database.
+    config: ConnectionConfig,
+    host: String,
     port: u16,
-    username: &str,
-    password: &str,
-) -> Result<ConnectionManager, String> {
-    let url = build_connection_url(host, port, username, password);
-    let client =
-        redis::Client::open(url).map_err(|e| format!("Failed to create Redis client: {}", e))?;
-    let manager = ConnectionManager::new(client)
-        .await
-        .map_err(|e| format!("Failed to connect to Redis: {}", e))?;
-    Ok(manager)
+    /// A connection per database, opened on first use. Selecting on a shared
+    /// connection would race: another caller's SELECT can land between ours
+    /// and the command it was meant for.
+    databases: tokio::sync::Mutex<HashMap<i64, RedisConnection>>,
+    /// The database the query editor last switched to.
+    selected: Mutex<String>,
+    /// Separates a key's namespace from the rest of it.
+    delimiter: String,
+    /// Wakes the task awaiting each in-flight command so it can drop it.
+    running: RunningQueries<Arc<Notify>>,
 }
 
-fn build_connection_url(host: &str, port: u16, username: &str, password: &str) -> String {
-    match (username.is_empty(), password.is_empty()) {
-        (true, true) => format!("redis://{}:{}", host, port),
-        (true, false) => format!("redis://:{}@{}:{}", password, host, port),
-        (false, _) => format!("redis://{}:{}@{}:{}", username, password, host, port),
+impl RedisDriver {
+    pub async fn connect(config: &ConnectionConfig, host: &str, port: u16) -> Result<Self, String> {
+        let conn = redis_connection::connect(config, host, port, 0).await?;
+        let delimiter = config
+            .options
+            .redis_key_delimiter
+            .clone()
+            .filter(|d| !d.is_empty())
+            .unwrap_or_else(|| redis_keyspace::DEFAULT_DELIMITER.to_string());
+        Ok(Self {
+            conn: conn.clone(),
+            config: config.clone(),
+            host: host.to_string(),
+            port,
+            databases: tokio::sync::Mutex::new(HashMap::from([(0, conn)])),
+            selected: Mutex::new("0".to_string()),
+            delimiter,
+            running: RunningQueries::new(),
+        })
     }
-}
 
-pub async fn list_databases(_conn: &mut ConnectionManager) -> Result<Vec<String>, String> {
-    Ok((0..16).map(|i| i.to_string()).collect())
-}
+    /// The connection for `database`, opening it if this is its first use.
+    async fn database(&self, database: &str) -> Result<RedisConnection, String> {
+        let index: i64 = database
+            .parse()
+            .map_err(|_| format!("Invalid database index: {}", database))?;
+        let mut databases = self.databases.lock().await;
+        if let Some(conn) = databases.get(&index) {
+            return Ok(conn.clone());
+        }
+        if self.conn.is_cluster() {
+            return Err("A Redis cluster only has database 0".to_string());
+        }
+        let conn = redis_connection::connect(&self.config, &self.host, self.port, index)
+            .await
+            .map_err(|e| format!("Failed to switch database: {}", e))?;
+        databases.insert(index, conn.clone());
+        Ok(conn)
+    }
 
-pub async fn list_schemas(
-    _conn: &mut ConnectionManager,
-    _database: &str,
-) -> Result<Vec<String>, String> {
-    Ok(vec![])
+    /// Every logical database, with how many keys it holds and expires.
+    pub async fn list_database_info(&self) -> Result<Vec<RedisDatabaseInfo>, String> {
+        redis_keyspace::list_databases(&mut self.conn.clone()).await
+    }
+
+    /// Key namespaces in `database`, including the unnamed one for keys
+    /// without a delimiter.
+    pub async fn list_namespaces(&self, database: &str) -> Result<Vec<RedisNamespace>, String> {
+        let mut conn = self.database(database).await?;
+        redis_keyspace::list_namespaces(&mut conn, &self.delimiter).await
+    }
+
+    /// One page of the keys in `namespace`, continuing from `cursor`.
+    pub async fn scan_namespace(
+        &self,
+        database: &str,
+        namespace: &str,
+        cursor: &str,
+        count: usize,
+    ) -> Result<RedisKeyPage, String> {
+        let mut conn = self.database(database).await?;
+        let pattern = redis_keyspace::namespace_pattern(namespace, &self.delimiter);
+        let scan = KeyScan {
+            pattern: Some(&pattern),
+            exclude_delimiter: namespace.is_empty().then_some(self.delimiter.as_str()),
+            ..Default::default()
+        };
+        redis_keyspace::scan_keys(&mut conn, cursor, count, &scan).await
+    }
+
+    pub async fn get_key(
+        &self,
+        database: &str,
+        key: &RedisBytes,
+        cursor: Option<&str>,
+        count: usize,
+    ) -> Result<RedisKeyValue, String> {
+        let mut conn = self.database(database).await?;
+        redis_value::get_key(&mut conn, key, cursor, count).await
+    }
+
+    pub async fn edit_key(
+        &self,
+        database: &str,
+        key: &RedisBytes,
+        edit: RedisEdit,
+    ) -> Result<(), String> {
+        let mut conn = self.database(database).await?;
+        redis_value::edit_key(&mut conn, key, edit).await
+    }
+
+    pub async fn delete_members(
+        &self,
+        database: &str,
+        key: &RedisBytes,
+        members: Vec<RedisBytes>,
+    ) -> Result<u64, String> {
+        let mut conn = self.database(database).await?;
+        redis_value::delete_members(&mut conn, key, members).await
+    }
 }
 
-pub async fn list_tables(
-    _conn: &mut ConnectionManager,
-    _database: &str,
-    _schema: &str,
-) -> Result<Vec<String>, String> {
-    Ok(vec![])
+#[async_trait]
+impl DatabaseDriver for RedisDriver {
+    fn capabilities(&self) -> DriverCapabilities {
+        DriverCapabilities {
+            engine: "Redis",
+            supports_query_cancel: true,
+            switches_database_in_place: true,
+            ..Default::default()
+        }
+    }
+
+    async fn list_databases(&self) -> Result<Vec<String>, String> {
+        list_databases(&mut self.conn.clone()).await
+    }
+
+    async fn list_schemas(&self, database: &str) -> Result<Vec<String>, String> {
+        list_schemas(&mut self.conn.clone(), database).await
+    }
+
+    /// Named namespaces only: the keys outside every namespace have no name
+    /// to open them by.
+    async fn list_tables(&self, database: &str, _schema: &str) -> Result<Vec<String>, String> {
+        Ok(self
+            .list_namespaces(database)
+            .await?
+            .into_iter()
+            .map(|ns| ns.name)
+            .filter(|name| !name.is_empty())
+            .collect())
+    }
+
+    async fn list_views(&self, database: &str, schema: &str) -> Result<Vec<String>, String> {
+        list_views(&mut self.conn.clone(), database, schema).await
+    }
+
+    async fn list_columns(
+        &self,
+        database: &str,
+        schema: &str,
+        table: &str,
+    ) -> Result<Vec<ColumnInfo>, String> {
+        list_columns(database, schema, table)
+    }
+
+    async fn list_indexes(
+        &self,
+        database: &str,
+        schema: &str,
+        table: &str,
+    ) -> Result<Vec<IndexInfo>, String> {
+        list_indexes(&mut self.conn.clone(), database, schema, table).await
+    }
+
+    async fn list_constraints(
+        &self,
+        database: &str,
+        schema: &str,
+        table: &str,
+    ) -> Result<Vec<ConstraintInfo>, String> {
+        list_constraints(&mut self.conn.clone(), database, schema, table).await
+    }
+
+    async fn execute_query(
+        &self,
+        app: &AppHandle,
+        query_id: &str,
+        query: &str,
+        database: Option<&str>,
+        _max_rows: usize,
+    ) -> Result<QueryResult, String> {
+        // SELECT would switch the connection every caller shares; switch
+        // which connection the editor uses instead.
+        if let Some(database) = select_command(query) {
+            self.switch_database(&database).await?;
+            return Ok(QueryResult {
+                message: Some(format!("Switched to database {}", database)),
+                ..Default::default()
+            });
+        }
+        let database = match database {
+            Some(db) => db.to_string(),
+            None => self.selected.lock().unwrap().clone(),
+        };
+        let mut conn = self.database(&database).await?;
+        let start = Instant::now();
+        emit_progress(app, query_id, "executing", 0, 0, None, None, None);
+        let cancelled = Arc::new(Notify::new());
+        let result = {
+            let _running = self.running.register(query_id, cancelled.clone());
+            // Redis has no per-command cancel; dropping the future abandons the
+            // reply, which the multiplexed connection discards when it arrives.
+            tokio::select! {
+                result = execute_query(&mut conn, query) => result?,
+                _ = cancelled.notified() => return Err("Query cancelled.".to_string()),
+            }
+        };
+        let total_ms = start.elapsed().as_millis() as u64;
+        emit_progress(
+            app,
+            query_id,
+            "done",
+            result.row_count,
+            total_ms,
+            Some(total_ms),
+            Some(0),
+            None,
+        );
+        Ok(QueryResult {
+            server_time_ms: Some(total_ms),
+            transfer_time_ms: Some(0),
+            ..result
+        })
+    }
+
+    async fn switch_database(&self, database: &str) -> Result<(), String> {
+        self.database(database).await?;
+        *self.selected.lock().unwrap() = database.to_string();
+        Ok(())
+    }
+
+    async fn cancel_query(&self, query_id: &str) -> Result<bool, String> {
+        match self.running.get(query_id) {
+            Some(cancelled) => {
+                cancelled.notify_one();
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn as_redis(&self) -> Option<&RedisDriver> {
+        Some(self)
+    }
 }
 
-pub async fn list_views(
-    _conn: &mut ConnectionManager,
-    _database: &str,
-    _schema: &str,
-) -> Result<Vec<String>, String> {
-    Ok(vec![])
+/// A client for `config` at `host:port`. Require and the verify modes mean
+/// `rediss://`. Redis has no way to offer TLS, so Prefer can't upgrade and
+/// stays plaintext, as saved connections have always been. Require skips
+/// certificate checks; rustls always checks the host name, so VerifyCa
+/// verifies as much as VerifyFull.
+pub(super) fn client(
+    config: &ConnectionConfig,
+    host: &str,
+    port: u16,
+    db: i64,
+) -> Result<redis::Client, String> {
+    let mut info = connection_info(config, host, port);
+    info.redis.db = db;
+    let client = match tls_certificates(config)? {
+        Some(certificates) => redis::Client::build_with_tls(info, certificates),
+        None => redis::Client::open(info),
+    };
+    client.map_err(|e| format!("Failed to create Redis client: {}", e))
 }
 
-pub async fn list_functions(
-    _conn: &mut ConnectionManager,
-    _database: &str,
-    _schema: &str,
-) -> Result<Vec<String>, String> {
-    Ok(vec![])
+pub(super) fn tls_mode(ssl_mode: &SslMode) -> Option<TlsMode> {
+    match ssl_mode {
+        SslMode::Disable | SslMode::Prefer => None,
+        SslMode::Require => Some(TlsMode::Insecure),
+        SslMode::VerifyCa | SslMode::VerifyFull => Some(TlsMode::Secure),
+    }
 }
 
-pub async fn get_function_definition(
-    _conn: &mut ConnectionManager,
-    _database: &str,
-    _schema: &str,
-    _function_name: &str,
-) -> Result<FunctionInfo, String> {
-    Err("Redis does not support functions in the traditional sense".to_string())
+/// The CA and client certificate files, read. None when TLS is off or uses
+/// the system's trust store without a client certificate.
+pub(super) fn tls_certificates(
+    config: &ConnectionConfig,
+) -> Result<Option<TlsCertificates>, String> {
+    let root_cert = config.ssl_root_cert.as_deref().filter(|p| !p.is_empty());
+    let client_identity = config.ssl_client_identity()?;
+    if tls_mode(&config.ssl_mode).is_none() || (root_cert.is_none() && client_identity.is_none()) {
+        return Ok(None);
+    }
+    let read =
+        |path: &str| std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e));
+    Ok(Some(TlsCertificates {
+        client_tls: match client_identity {
+            Some((cert, key)) => Some(ClientTlsConfig {
+                client_cert: read(cert)?,
+                client_key: read(key)?,
+            }),
+            None => None,
+        },
+        root_cert: root_cert.map(read).transpose()?,
+    }))
 }
 
-pub async fn get_view_definition(
-    _conn: &mut ConnectionManager,
-    _database: &str,
-    _schema: &str,
-    _view_name: &str,
-) -> Result<FunctionInfo, String> {
-    Err("Redis does not support views".to_string())
+/// Where and as whom to connect. Built field by field so credentials never
+/// pass through a URL, where `@`, `/` or `#` would need escaping.
+pub(super) fn connection_info(
+    config: &ConnectionConfig,
+    host: &str,
+    port: u16,
+) -> ConnectionInfo {
+    let addr = match tls_mode(&config.ssl_mode) {
+        None => ConnectionAddr::Tcp(host.to_string(), port),
+        Some(mode) => ConnectionAddr::TcpTls {
+            host: host.to_string(),
+            port,
+            insecure: mode == TlsMode::Insecure,
+            tls_params: None,
+        },
+    };
+    ConnectionInfo {
+        addr,
+        redis: credentials(config),
+    }
 }
 
-pub async fn list_materialized_views(
-    _conn: &mut ConnectionManager,
-    _database: &str,
-    _schema: &str,
-) -> Result<Vec<String>, String> {
-    Ok(vec![])
+pub(super) fn credentials(config: &ConnectionConfig) -> RedisConnectionInfo {
+    let non_empty = |s: &str| (!s.is_empty()).then(|| s.to_string());
+    RedisConnectionInfo {
+        username: non_empty(&config.username),
+        password: non_empty(&config.password),
+        ..Default::default()
+    }
 }
 
-pub async fn list_sequences(
-    _conn: &mut ConnectionManager,
-    _database: &str,
-    _schema: &str,
-) -> Result<Vec<String>, String> {
-    Ok(vec![])
+pub async fn list_databases(conn: &mut RedisConnection) -> Result<Vec<String>, String> {
+    Ok(redis_keyspace::list_databases(conn)
+        .await?
+        .into_iter()
+        .map(|db| db.name)
+        .collect())
 }
 
-pub async fn list_triggers(
-    _conn: &mut ConnectionManager,
+pub async fn list_schemas(
+    _conn: &mut RedisConnection,
     _database: &str,
-    _schema: &str,
 ) -> Result<Vec<String>, String> {
     Ok(vec![])
 }
 
-pub async fn list_procedures(
-    _conn: &mut ConnectionManager,
+pub async fn list_views(
+    _conn: &mut RedisConnection,
     _database: &str,
     _schema: &str,
 ) -> Result<Vec<String>, String> {
     Ok(vec![])
 }
 
-pub async fn list_columns(
-    _conn: &mut ConnectionManager,
+/// A namespace's keys read as rows of BROWSE's columns.
+pub fn list_columns(
     _database: &str,
     _schema: &str,
     _table: &str,
 ) -> Result<Vec<ColumnInfo>, String> {
-    Ok(vec![])
+    let column = |name: &str, data_type: &str, is_nullable: bool| ColumnInfo {
+        name: name.to_string(),
+        data_type: data_type.to_string(),
+        is_nullable,
+        column_default: None,
+        is_primary_key: name == "key",
+    };
+    Ok(vec![
+        column("key", "string", false),
+        column("type", "string", false),
+        column("ttl_ms", "integer", true),
+        column("memory_bytes", "integer", true),
+    ])
 }
 
 pub async fn list_indexes(
-    _conn: &mut ConnectionManager,
+    _conn: &mut RedisConnection,
     _database: &str,
     _schema: &str,
     _table: &str,
@@ -132,7 +414,7 @@ pub async fn list_indexes(
 }
 
 pub async fn list_constraints(
-    _conn: &mut ConnectionManager,
+    _conn: &mut RedisConnection,
     _database: &str,
     _schema: &str,
     _table: &str,
@@ -140,10 +422,10 @@ pub async fn list_constraints(
     Ok(vec![])
 }
 
-async fn browse_keys(conn: &mut ConnectionManager, args: &[&str]) -> Result<QueryResult, String> {
+async fn browse_keys(conn: &mut RedisConnection, args: &[&str]) -> Result<QueryResult, String> {
     // Parse arguments: BROWSE [cursor] [COUNT n] [MATCH pattern] [TYPE type]
-    let mut cursor: i64 = 0;
-    let mut count: i64 = 100;
+    let mut cursor = "0";
+    let mut count: usize = 100;
     let mut pattern: Option<&str> = None;
     let mut type_filter: Option<&str> = None;
 
@@ -151,7 +433,7 @@ async fn browse_keys(conn: &mut ConnectionManager, args: &[&str]) -> Result<Quer
     while i < args.len() {
         match args[i].to_uppercase().as_str() {
             "COUNT" if i + 1 < args.len() => {
-                count = args[i + 1].parse().unwrap_or(100);
+                count = args[i + 1].parse().unwrap_or(100).max(1);
                 i += 2;
             }
             "MATCH" if i + 1 < args.len() => {
@@ -165,82 +447,62 @@ async fn browse_keys(conn: &mut ConnectionManager, args: &[&str]) -> Result<Quer
             _ => {
                 // First non-keyword argument is the cursor
                 if i == 0 {
-                    cursor = args[i].parse().unwrap_or(0);
+                    cursor = args[i];
                 }
                 i += 1;
             }
         }
     }
 
-    // Build SCAN command
-    let mut cmd = redis::cmd("SCAN");
-    cmd.arg(cursor);
-    cmd.arg("COUNT").arg(count);
-    if let Some(p) = pattern {
-        cmd.arg("MATCH").arg(p);
-    }
-    if let Some(t) = type_filter {
-        cmd.arg("TYPE").arg(t);
-    }
-
-    let result: RedisResult<Value> = cmd.query_async(conn).await;
-
-    match result {
-        Ok(Value::Array(arr)) if arr.len() == 2 => {
-            let next_cursor = match &arr[0] {
-                Value::BulkString(b) => String::from_utf8_lossy(b).to_string(),
-                Value::Int(i) => i.to_string(),
-                _ => "0".to_string(),
-            };
-
-            let keys = match &arr[1] {
-                Value::Array(keys) => keys,
-                _ => return Err("Invalid SCAN response".to_string()),
-            };
-
-            // Get type for each key
-            let mut rows = Vec::new();
-            for key in keys {
-                let key_str = match key {
-                    Value::BulkString(b) => String::from_utf8_lossy(b).to_string(),
-                    _ => continue,
-                };
-
-                let type_result: RedisResult<String> = redis::cmd("TYPE")
-                    .arg(&key_str)
-                    .query_async(conn)
-                    .await;
-
-                let key_type = type_result.unwrap_or_else(|_| "unknown".to_string());
-
-                rows.push(vec![
-                    serde_json::Value::String(key_str),
-                    serde_json::Value::String(key_type),
-                ]);
-            }
-
-            let row_count = rows.len();
-            let message = if next_cursor == "0" {
-                Some("Scan complete".to_string())
-            } else {
-                Some(format!("Next cursor: {} (run BROWSE {} to continue)", next_cursor, next_cursor))
-            };
+    let scan = KeyScan {
+        pattern,
+        key_type: type_filter,
+        ..Default::default()
+    };
+    let page = redis_keyspace::scan_keys(conn, cursor, count, &scan).await?;
 
-            Ok(QueryResult {
-                columns: vec!["key".to_string(), "type".to_string()],
-                rows,
-                row_count,
-                message,
-                ..Default::default()
-            })
+    let rows: Vec<Vec<serde_json::Value>> = page
+        .keys
+        .into_iter()
+        .map(|key| {
+            vec![
+                key.key.to_json(),
+                serde_json::Value::String(key.key_type),
+                key.ttl_ms.map_or(serde_json::Value::Null, Into::into),
+                key.memory_bytes.map_or(serde_json::Value::Null, Into::into),
+            ]
+        })
+        .collect();
+    let row_count = rows.len();
+    let message = if page.cursor == "0" {
+        Some("Scan complete".to_string())
+    } else {
+        let mut next = format!("BROWSE {} COUNT {}", page.cursor, count);
+        if let Some(p) = pattern {
+            next.push_str(&format!(" MATCH {}", p));
         }
-        Ok(_) => Err("Invalid SCAN response format".to_string()),
-        Err(e) => Err(format!("Redis error: {}", e)),
-    }
+        if let Some(t) = type_filter {
+            next.push_str(&format!(" TYPE {}", t));
+        }
+        Some(format!("Next cursor: {} (run {} to continue)", page.cursor, next))
+    };
+
+    Ok(QueryResult {
+        columns: vec![
+            "key".to_string(),
+            "type".to_string(),
+            "ttl_ms".to_string(),
+            "memory_bytes".to_string(),
+        ],
+        rows,
+        row_count,
+        message,
+        ..Default::default()
+    })
 }
 
 pub async fn execute_query(
-    conn: &mut ConnectionManager,
+    conn: &mut RedisConnection,
     query: &str,
 ) -> Result<QueryResult, String> {
     // Strip trailing semicolons - Redis doesn't use them
@@ -269,27 +531,7 @@ pub async fn execute_query(
     let cmd_name = parts[0].to_uppercase();
     let args: Vec<&str> = parts[1..].iter().map(|s| s.as_str()).collect();
 
-    // Handle SELECT command specially to switch databases
-    if cmd_name == "SELECT" && args.len() == 1 {
-        if let Ok(db_index) = args[0].parse::<i64>() {
-            let result: RedisResult<Value> = redis::cmd("SELECT")
-                .arg(db_index)
-                .query_async(conn)
-                .await;
-            return match result {
-                Ok(_) => Ok(QueryResult {
-                    columns: vec![],
-                    rows: vec![],
-                    row_count: 0,
-                    message: Some(format!("Switched to database {}", db_index)),
-                    ..Default::default()
-                }),
-                Err(e) => Err(format!("Redis error: {}", e)),
-            };
-        }
-    }
-
-    // Handle BROWSE command - SCAN with TYPE info for each key
+    // Handle BROWSE command - SCAN with TYPE, TTL and size for each key
     // Usage: BROWSE [cursor] [COUNT n] [MATCH pattern] [TYPE type]
     if cmd_name == "BROWSE" {
         return browse_keys(conn, &args).await;
@@ -630,16 +872,55 @@ fn value_to_string(value: &Value) -> String {
     }
 }
 
-pub async fn switch_database(conn: &mut ConnectionManager, database: &str) -> Result<(), String> {
-    let db_index: i64 = database
-        .parse()
-        .map_err(|_| format!("Invalid database index: {}", database))?;
+/// The database a `SELECT n` command switches to.
+fn select_command(query: &str) -> Option<String> {
+    let parts = parse_command(query.trim().trim_end_matches(';'));
+    match parts.as_slice() {
+        [cmd, index] if cmd.eq_ignore_ascii_case("SELECT") && index.parse::<i64>().is_ok() => {
+            Some(index.clone())
+        }
+        _ => None,
+    }
+}
 
-    let _: () = redis::cmd("SELECT")
-        .arg(db_index)
-        .query_async(conn)
-        .await
-        .map_err(|e| format!("Failed to switch database: {}", e))?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connection_info_keeps_credentials_verbatim() {
+        let mut config = ConnectionConfig::new(
+            "cache".to_string(),
+            crate::storage::DatabaseType::Redis,
+            "cache.internal".to_string(),
+            6380,
+            String::new(),
+            "p@ss/w#rd?".to_string(),
+            None,
+            None,
+        );
+        config.ssl_mode = SslMode::Require;
+
+        let info = connection_info(&config, "127.0.0.1", 40000);
+        assert!(matches!(
+            info.addr,
+            ConnectionAddr::TcpTls { ref host, port: 40000, insecure: true, .. } if host == "127.0.0.1"
+        ));
+
+        // Prefer can't negotiate TLS with Redis, so it stays plaintext.
+        config.ssl_mode = SslMode::Prefer;
+        let info = connection_info(&config, "127.0.0.1", 40000);
+        assert!(matches!(info.addr, ConnectionAddr::Tcp(_, 40000)));
+        assert_eq!(info.redis.username, None);
+        assert_eq!(info.redis.password.as_deref(), Some("p@ss/w#rd?"));
+    }
 
-    Ok(())
+    #[test]
+    fn select_commands_name_a_database_index() {
+        assert_eq!(select_command("select 3;").as_deref(), Some("3"));
+        assert_eq!(select_command("SELECT \"12\""), Some("12".to_string()));
+        assert_eq!(select_command("SELECT db"), None);
+        assert_eq!(select_command("SELECT 1 2"), None);
+        assert_eq!(select_command("GET SELECT"), None);
+    }
 }
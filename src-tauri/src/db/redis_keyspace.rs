@@ -0,0 +1,584 @@
+// ABOUTME: Redis keyspace browsing: databases, namespaces sampled with SCAN and paged key lists.
+// ABOUTME: Key details (TYPE, PTTL, MEMORY USAGE) are fetched in pipelines, not per key.
+
+use super::redis_connection::{NodeAddr, RedisConnection};
+use super::value;
+use redis::{RedisWrite, ToRedisArgs, Value};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Keys to look at when estimating namespace sizes. Larger keyspaces are
+/// extrapolated from this sample using DBSIZE.
+const NAMESPACE_SAMPLE_KEYS: usize = 10_000;
+/// SCAN's COUNT hint while sampling.
+const SAMPLE_SCAN_COUNT: usize = 1_000;
+/// How long one page of keys may keep scanning for matches. A filter that
+/// matches few keys then returns a short page and a cursor to go on from,
+/// instead of walking the whole keyspace in one call.
+const PAGE_SCAN_BUDGET: Duration = Duration::from_secs(1);
+
+pub const DEFAULT_DELIMITER: &str = ":";
+
+/// A logical database, with its figures from `INFO keyspace`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RedisDatabaseInfo {
+    /// The index SELECT takes.
+    pub name: String,
+    pub keys: u64,
+    /// Keys with a TTL.
+    pub expires: u64,
+    /// Redis's sampled estimate of the mean TTL of the expiring keys; None
+    /// when none expire or Redis has not sampled them yet.
+    pub avg_ttl_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct KeyspaceStats {
+    keys: u64,
+    expires: u64,
+    avg_ttl_ms: u64,
+}
+
+impl KeyspaceStats {
+    /// Adds another node's figures for the same database. The TTL products
+    /// are summed in u128; their average lies between the two TTLs, so it
+    /// fits back in u64.
+    fn merge(&mut self, other: KeyspaceStats) {
+        let expires = self.expires + other.expires;
+        let weighted = self.avg_ttl_ms as u128 * self.expires as u128
+            + other.avg_ttl_ms as u128 * other.expires as u128;
+        if let Some(avg_ttl_ms) = weighted.checked_div(expires as u128) {
+            self.avg_ttl_ms = avg_ttl_ms as u64;
+        }
+        self.keys += other.keys;
+        self.expires = expires;
+    }
+}
+
+/// The `db0:keys=1,expires=0,avg_ttl=0` lines of `INFO keyspace`, by index.
+/// Databases without keys are not listed there at all.
+fn parse_keyspace_info(info: &str) -> BTreeMap<u32, KeyspaceStats> {
+    info.lines()
+        .filter_map(|line| {
+            let (db, fields) = line.trim().split_once(':')?;
+            let index = db.strip_prefix("db")?.parse().ok()?;
+            let mut stats = KeyspaceStats::default();
+            for (name, value) in fields.split(',').filter_map(|f| f.split_once('=')) {
+                let value = value.parse().unwrap_or(0);
+                match name {
+                    "keys" => stats.keys = value,
+                    "expires" => stats.expires = value,
+                    "avg_ttl" => stats.avg_ttl_ms = value,
+                    _ => {}
+                }
+            }
+            Some((index, stats))
+        })
+        .collect()
+}
+
+/// Keys sharing the prefix before the first delimiter. The empty name holds
+/// keys without a delimiter.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RedisNamespace {
+    pub name: String,
+    /// SCAN pattern matching the namespace's keys.
+    pub pattern: String,
+    pub key_count: u64,
+    /// `key_count` was extrapolated from a sample rather than counted.
+    pub approximate: bool,
+}
+
+/// A key name, field, member or value, which Redis allows to be any bytes.
+/// Sent to the frontend as a string when it is UTF-8 and as a
+/// [`value::binary`] cell otherwise, and accepted back in either form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedisBytes(pub Vec<u8>);
+
+impl RedisBytes {
+    /// The text for display; bytes that aren't UTF-8 show as U+FFFD.
+    pub fn text(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.0)
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        match std::str::from_utf8(&self.0) {
+            Ok(text) => serde_json::Value::String(text.to_string()),
+            Err(_) => value::binary(&self.0),
+        }
+    }
+}
+
+impl From<&str> for RedisBytes {
+    fn from(key: &str) -> Self {
+        Self(key.as_bytes().to_vec())
+    }
+}
+
+impl fmt::Display for RedisBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text())
+    }
+}
+
+impl ToRedisArgs for RedisBytes {
+    fn write_redis_args<W: ?Sized + RedisWrite>(&self, out: &mut W) {
+        out.write_arg(&self.0)
+    }
+}
+
+impl Serialize for RedisBytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_json().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for RedisBytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::String(text) => Ok(Self(text.into_bytes())),
+            other => value::binary_bytes(&other)
+                .map(Self)
+                .ok_or_else(|| serde::de::Error::custom("expected a string or binary value")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RedisKeyInfo {
+    pub key: RedisBytes,
+    pub key_type: String,
+    /// Milliseconds until the key expires; None if it never does.
+    pub ttl_ms: Option<i64>,
+    /// None when MEMORY USAGE is unavailable (older servers, ACLs).
+    pub memory_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RedisKeyPage {
+    pub keys: Vec<RedisKeyInfo>,
+    /// SCAN cursor to continue from; "0" once the scan is complete. A string
+    /// because cursors are 64-bit, and in a cluster also name the primary.
+    pub cursor: String,
+}
+
+/// What a key page scans for.
+#[derive(Debug, Clone, Default)]
+pub struct KeyScan<'a> {
+    pub pattern: Option<&'a str>,
+    pub key_type: Option<&'a str>,
+    /// Skip keys containing this, for the keys outside every namespace.
+    pub exclude_delimiter: Option<&'a str>,
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    needle.is_empty() || haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+/// The namespace `key` belongs to.
+fn namespace_of<'k>(key: &'k str, delimiter: &str) -> &'k str {
+    key.split_once(delimiter).map(|(ns, _)| ns).unwrap_or("")
+}
+
+/// Escapes glob metacharacters so `text` matches literally in a SCAN pattern.
+fn escape_glob(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// The SCAN pattern for a namespace's keys. The keys outside every namespace
+/// cannot be expressed as a glob, so they scan everything and are filtered
+/// with [`KeyScan::exclude_delimiter`].
+pub fn namespace_pattern(namespace: &str, delimiter: &str) -> String {
+    if namespace.is_empty() {
+        "*".to_string()
+    } else {
+        format!("{}{}*", escape_glob(namespace), escape_glob(delimiter))
+    }
+}
+
+/// Groups sampled keys into namespaces, sorted by name. When the sample did
+/// not cover all `total_keys`, counts are scaled up proportionally.
+fn group_namespaces<'k>(
+    keys: impl IntoIterator<Item = &'k str>,
+    delimiter: &str,
+    total_keys: u64,
+    complete: bool,
+) -> Vec<RedisNamespace> {
+    let mut counts: BTreeMap<&str, u64> = BTreeMap::new();
+    let mut sampled = 0u64;
+    for key in keys {
+        *counts.entry(namespace_of(key, delimiter)).or_default() += 1;
+        sampled += 1;
+    }
+    let scale = |count: u64| {
+        if complete || sampled == 0 {
+            count
+        } else {
+            ((count as f64) * (total_keys as f64) / (sampled as f64)).round() as u64
+        }
+    };
+    counts
+        .into_iter()
+        .map(|(name, count)| RedisNamespace {
+            name: name.to_string(),
+            pattern: namespace_pattern(name, delimiter),
+            key_count: scale(count),
+            approximate: !complete,
+        })
+        .collect()
+}
+
+/// The server, or each cluster primary in turn: in a cluster, SCAN and INFO
+/// only cover the node they reach.
+async fn node_targets(conn: &mut RedisConnection) -> Result<Vec<Option<NodeAddr>>, String> {
+    let primaries = conn.primaries().await?;
+    if primaries.is_empty() {
+        return Ok(vec![None]);
+    }
+    Ok(primaries.into_iter().map(Some).collect())
+}
+
+/// Splits a cursor into the index of the target it scans and that target's
+/// own SCAN cursor. Across several targets the cursor is `index-cursor`;
+/// a lone "0" starts at the first.
+fn split_cursor(cursor: &str, targets: usize) -> Result<(usize, &str), String> {
+    if targets == 1 || cursor == "0" {
+        return Ok((0, cursor));
+    }
+    cursor
+        .split_once('-')
+        .and_then(|(index, cursor)| Some((index.parse().ok().filter(|i| *i < targets)?, cursor)))
+        .ok_or_else(|| format!("Invalid SCAN cursor: {}", cursor))
+}
+
+/// The cursor after target `index` answered `next`, moving on to the next
+/// target when it is done.
+fn join_cursor(index: usize, next: &str, targets: usize) -> String {
+    match next {
+        _ if targets == 1 => next.to_string(),
+        "0" if index + 1 == targets => "0".to_string(),
+        "0" => format!("{}-0", index + 1),
+        _ => format!("{}-{}", index, next),
+    }
+}
+
+async fn scan_step(
+    conn: &mut RedisConnection,
+    targets: &[Option<NodeAddr>],
+    cursor: &str,
+    count: usize,
+    scan: &KeyScan<'_>,
+) -> Result<(String, Vec<RedisBytes>), String> {
+    let (index, node_cursor) = split_cursor(cursor, targets.len())?;
+    let mut cmd = redis::cmd("SCAN");
+    cmd.arg(node_cursor).arg("COUNT").arg(count);
+    if let Some(pattern) = scan.pattern {
+        cmd.arg("MATCH").arg(pattern);
+    }
+    if let Some(key_type) = scan.key_type {
+        cmd.arg("TYPE").arg(key_type);
+    }
+    let (next, keys): (String, Vec<Vec<u8>>) = conn
+        .query_node(targets[index].as_ref(), &cmd)
+        .await
+        .map_err(|e| format!("Redis error: {}", e))?;
+    let keys = keys
+        .into_iter()
+        .filter(|k| {
+            scan.exclude_delimiter
+                .is_none_or(|d| !contains(k, d.as_bytes()))
+        })
+        .map(RedisBytes)
+        .collect();
+    Ok((join_cursor(index, &next, targets.len()), keys))
+}
+
+/// Every logical database with its key figures. The count comes from
+/// `CONFIG GET databases`; where managed services disable CONFIG, it runs up
+/// to the highest database that holds keys. A cluster only has database 0.
+pub async fn list_databases(
+    conn: &mut RedisConnection,
+) -> Result<Vec<RedisDatabaseInfo>, String> {
+    let mut info_cmd = redis::cmd("INFO");
+    info_cmd.arg("keyspace");
+    let mut stats: BTreeMap<u32, KeyspaceStats> = BTreeMap::new();
+    for target in node_targets(conn).await? {
+        let info: String = conn
+            .query_node(target.as_ref(), &info_cmd)
+            .await
+            .map_err(|e| format!("Redis error: {}", e))?;
+        for (index, node_stats) in parse_keyspace_info(&info) {
+            stats.entry(index).or_default().merge(node_stats);
+        }
+    }
+
+    let configured = if conn.is_cluster() {
+        Some(1)
+    } else {
+        redis::cmd("CONFIG")
+            .arg("GET")
+            .arg("databases")
+            .query_async::<HashMap<String, String>>(conn)
+            .await
+            .ok()
+            .and_then(|config| config.get("databases")?.parse::<u32>().ok())
+    };
+    let count = configured.unwrap_or_else(|| stats.keys().next_back().map_or(1, |max| max + 1));
+
+    Ok((0..count)
+        .map(|index| {
+            let db = stats.get(&index).copied().unwrap_or_default();
+            RedisDatabaseInfo {
+                name: index.to_string(),
+                keys: db.keys,
+                expires: db.expires,
+                avg_ttl_ms: (db.expires > 0 && db.avg_ttl_ms > 0).then_some(db.avg_ttl_ms),
+            }
+        })
+        .collect())
+}
+
+/// Samples the current database's keys and groups them by `delimiter`.
+pub async fn list_namespaces(
+    conn: &mut RedisConnection,
+    delimiter: &str,
+) -> Result<Vec<RedisNamespace>, String> {
+    let total: u64 = redis::cmd("DBSIZE")
+        .query_async(conn)
+        .await
+        .map_err(|e| format!("Redis error: {}", e))?;
+
+    let targets = node_targets(conn).await?;
+    let mut sample = Vec::new();
+    let mut cursor = "0".to_string();
+    loop {
+        let (next, keys) =
+            scan_step(conn, &targets, &cursor, SAMPLE_SCAN_COUNT, &KeyScan::default()).await?;
+        sample.extend(keys);
+        cursor = next;
+        if cursor == "0" || sample.len() >= NAMESPACE_SAMPLE_KEYS {
+            break;
+        }
+    }
+    let complete = cursor == "0";
+    let names: Vec<Cow<str>> = sample.iter().map(RedisBytes::text).collect();
+    Ok(group_namespaces(
+        names.iter().map(Cow::as_ref),
+        delimiter,
+        total,
+        complete,
+    ))
+}
+
+/// Scans from `cursor` until at least `count` matching keys are found, the
+/// keyspace is exhausted or [`PAGE_SCAN_BUDGET`] runs out, then fetches the
+/// keys' details in two pipelines. The page may be short, or even empty,
+/// while the returned cursor is not yet "0".
+pub async fn scan_keys(
+    conn: &mut RedisConnection,
+    cursor: &str,
+    count: usize,
+    scan: &KeyScan<'_>,
+) -> Result<RedisKeyPage, String> {
+    let targets = node_targets(conn).await?;
+    let started = Instant::now();
+    let mut keys = Vec::new();
+    let mut cursor = cursor.to_string();
+    loop {
+        let (next, found) = scan_step(conn, &targets, &cursor, count, scan).await?;
+        keys.extend(found);
+        cursor = next;
+        if page_done(&cursor, keys.len(), count, started.elapsed()) {
+            break;
+        }
+    }
+    let keys = describe_keys(conn, keys).await?;
+    Ok(RedisKeyPage { keys, cursor })
+}
+
+/// Whether a page scan should stop after a step that left it at `cursor`
+/// with `found` matching keys.
+fn page_done(cursor: &str, found: usize, count: usize, elapsed: Duration) -> bool {
+    cursor == "0" || found >= count || elapsed >= PAGE_SCAN_BUDGET
+}
+
+/// TYPE and PTTL for each key in one round trip, then MEMORY USAGE in a
+/// second one that may fail as a whole on servers that do not allow it.
+pub async fn describe_keys(
+    conn: &mut RedisConnection,
+    keys: Vec<RedisBytes>,
+) -> Result<Vec<RedisKeyInfo>, String> {
+    if keys.is_empty() {
+        return Ok(vec![]);
+    }
+    let mut details = redis::pipe();
+    for key in &keys {
+        details.cmd("TYPE").arg(key).cmd("PTTL").arg(key);
+    }
+    let details: Vec<Value> = details
+        .query_async(conn)
+        .await
+        .map_err(|e| format!("Redis error: {}", e))?;
+
+    let mut usage = redis::pipe();
+    for key in &keys {
+        usage.cmd("MEMORY").arg("USAGE").arg(key);
+    }
+    let usage: Vec<Option<u64>> = usage.query_async(conn).await.unwrap_or_default();
+
+    Ok(keys
+        .into_iter()
+        .enumerate()
+        .map(|(i, key)| {
+            let key_type = match details.get(2 * i) {
+                Some(Value::SimpleString(s)) => s.clone(),
+                Some(Value::BulkString(b)) => String::from_utf8_lossy(b).into_owned(),
+                _ => "unknown".to_string(),
+            };
+            let ttl_ms = match details.get(2 * i + 1) {
+                Some(Value::Int(ms)) if *ms >= 0 => Some(*ms),
+                _ => None,
+            };
+            RedisKeyInfo {
+                key,
+                key_type,
+                ttl_ms,
+                memory_bytes: usage.get(i).copied().flatten(),
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn namespaces_group_on_first_delimiter() {
+        let keys = ["user:1", "user:2:profile", "session:abc", "counter"];
+        let namespaces = group_namespaces(keys, ":", 4, true);
+        let summary: Vec<_> = namespaces
+            .iter()
+            .map(|ns| (ns.name.as_str(), ns.key_count, ns.pattern.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("", 1, "*"),
+                ("session", 1, "session:*"),
+                ("user", 2, "user:*")
+            ]
+        );
+        assert!(namespaces.iter().all(|ns| !ns.approximate));
+    }
+
+    #[test]
+    fn partial_samples_scale_to_dbsize() {
+        let keys = ["a:1", "a:2", "a:3", "b:1"];
+        let namespaces = group_namespaces(keys, ":", 400, false);
+        assert_eq!(namespaces[0].key_count, 300);
+        assert_eq!(namespaces[1].key_count, 100);
+        assert!(namespaces[0].approximate);
+    }
+
+    #[test]
+    fn cluster_cursors_walk_every_primary() {
+        assert_eq!(split_cursor("0", 3), Ok((0, "0")));
+        assert_eq!(join_cursor(0, "1792", 3), "0-1792");
+        assert_eq!(split_cursor("0-1792", 3), Ok((0, "1792")));
+        assert_eq!(join_cursor(0, "0", 3), "1-0");
+        assert_eq!(split_cursor("1-0", 3), Ok((1, "0")));
+        assert_eq!(join_cursor(2, "0", 3), "0");
+        assert!(split_cursor("3-0", 3).is_err());
+        assert_eq!(split_cursor("1792", 1), Ok((0, "1792")));
+        assert_eq!(join_cursor(0, "1792", 1), "1792");
+    }
+
+    #[test]
+    fn keyspace_info_parses_per_database_figures() {
+        let info = "# Keyspace\r\n\
+            db0:keys=120,expires=20,avg_ttl=3000,subexpiry=0\r\n\
+            db5:keys=3,expires=0,avg_ttl=0\r\n";
+        let stats = parse_keyspace_info(info);
+        assert_eq!(stats.keys().copied().collect::<Vec<_>>(), vec![0, 5]);
+        assert_eq!(
+            stats[&0],
+            KeyspaceStats {
+                keys: 120,
+                expires: 20,
+                avg_ttl_ms: 3000
+            }
+        );
+    }
+
+    #[test]
+    fn page_scans_stop_when_the_time_budget_runs_out() {
+        let quick = Duration::from_millis(10);
+        assert!(!page_done("42", 3, 100, quick));
+        assert!(page_done("42", 100, 100, quick));
+        assert!(page_done("0", 3, 100, quick));
+        assert!(page_done("42", 3, 100, PAGE_SCAN_BUDGET));
+    }
+
+    #[test]
+    fn merged_ttl_is_weighted_by_expiring_keys() {
+        let mut total = KeyspaceStats {
+            keys: 10,
+            expires: 1,
+            avg_ttl_ms: 1000,
+        };
+        total.merge(KeyspaceStats {
+            keys: 10,
+            expires: 3,
+            avg_ttl_ms: 5000,
+        });
+        assert_eq!((total.keys, total.expires, total.avg_ttl_ms), (20, 4, 4000));
+    }
+
+    #[test]
+    fn merging_long_ttls_on_many_keys_does_not_overflow() {
+        let mut total = KeyspaceStats {
+            keys: 1 << 40,
+            expires: 1 << 40,
+            avg_ttl_ms: 1 << 40,
+        };
+        total.merge(KeyspaceStats {
+            keys: 1 << 40,
+            expires: 1 << 40,
+            avg_ttl_ms: 3 << 40,
+        });
+        assert_eq!(total.avg_ttl_ms, 2 << 40);
+    }
+
+    #[test]
+    fn keys_that_are_not_utf8_round_trip_as_binary() {
+        let text = RedisBytes::from("user:1");
+        assert_eq!(serde_json::to_value(&text).unwrap(), "user:1");
+
+        let bytes = RedisBytes(vec![b'k', 0xff, 0x00]);
+        let json = serde_json::to_value(&bytes).unwrap();
+        assert_eq!(json["hex"], "6bff00");
+        assert_eq!(serde_json::from_value::<RedisBytes>(json).unwrap(), bytes);
+        assert_eq!(
+            serde_json::from_value::<RedisBytes>("user:1".into()).unwrap(),
+            text
+        );
+        assert!(serde_json::from_value::<RedisBytes>(1.into()).is_err());
+    }
+
+    #[test]
+    fn patterns_escape_glob_characters() {
+        assert_eq!(namespace_pattern("cache[v2]", "::"), "cache\\[v2\\]::*");
+        assert_eq!(namespace_pattern("a*b", "/"), "a\\*b/*");
+    }
+}
@@ -0,0 +1,530 @@
+// ABOUTME: Typed reads and edits of a single Redis key (string, hash, list, set, zset, stream, JSON).
+// ABOUTME: Collections are read a page at a time with HSCAN/SSCAN/ZSCAN, LRANGE and XRANGE.
+
+use super::redis_keyspace::{self, RedisBytes, RedisKeyInfo};
+use super::redis_connection::RedisConnection;
+use redis::{RedisResult, Value};
+use serde::{Deserialize, Serialize};
+
+/// Strings longer than this are cut off so a huge value is never loaded at once.
+const STRING_PREVIEW_BYTES: usize = 1024 * 1024;
+
+/// TYPE reports RedisJSON documents under the module's type name.
+const JSON_TYPE: &str = "ReJSON-RL";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RedisKeyValue {
+    #[serde(flatten)]
+    pub info: RedisKeyInfo,
+    /// OBJECT ENCODING, e.g. `listpack` or `hashtable`.
+    pub encoding: Option<String>,
+    /// Bytes for strings, elements for collections.
+    pub length: Option<u64>,
+    pub data: RedisValueData,
+    /// Pass back to read the next page; None when this page was the last.
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RedisField {
+    pub field: RedisBytes,
+    pub value: RedisBytes,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RedisScoredMember {
+    pub member: RedisBytes,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RedisStreamEntry {
+    pub id: String,
+    pub fields: Vec<RedisField>,
+}
+
+/// One page of a key's value.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum RedisValueData {
+    /// `truncated` values are refused by [`edit_key`], since saving the
+    /// preview would drop the rest.
+    String {
+        value: RedisBytes,
+        truncated: bool,
+    },
+    Hash {
+        fields: Vec<RedisField>,
+    },
+    /// `offset` is the index of the first item.
+    List {
+        items: Vec<RedisBytes>,
+        offset: u64,
+    },
+    Set {
+        members: Vec<RedisBytes>,
+    },
+    Zset {
+        members: Vec<RedisScoredMember>,
+    },
+    Stream {
+        entries: Vec<RedisStreamEntry>,
+    },
+    Json {
+        value: serde_json::Value,
+    },
+    /// A module type this viewer does not know how to read.
+    Unsupported,
+}
+
+/// A change to one key. Collection edits add or overwrite; removals go
+/// through [`delete_members`].
+#[derive(Debug, Clone)]
+pub enum RedisEdit {
+    String {
+        value: String,
+    },
+    HashFields {
+        fields: Vec<(String, String)>,
+    },
+    ListItem {
+        index: i64,
+        value: String,
+    },
+    ListPush {
+        values: Vec<String>,
+        head: bool,
+    },
+    SetMembers {
+        members: Vec<String>,
+    },
+    ZsetMembers {
+        members: Vec<RedisScoredMember>,
+    },
+    StreamEntry {
+        fields: Vec<(String, String)>,
+    },
+    Json {
+        path: String,
+        value: serde_json::Value,
+    },
+    /// None removes the expiry.
+    Ttl {
+        ttl_ms: Option<i64>,
+    },
+}
+
+fn redis_error(e: redis::RedisError) -> String {
+    format!("Redis error: {}", e)
+}
+
+/// Splits a flat `[a1, b1, a2, b2, ...]` reply into fields.
+fn pairs(flat: Vec<RedisBytes>) -> Vec<RedisField> {
+    let mut fields = Vec::with_capacity(flat.len() / 2);
+    let mut it = flat.into_iter();
+    while let (Some(field), Some(value)) = (it.next(), it.next()) {
+        fields.push(RedisField { field, value });
+    }
+    fields
+}
+
+/// The first [`STRING_PREVIEW_BYTES`] of a string. A UTF-8 character cut in
+/// half at the end is dropped so the preview still reads as text.
+fn preview(mut bytes: Vec<u8>) -> RedisBytes {
+    if let Err(e) = std::str::from_utf8(&bytes) {
+        if e.error_len().is_none() && e.valid_up_to() > 0 {
+            bytes.truncate(e.valid_up_to());
+        }
+    }
+    RedisBytes(bytes)
+}
+
+/// Refuses to overwrite a string longer than its preview, which would lose
+/// everything after the first [`STRING_PREVIEW_BYTES`].
+fn check_fully_loaded(key: &RedisBytes, length: u64) -> Result<(), String> {
+    if length > STRING_PREVIEW_BYTES as u64 {
+        return Err(format!(
+            "{} is {} bytes, too large to edit here; use the query editor instead",
+            key, length
+        ));
+    }
+    Ok(())
+}
+
+/// The cursor after a list page, or None if it reached the end.
+fn next_list_offset(offset: u64, returned: usize, length: u64) -> Option<String> {
+    let next = offset + returned as u64;
+    (returned > 0 && next < length).then(|| next.to_string())
+}
+
+/// Runs HSCAN/SSCAN/ZSCAN from `cursor` until at least `count` elements came
+/// back or the scan finished. Returns the flat elements and the next cursor.
+async fn scan_collection(
+    conn: &mut RedisConnection,
+    command: &str,
+    key: &RedisBytes,
+    cursor: &str,
+    count: usize,
+    per_element: usize,
+) -> Result<(Vec<RedisBytes>, Option<String>), String> {
+    let mut elements = Vec::new();
+    let mut cursor = cursor.to_string();
+    loop {
+        let (next, found): (String, Vec<Vec<u8>>) = redis::cmd(command)
+            .arg(key)
+            .arg(&cursor)
+            .arg("COUNT")
+            .arg(count)
+            .query_async(conn)
+            .await
+            .map_err(redis_error)?;
+        elements.extend(found.into_iter().map(RedisBytes));
+        cursor = next;
+        if cursor == "0" || elements.len() >= count * per_element {
+            break;
+        }
+    }
+    Ok((elements, (cursor != "0").then_some(cursor)))
+}
+
+/// Reads one page of `key`. `cursor` is None for the first page, then the
+/// cursor returned with the previous one.
+pub async fn get_key(
+    conn: &mut RedisConnection,
+    key: &RedisBytes,
+    cursor: Option<&str>,
+    count: usize,
+) -> Result<RedisKeyValue, String> {
+    if count == 0 {
+        return Err("Page size must be at least 1".to_string());
+    }
+    let info = redis_keyspace::describe_keys(conn, vec![key.clone()])
+        .await?
+        .pop()
+        .ok_or_else(|| format!("Key not found: {}", key))?;
+    if info.key_type == "none" {
+        return Err(format!("Key not found: {}", key));
+    }
+    let encoding: Option<String> = redis::cmd("OBJECT")
+        .arg("ENCODING")
+        .arg(key)
+        .query_async(conn)
+        .await
+        .unwrap_or(None);
+
+    let length_command = match info.key_type.as_str() {
+        "string" => Some("STRLEN"),
+        "hash" => Some("HLEN"),
+        "list" => Some("LLEN"),
+        "set" => Some("SCARD"),
+        "zset" => Some("ZCARD"),
+        "stream" => Some("XLEN"),
+        _ => None,
+    };
+    let length: Option<u64> = match length_command {
+        Some(command) => Some(
+            redis::cmd(command)
+                .arg(key)
+                .query_async(conn)
+                .await
+                .map_err(redis_error)?,
+        ),
+        None => None,
+    };
+
+    let start = cursor.unwrap_or("0");
+    let (data, cursor) = match info.key_type.as_str() {
+        "string" => {
+            let truncated = length.unwrap_or(0) > STRING_PREVIEW_BYTES as u64;
+            let bytes: Vec<u8> = redis::cmd("GETRANGE")
+                .arg(key)
+                .arg(0)
+                .arg(STRING_PREVIEW_BYTES - 1)
+                .query_async(conn)
+                .await
+                .map_err(redis_error)?;
+            let value = if truncated {
+                preview(bytes)
+            } else {
+                RedisBytes(bytes)
+            };
+            (RedisValueData::String { value, truncated }, None)
+        }
+        "hash" => {
+            let (flat, next) = scan_collection(conn, "HSCAN", key, start, count, 2).await?;
+            (
+                RedisValueData::Hash {
+                    fields: pairs(flat),
+                },
+                next,
+            )
+        }
+        "set" => {
+            let (members, next) = scan_collection(conn, "SSCAN", key, start, count, 1).await?;
+            (RedisValueData::Set { members }, next)
+        }
+        "zset" => {
+            let (flat, next) = scan_collection(conn, "ZSCAN", key, start, count, 2).await?;
+            let members = pairs(flat)
+                .into_iter()
+                .map(|f| RedisScoredMember {
+                    score: f.value.text().parse().unwrap_or(f64::NAN),
+                    member: f.field,
+                })
+                .collect();
+            (RedisValueData::Zset { members }, next)
+        }
+        "list" => {
+            let offset: u64 = start
+                .parse()
+                .map_err(|_| format!("Invalid cursor: {}", start))?;
+            let items: Vec<Vec<u8>> = redis::cmd("LRANGE")
+                .arg(key)
+                .arg(offset)
+                .arg(offset + count as u64 - 1)
+                .query_async(conn)
+                .await
+                .map_err(redis_error)?;
+            let next = next_list_offset(offset, items.len(), length.unwrap_or(0));
+            let items = items.into_iter().map(RedisBytes).collect();
+            (RedisValueData::List { items, offset }, next)
+        }
+        "stream" => {
+            // The cursor is the last ID already shown; "(" makes the range exclusive.
+            let from = match cursor {
+                Some(id) => format!("({}", id),
+                None => "-".to_string(),
+            };
+            let raw: Vec<(String, Vec<Vec<u8>>)> = redis::cmd("XRANGE")
+                .arg(key)
+                .arg(from)
+                .arg("+")
+                .arg("COUNT")
+                .arg(count)
+                .query_async(conn)
+                .await
+                .map_err(redis_error)?;
+            let next = if raw.len() == count {
+                raw.last().map(|(id, _)| id.clone())
+            } else {
+                None
+            };
+            let entries = raw
+                .into_iter()
+                .map(|(id, flat)| RedisStreamEntry {
+                    id,
+                    fields: pairs(flat.into_iter().map(RedisBytes).collect()),
+                })
+                .collect();
+            (RedisValueData::Stream { entries }, next)
+        }
+        JSON_TYPE => {
+            let text: String = redis::cmd("JSON.GET")
+                .arg(key)
+                .query_async(conn)
+                .await
+                .map_err(redis_error)?;
+            let value = serde_json::from_str(&text)
+                .map_err(|e| format!("Invalid JSON in {}: {}", key, e))?;
+            (RedisValueData::Json { value }, None)
+        }
+        _ => (RedisValueData::Unsupported, None),
+    };
+
+    Ok(RedisKeyValue {
+        info,
+        encoding,
+        length,
+        data,
+        cursor,
+    })
+}
+
+/// Applies `edit` to `key`, creating the key if it does not exist. Setting a
+/// string keeps its TTL, and fails if the string was too long to load whole.
+pub async fn edit_key(
+    conn: &mut RedisConnection,
+    key: &RedisBytes,
+    edit: RedisEdit,
+) -> Result<(), String> {
+    let cmd = match edit {
+        RedisEdit::String { value } => {
+            let length: u64 = redis::cmd("STRLEN")
+                .arg(key)
+                .query_async(conn)
+                .await
+                .map_err(redis_error)?;
+            check_fully_loaded(key, length)?;
+            let mut cmd = redis::cmd("SET");
+            cmd.arg(key).arg(value).arg("KEEPTTL");
+            cmd
+        }
+        RedisEdit::HashFields { fields } => {
+            if fields.is_empty() {
+                return Ok(());
+            }
+            let mut cmd = redis::cmd("HSET");
+            cmd.arg(key);
+            for (field, value) in fields {
+                cmd.arg(field).arg(value);
+            }
+            cmd
+        }
+        RedisEdit::ListItem { index, value } => {
+            let mut cmd = redis::cmd("LSET");
+            cmd.arg(key).arg(index).arg(value);
+            cmd
+        }
+        RedisEdit::ListPush { values, head } => {
+            if values.is_empty() {
+                return Ok(());
+            }
+            let mut cmd = redis::cmd(if head { "LPUSH" } else { "RPUSH" });
+            cmd.arg(key).arg(values);
+            cmd
+        }
+        RedisEdit::SetMembers { members } => {
+            if members.is_empty() {
+                return Ok(());
+            }
+            let mut cmd = redis::cmd("SADD");
+            cmd.arg(key).arg(members);
+            cmd
+        }
+        RedisEdit::ZsetMembers { members } => {
+            if members.is_empty() {
+                return Ok(());
+            }
+            let mut cmd = redis::cmd("ZADD");
+            cmd.arg(key);
+            for m in members {
+                cmd.arg(m.score).arg(m.member);
+            }
+            cmd
+        }
+        RedisEdit::StreamEntry { fields } => {
+            if fields.is_empty() {
+                return Err("A stream entry needs at least one field".to_string());
+            }
+            let mut cmd = redis::cmd("XADD");
+            cmd.arg(key).arg("*");
+            for (field, value) in fields {
+                cmd.arg(field).arg(value);
+            }
+            cmd
+        }
+        RedisEdit::Json { path, value } => {
+            let mut cmd = redis::cmd("JSON.SET");
+            cmd.arg(key).arg(path).arg(value.to_string());
+            cmd
+        }
+        RedisEdit::Ttl { ttl_ms: Some(ms) } => {
+            let mut cmd = redis::cmd("PEXPIRE");
+            cmd.arg(key).arg(ms);
+            cmd
+        }
+        RedisEdit::Ttl { ttl_ms: None } => {
+            let mut cmd = redis::cmd("PERSIST");
+            cmd.arg(key);
+            cmd
+        }
+    };
+    let result: RedisResult<Value> = cmd.query_async(conn).await;
+    result.map(|_| ()).map_err(redis_error)
+}
+
+/// Removes `members` from `key`: fields of a hash, members of a set or
+/// sorted set, entry IDs of a stream, or every occurrence of a list value.
+/// Returns how many were removed.
+pub async fn delete_members(
+    conn: &mut RedisConnection,
+    key: &RedisBytes,
+    members: Vec<RedisBytes>,
+) -> Result<u64, String> {
+    if members.is_empty() {
+        return Ok(0);
+    }
+    let key_type: String = redis::cmd("TYPE")
+        .arg(key)
+        .query_async(conn)
+        .await
+        .map_err(redis_error)?;
+    let command = match key_type.as_str() {
+        "hash" => "HDEL",
+        "set" => "SREM",
+        "zset" => "ZREM",
+        "stream" => "XDEL",
+        "list" => {
+            let mut pipe = redis::pipe();
+            for value in &members {
+                pipe.cmd("LREM").arg(key).arg(0).arg(value);
+            }
+            let removed: Vec<u64> = pipe.query_async(conn).await.map_err(redis_error)?;
+            return Ok(removed.into_iter().sum());
+        }
+        "none" => return Err(format!("Key not found: {}", key)),
+        other => return Err(format!("Cannot delete members of a {} key", other)),
+    };
+    redis::cmd(command)
+        .arg(key)
+        .arg(members)
+        .query_async(conn)
+        .await
+        .map_err(redis_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flat_replies_pair_up_and_drop_a_dangling_element() {
+        let flat = ["a", "1", "b", "2", "c"].map(RedisBytes::from).to_vec();
+        assert_eq!(
+            pairs(flat),
+            vec![
+                RedisField {
+                    field: "a".into(),
+                    value: "1".into()
+                },
+                RedisField {
+                    field: "b".into(),
+                    value: "2".into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn values_that_are_not_utf8_serialize_as_binary() {
+        let data = RedisValueData::List {
+            items: vec![RedisBytes::from("text"), RedisBytes(vec![0xff, 0x00])],
+            offset: 0,
+        };
+        let json = serde_json::to_value(&data).unwrap();
+        assert_eq!(json["items"][0], "text");
+        assert_eq!(json["items"][1]["$type"], "binary");
+        assert_eq!(json["items"][1]["hex"], "ff00");
+    }
+
+    #[test]
+    fn previews_drop_a_character_cut_in_half() {
+        let cut = "añ".as_bytes()[..2].to_vec();
+        assert_eq!(preview(cut), RedisBytes::from("a"));
+        assert_eq!(preview(vec![0xff, b'a']), RedisBytes(vec![0xff, b'a']));
+    }
+
+    #[test]
+    fn strings_longer_than_the_preview_cannot_be_edited() {
+        let key = RedisBytes::from("big");
+        assert!(check_fully_loaded(&key, STRING_PREVIEW_BYTES as u64).is_ok());
+        assert!(check_fully_loaded(&key, STRING_PREVIEW_BYTES as u64 + 1).is_err());
+    }
+
+    #[test]
+    fn list_pages_stop_at_the_end() {
+        assert_eq!(next_list_offset(0, 100, 250).as_deref(), Some("100"));
+        assert_eq!(next_list_offset(200, 50, 250), None);
+        assert_eq!(next_list_offset(300, 0, 250), None);
+    }
+}
@@ -0,0 +1,309 @@
+// ABOUTME: Paged result sessions that keep a query open and hand out rows on demand.
+// ABOUTME: Avoids materializing huge result sets when the grid only shows one page.
+
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+use super::value::{ColumnMeta, LogicalType};
+use serde::Serialize;
+use sqlx::{Column, Database, Executor, Pool, Row, TypeInfo};
+use std::collections::VecDeque;
+use std::future::Future;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Rows buffered between the producer task and the cursor. Small enough that
+/// an abandoned stream does not hold much memory, large enough that a page
+/// fetch is not a channel round-trip per row.
+const STREAM_BUFFER_ROWS: usize = 512;
+
+/// Rows a [`StreamCursor`] keeps behind the page it last served, so recent
+/// pages can be revisited without holding the whole result.
+const STREAM_WINDOW_ROWS: usize = 10_000;
+
+/// Hands rows from `stream` to `keep` until it ends or `max_rows` have been
+/// kept. Returns whether the result had more: reading stops at the first row
+/// past the cap, so the rest is never pulled from the connection.
+pub async fn read_capped<S, R, E>(
+    stream: &mut S,
+    max_rows: usize,
+    mut keep: impl FnMut(R),
+) -> Result<bool, E>
+where
+    S: Stream<Item = Result<R, E>> + Unpin,
+{
+    let mut kept = 0;
+    while let Some(row) = stream.next().await {
+        let row = row?;
+        if kept == max_rows {
+            return Ok(true);
+        }
+        keep(row);
+        kept += 1;
+    }
+    Ok(false)
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ResultPage {
+    pub columns: Vec<String>,
+    /// Type information for each entry of `columns`, in the same order.
+    pub column_meta: Vec<ColumnMeta>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+    pub offset: usize,
+    /// More rows are available after this page.
+    pub has_more: bool,
+    /// The session's row cap was reached; rows beyond it will not be served.
+    pub truncated: bool,
+}
+
+/// An open, row-returning query that can be read one page at a time.
+#[async_trait]
+pub trait ResultCursor: Send {
+    /// Returns up to `limit` rows starting at the zero-based `offset`.
+    async fn fetch(&mut self, offset: usize, limit: usize) -> Result<ResultPage, String>;
+
+    /// Releases server-side resources. Cursors must also clean up on drop,
+    /// but closing explicitly lets the connection go back to the pool intact.
+    async fn close(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+pub enum StreamItem {
+    Columns(Vec<ColumnMeta>),
+    Row(Vec<serde_json::Value>),
+    Error(String),
+}
+
+/// Cursor over a forward-only row stream produced by a background task.
+/// Rows are pulled only as far as the furthest page requested, and the last
+/// [`STREAM_WINDOW_ROWS`] are kept so recent pages can be revisited; older
+/// ones are dropped and can only be read again by reopening the result. The
+/// channel's bound gives backpressure so the server is not read ahead of the UI.
+pub struct StreamCursor {
+    rx: mpsc::Receiver<StreamItem>,
+    column_meta: Vec<ColumnMeta>,
+    buffer: VecDeque<Vec<serde_json::Value>>,
+    /// Offset of the first row in `buffer`.
+    base: usize,
+    done: bool,
+    task: JoinHandle<()>,
+}
+
+impl StreamCursor {
+    /// Spawns `producer` and waits for its first item so that errors such as
+    /// a syntax error surface when the result is opened, not on first fetch.
+    pub async fn start<F, Fut>(producer: F) -> Result<Self, String>
+    where
+        F: FnOnce(mpsc::Sender<StreamItem>) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER_ROWS);
+        let task = tokio::spawn(producer(tx));
+        let mut cursor = Self {
+            rx,
+            column_meta: Vec::new(),
+            buffer: VecDeque::new(),
+            base: 0,
+            done: false,
+            task,
+        };
+        match cursor.rx.recv().await {
+            Some(item) => cursor.accept(item)?,
+            None => cursor.done = true,
+        }
+        Ok(cursor)
+    }
+
+    fn accept(&mut self, item: StreamItem) -> Result<(), String> {
+        match item {
+            StreamItem::Columns(meta) => self.column_meta = meta,
+            StreamItem::Row(row) => self.buffer.push_back(row),
+            StreamItem::Error(e) => {
+                self.done = true;
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Offset just past the last buffered row.
+    fn buffered_end(&self) -> usize {
+        self.base + self.buffer.len()
+    }
+
+    /// Drops the oldest rows beyond the window, but none from `offset` on.
+    fn trim(&mut self, offset: usize) {
+        let excess = self.buffer.len().saturating_sub(STREAM_WINDOW_ROWS);
+        let dropped = excess.min(offset.saturating_sub(self.base));
+        self.buffer.drain(..dropped);
+        self.base += dropped;
+    }
+}
+
+impl Drop for StreamCursor {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[async_trait]
+impl ResultCursor for StreamCursor {
+    async fn fetch(&mut self, offset: usize, limit: usize) -> Result<ResultPage, String> {
+        if offset < self.base {
+            return Err(format!(
+                "Rows before {} are no longer held; reopen the result to read them",
+                self.base
+            ));
+        }
+        // Read one row past the page so has_more is exact.
+        let wanted = offset.saturating_add(limit).saturating_add(1);
+        while !self.done && self.buffered_end() < wanted {
+            match self.rx.recv().await {
+                Some(item) => self.accept(item)?,
+                None => self.done = true,
+            }
+            self.trim(offset);
+        }
+
+        let end = offset.saturating_add(limit).min(self.buffered_end());
+        let start = offset.min(end);
+        let rows = self
+            .buffer
+            .range(start - self.base..end - self.base)
+            .cloned()
+            .collect();
+        let page = ResultPage {
+            columns: self.column_meta.iter().map(|c| c.name.clone()).collect(),
+            column_meta: self.column_meta.clone(),
+            rows,
+            offset,
+            has_more: self.buffered_end() > end,
+            truncated: false,
+        };
+        self.trim(offset);
+        Ok(page)
+    }
+
+    async fn close(&mut self) -> Result<(), String> {
+        self.task.abort();
+        self.done = true;
+        Ok(())
+    }
+}
+
+/// Opens a [`StreamCursor`] that runs `query` on a connection from `pool` and
+/// decodes each row with the engine's `decode` function. `logical_type` maps
+/// the engine's type names for the page's column metadata.
+pub async fn open_pool_stream<DB>(
+    pool: Pool<DB>,
+    query: String,
+    decode: fn(&DB::Row, usize, &str) -> serde_json::Value,
+    logical_type: fn(&str) -> LogicalType,
+) -> Result<StreamCursor, String>
+where
+    DB: Database,
+    for<'c> &'c Pool<DB>: Executor<'c, Database = DB>,
+{
+    StreamCursor::start(move |tx| async move {
+        let mut stream = sqlx::raw_sql(&query).fetch(&pool);
+        let mut sent_columns = false;
+        while let Some(row_res) = stream.next().await {
+            let row = match row_res {
+                Ok(row) => row,
+                Err(e) => {
+                    let _ = tx.send(StreamItem::Error(format!("Query failed: {}", e))).await;
+                    return;
+                }
+            };
+            if !sent_columns {
+                sent_columns = true;
+                let meta = row
+                    .columns()
+                    .iter()
+                    .map(|c| {
+                        let type_name = c.type_info().name();
+                        ColumnMeta::new(c.name(), type_name, logical_type(type_name))
+                    })
+                    .collect();
+                if tx.send(StreamItem::Columns(meta)).await.is_err() {
+                    return;
+                }
+            }
+            let values = row
+                .columns()
+                .iter()
+                .enumerate()
+                .map(|(i, col)| decode(&row, i, col.type_info().name()))
+                .collect();
+            // A closed channel means the cursor was closed or dropped.
+            if tx.send(StreamItem::Row(values)).await.is_err() {
+                return;
+            }
+        }
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn capped_reads_stop_at_the_first_row_past_the_cap() {
+        let mut pulled = 0;
+        let mut rows = stream::iter(0..1_000).map(|i| {
+            pulled += 1;
+            Ok::<_, String>(i)
+        });
+        let mut kept = Vec::new();
+        let truncated = read_capped(&mut rows, 3, |row| kept.push(row))
+            .await
+            .unwrap();
+        drop(rows);
+        assert!(truncated);
+        assert_eq!(kept, vec![0, 1, 2]);
+        assert_eq!(pulled, 4);
+    }
+
+    #[tokio::test]
+    async fn capped_reads_of_short_results_are_not_truncated() {
+        let mut rows = stream::iter([Ok::<_, String>(1), Ok(2)]);
+        let mut kept = Vec::new();
+        let truncated = read_capped(&mut rows, 2, |row| kept.push(row))
+            .await
+            .unwrap();
+        assert!(!truncated);
+        assert_eq!(kept, vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn stream_cursors_keep_only_a_window_of_rows() {
+        let total = STREAM_WINDOW_ROWS * 3;
+        let mut cursor = StreamCursor::start(move |tx| async move {
+            for i in 0..total {
+                if tx.send(StreamItem::Row(vec![json!(i)])).await.is_err() {
+                    return;
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        let deep = STREAM_WINDOW_ROWS * 2;
+        let page = cursor.fetch(deep, 10).await.unwrap();
+        assert_eq!(page.rows[0], vec![json!(deep)]);
+        assert!(page.has_more);
+        assert!(cursor.buffer.len() <= STREAM_WINDOW_ROWS + 11);
+
+        let recent = cursor.fetch(deep - 100, 10).await.unwrap();
+        assert_eq!(recent.rows[0], vec![json!(deep - 100)]);
+        assert!(cursor.fetch(0, 10).await.is_err());
+
+        let last = cursor.fetch(total - 5, 10).await.unwrap();
+        assert_eq!(last.rows.len(), 5);
+        assert!(!last.has_more);
+    }
+}
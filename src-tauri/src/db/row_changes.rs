@@ -0,0 +1,606 @@
+// ABOUTME: Turns edits to table rows into parameterized INSERT, UPDATE and DELETE statements.
+// ABOUTME: Rows are matched on a primary or unique key, and each statement must hit exactly one row.
+
+use super::value;
+use super::{ColumnInfo, ConstraintInfo};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::query::Query;
+use sqlx::{Database, Encode, Type};
+use std::collections::{BTreeMap, HashMap};
+
+/// One edit to a table row. `key` holds the row's values as they were read;
+/// only the key columns are used from it, so passing the whole row is fine.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum RowChange {
+    Update {
+        key: BTreeMap<String, Value>,
+        values: BTreeMap<String, Value>,
+    },
+    /// Columns left out get their defaults.
+    Insert {
+        values: BTreeMap<String, Value>,
+    },
+    Delete {
+        key: BTreeMap<String, Value>,
+    },
+}
+
+/// The edits to one table. `key_columns` may be empty, in which case the
+/// table's primary key (or a unique NOT NULL key) is used.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TableChanges {
+    pub database: String,
+    pub schema: String,
+    pub table: String,
+    #[serde(default)]
+    pub key_columns: Vec<String>,
+    pub changes: Vec<RowChange>,
+}
+
+/// A [`TableChanges`] checked against the table it edits.
+#[derive(Debug, Clone)]
+pub struct TableEdit {
+    pub target: TableChanges,
+    pub columns: Vec<ColumnInfo>,
+    /// The columns rows are matched on, from [`choose_key`].
+    pub key: Vec<String>,
+}
+
+impl TableEdit {
+    pub fn statements(
+        &self,
+        dialect: Dialect,
+        table_ref: &str,
+        casts: &HashMap<String, String>,
+    ) -> Result<Vec<RowStatement>, String> {
+        build_statements(
+            dialect,
+            table_ref,
+            &self.columns,
+            &self.key,
+            casts,
+            &self.target.changes,
+        )
+        .map_err(|e| self.in_table(e))
+    }
+
+    /// Counts nothing yet.
+    pub fn applied(&self) -> AppliedRowChanges {
+        AppliedRowChanges {
+            key_columns: self.key.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Names the table in `message`, since a save can span several.
+    pub fn in_table(&self, message: String) -> String {
+        format!("{}: {}", self.target.table, message)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Insert,
+    Update,
+    Delete,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AppliedRowChanges {
+    /// The key the rows were matched on.
+    pub key_columns: Vec<String>,
+    pub inserted: u64,
+    pub updated: u64,
+    pub deleted: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    Postgres,
+    Mysql,
+    Sqlite,
+}
+
+impl Dialect {
+    pub fn quote(self, ident: &str) -> String {
+        match self {
+            Dialect::Mysql => format!("`{}`", ident.replace('`', "``")),
+            Dialect::Postgres | Dialect::Sqlite => format!("\"{}\"", ident.replace('"', "\"\"")),
+        }
+    }
+
+    fn placeholder(self, n: usize) -> String {
+        match self {
+            Dialect::Postgres => format!("${}", n),
+            Dialect::Mysql | Dialect::Sqlite => "?".to_string(),
+        }
+    }
+}
+
+/// A statement for one [`RowChange`], with its parameters in placeholder order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowStatement {
+    pub kind: ChangeKind,
+    /// Position of the change in the batch, for error messages.
+    pub index: usize,
+    pub sql: String,
+    pub params: Vec<Value>,
+}
+
+impl RowStatement {
+    fn describe(&self) -> String {
+        let kind = match self.kind {
+            ChangeKind::Insert => "Insert",
+            ChangeKind::Update => "Update",
+            ChangeKind::Delete => "Delete",
+        };
+        format!("{} #{}", kind, self.index + 1)
+    }
+
+    pub fn failed(&self, error: impl std::fmt::Display) -> String {
+        format!("{} failed, nothing was saved: {}", self.describe(), error)
+    }
+}
+
+impl AppliedRowChanges {
+    /// Counts a statement that affected `rows` rows, or refuses anything but
+    /// exactly one so the caller rolls the batch back.
+    pub fn record(&mut self, statement: &RowStatement, rows: u64) -> Result<(), String> {
+        match rows {
+            1 => {}
+            0 => {
+                return Err(statement.failed(
+                    "no row matched; it may have been changed or deleted since it was read",
+                ))
+            }
+            n => return Err(statement.failed(format!("it would have affected {} rows", n))),
+        }
+        match statement.kind {
+            ChangeKind::Insert => self.inserted += 1,
+            ChangeKind::Update => self.updated += 1,
+            ChangeKind::Delete => self.deleted += 1,
+        }
+        Ok(())
+    }
+}
+
+/// Keys that identify a row: the primary key, then unique constraints whose
+/// columns are all NOT NULL (a NULL would let several rows share the key).
+pub fn candidate_keys(columns: &[ColumnInfo], constraints: &[ConstraintInfo]) -> Vec<Vec<String>> {
+    let not_null = |name: &String| columns.iter().any(|c| &c.name == name && !c.is_nullable);
+    let primary: Vec<String> = columns
+        .iter()
+        .filter(|c| c.is_primary_key)
+        .map(|c| c.name.clone())
+        .collect();
+    let mut keys = Vec::new();
+    if !primary.is_empty() {
+        keys.push(primary);
+    }
+    for constraint in constraints {
+        let usable = match constraint.constraint_type.to_uppercase().as_str() {
+            "PRIMARY KEY" => true,
+            "UNIQUE" => constraint.columns.iter().all(not_null),
+            _ => false,
+        };
+        if usable && !constraint.columns.is_empty() && !keys.contains(&constraint.columns) {
+            keys.push(constraint.columns.clone());
+        }
+    }
+    keys
+}
+
+/// The columns to match rows on. `requested` is used when it includes a whole
+/// key (extra columns only narrow the match); when empty, the primary key or
+/// the first usable unique key is picked.
+pub fn choose_key(
+    table: &str,
+    columns: &[ColumnInfo],
+    constraints: &[ConstraintInfo],
+    requested: &[String],
+) -> Result<Vec<String>, String> {
+    if let Some(unknown) = requested
+        .iter()
+        .find(|r| !columns.iter().any(|c| &c.name == *r))
+    {
+        return Err(format!("{} has no column {}", table, unknown));
+    }
+    let keys = candidate_keys(columns, constraints);
+    if requested.is_empty() {
+        return keys.into_iter().next().ok_or_else(|| {
+            format!(
+                "{} has no primary key or unique NOT NULL columns, so its rows cannot be edited safely",
+                table
+            )
+        });
+    }
+    if keys
+        .iter()
+        .any(|key| key.iter().all(|k| requested.contains(k)))
+    {
+        Ok(requested.to_vec())
+    } else {
+        Err(format!(
+            "{} does not identify a single row of {}: it is neither the primary key nor a unique key",
+            requested.join(", "),
+            table
+        ))
+    }
+}
+
+/// Builds one statement per change. `table` is the quoted, qualified table
+/// name. Postgres parameters are bound as text and cast to the column's type
+/// from `casts`, since it will not compare or assign text to other types.
+pub fn build_statements(
+    dialect: Dialect,
+    table: &str,
+    columns: &[ColumnInfo],
+    key: &[String],
+    casts: &HashMap<String, String>,
+    changes: &[RowChange],
+) -> Result<Vec<RowStatement>, String> {
+    let check_column = |name: &String| {
+        if columns.iter().any(|c| &c.name == name) {
+            Ok(())
+        } else {
+            Err(format!("Unknown column {}", name))
+        }
+    };
+    changes
+        .iter()
+        .enumerate()
+        .map(|(index, change)| {
+            let mut params = Vec::new();
+            let mut param = |value: &Value, column: &str| {
+                params.push(value.clone());
+                let placeholder = dialect.placeholder(params.len());
+                match casts.get(column) {
+                    Some(ty) if dialect == Dialect::Postgres => {
+                        format!("CAST({} AS {})", placeholder, ty)
+                    }
+                    _ => placeholder,
+                }
+            };
+            let (kind, sql) = match change {
+                RowChange::Insert { values } => {
+                    values.keys().try_for_each(check_column)?;
+                    let sql = if values.is_empty() {
+                        match dialect {
+                            Dialect::Mysql => format!("INSERT INTO {} () VALUES ()", table),
+                            _ => format!("INSERT INTO {} DEFAULT VALUES", table),
+                        }
+                    } else {
+                        let names: Vec<String> = values.keys().map(|c| dialect.quote(c)).collect();
+                        let placeholders: Vec<String> =
+                            values.iter().map(|(c, v)| param(v, c)).collect();
+                        format!(
+                            "INSERT INTO {} ({}) VALUES ({})",
+                            table,
+                            names.join(", "),
+                            placeholders.join(", ")
+                        )
+                    };
+                    (ChangeKind::Insert, sql)
+                }
+                RowChange::Update { key: row, values } => {
+                    values.keys().try_for_each(check_column)?;
+                    if values.is_empty() {
+                        return Err(format!("Update #{} changes no columns", index + 1));
+                    }
+                    let set: Vec<String> = values
+                        .iter()
+                        .map(|(c, v)| format!("{} = {}", dialect.quote(c), param(v, c)))
+                        .collect();
+                    let filter = key_filter(dialect, key, row, index, &mut param)?;
+                    let sql = format!("UPDATE {} SET {} WHERE {}", table, set.join(", "), filter);
+                    (ChangeKind::Update, sql)
+                }
+                RowChange::Delete { key: row } => {
+                    let filter = key_filter(dialect, key, row, index, &mut param)?;
+                    (
+                        ChangeKind::Delete,
+                        format!("DELETE FROM {} WHERE {}", table, filter),
+                    )
+                }
+            };
+            Ok(RowStatement {
+                kind,
+                index,
+                sql,
+                params,
+            })
+        })
+        .collect()
+}
+
+fn key_filter(
+    dialect: Dialect,
+    key: &[String],
+    row: &BTreeMap<String, Value>,
+    index: usize,
+    param: &mut impl FnMut(&Value, &str) -> String,
+) -> Result<String, String> {
+    let conditions = key
+        .iter()
+        .map(|column| match row.get(column) {
+            None => Err(format!(
+                "Change #{} is missing key column {}",
+                index + 1,
+                column
+            )),
+            Some(Value::Null) => Ok(format!("{} IS NULL", dialect.quote(column))),
+            Some(value) => Ok(format!(
+                "{} = {}",
+                dialect.quote(column),
+                param(value, column)
+            )),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(conditions.join(" AND "))
+}
+
+fn json_text(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+/// A parameter as Postgres text input, which the statement casts to the
+/// column's type. Arrays become array literals and objects JSON. Binary
+/// cells are not text: bind the bytes [`value::binary_bytes`] decodes.
+pub fn param_text(value: &Value) -> Option<String> {
+    match value {
+        Value::Array(items) => Some(array_literal(items)),
+        other => json_text(other),
+    }
+}
+
+/// `{"a","b"}`, with every element quoted so commas, braces and spaces in
+/// it stay literal.
+fn array_literal(items: &[Value]) -> String {
+    let elements: Vec<String> = items
+        .iter()
+        .map(|item| {
+            let text = match item {
+                Value::Null => return "NULL".to_string(),
+                Value::Array(inner) => return array_literal(inner),
+                other => match value::binary_bytes(other) {
+                    Some(bytes) => format!("\\x{}", value::hex(&bytes)),
+                    None => json_text(other).unwrap_or_default(),
+                },
+            };
+            format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+        })
+        .collect();
+    format!("{{{}}}", elements.join(","))
+}
+
+/// Binds a parameter with the closest native type, leaving conversion to the
+/// column's type to the database. Arrays and objects are bound as JSON.
+pub fn bind_param<'q, DB: Database>(
+    query: Query<'q, DB, <DB as Database>::Arguments<'q>>,
+    value: &Value,
+) -> Query<'q, DB, <DB as Database>::Arguments<'q>>
+where
+    bool: Encode<'q, DB> + Type<DB>,
+    i64: Encode<'q, DB> + Type<DB>,
+    f64: Encode<'q, DB> + Type<DB>,
+    Option<String>: Encode<'q, DB> + Type<DB>,
+    Vec<u8>: Encode<'q, DB> + Type<DB>,
+{
+    match value {
+        Value::Bool(b) => query.bind(*b),
+        Value::Number(n) => match (n.as_i64(), n.as_f64()) {
+            (Some(i), _) => query.bind(i),
+            (None, Some(f)) if !n.is_u64() => query.bind(f),
+            _ => query.bind(Some(n.to_string())),
+        },
+        other => match value::binary_bytes(other) {
+            Some(bytes) => query.bind(bytes),
+            None => query.bind(json_text(other)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn column(name: &str, nullable: bool, primary: bool) -> ColumnInfo {
+        ColumnInfo {
+            name: name.to_string(),
+            data_type: "text".to_string(),
+            is_nullable: nullable,
+            column_default: None,
+            is_primary_key: primary,
+        }
+    }
+
+    fn unique(columns: &[&str]) -> ConstraintInfo {
+        ConstraintInfo {
+            name: "uq".to_string(),
+            constraint_type: "UNIQUE".to_string(),
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            foreign_table: None,
+            foreign_columns: None,
+        }
+    }
+
+    fn row(pairs: &[(&str, Value)]) -> BTreeMap<String, Value> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn key_prefers_primary_then_non_null_unique() {
+        let columns = [
+            column("id", false, true),
+            column("email", false, false),
+            column("nick", true, false),
+        ];
+        let constraints = [unique(&["nick"]), unique(&["email"])];
+        assert_eq!(
+            choose_key("users", &columns, &constraints, &[]).unwrap(),
+            vec!["id"]
+        );
+
+        let no_pk = [column("email", false, false), column("nick", true, false)];
+        assert_eq!(
+            choose_key("users", &no_pk, &constraints, &[]).unwrap(),
+            vec!["email"]
+        );
+        assert!(choose_key("users", &no_pk, &[unique(&["nick"])], &[]).is_err());
+    }
+
+    #[test]
+    fn requested_key_must_cover_a_real_key() {
+        let columns = [column("id", false, true), column("name", true, false)];
+        let requested = ["id".to_string(), "name".to_string()];
+        assert_eq!(
+            choose_key("t", &columns, &[], &requested).unwrap(),
+            requested.to_vec()
+        );
+        assert!(choose_key("t", &columns, &[], &["name".to_string()]).is_err());
+        assert!(choose_key("t", &columns, &[], &["missing".to_string()]).is_err());
+    }
+
+    #[test]
+    fn postgres_statements_cast_text_parameters() {
+        let columns = [column("id", false, true), column("name", true, false)];
+        let casts = HashMap::from([
+            ("id".to_string(), "integer".to_string()),
+            ("name".to_string(), "text".to_string()),
+        ]);
+        let changes = [
+            RowChange::Update {
+                key: row(&[("id", json!(7)), ("name", json!("old"))]),
+                values: row(&[("name", json!("new"))]),
+            },
+            RowChange::Delete {
+                key: row(&[("id", json!(8))]),
+            },
+        ];
+        let statements = build_statements(
+            Dialect::Postgres,
+            "\"public\".\"users\"",
+            &columns,
+            &["id".to_string()],
+            &casts,
+            &changes,
+        )
+        .unwrap();
+        assert_eq!(
+            statements[0].sql,
+            "UPDATE \"public\".\"users\" SET \"name\" = CAST($1 AS text) WHERE \"id\" = CAST($2 AS integer)"
+        );
+        assert_eq!(statements[0].params, vec![json!("new"), json!(7)]);
+        assert_eq!(
+            statements[1].sql,
+            "DELETE FROM \"public\".\"users\" WHERE \"id\" = CAST($1 AS integer)"
+        );
+    }
+
+    #[test]
+    fn mysql_statements_quote_and_match_null_keys() {
+        let columns = [column("a`b", false, false), column("c", true, false)];
+        let changes = [
+            RowChange::Delete {
+                key: row(&[("a`b", json!("x")), ("c", Value::Null)]),
+            },
+            RowChange::Insert { values: row(&[]) },
+        ];
+        let statements = build_statements(
+            Dialect::Mysql,
+            "`db`.`t`",
+            &columns,
+            &["a`b".to_string(), "c".to_string()],
+            &HashMap::new(),
+            &changes,
+        )
+        .unwrap();
+        assert_eq!(
+            statements[0].sql,
+            "DELETE FROM `db`.`t` WHERE `a``b` = ? AND `c` IS NULL"
+        );
+        assert_eq!(statements[0].params, vec![json!("x")]);
+        assert_eq!(statements[1].sql, "INSERT INTO `db`.`t` () VALUES ()");
+    }
+
+    #[test]
+    fn postgres_arrays_bind_as_array_literals() {
+        let binary = value::binary(&[0xde, 0xad]);
+        assert_eq!(
+            param_text(&json!([1, null, "a,b", "say \"hi\"", [2, 3]])).as_deref(),
+            Some(r#"{"1",NULL,"a,b","say \"hi\"",{"2","3"}}"#)
+        );
+        assert_eq!(
+            param_text(&json!([binary])).as_deref(),
+            Some(r#"{"\\xdead"}"#)
+        );
+        assert_eq!(param_text(&json!({"a": 1})).as_deref(), Some(r#"{"a":1}"#));
+    }
+
+    #[tokio::test]
+    async fn binary_keys_match_their_rows() {
+        let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::query("CREATE TABLE t (id BLOB PRIMARY KEY, name TEXT)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO t VALUES (x'de00ad', 'old')")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let columns = [column("id", false, true), column("name", true, false)];
+        let changes = [RowChange::Update {
+            key: row(&[("id", value::binary(&[0xde, 0x00, 0xad]))]),
+            values: row(&[("name", json!("new"))]),
+        }];
+        let statements = build_statements(
+            Dialect::Sqlite,
+            "\"t\"",
+            &columns,
+            &["id".to_string()],
+            &HashMap::new(),
+            &changes,
+        )
+        .unwrap();
+        let query = statements[0]
+            .params
+            .iter()
+            .fold(sqlx::query(&statements[0].sql), bind_param);
+        assert_eq!(query.execute(&pool).await.unwrap().rows_affected(), 1);
+
+        let name: String = sqlx::query_scalar("SELECT name FROM t WHERE id = x'de00ad'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(name, "new");
+    }
+
+    #[test]
+    fn only_single_row_results_are_recorded() {
+        let statement = RowStatement {
+            kind: ChangeKind::Update,
+            index: 2,
+            sql: String::new(),
+            params: vec![],
+        };
+        let mut applied = AppliedRowChanges::default();
+        applied.record(&statement, 1).unwrap();
+        assert_eq!(applied.updated, 1);
+        assert!(applied
+            .record(&statement, 0)
+            .unwrap_err()
+            .starts_with("Update #3"));
+        assert!(applied.record(&statement, 2).is_err());
+    }
+}
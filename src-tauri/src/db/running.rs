@@ -0,0 +1,119 @@
+// ABOUTME: Registry of in-flight queries keyed by the frontend's query_id.
+// ABOUTME: Lets a driver cancel exactly one query instead of everything on the server.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long a parked session may sit before it goes back to the pool, which
+/// health-checks connections before handing them out again.
+const IDLE_SESSION_REUSE: Duration = Duration::from_secs(30);
+
+/// Maps a query_id to whatever the engine needs to interrupt that query: a
+/// backend PID, a connection id, an interrupt flag.
+pub struct RunningQueries<T> {
+    queries: Mutex<HashMap<String, T>>,
+}
+
+impl<T: Clone> RunningQueries<T> {
+    pub fn new() -> Self {
+        Self {
+            queries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records `handle` for `query_id` until the returned guard is dropped.
+    /// Drop the guard before releasing the connection the query ran on, so a
+    /// late cancel can never hit a connection that is already running
+    /// someone else's query.
+    pub fn register(&self, query_id: &str, handle: T) -> RunningGuard<'_, T> {
+        self.queries
+            .lock()
+            .unwrap()
+            .insert(query_id.to_string(), handle);
+        RunningGuard {
+            registry: self,
+            query_id: query_id.to_string(),
+        }
+    }
+
+    pub fn get(&self, query_id: &str) -> Option<T> {
+        self.queries.lock().unwrap().get(query_id).cloned()
+    }
+}
+
+pub struct RunningGuard<'a, T> {
+    registry: &'a RunningQueries<T>,
+    query_id: String,
+}
+
+impl<T> Drop for RunningGuard<'_, T> {
+    fn drop(&mut self) {
+        self.registry.queries.lock().unwrap().remove(&self.query_id);
+    }
+}
+
+/// Parks the connection of the last one-shot query together with its cancel
+/// handle, so the next query reuses both instead of acquiring a connection
+/// and looking the handle up again. A parked session goes back to the pool
+/// after [`IDLE_SESSION_REUSE`] even if no query takes it.
+pub struct IdleSession<S> {
+    slot: Arc<Mutex<Option<(S, Instant)>>>,
+    /// False for a one-connection pool, where a parked session would make
+    /// every metadata call wait out the acquire timeout.
+    enabled: bool,
+}
+
+impl<S: Send + 'static> IdleSession<S> {
+    pub fn new(pool_size: u32) -> Self {
+        Self {
+            slot: Arc::new(Mutex::new(None)),
+            enabled: pool_size >= 2,
+        }
+    }
+
+    /// The parked session, if it was parked recently enough to trust.
+    pub fn take(&self) -> Option<S> {
+        let (session, parked_at) = self.slot.lock().unwrap().take()?;
+        (parked_at.elapsed() < IDLE_SESSION_REUSE).then_some(session)
+    }
+
+    /// Parks `session`, releasing whichever one was parked before. When
+    /// parking is disabled the session is released straight away.
+    pub fn park(&self, session: S) {
+        if !self.enabled {
+            return;
+        }
+        let parked_at = Instant::now();
+        *self.slot.lock().unwrap() = Some((session, parked_at));
+        let slot = Arc::downgrade(&self.slot);
+        tokio::spawn(async move {
+            tokio::time::sleep(IDLE_SESSION_REUSE).await;
+            let Some(slot) = slot.upgrade() else { return };
+            let mut slot = slot.lock().unwrap();
+            if slot.as_ref().is_some_and(|(_, at)| *at == parked_at) {
+                slot.take();
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn parked_sessions_are_reused() {
+        let idle = IdleSession::new(2);
+        idle.park(7);
+        assert_eq!(idle.take(), Some(7));
+        assert_eq!(idle.take(), None);
+    }
+
+    #[tokio::test]
+    async fn a_one_connection_pool_never_parks() {
+        let idle = IdleSession::new(1);
+        idle.park(7);
+        assert_eq!(idle.take(), None);
+    }
+}
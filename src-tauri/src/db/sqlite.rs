@@ -1,8 +1,249 @@
 // ABOUTME: SQLite-specific database introspection queries.
 // ABOUTME: Uses PRAGMA statements and sqlite_master for schema information.
 
-use super::{ColumnInfo, ConstraintInfo, FunctionInfo, IndexInfo};
-use sqlx::Row;
+use super::driver::{DatabaseDriver, DriverCapabilities, DriverSession};
+use super::query_plan::{self, QueryPlan, SqlitePlanRow};
+use super::result_set::{open_pool_stream, ResultCursor};
+use super::row_changes::{bind_param, AppliedRowChanges, Dialect, TableEdit};
+use super::running::RunningQueries;
+use super::{pool_options, ColumnInfo, ConstraintInfo, FunctionInfo, IndexInfo, QueryResult};
+use crate::sql_analyzer;
+use crate::storage::ConnectionConfig;
+use async_trait::async_trait;
+use sqlx::pool::PoolConnection;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection};
+use sqlx::{Row, Sqlite};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tauri::AppHandle;
+
+/// SQLite VM instructions between checks of a query's interrupt flag.
+const INTERRUPT_CHECK_OPS: i32 = 1000;
+
+pub struct SqliteDriver {
+    pool: sqlx::SqlitePool,
+    /// Interrupt flag polled by the progress handler of each in-flight query.
+    running: Arc<RunningQueries<Arc<AtomicBool>>>,
+}
+
+impl SqliteDriver {
+    pub async fn connect(config: &ConnectionConfig) -> Result<Self, String> {
+        let pool = pool_options(config)
+            .connect_with(connect_options(config))
+            .await
+            .map_err(|e| format!("Failed to connect to SQLite: {}", e))?;
+        Ok(Self {
+            pool,
+            running: Arc::new(RunningQueries::new()),
+        })
+    }
+}
+
+/// For SQLite, the host field holds the file path.
+pub(super) fn connect_options(config: &ConnectionConfig) -> SqliteConnectOptions {
+    SqliteConnectOptions::new().filename(&config.host)
+}
+
+#[async_trait]
+impl DatabaseDriver for SqliteDriver {
+    fn capabilities(&self) -> DriverCapabilities {
+        DriverCapabilities {
+            engine: "SQLite",
+            supports_triggers: true,
+            supports_query_cancel: true,
+            supports_paged_results: true,
+            supports_transactions: true,
+            supports_query_plans: true,
+            ..Default::default()
+        }
+    }
+
+    async fn list_databases(&self) -> Result<Vec<String>, String> {
+        list_databases(&self.pool).await
+    }
+
+    async fn list_schemas(&self, database: &str) -> Result<Vec<String>, String> {
+        list_schemas(&self.pool, database).await
+    }
+
+    async fn list_tables(&self, database: &str, schema: &str) -> Result<Vec<String>, String> {
+        list_tables(&self.pool, database, schema).await
+    }
+
+    async fn list_views(&self, database: &str, schema: &str) -> Result<Vec<String>, String> {
+        list_views(&self.pool, database, schema).await
+    }
+
+    async fn list_columns(
+        &self,
+        database: &str,
+        schema: &str,
+        table: &str,
+    ) -> Result<Vec<ColumnInfo>, String> {
+        list_columns(&self.pool, database, schema, table).await
+    }
+
+    async fn list_schema_columns(
+        &self,
+        _database: &str,
+        _schema: &str,
+    ) -> Result<HashMap<String, Vec<ColumnInfo>>, String> {
+        list_schema_columns(&self.pool).await
+    }
+
+    async fn list_indexes(
+        &self,
+        database: &str,
+        schema: &str,
+        table: &str,
+    ) -> Result<Vec<IndexInfo>, String> {
+        list_indexes(&self.pool, database, schema, table).await
+    }
+
+    async fn list_constraints(
+        &self,
+        database: &str,
+        schema: &str,
+        table: &str,
+    ) -> Result<Vec<ConstraintInfo>, String> {
+        list_constraints(&self.pool, database, schema, table).await
+    }
+
+    fn split_script(&self, script: &str) -> Vec<String> {
+        sql_analyzer::split_statements(script, "sqlite")
+    }
+
+    async fn execute_query(
+        &self,
+        app: &AppHandle,
+        query_id: &str,
+        query: &str,
+        _database: Option<&str>,
+        max_rows: usize,
+    ) -> Result<QueryResult, String> {
+        let mut session = SqliteSession::open(&self.pool, self.running.clone()).await?;
+        let result = session.execute_query(app, query_id, query, max_rows).await;
+        // Close a connection that may be inside a transaction rather than
+        // returning it to the pool.
+        if result.is_err() || super::transaction_control(query).is_some() {
+            session.discard();
+        }
+        result
+    }
+
+    async fn open_session(&self) -> Result<Box<dyn DriverSession>, String> {
+        Ok(Box::new(SqliteSession::open(&self.pool, self.running.clone()).await?))
+    }
+
+    async fn open_result(&self, query: &str) -> Result<Box<dyn ResultCursor>, String> {
+        Ok(Box::new(
+            open_pool_stream(
+                self.pool.clone(),
+                query.to_string(),
+                super::sqlite_value_to_json,
+                super::sqlite_logical_type,
+            )
+            .await?,
+        ))
+    }
+
+    async fn cancel_query(&self, query_id: &str) -> Result<bool, String> {
+        match self.running.get(query_id) {
+            Some(interrupted) => {
+                interrupted.store(true, Ordering::Relaxed);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn list_triggers(&self, database: &str, schema: &str) -> Result<Vec<String>, String> {
+        list_triggers(&self.pool, database, schema).await
+    }
+
+    async fn apply_row_changes(
+        &self,
+        edits: &[TableEdit],
+    ) -> Result<Vec<AppliedRowChanges>, String> {
+        apply_row_changes(&self.pool, edits).await
+    }
+
+    /// SQLite can't report rows or timings for a run, so `analyze` is ignored.
+    async fn explain_query(&self, query: &str, _analyze: bool) -> Result<QueryPlan, String> {
+        explain_query(&self.pool, query).await
+    }
+
+    async fn get_view_definition(
+        &self,
+        database: &str,
+        schema: &str,
+        view_name: &str,
+    ) -> Result<FunctionInfo, String> {
+        get_view_definition(&self.pool, database, schema, view_name).await
+    }
+}
+
+/// A pooled connection whose statements can be interrupted by query_id.
+pub struct SqliteSession {
+    conn: PoolConnection<Sqlite>,
+    running: Arc<RunningQueries<Arc<AtomicBool>>>,
+}
+
+impl SqliteSession {
+    async fn open(
+        pool: &sqlx::SqlitePool,
+        running: Arc<RunningQueries<Arc<AtomicBool>>>,
+    ) -> Result<Self, String> {
+        let conn = pool
+            .acquire()
+            .await
+            .map_err(|e| format!("Failed to acquire connection: {}", e))?;
+        Ok(Self { conn, running })
+    }
+}
+
+#[async_trait]
+impl DriverSession for SqliteSession {
+    async fn execute_query(
+        &mut self,
+        app: &AppHandle,
+        query_id: &str,
+        query: &str,
+        max_rows: usize,
+    ) -> Result<QueryResult, String> {
+        // SQLite runs in-process, so there is no server to ask. Instead a
+        // progress handler aborts the statement once the flag is raised.
+        let interrupted = Arc::new(AtomicBool::new(false));
+        let flag = interrupted.clone();
+        self.conn
+            .lock_handle()
+            .await
+            .map_err(|e| format!("Failed to lock connection: {}", e))?
+            .set_progress_handler(INTERRUPT_CHECK_OPS, move || !flag.load(Ordering::Relaxed));
+
+        let result = {
+            let _running = self.running.register(query_id, interrupted);
+            super::execute_query_sqlite(app, query_id, &mut self.conn, query, max_rows).await
+        };
+
+        if let Ok(mut handle) = self.conn.lock_handle().await {
+            handle.remove_progress_handler();
+        }
+        result
+    }
+
+    async fn apply_row_changes(
+        &mut self,
+        edits: &[TableEdit],
+    ) -> Result<Vec<AppliedRowChanges>, String> {
+        apply_edits(&mut self.conn, edits).await
+    }
+
+    fn discard(&mut self) {
+        self.conn.close_on_drop();
+    }
+}
 
 pub async fn list_databases(_pool: &sqlx::SqlitePool) -> Result<Vec<String>, String> {
     // SQLite is file-based, so there's just one "database" - we call it "main"
@@ -45,25 +286,6 @@ pub async fn list_views(
     Ok(rows.iter().map(|r| r.get("name")).collect())
 }
 
-pub async fn list_functions(
-    _pool: &sqlx::SqlitePool,
-    _database: &str,
-    _schema: &str,
-) -> Result<Vec<String>, String> {
-    // SQLite doesn't have user-defined functions in the schema
-    Ok(vec![])
-}
-
-pub async fn get_function_definition(
-    _pool: &sqlx::SqlitePool,
-    _database: &str,
-    _schema: &str,
-    _function_name: &str,
-) -> Result<FunctionInfo, String> {
-    // SQLite doesn't have user-defined functions in the schema
-    Err("SQLite does not support user-defined functions in the schema".to_string())
-}
-
 pub async fn get_view_definition(
     pool: &sqlx::SqlitePool,
     _database: &str,
@@ -86,24 +308,6 @@ pub async fn get_view_definition(
     })
 }
 
-pub async fn list_materialized_views(
-    _pool: &sqlx::SqlitePool,
-    _database: &str,
-    _schema: &str,
-) -> Result<Vec<String>, String> {
-    // SQLite does not support materialized views
-    Ok(vec![])
-}
-
-pub async fn list_sequences(
-    _pool: &sqlx::SqlitePool,
-    _database: &str,
-    _schema: &str,
-) -> Result<Vec<String>, String> {
-    // SQLite does not have sequences
-    Ok(vec![])
-}
-
 pub async fn list_triggers(
     pool: &sqlx::SqlitePool,
     _database: &str,
@@ -119,15 +323,6 @@ pub async fn list_triggers(
     Ok(rows.iter().map(|r| r.get("name")).collect())
 }
 
-pub async fn list_procedures(
-    _pool: &sqlx::SqlitePool,
-    _database: &str,
-    _schema: &str,
-) -> Result<Vec<String>, String> {
-    // SQLite does not have stored procedures
-    Ok(vec![])
-}
-
 pub async fn list_columns(
     pool: &sqlx::SqlitePool,
     _database: &str,
@@ -140,16 +335,44 @@ pub async fn list_columns(
         .await
         .map_err(|e| format!("Failed to list columns: {}", e))?;
 
-    Ok(rows
-        .iter()
-        .map(|r| ColumnInfo {
-            name: r.get("name"),
-            data_type: r.get("type"),
-            is_nullable: r.get::<i32, _>("notnull") == 0,
-            column_default: r.get("dflt_value"),
-            is_primary_key: r.get::<i32, _>("pk") > 0,
-        })
-        .collect())
+    Ok(rows.iter().map(column_info).collect())
+}
+
+/// Columns of every table and view, in one query over `pragma_table_info`.
+pub async fn list_schema_columns(
+    pool: &sqlx::SqlitePool,
+) -> Result<HashMap<String, Vec<ColumnInfo>>, String> {
+    let rows = sqlx::query(
+        r#"
+        SELECT m.name AS table_name, p.name, p.type, p."notnull", p.dflt_value, p.pk
+        FROM sqlite_master m
+        JOIN pragma_table_info(m.name) p
+        WHERE m.type IN ('table', 'view')
+        ORDER BY m.name, p.cid
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to list columns: {}", e))?;
+
+    let mut columns: HashMap<String, Vec<ColumnInfo>> = HashMap::new();
+    for row in &rows {
+        columns
+            .entry(row.get("table_name"))
+            .or_default()
+            .push(column_info(row));
+    }
+    Ok(columns)
+}
+
+fn column_info(r: &sqlx::sqlite::SqliteRow) -> ColumnInfo {
+    ColumnInfo {
+        name: r.get("name"),
+        data_type: r.get("type"),
+        is_nullable: r.get::<i32, _>("notnull") == 0,
+        column_default: r.get("dflt_value"),
+        is_primary_key: r.get::<i32, _>("pk") > 0,
+    }
 }
 
 pub async fn list_indexes(
@@ -258,3 +481,134 @@ pub async fn list_constraints(
 
     Ok(constraints)
 }
+
+pub async fn explain_query(pool: &sqlx::SqlitePool, query: &str) -> Result<QueryPlan, String> {
+    let rows = sqlx::query(&format!("EXPLAIN QUERY PLAN {}", query))
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to explain query: {}", e))?;
+    let rows: Vec<SqlitePlanRow> = rows
+        .iter()
+        .map(|r| SqlitePlanRow {
+            id: r.get("id"),
+            parent: r.get("parent"),
+            detail: r.get("detail"),
+        })
+        .collect();
+    Ok(query_plan::parse_sqlite(&rows))
+}
+
+/// Applies `edits` in a transaction of their own.
+pub async fn apply_row_changes(
+    pool: &sqlx::SqlitePool,
+    edits: &[TableEdit],
+) -> Result<Vec<AppliedRowChanges>, String> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let results = apply_edits(&mut tx, edits).await?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to commit changes: {}", e))?;
+    Ok(results)
+}
+
+/// Runs `edits` on `conn`, inside whatever transaction the caller opened.
+async fn apply_edits(
+    conn: &mut SqliteConnection,
+    edits: &[TableEdit],
+) -> Result<Vec<AppliedRowChanges>, String> {
+    let dialect = Dialect::Sqlite;
+    let batches = edits
+        .iter()
+        .map(|edit| edit.statements(dialect, &dialect.quote(&edit.target.table), &HashMap::new()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut results = Vec::with_capacity(edits.len());
+    for (edit, statements) in edits.iter().zip(&batches) {
+        let mut applied = edit.applied();
+        for statement in statements {
+            let query = statement
+                .params
+                .iter()
+                .fold(sqlx::query(&statement.sql), bind_param);
+            let result = query
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| edit.in_table(statement.failed(e)))?;
+            applied
+                .record(statement, result.rows_affected())
+                .map_err(|e| edit.in_table(e))?;
+        }
+        results.push(applied);
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::row_changes::{RowChange, TableChanges};
+    use serde_json::json;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    #[tokio::test]
+    async fn session_edits_roll_back_with_the_transaction() {
+        // One connection, since each in-memory connection is its own database.
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::query("CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO t VALUES (1, 'old')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let column = |name: &str, primary: bool| ColumnInfo {
+            name: name.to_string(),
+            data_type: "TEXT".to_string(),
+            is_nullable: !primary,
+            column_default: None,
+            is_primary_key: primary,
+        };
+        let edit = TableEdit {
+            target: TableChanges {
+                database: "main".to_string(),
+                schema: "main".to_string(),
+                table: "t".to_string(),
+                key_columns: vec![],
+                changes: vec![RowChange::Update {
+                    key: [("id".to_string(), json!(1))].into(),
+                    values: [("name".to_string(), json!("new"))].into(),
+                }],
+            },
+            columns: vec![column("id", true), column("name", false)],
+            key: vec!["id".to_string()],
+        };
+
+        let mut session = SqliteSession::open(&pool, Arc::new(RunningQueries::new()))
+            .await
+            .unwrap();
+        sqlx::query("BEGIN")
+            .execute(&mut *session.conn)
+            .await
+            .unwrap();
+        let applied = session.apply_row_changes(&[edit]).await.unwrap();
+        assert_eq!(applied[0].updated, 1);
+        sqlx::query("ROLLBACK")
+            .execute(&mut *session.conn)
+            .await
+            .unwrap();
+
+        let name: String = sqlx::query_scalar("SELECT name FROM t WHERE id = 1")
+            .fetch_one(&mut *session.conn)
+            .await
+            .unwrap();
+        assert_eq!(name, "old");
+    }
+}
@@ -0,0 +1,528 @@
+// ABOUTME: Reads the user's OpenSSH client config to resolve Host aliases for SSH tunnels.
+// ABOUTME: Supports Include, wildcard and negated Host patterns, and the options a tunnel needs.
+
+use super::known_hosts::wildcard_matches;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Nested `Include`s and `ProxyJump` chains deeper than this are treated as loops.
+const MAX_DEPTH: usize = 16;
+
+#[derive(Debug, Clone)]
+struct Block {
+    /// `Host` patterns. `None` for options before the first `Host` line, which
+    /// apply to every host; `Match` blocks get an empty list and never apply.
+    patterns: Option<Vec<String>>,
+    options: Vec<(String, String)>,
+}
+
+impl Block {
+    fn applies_to(&self, alias: &str) -> bool {
+        let Some(patterns) = &self.patterns else {
+            return true;
+        };
+        let alias = alias.to_ascii_lowercase();
+        let mut matched = false;
+        for pattern in patterns {
+            let pattern = pattern.to_ascii_lowercase();
+            if let Some(negated) = pattern.strip_prefix('!') {
+                if wildcard_matches(alias.as_bytes(), negated.as_bytes()) {
+                    return false;
+                }
+            } else if wildcard_matches(alias.as_bytes(), pattern.as_bytes()) {
+                matched = true;
+            }
+        }
+        matched
+    }
+}
+
+/// A `Host` alias as resolved from the config, for picking in the connection form.
+#[derive(Debug, Clone, Serialize)]
+pub struct SshConfigHost {
+    pub alias: String,
+    pub host_name: String,
+    pub port: u16,
+    pub user: Option<String>,
+    pub proxy_jump: Option<String>,
+}
+
+/// The options for one host, after applying every matching block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedHost {
+    pub host_name: String,
+    pub port: u16,
+    pub user: Option<String>,
+    pub identity_files: Vec<String>,
+    pub identities_only: bool,
+    /// Comma-separated `[user@]host[:port]` hops, as written.
+    pub proxy_jump: Option<String>,
+}
+
+/// One hop of a resolved `ProxyJump` chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JumpHop {
+    pub host_name: String,
+    pub port: u16,
+    pub user: Option<String>,
+    pub identity_files: Vec<String>,
+    pub identities_only: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct SshConfig {
+    blocks: Vec<Block>,
+    home: Option<PathBuf>,
+}
+
+impl SshConfig {
+    /// Loads `~/.ssh/config`. A missing file is an empty config.
+    pub fn load_user_config() -> Self {
+        let home = dirs::home_dir();
+        let mut config = SshConfig {
+            blocks: Vec::new(),
+            home: home.clone(),
+        };
+        if let Some(home) = home {
+            let ssh_dir = home.join(".ssh");
+            config.include_file(&ssh_dir.join("config"), &ssh_dir, None, 0);
+        }
+        config
+    }
+
+    /// Parses config text. Relative `Include` paths resolve against `ssh_dir`.
+    pub fn parse(content: &str, ssh_dir: &Path, home: Option<PathBuf>) -> Self {
+        let mut config = SshConfig {
+            blocks: Vec::new(),
+            home,
+        };
+        config.parse_into(content, ssh_dir, None, 0);
+        config
+    }
+
+    fn include_file(
+        &mut self,
+        path: &Path,
+        ssh_dir: &Path,
+        condition: Option<Vec<String>>,
+        depth: usize,
+    ) {
+        if let Ok(content) = std::fs::read_to_string(path) {
+            self.parse_into(&content, ssh_dir, condition, depth);
+        }
+    }
+
+    /// Appends the file's blocks. `condition` is the `Host` block an
+    /// `Include` appeared in: options before the included file's own first
+    /// `Host` line stay under it, as with ssh.
+    fn parse_into(
+        &mut self,
+        content: &str,
+        ssh_dir: &Path,
+        condition: Option<Vec<String>>,
+        depth: usize,
+    ) {
+        if depth > MAX_DEPTH {
+            return;
+        }
+        self.blocks.push(Block {
+            patterns: condition.clone(),
+            options: Vec::new(),
+        });
+        for line in content.lines() {
+            let Some((key, value)) = split_option(line) else {
+                continue;
+            };
+            match key.as_str() {
+                "host" => self.blocks.push(Block {
+                    patterns: Some(split_args(&value)),
+                    options: Vec::new(),
+                }),
+                "match" => self.blocks.push(Block {
+                    patterns: Some(Vec::new()),
+                    options: Vec::new(),
+                }),
+                "include" => {
+                    let current = self.blocks.last().and_then(|b| b.patterns.clone());
+                    for pattern in split_args(&value) {
+                        for path in self.include_paths(&pattern, ssh_dir) {
+                            self.include_file(&path, ssh_dir, current.clone(), depth + 1);
+                        }
+                    }
+                    // Options after the Include belong to the enclosing block again.
+                    self.blocks.push(Block {
+                        patterns: current,
+                        options: Vec::new(),
+                    });
+                }
+                _ => self
+                    .blocks
+                    .last_mut()
+                    .expect("a block is always open")
+                    .options
+                    .push((key, value)),
+            }
+        }
+    }
+
+    fn include_paths(&self, pattern: &str, ssh_dir: &Path) -> Vec<PathBuf> {
+        let expanded = self.expand_tilde(pattern);
+        let path = if Path::new(&expanded).is_absolute() {
+            PathBuf::from(expanded)
+        } else {
+            ssh_dir.join(expanded)
+        };
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            return Vec::new();
+        };
+        if !name.contains(['*', '?']) {
+            return vec![path];
+        }
+        let Some(dir) = path.parent() else {
+            return Vec::new();
+        };
+        let mut matches: Vec<PathBuf> = std::fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| wildcard_matches(n.as_bytes(), name.as_bytes()))
+            })
+            .collect();
+        matches.sort();
+        matches
+    }
+
+    fn expand_tilde(&self, path: &str) -> String {
+        match (path.strip_prefix("~/"), &self.home) {
+            (Some(rest), Some(home)) => home.join(rest).display().to_string(),
+            _ => path.to_string(),
+        }
+    }
+
+    /// Applies every block matching `alias`. As with ssh, the first value
+    /// found for an option wins, except `IdentityFile`, which accumulates.
+    pub fn resolve(&self, alias: &str) -> ResolvedHost {
+        let mut host_name = None;
+        let mut port = None;
+        let mut user = None;
+        let mut identity_files = Vec::new();
+        let mut identities_only = None;
+        let mut proxy_jump = None;
+        for block in self.blocks.iter().filter(|b| b.applies_to(alias)) {
+            for (key, value) in &block.options {
+                match key.as_str() {
+                    "hostname" if host_name.is_none() => host_name = Some(value.clone()),
+                    "port" if port.is_none() => port = value.parse::<u16>().ok(),
+                    "user" if user.is_none() => user = Some(value.clone()),
+                    "identityfile" => identity_files.push(value.clone()),
+                    "identitiesonly" if identities_only.is_none() => {
+                        identities_only = Some(value.eq_ignore_ascii_case("yes"))
+                    }
+                    "proxyjump" if proxy_jump.is_none() => proxy_jump = Some(value.clone()),
+                    _ => {}
+                }
+            }
+        }
+
+        let host_name = host_name
+            .map(|h| expand_tokens(&h, alias, alias, 22, None))
+            .unwrap_or_else(|| alias.to_string());
+        let port = port.unwrap_or(22);
+        let identity_files = identity_files
+            .iter()
+            .map(|f| self.expand_tilde(&expand_tokens(f, alias, &host_name, port, user.as_deref())))
+            .collect();
+        ResolvedHost {
+            host_name,
+            port,
+            user,
+            identity_files,
+            identities_only: identities_only.unwrap_or(false),
+            proxy_jump: proxy_jump.filter(|p| !p.eq_ignore_ascii_case("none")),
+        }
+    }
+
+    /// The jump hosts to reach `alias` through, outermost first. Each hop is
+    /// itself looked up in the config, and the first hop's own `ProxyJump` is
+    /// followed, which is how ssh chains them.
+    pub fn jump_chain(&self, alias: &str) -> Result<Vec<JumpHop>, String> {
+        self.jump_chain_at(alias, 0)
+    }
+
+    fn jump_chain_at(&self, alias: &str, depth: usize) -> Result<Vec<JumpHop>, String> {
+        if depth > MAX_DEPTH {
+            return Err(format!("ProxyJump for '{}' loops", alias));
+        }
+        let Some(spec) = self.resolve(alias).proxy_jump else {
+            return Ok(Vec::new());
+        };
+        let mut chain = Vec::new();
+        for (i, hop) in spec.split(',').map(str::trim).enumerate() {
+            let (user, rest) = match hop.rsplit_once('@') {
+                Some((user, rest)) => (Some(user.to_string()), rest),
+                None => (None, hop),
+            };
+            let (name, port) = split_host_port(rest)?;
+            if i == 0 {
+                chain.extend(self.jump_chain_at(name, depth + 1)?);
+            }
+            let resolved = self.resolve(name);
+            chain.push(JumpHop {
+                host_name: resolved.host_name,
+                port: port.unwrap_or(resolved.port),
+                user: user.or(resolved.user),
+                identity_files: resolved.identity_files,
+                identities_only: resolved.identities_only,
+            });
+        }
+        Ok(chain)
+    }
+
+    /// Every concrete alias (no wildcards or negation) in file order.
+    pub fn hosts(&self) -> Vec<SshConfigHost> {
+        let mut aliases: Vec<&str> = Vec::new();
+        for block in &self.blocks {
+            for pattern in block.patterns.iter().flatten() {
+                if !pattern.contains(['*', '?', '!']) && !aliases.contains(&pattern.as_str()) {
+                    aliases.push(pattern);
+                }
+            }
+        }
+        aliases
+            .into_iter()
+            .map(|alias| {
+                let resolved = self.resolve(alias);
+                SshConfigHost {
+                    alias: alias.to_string(),
+                    host_name: resolved.host_name,
+                    port: resolved.port,
+                    user: resolved.user,
+                    proxy_jump: resolved.proxy_jump,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Splits `Key value`, `Key=value` or `Key = "value"` into a lowercased key
+/// and the value. Comments and blank lines give `None`.
+fn split_option(line: &str) -> Option<(String, String)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let end = line.find(|c: char| c.is_whitespace() || c == '=')?;
+    let (key, rest) = line.split_at(end);
+    let rest = rest.trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest).trim();
+    Some((key.to_ascii_lowercase(), unquote(rest).to_string()))
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+/// Whitespace-separated arguments, honouring double quotes.
+fn split_args(value: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in value.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        args.push(current);
+    }
+    args
+}
+
+/// `host`, `host:port` or `[host]:port`.
+fn split_host_port(spec: &str) -> Result<(&str, Option<u16>), String> {
+    let parse_port = |p: &str| {
+        p.parse::<u16>()
+            .map(Some)
+            .map_err(|_| format!("Invalid port in ProxyJump host '{}'", spec))
+    };
+    if let Some(rest) = spec.strip_prefix('[') {
+        let (host, port) = rest
+            .split_once(']')
+            .ok_or_else(|| format!("Invalid ProxyJump host '{}'", spec))?;
+        return match port.strip_prefix(':') {
+            Some(p) => Ok((host, parse_port(p)?)),
+            None => Ok((host, None)),
+        };
+    }
+    match spec.split_once(':') {
+        Some((host, p)) => Ok((host, parse_port(p)?)),
+        None => Ok((spec, None)),
+    }
+}
+
+/// Expands the `%` tokens ssh allows in HostName and IdentityFile.
+fn expand_tokens(value: &str, alias: &str, host: &str, port: u16, user: Option<&str>) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => out.push('%'),
+            Some('h') => out.push_str(host),
+            Some('n') => out.push_str(alias),
+            Some('p') => out.push_str(&port.to_string()),
+            Some('r') => out.push_str(user.unwrap_or_default()),
+            Some('d') => {
+                if let Some(home) = dirs::home_dir() {
+                    out.push_str(&home.display().to_string());
+                }
+            }
+            Some(other) => {
+                out.push('%');
+                out.push(other);
+            }
+            None => out.push('%'),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> SshConfig {
+        SshConfig::parse(
+            content,
+            Path::new("/nonexistent"),
+            Some(PathBuf::from("/home/me")),
+        )
+    }
+
+    #[test]
+    fn first_value_wins_and_identity_files_accumulate() {
+        let config = parse(
+            "Host db-bastion\n  HostName 10.0.0.1\n  User ops\n  IdentityFile ~/.ssh/ops\n\
+             Host *\n  User nobody\n  Port 2222\n  IdentityFile ~/.ssh/id_%r\n",
+        );
+        let host = config.resolve("db-bastion");
+        assert_eq!(host.host_name, "10.0.0.1");
+        assert_eq!(host.user.as_deref(), Some("ops"));
+        assert_eq!(host.port, 2222);
+        assert_eq!(
+            host.identity_files,
+            vec![
+                "/home/me/.ssh/ops".to_string(),
+                "/home/me/.ssh/id_ops".to_string()
+            ]
+        );
+        assert!(!host.identities_only);
+    }
+
+    #[test]
+    fn wildcards_negation_and_equals_syntax() {
+        let config = parse(
+            "Host *.prod !legacy.prod\n  IdentitiesOnly=yes\n  HostName = \"%h.example.com\"\n",
+        );
+        let host = config.resolve("api.prod");
+        assert!(host.identities_only);
+        assert_eq!(host.host_name, "api.prod.example.com");
+        let legacy = config.resolve("legacy.prod");
+        assert!(!legacy.identities_only);
+        assert_eq!(legacy.host_name, "legacy.prod");
+    }
+
+    #[test]
+    fn match_blocks_are_skipped() {
+        let config = parse("Match exec \"true\"\n  User matched\nHost a\n  User plain\n");
+        assert_eq!(config.resolve("a").user.as_deref(), Some("plain"));
+        assert_eq!(config.resolve("b").user, None);
+    }
+
+    #[test]
+    fn proxy_jump_chains_follow_the_first_hop() {
+        let config = parse(
+            "Host db\n  HostName 10.1.0.5\n  ProxyJump inner\n\
+             Host inner\n  HostName 10.0.0.9\n  User jump\n  ProxyJump admin@outer:2200\n\
+             Host outer\n  HostName outer.example.com\n  User ignored\n",
+        );
+        let chain = config.jump_chain("db").unwrap();
+        let names: Vec<_> = chain
+            .iter()
+            .map(|h| (h.host_name.as_str(), h.port, h.user.as_deref()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("outer.example.com", 2200, Some("admin")),
+                ("10.0.0.9", 22, Some("jump")),
+            ]
+        );
+        assert!(parse("Host a\n  ProxyJump none\n")
+            .jump_chain("a")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn proxy_jump_loops_are_errors() {
+        let config = parse("Host a\n  ProxyJump b\nHost b\n  ProxyJump a\n");
+        assert!(config.jump_chain("a").is_err());
+    }
+
+    #[test]
+    fn hosts_lists_concrete_aliases_once() {
+        let config = parse("Host a b *.lan !c\nHost a\n  Port 2022\nHost d\n");
+        let aliases: Vec<_> = config
+            .hosts()
+            .into_iter()
+            .map(|h| (h.alias, h.port))
+            .collect();
+        assert_eq!(
+            aliases,
+            vec![
+                ("a".to_string(), 2022),
+                ("b".to_string(), 22),
+                ("d".to_string(), 22)
+            ]
+        );
+    }
+
+    #[test]
+    fn includes_resolve_relative_to_the_ssh_dir() {
+        let dir = std::env::temp_dir().join(format!("dbui-ssh-config-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("config.d")).unwrap();
+        std::fs::write(
+            dir.join("config.d/10-work"),
+            "Host work\n  HostName work.example.com\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("config.d/20-home"), "User fromblock\n").unwrap();
+
+        let config = SshConfig::parse(
+            "Include config.d/10-*\nHost home\n  Include config.d/20-home\n",
+            &dir,
+            None,
+        );
+        assert_eq!(config.resolve("work").host_name, "work.example.com");
+        // Options at the top of an included file stay under the enclosing Host.
+        assert_eq!(config.resolve("home").user.as_deref(), Some("fromblock"));
+        assert_eq!(config.resolve("work").user, None);
+    }
+}
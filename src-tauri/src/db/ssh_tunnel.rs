@@ -1,52 +1,536 @@
-// ABOUTME: Establishes SSH tunnels for forwarding database connections via russh.
-// ABOUTME: Supports ssh-agent (SSH_AUTH_SOCK), private-key, and password authentication.
+// ABOUTME: Establishes SSH tunnels, optionally through a chain of jump hosts, via russh.
+// ABOUTME: Verifies host keys against known_hosts, sends keepalives and reconnects dropped sessions.
 
-use crate::storage::{SshAuthMethod, SshTunnelConfig};
+use super::known_hosts::{self, HostKeyStatus, KnownHosts};
+use super::ssh_config::SshConfig;
+use crate::storage::{SshAuthMethod, SshHop, SshTunnelConfig};
 use russh::ChannelMsg;
 use russh::Preferred;
 use russh::client::{self, Config, Handle};
+use russh::Disconnect;
 use russh::compression;
 use russh::keys::PrivateKeyWithHashAlg;
+use russh::keys::ssh_key::PublicKey;
 #[cfg(unix)]
 use russh::keys::agent::client::AgentClient;
+use serde::Serialize;
 use std::borrow::Cow;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock};
 use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::task::JoinHandle;
+use tokio::sync::{oneshot, watch};
+use tokio::task::{JoinHandle, JoinSet};
 
 const SSH_CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+const HOST_KEY_PROMPT_EVENT: &str = "ssh-host-key-prompt";
+/// How long an unknown host key waits for the user before it is rejected.
+const HOST_KEY_PROMPT_TIMEOUT: Duration = Duration::from_secs(300);
 
-struct Client;
+const TUNNEL_STATUS_EVENT: &str = "tunnel-status";
+const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+/// Keepalives go out after this long without traffic from the server; after
+/// KEEPALIVE_MAX unanswered ones the session is considered dead.
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+const KEEPALIVE_MAX: usize = 3;
+/// How often the monitor checks whether a session has closed.
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Reconnect attempts before the tunnel gives up, waiting RECONNECT_BACKOFF
+/// after the first failure and twice as long after each one after that.
+const RECONNECT_ATTEMPTS: u32 = 5;
+const RECONNECT_BACKOFF: Duration = Duration::from_secs(1);
+/// How long a new local connection waits for a reconnecting tunnel.
+const RECONNECT_WAIT: Duration = Duration::from_secs(60);
+
+/// Trust prompts waiting for an answer from the UI, by request id.
+static HOST_KEY_PROMPTS: LazyLock<std::sync::Mutex<HashMap<String, oneshot::Sender<bool>>>> =
+    LazyLock::new(|| std::sync::Mutex::new(HashMap::new()));
+
+/// Sent to the UI when a tunnel's SSH server presents a key no known_hosts
+/// file records. The connect waits until [`answer_host_key_prompt`] is called.
+#[derive(Debug, Clone, Serialize)]
+pub struct HostKeyPrompt {
+    pub request_id: String,
+    pub host: String,
+    pub port: u16,
+    pub key_type: String,
+    pub fingerprint: String,
+}
+
+/// Where a tunnel is in its life, as reported in [`TunnelStatus`] events.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "lowercase")]
+pub enum TunnelState {
+    Connected,
+    /// The SSH session dropped and is being re-established.
+    Reconnecting { attempt: u32, max_attempts: u32 },
+    /// Reconnecting gave up; the connection has to be reopened.
+    Failed { error: String },
+}
+
+/// Emitted as `tunnel-status` whenever a tunnel's state changes, and when a
+/// single forwarded connection fails.
+#[derive(Debug, Clone, Serialize)]
+pub struct TunnelStatus {
+    pub connection_id: String,
+    #[serde(flatten)]
+    pub state: TunnelState,
+    /// Why a forwarded connection failed. The tunnel itself stays in `state`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forward_error: Option<String>,
+}
+
+struct Client {
+    host: String,
+    port: u16,
+    known_hosts: Arc<KnownHosts>,
+    /// The key and verdict when `check_server_key` refused the server, so
+    /// the caller can tell a host key problem from any other connect error.
+    rejected: Arc<std::sync::Mutex<Option<(PublicKey, HostKeyStatus)>>>,
+}
 
 impl client::Handler for Client {
     type Error = russh::Error;
 
     async fn check_server_key(
         &mut self,
-        _server_public_key: &russh::keys::ssh_key::PublicKey,
+        server_public_key: &PublicKey,
     ) -> Result<bool, Self::Error> {
-        // TODO: verify against ~/.ssh/known_hosts and prompt on first-use.
-        Ok(true)
+        match self.known_hosts.check(&self.host, self.port, server_public_key) {
+            HostKeyStatus::Trusted => Ok(true),
+            status => {
+                *self.rejected.lock().unwrap() = Some((server_public_key.clone(), status));
+                Ok(false)
+            }
+        }
+    }
+}
+
+/// The known_hosts file dbui writes keys the user accepted to. It is read
+/// together with the user's and the system's files, which dbui never modifies.
+fn app_known_hosts_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_config_dir()
+        .map(|dir| dir.join("known_hosts"))
+        .map_err(|e| format!("Failed to get config directory: {}", e))
+}
+
+fn load_known_hosts(app_known_hosts: &Path) -> KnownHosts {
+    let mut known = KnownHosts::new();
+    if let Some(home) = dirs::home_dir() {
+        known.load(&home.join(".ssh").join("known_hosts"));
+    }
+    known.load(Path::new("/etc/ssh/ssh_known_hosts"));
+    known.load(app_known_hosts);
+    known
+}
+
+enum Verified {
+    Connected(Handle<Client>),
+    /// The host is not in any known_hosts file; this is the key it presented.
+    Unknown(PublicKey),
+}
+
+/// Opens an SSH session to `hop`: over TCP for the first hop, or through a
+/// direct-tcpip channel of the previous hop's session (`via`) for later ones.
+async fn connect_verified(
+    client_config: Arc<Config>,
+    cfg: &SshHop,
+    via: Option<&Handle<Client>>,
+    app_known_hosts: &Path,
+) -> Result<Verified, String> {
+    let rejected = Arc::new(std::sync::Mutex::new(None));
+    let handler = Client {
+        host: cfg.host.clone(),
+        port: cfg.port,
+        known_hosts: Arc::new(load_known_hosts(app_known_hosts)),
+        rejected: rejected.clone(),
+    };
+    let connect_fut = async {
+        match via {
+            None => client::connect(client_config, (cfg.host.as_str(), cfg.port), handler)
+                .await
+                .map_err(|e| format!("{}", e)),
+            Some(previous) => {
+                let channel = previous
+                    .channel_open_direct_tcpip(cfg.host.clone(), cfg.port as u32, "127.0.0.1", 0)
+                    .await
+                    .map_err(|e| format!("the previous hop could not reach it: {}", e))?;
+                client::connect_stream(client_config, channel.into_stream(), handler)
+                    .await
+                    .map_err(|e| format!("{}", e))
+            }
+        }
+    };
+    let result = tokio::time::timeout(SSH_CONNECT_TIMEOUT, connect_fut)
+        .await
+        .map_err(|_| format!("SSH connect to {}:{} timed out", cfg.host, cfg.port))?;
+    let error = match result {
+        Ok(session) => return Ok(Verified::Connected(session)),
+        Err(e) => e,
+    };
+    let rejected = rejected.lock().unwrap().take();
+    match rejected {
+        Some((key, HostKeyStatus::Unknown)) => Ok(Verified::Unknown(key)),
+        Some((key, HostKeyStatus::Changed { expected })) => {
+            let recorded = expected
+                .iter()
+                .map(|k| format!("{} {} ({}:{})", k.key_type, k.fingerprint, k.source, k.line))
+                .collect::<Vec<_>>()
+                .join(", ");
+            Err(format!(
+                "Host key verification failed: the SSH host key for {} has changed. \
+                 The server presented {} {}, but known_hosts records {}. \
+                 This could mean someone is intercepting the connection. \
+                 If the server's key was legitimately replaced, remove the old entry and reconnect.",
+                known_hosts::host_name(&cfg.host, cfg.port),
+                key.algorithm(),
+                known_hosts::fingerprint(&key),
+                recorded
+            ))
+        }
+        Some((key, HostKeyStatus::Revoked { source, line })) => Err(format!(
+            "Host key verification failed: the SSH host key {} {} for {} is revoked ({}:{})",
+            key.algorithm(),
+            known_hosts::fingerprint(&key),
+            known_hosts::host_name(&cfg.host, cfg.port),
+            source,
+            line
+        )),
+        Some((_, HostKeyStatus::Trusted)) | None => Err(format!(
+            "SSH connect to {}:{} failed: {}",
+            cfg.host, cfg.port, error
+        )),
+    }
+}
+
+/// Asks the UI whether to trust an unknown host key. Resolves to false when
+/// the user rejects it or doesn't answer in time.
+async fn prompt_host_key(app: &AppHandle, cfg: &SshHop, key: &PublicKey) -> bool {
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let (tx, rx) = oneshot::channel();
+    HOST_KEY_PROMPTS
+        .lock()
+        .unwrap()
+        .insert(request_id.clone(), tx);
+    let _ = app.emit(
+        HOST_KEY_PROMPT_EVENT,
+        HostKeyPrompt {
+            request_id: request_id.clone(),
+            host: cfg.host.clone(),
+            port: cfg.port,
+            key_type: key.algorithm().to_string(),
+            fingerprint: known_hosts::fingerprint(key),
+        },
+    );
+    let answer = tokio::time::timeout(HOST_KEY_PROMPT_TIMEOUT, rx).await;
+    HOST_KEY_PROMPTS.lock().unwrap().remove(&request_id);
+    matches!(answer, Ok(Ok(true)))
+}
+
+/// Delivers the user's answer to a [`HostKeyPrompt`].
+pub fn answer_host_key_prompt(request_id: &str, trust: bool) -> Result<(), String> {
+    let sender = HOST_KEY_PROMPTS
+        .lock()
+        .unwrap()
+        .remove(request_id)
+        .ok_or_else(|| "This host key prompt has expired".to_string())?;
+    let _ = sender.send(trust);
+    Ok(())
+}
+
+fn remember_host_key(path: &Path, cfg: &SshHop, key: &PublicKey) -> Result<(), String> {
+    let line = known_hosts::format_entry(&cfg.host, cfg.port, key)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).ok();
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// What the listener and the monitor share: the sessions in use, and how to
+/// open new ones when they drop.
+struct Tunnel {
+    app: AppHandle,
+    connection_id: String,
+    client_config: Arc<Config>,
+    hops: Vec<SshHop>,
+    app_known_hosts: PathBuf,
+    /// One session per hop, in connection order. Each later session runs
+    /// over a channel of the one before it.
+    sessions: std::sync::Mutex<Vec<Arc<Handle<Client>>>>,
+    state: watch::Sender<TunnelState>,
+}
+
+impl Tunnel {
+    fn set_state(&self, state: TunnelState) {
+        self.state.send_replace(state.clone());
+        self.emit_status(state, None);
+    }
+
+    fn report_forward_error(&self, error: String) {
+        let state = self.state.borrow().clone();
+        self.emit_status(state, Some(error));
+    }
+
+    fn emit_status(&self, state: TunnelState, forward_error: Option<String>) {
+        let _ = self.app.emit(
+            TUNNEL_STATUS_EVENT,
+            TunnelStatus {
+                connection_id: self.connection_id.clone(),
+                state,
+                forward_error,
+            },
+        );
+    }
+
+    /// True once any hop's session has closed, which takes every later one
+    /// down with it.
+    fn is_down(&self) -> bool {
+        self.sessions.lock().unwrap().iter().any(|s| s.is_closed())
+    }
+
+    /// Takes the sessions out of the tunnel, so new forwards can't pick them
+    /// up, for [`disconnect_chain`] to close.
+    fn take_sessions(&self) -> Vec<Arc<Handle<Client>>> {
+        std::mem::take(&mut *self.sessions.lock().unwrap())
+    }
+
+    /// The session to forward a new local connection through, waiting for a
+    /// reconnect in progress to finish.
+    async fn session(&self) -> Result<Arc<Handle<Client>>, String> {
+        let mut state = self.state.subscribe();
+        let settled = tokio::time::timeout(
+            RECONNECT_WAIT,
+            state.wait_for(|s| !matches!(s, TunnelState::Reconnecting { .. })),
+        )
+        .await
+        .map_err(|_| "SSH tunnel is still reconnecting".to_string())?
+        .map_err(|_| "SSH tunnel was closed".to_string())?
+        .clone();
+        match settled {
+            TunnelState::Failed { error } => Err(format!("SSH tunnel is down: {}", error)),
+            _ => self
+                .sessions
+                .lock()
+                .unwrap()
+                .last()
+                .cloned()
+                .ok_or_else(|| "SSH tunnel has no session".to_string()),
+        }
+    }
+
+    /// Waits for a session to drop, then reconnects with backoff. Returns
+    /// when reconnecting fails for good.
+    async fn monitor(self: Arc<Self>) {
+        loop {
+            tokio::time::sleep(HEALTH_CHECK_INTERVAL).await;
+            if !self.is_down() {
+                continue;
+            }
+            disconnect_chain(self.take_sessions()).await;
+
+            let mut delay = RECONNECT_BACKOFF;
+            let mut last_error = String::new();
+            let mut reconnected = false;
+            for attempt in 1..=RECONNECT_ATTEMPTS {
+                self.set_state(TunnelState::Reconnecting {
+                    attempt,
+                    max_attempts: RECONNECT_ATTEMPTS,
+                });
+                match open_chain(&self.app, &self.client_config, &self.hops, &self.app_known_hosts)
+                    .await
+                {
+                    Ok(sessions) => {
+                        *self.sessions.lock().unwrap() = sessions;
+                        reconnected = true;
+                        break;
+                    }
+                    Err(e) => last_error = e,
+                }
+                if attempt < RECONNECT_ATTEMPTS {
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                }
+            }
+            if !reconnected {
+                self.set_state(TunnelState::Failed { error: last_error });
+                return;
+            }
+            self.set_state(TunnelState::Connected);
+        }
     }
 }
 
-/// Holds a live SSH tunnel. Dropping it aborts the forwarding task and
-/// disconnects the underlying SSH session.
+/// Holds a live SSH tunnel. Dropping it stops forwarding and reconnecting
+/// and disconnects the SSH sessions, innermost hop first.
 pub struct TunnelHandle {
     pub local_port: u16,
     listener_task: JoinHandle<()>,
-    _session: Arc<Handle<Client>>,
+    monitor_task: JoinHandle<()>,
+    tunnel: Arc<Tunnel>,
 }
 
 impl Drop for TunnelHandle {
     fn drop(&mut self) {
+        // Aborting the listener drops its JoinSet, which aborts every
+        // forward still running.
         self.listener_task.abort();
+        self.monitor_task.abort();
+        let sessions = self.tunnel.take_sessions();
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(disconnect_chain(sessions));
+        }
+    }
+}
+
+/// Disconnects each hop's session, innermost first: every later session runs
+/// over a channel of the one before it, so an outer hop closing first would
+/// cut the inner ones off without a goodbye. Forwards that still hold a
+/// session's handle can't keep it open.
+async fn disconnect_chain(sessions: Vec<Arc<Handle<Client>>>) {
+    for session in sessions.into_iter().rev() {
+        let _ = session
+            .disconnect(Disconnect::ByApplication, "", "en")
+            .await;
+    }
+}
+
+/// The hops to connect through, in order. For an ssh_config alias the hosts,
+/// ports, users, identity files and jump hosts come from `~/.ssh/config`,
+/// with explicitly configured jump hosts taking precedence over ProxyJump.
+fn resolve_hops(cfg: &SshTunnelConfig) -> Result<Vec<SshHop>, String> {
+    let Some(alias) = cfg.ssh_config_host.as_deref().filter(|a| !a.is_empty()) else {
+        return Ok(cfg.hops());
+    };
+    let config = SshConfig::load_user_config();
+    let resolved = config.resolve(alias);
+    let local_user = || {
+        std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .unwrap_or_default()
+    };
+
+    let mut hops = if cfg.jump_hosts.is_empty() {
+        config
+            .jump_chain(alias)?
+            .into_iter()
+            .map(|jump| SshHop {
+                host: jump.host_name,
+                port: jump.port,
+                username: jump.user.unwrap_or_else(local_user),
+                auth: SshAuthMethod::Agent,
+                identity_files: with_default_identities(jump.identity_files),
+                identities_only: jump.identities_only,
+            })
+            .collect()
+    } else {
+        cfg.jump_hosts.clone()
+    };
+    hops.push(SshHop {
+        host: resolved.host_name,
+        port: resolved.port,
+        username: if cfg.username.is_empty() {
+            resolved.user.unwrap_or_else(local_user)
+        } else {
+            cfg.username.clone()
+        },
+        auth: cfg.auth.clone(),
+        identity_files: with_default_identities(resolved.identity_files),
+        identities_only: resolved.identities_only,
+    });
+    Ok(hops)
+}
+
+/// Without an `IdentityFile`, ssh tries the standard key files that exist.
+fn with_default_identities(identity_files: Vec<String>) -> Vec<String> {
+    if !identity_files.is_empty() {
+        return identity_files;
+    }
+    let Some(ssh_dir) = dirs::home_dir().map(|home| home.join(".ssh")) else {
+        return identity_files;
+    };
+    ["id_ed25519", "id_ecdsa", "id_rsa"]
+        .iter()
+        .map(|name| ssh_dir.join(name))
+        .filter(|path| path.exists())
+        .map(|path| path.display().to_string())
+        .collect()
+}
+
+/// Connects and authenticates to one hop. Unknown hosts are trusted on first
+/// use: the connection is refused, the user is shown the key's fingerprint,
+/// and if they accept it the key is recorded and the connection retried.
+async fn open_hop(
+    app: &AppHandle,
+    client_config: Arc<Config>,
+    hop: &SshHop,
+    via: Option<&Handle<Client>>,
+    app_known_hosts: &Path,
+) -> Result<Handle<Client>, String> {
+    let mut session = match connect_verified(client_config.clone(), hop, via, app_known_hosts).await? {
+        Verified::Connected(session) => session,
+        Verified::Unknown(key) => {
+            if !prompt_host_key(app, hop, &key).await {
+                return Err(format!(
+                    "The SSH host key {} for {} was not trusted",
+                    known_hosts::fingerprint(&key),
+                    known_hosts::host_name(&hop.host, hop.port)
+                ));
+            }
+            remember_host_key(app_known_hosts, hop, &key)?;
+            match connect_verified(client_config, hop, via, app_known_hosts).await? {
+                Verified::Connected(session) => session,
+                Verified::Unknown(_) => {
+                    return Err(format!(
+                        "The SSH server at {}:{} presented a different key after it was trusted",
+                        hop.host, hop.port
+                    ))
+                }
+            }
+        }
+    };
+    authenticate(&mut session, hop).await?;
+    Ok(session)
+}
+
+/// Connects to every hop in order, each through the one before it.
+async fn open_chain(
+    app: &AppHandle,
+    client_config: &Arc<Config>,
+    hops: &[SshHop],
+    app_known_hosts: &Path,
+) -> Result<Vec<Arc<Handle<Client>>>, String> {
+    let mut sessions: Vec<Arc<Handle<Client>>> = Vec::with_capacity(hops.len());
+    for (i, hop) in hops.iter().enumerate() {
+        let via = sessions.last().map(|s| s.as_ref());
+        let session = open_hop(app, client_config.clone(), hop, via, app_known_hosts)
+            .await
+            .map_err(|e| {
+                if hops.len() > 1 {
+                    format!("SSH hop {} of {} ({}): {}", i + 1, hops.len(), hop.label(), e)
+                } else {
+                    e
+                }
+            })?;
+        sessions.push(Arc::new(session));
     }
+    Ok(sessions)
 }
 
+/// Opens the tunnel for `connection_id`. Its `tunnel-status` events carry
+/// that id.
 pub async fn establish_tunnel(
+    app: &AppHandle,
+    connection_id: &str,
     cfg: &SshTunnelConfig,
     remote_host: String,
     remote_port: u16,
@@ -75,18 +559,15 @@ pub async fn establish_tunnel(
         // (like per-row MySQL/Postgres frames) coalesce with delayed-ACKs and
         // add RTT-sized latency spikes per row.
         nodelay: true,
+        keepalive_interval: Some(KEEPALIVE_INTERVAL),
+        keepalive_max: KEEPALIVE_MAX,
         preferred,
         ..Default::default()
     });
 
-    let ssh_addr = (cfg.host.as_str(), cfg.port);
-    let connect_fut = client::connect(client_config, ssh_addr, Client);
-    let mut session = tokio::time::timeout(SSH_CONNECT_TIMEOUT, connect_fut)
-        .await
-        .map_err(|_| format!("SSH connect to {}:{} timed out", cfg.host, cfg.port))?
-        .map_err(|e| format!("SSH connect to {}:{} failed: {}", cfg.host, cfg.port, e))?;
-
-    authenticate(&mut session, cfg).await?;
+    let app_known_hosts = app_known_hosts_path(app)?;
+    let hops = resolve_hops(cfg)?;
+    let sessions = open_chain(app, &client_config, &hops, &app_known_hosts).await?;
 
     let listener = TcpListener::bind("127.0.0.1:0")
         .await
@@ -96,23 +577,52 @@ pub async fn establish_tunnel(
         .map_err(|e| format!("Failed to read local forwarding addr: {}", e))?
         .port();
 
-    let session = Arc::new(session);
-    let session_for_task = session.clone();
+    let tunnel = Arc::new(Tunnel {
+        app: app.clone(),
+        connection_id: connection_id.to_string(),
+        client_config,
+        hops,
+        app_known_hosts,
+        sessions: std::sync::Mutex::new(sessions),
+        state: watch::Sender::new(TunnelState::Connected),
+    });
+    tunnel.set_state(TunnelState::Connected);
+
+    let listener_tunnel = tunnel.clone();
     let listener_task = tokio::spawn(async move {
+        // Owned by this task so aborting it stops the forwards too.
+        let mut forwards = JoinSet::new();
         loop {
-            let (socket, peer) = match listener.accept().await {
+            let accepted = listener.accept().await;
+            while forwards.try_join_next().is_some() {}
+            let (socket, peer) = match accepted {
                 Ok(v) => v,
                 Err(e) => {
-                    eprintln!("SSH tunnel accept error: {}", e);
+                    listener_tunnel.report_forward_error(format!(
+                        "Failed to accept a local connection: {}",
+                        e
+                    ));
+                    // Accept errors such as running out of file descriptors
+                    // tend to repeat; don't spin on them.
+                    tokio::time::sleep(ACCEPT_ERROR_BACKOFF).await;
                     continue;
                 }
             };
             // Same reasoning as on the SSH side: avoid Nagle/delayed-ACK
             // pairing when forwarding per-row database protocol frames.
             let _ = socket.set_nodelay(true);
-            let session = session_for_task.clone();
+            let tunnel = listener_tunnel.clone();
             let remote_host = remote_host.clone();
-            tokio::spawn(async move {
+            forwards.spawn(async move {
+                // Dropping the socket tells the database client the
+                // connection failed, rather than leaving it hanging.
+                let session = match tunnel.session().await {
+                    Ok(session) => session,
+                    Err(e) => {
+                        tunnel.report_forward_error(e);
+                        return;
+                    }
+                };
                 if let Err(e) = forward(
                     session,
                     socket,
@@ -123,23 +633,23 @@ pub async fn establish_tunnel(
                 )
                 .await
                 {
-                    eprintln!("SSH tunnel forward error: {}", e);
+                    tunnel.report_forward_error(e);
                 }
             });
         }
     });
 
+    let monitor_task = tokio::spawn(tunnel.clone().monitor());
+
     Ok(TunnelHandle {
         local_port,
         listener_task,
-        _session: session,
+        monitor_task,
+        tunnel,
     })
 }
 
-async fn authenticate(
-    session: &mut Handle<Client>,
-    cfg: &SshTunnelConfig,
-) -> Result<(), String> {
+async fn authenticate(session: &mut Handle<Client>, cfg: &SshHop) -> Result<(), String> {
     match &cfg.auth {
         SshAuthMethod::Password { password } => {
             let res = session
@@ -169,6 +679,9 @@ async fn authenticate(
                 return Err("SSH key authentication was rejected".into());
             }
         }
+        SshAuthMethod::Agent if !cfg.identity_files.is_empty() => {
+            return authenticate_with_identities(session, cfg).await;
+        }
         SshAuthMethod::Agent => {
             #[cfg(not(unix))]
             {
@@ -218,6 +731,72 @@ async fn authenticate(
     Ok(())
 }
 
+/// ssh's order for a host from ssh_config: agent keys first (only those
+/// matching an `IdentityFile` with `IdentitiesOnly`), then the identity files
+/// themselves. Files that need a passphrase can only be used via the agent.
+async fn authenticate_with_identities(
+    session: &mut Handle<Client>,
+    cfg: &SshHop,
+) -> Result<(), String> {
+    let hash_alg = session
+        .best_supported_rsa_hash()
+        .await
+        .map_err(|e| format!("SSH negotiation failed: {}", e))?
+        .flatten();
+
+    #[cfg(unix)]
+    if let Ok(mut agent) = AgentClient::connect_env().await {
+        let wanted: Vec<PublicKey> = cfg
+            .identity_files
+            .iter()
+            .filter_map(|path| russh::keys::load_public_key(format!("{}.pub", path)).ok())
+            .collect();
+        let identities = agent.request_identities().await.unwrap_or_default();
+        for ident in identities {
+            let pub_key = ident.public_key().into_owned();
+            if cfg.identities_only && !wanted.iter().any(|w| w.key_data() == pub_key.key_data()) {
+                continue;
+            }
+            let res = session
+                .authenticate_publickey_with(&cfg.username, pub_key, hash_alg, &mut agent)
+                .await
+                .map_err(|e| format!("SSH agent auth error: {}", e))?;
+            if res.success() {
+                return Ok(());
+            }
+        }
+    }
+
+    let mut unusable = Vec::new();
+    for path in &cfg.identity_files {
+        let key = match russh::keys::load_secret_key(path, None) {
+            Ok(key) => key,
+            Err(e) => {
+                unusable.push(format!("{} ({})", path, e));
+                continue;
+            }
+        };
+        let res = session
+            .authenticate_publickey(
+                &cfg.username,
+                PrivateKeyWithHashAlg::new(Arc::new(key), hash_alg),
+            )
+            .await
+            .map_err(|e| format!("SSH key auth error: {}", e))?;
+        if res.success() {
+            return Ok(());
+        }
+    }
+    let mut message = format!(
+        "SSH authentication as '{}' failed: the server rejected every agent key and identity file",
+        cfg.username
+    );
+    if !unusable.is_empty() {
+        message.push_str(&format!("; could not load {}", unusable.join(", ")));
+    }
+    Err(message)
+}
+
 async fn forward(
     session: Arc<Handle<Client>>,
     mut socket: TcpStream,
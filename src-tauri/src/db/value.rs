@@ -0,0 +1,137 @@
+// ABOUTME: Column metadata and the cell encoding shared by every engine's row decoder.
+// ABOUTME: Keeps values JSON cannot hold faithfully (decimals, bytes) lossless for the grid and exporters.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Engine-independent category of a column, so the frontend can format,
+/// edit and export values without knowing every engine's type names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogicalType {
+    Boolean,
+    Integer,
+    Float,
+    /// Exact numeric, encoded as a string so no digits are lost.
+    Decimal,
+    Text,
+    /// Encoded with [`binary`].
+    Binary,
+    Date,
+    Time,
+    Timestamp,
+    TimestampTz,
+    Interval,
+    Uuid,
+    Json,
+    /// Encoded as a JSON array of element values.
+    Array,
+    Network,
+    Geometry,
+    Enum,
+    Bit,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnMeta {
+    pub name: String,
+    /// The engine's own type name, e.g. `INT8`, `VARCHAR`, `DECIMAL`.
+    pub type_name: String,
+    /// Whether the source column accepts NULL according to its table
+    /// definition. `None` for computed columns or when the engine doesn't say.
+    pub nullable: Option<bool>,
+    pub logical_type: LogicalType,
+}
+
+impl ColumnMeta {
+    pub fn new(name: &str, type_name: &str, logical_type: LogicalType) -> Self {
+        Self {
+            name: name.to_string(),
+            type_name: type_name.to_string(),
+            nullable: None,
+            logical_type,
+        }
+    }
+}
+
+/// Tag marking a cell that is an encoded value rather than plain JSON.
+pub const TYPE_TAG: &str = "$type";
+
+/// Encodes bytes as `{"$type": "binary", "hex": "...", "length": n}`. Hex keeps
+/// the value readable in the grid and trivially convertible to a SQL literal.
+pub fn binary(bytes: &[u8]) -> Value {
+    serde_json::json!({
+        TYPE_TAG: "binary",
+        "hex": hex(bytes),
+        "length": bytes.len(),
+    })
+}
+
+/// The bytes a [`binary`] cell holds; None for any other value.
+pub fn binary_bytes(value: &Value) -> Option<Vec<u8>> {
+    if value.get(TYPE_TAG)?.as_str()? != "binary" {
+        return None;
+    }
+    unhex(value.get("hex")?.as_str()?)
+}
+
+/// Exact numerics stay strings; f64 would silently round `NUMERIC(38, 10)`.
+pub fn decimal(text: &str) -> Value {
+    Value::String(text.trim().to_string())
+}
+
+/// f64 as a JSON number, or a string for NaN and infinities.
+pub fn float(v: f64) -> Value {
+    serde_json::Number::from_f64(v)
+        .map(Value::Number)
+        .unwrap_or_else(|| Value::String(v.to_string()))
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        out.push(DIGITS[(b >> 4) as usize] as char);
+        out.push(DIGITS[(b & 0x0f) as usize] as char);
+    }
+    out
+}
+
+pub fn unhex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary_is_tagged_hex_with_length() {
+        let v = binary(&[0xde, 0xad, 0x00, 0x0f]);
+        assert_eq!(v[TYPE_TAG], "binary");
+        assert_eq!(v["hex"], "dead000f");
+        assert_eq!(v["length"], 4);
+        assert_eq!(binary_bytes(&v), Some(vec![0xde, 0xad, 0x00, 0x0f]));
+        assert_eq!(binary_bytes(&Value::String("dead".into())), None);
+    }
+
+    #[test]
+    fn decimal_keeps_every_digit() {
+        let v = decimal("12345678901234567890.0123456789");
+        assert_eq!(v, Value::String("12345678901234567890.0123456789".into()));
+    }
+
+    #[test]
+    fn non_finite_floats_become_strings() {
+        assert_eq!(float(1.5), serde_json::json!(1.5));
+        assert_eq!(float(f64::NAN), Value::String("NaN".into()));
+        assert_eq!(float(f64::INFINITY), Value::String("inf".into()));
+    }
+}
@@ -5,7 +5,9 @@ mod cloud;
 mod commands;
 mod db;
 mod history;
+mod secrets;
 mod sql_analyzer;
+mod sql_completion;
 mod storage;
 
 use commands::*;
@@ -29,16 +31,26 @@ pub fn run() {
             set_visible_databases,
             update_connection,
             save_last_selected,
+            secret_store_status,
+            unlock_secret_store,
+            lock_secret_store,
+            change_master_password,
+            set_secret_backend,
             list_users,
             get_user_grants,
-            cancel_queries,
+            cancel_query,
             connect,
+            answer_host_key_prompt,
+            list_ssh_config_hosts,
+            test_connection,
             disconnect,
             switch_database,
+            get_capabilities,
             list_databases,
             list_schemas,
             create_database,
             create_schema,
+            refresh_metadata,
             list_tables,
             list_views,
             list_functions,
@@ -52,7 +64,31 @@ pub fn run() {
             list_columns,
             list_indexes,
             list_constraints,
+            apply_row_changes,
+            redis_list_databases,
+            redis_list_namespaces,
+            redis_scan_namespace,
+            redis_get_key,
+            redis_set_string,
+            redis_set_hash_fields,
+            redis_set_list_item,
+            redis_push_list_items,
+            redis_set_set_members,
+            redis_set_zset_members,
+            redis_set_stream_entry,
+            redis_set_json,
+            redis_set_ttl,
+            redis_delete_members,
             execute_query,
+            begin_transaction,
+            commit,
+            rollback,
+            transaction_status,
+            open_transaction_count,
+            close_session,
+            open_result,
+            fetch_page,
+            close_result,
             save_query_history,
             get_query_history,
             search_query_history,
@@ -74,6 +110,9 @@ pub fn run() {
             get_kube_secret_value,
             parse_connection_url,
             extract_query_table,
+            analyze_query_columns,
+            complete_sql,
+            explain_query,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,596 @@
+// ABOUTME: Secret storage for connection passwords, kept out of connections.json.
+// ABOUTME: Default backend is an argon2 + AES-GCM vault unlocked by a master password; the OS keyring is optional.
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+
+pub const LOCKED_ERROR: &str =
+    "The secret store is locked. Unlock it with your master password to use saved passwords.";
+
+/// Key-value storage for secrets. Keys are the references kept in
+/// connections.json, e.g. `connection/<id>/password`.
+pub trait SecretStore: Send + Sync {
+    fn get(&self, key: &str) -> Result<Option<String>, String>;
+    fn set(&self, key: &str, value: &str) -> Result<(), String>;
+    fn delete(&self, key: &str) -> Result<(), String>;
+}
+
+const VAULT_VERSION: u32 = 1;
+/// Encrypted under the vault key so a wrong master password is detected even
+/// when the vault holds no secrets yet.
+const CHECK_PLAINTEXT: &[u8] = b"dbui-vault-check";
+const CHECK_AAD: &[u8] = b"check";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    /// OWASP's recommended argon2id baseline.
+    fn default() -> Self {
+        Self {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Sealed {
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    salt: String,
+    kdf: KdfParams,
+    check: Sealed,
+    #[serde(default)]
+    entries: BTreeMap<String, Sealed>,
+}
+
+/// Encrypted local vault. Each secret is sealed with AES-256-GCM under a key
+/// derived from the master password with argon2id; the secret's key is the
+/// associated data, so ciphertexts can't be swapped between entries.
+pub struct Vault {
+    path: PathBuf,
+    cipher: Aes256Gcm,
+    file: Mutex<VaultFile>,
+}
+
+impl Vault {
+    pub fn create(path: &Path, master_password: &str) -> Result<Self, String> {
+        Self::create_with(path, master_password, KdfParams::default())
+    }
+
+    pub fn create_with(path: &Path, master_password: &str, kdf: KdfParams) -> Result<Self, String> {
+        if master_password.is_empty() {
+            return Err("The master password cannot be empty.".to_string());
+        }
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let cipher = derive_cipher(master_password, &salt, kdf)?;
+        let file = VaultFile {
+            version: VAULT_VERSION,
+            salt: BASE64.encode(salt),
+            kdf,
+            check: seal(&cipher, CHECK_PLAINTEXT, CHECK_AAD)?,
+            entries: BTreeMap::new(),
+        };
+        write_vault(path, &file)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            cipher,
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn open(path: &Path, master_password: &str) -> Result<Self, String> {
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read secret vault: {}", e))?;
+        let file: VaultFile = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse secret vault: {}", e))?;
+        if file.version != VAULT_VERSION {
+            return Err(format!("Unsupported secret vault version {}", file.version));
+        }
+        let salt = BASE64
+            .decode(&file.salt)
+            .map_err(|e| format!("Corrupt secret vault: {}", e))?;
+        let cipher = derive_cipher(master_password, &salt, file.kdf)?;
+        match open_sealed(&cipher, &file.check, CHECK_AAD) {
+            Ok(check) if check == CHECK_PLAINTEXT => {}
+            _ => return Err("Incorrect master password.".to_string()),
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            cipher,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Re-encrypts every entry under a key derived from `new_password`.
+    pub fn change_password(&self, new_password: &str) -> Result<Vault, String> {
+        let file = self.file.lock().unwrap();
+        let mut entries = Vec::with_capacity(file.entries.len());
+        for (key, sealed) in &file.entries {
+            let plaintext = open_sealed(&self.cipher, sealed, key.as_bytes())?;
+            entries.push((key.clone(), plaintext));
+        }
+        // Build the new vault beside the old one so a failure part-way
+        // through leaves the original intact.
+        let staging = self.path.with_extension("vault.new");
+        let vault = Vault::create_with(&staging, new_password, file.kdf)?;
+        {
+            let mut new_file = vault.file.lock().unwrap();
+            for (key, plaintext) in entries {
+                let sealed = seal(&vault.cipher, &plaintext, key.as_bytes())?;
+                new_file.entries.insert(key, sealed);
+            }
+            write_vault(&self.path, &new_file)?;
+        }
+        fs::remove_file(&staging).ok();
+        Ok(Vault {
+            path: self.path.clone(),
+            ..vault
+        })
+    }
+}
+
+impl SecretStore for Vault {
+    fn get(&self, key: &str) -> Result<Option<String>, String> {
+        let file = self.file.lock().unwrap();
+        let Some(sealed) = file.entries.get(key) else {
+            return Ok(None);
+        };
+        let plaintext = open_sealed(&self.cipher, sealed, key.as_bytes())?;
+        String::from_utf8(plaintext)
+            .map(Some)
+            .map_err(|_| format!("Secret '{}' is not valid UTF-8", key))
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), String> {
+        let mut file = self.file.lock().unwrap();
+        let sealed = seal(&self.cipher, value.as_bytes(), key.as_bytes())?;
+        file.entries.insert(key.to_string(), sealed);
+        write_vault(&self.path, &file)
+    }
+
+    fn delete(&self, key: &str) -> Result<(), String> {
+        let mut file = self.file.lock().unwrap();
+        if file.entries.remove(key).is_some() {
+            write_vault(&self.path, &file)?;
+        }
+        Ok(())
+    }
+}
+
+fn derive_cipher(master_password: &str, salt: &[u8], kdf: KdfParams) -> Result<Aes256Gcm, String> {
+    let params = Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, Some(32))
+        .map_err(|e| format!("Invalid key derivation parameters: {}", e))?;
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(master_password.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Failed to derive vault key: {}", e))?;
+    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| e.to_string());
+    key.fill(0);
+    cipher
+}
+
+fn seal(cipher: &Aes256Gcm, plaintext: &[u8], aad: &[u8]) -> Result<Sealed, String> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| "Failed to encrypt secret".to_string())?;
+    Ok(Sealed {
+        nonce: BASE64.encode(nonce),
+        ciphertext: BASE64.encode(ciphertext),
+    })
+}
+
+fn open_sealed(cipher: &Aes256Gcm, sealed: &Sealed, aad: &[u8]) -> Result<Vec<u8>, String> {
+    let nonce = BASE64
+        .decode(&sealed.nonce)
+        .map_err(|e| format!("Corrupt secret vault: {}", e))?;
+    if nonce.len() != 12 {
+        return Err("Corrupt secret vault: bad nonce length".to_string());
+    }
+    let ciphertext = BASE64
+        .decode(&sealed.ciphertext)
+        .map_err(|e| format!("Corrupt secret vault: {}", e))?;
+    cipher
+        .decrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| {
+            "Failed to decrypt secret: the vault is corrupt or was tampered with".to_string()
+        })
+}
+
+/// Writes through a temporary file and a rename so a crash mid-write can't
+/// leave a truncated vault behind.
+fn write_vault(path: &Path, file: &VaultFile) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(file)
+        .map_err(|e| format!("Failed to serialize secret vault: {}", e))?;
+    let tmp = path.with_extension("vault.tmp");
+    // The mode only applies when the file is created, so a leftover temp
+    // file from an interrupted write must not be reused.
+    let _ = fs::remove_file(&tmp);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(&tmp)
+        .and_then(|mut f| {
+            use std::io::Write;
+            f.write_all(content.as_bytes())?;
+            f.sync_all()
+        })
+        .map_err(|e| format!("Failed to write secret vault: {}", e))?;
+    fs::rename(&tmp, path).map_err(|e| format!("Failed to write secret vault: {}", e))
+}
+
+/// Secrets kept in the operating system's keychain (macOS Keychain, Windows
+/// Credential Manager, Secret Service on Linux).
+#[cfg(feature = "keyring")]
+pub struct KeyringStore;
+
+#[cfg(feature = "keyring")]
+const KEYRING_SERVICE: &str = "dbui";
+
+#[cfg(feature = "keyring")]
+impl SecretStore for KeyringStore {
+    fn get(&self, key: &str) -> Result<Option<String>, String> {
+        let entry = keyring::Entry::new(KEYRING_SERVICE, key).map_err(|e| e.to_string())?;
+        match entry.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(format!("Failed to read from the OS keyring: {}", e)),
+        }
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), String> {
+        keyring::Entry::new(KEYRING_SERVICE, key)
+            .and_then(|entry| entry.set_password(value))
+            .map_err(|e| format!("Failed to write to the OS keyring: {}", e))
+    }
+
+    fn delete(&self, key: &str) -> Result<(), String> {
+        let entry = keyring::Entry::new(KEYRING_SERVICE, key).map_err(|e| e.to_string())?;
+        match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(format!("Failed to delete from the OS keyring: {}", e)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SecretBackend {
+    #[default]
+    Vault,
+    Keyring,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SecretStoreStatus {
+    pub backend: SecretBackend,
+    /// A vault file exists (always true for the keyring backend).
+    pub initialized: bool,
+    pub unlocked: bool,
+    /// This build can use the OS keyring.
+    pub keyring_available: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SecretSettings {
+    #[serde(default)]
+    backend: SecretBackend,
+}
+
+/// The app-wide secret store: remembers the chosen backend and, once the
+/// vault is unlocked, the open store. Implements [`SecretStore`] itself so
+/// callers only need it unlocked when a secret is actually read or written.
+pub struct SecretManager {
+    config_dir: PathBuf,
+    backend: RwLock<SecretBackend>,
+    store: RwLock<Option<Arc<dyn SecretStore>>>,
+}
+
+impl SecretManager {
+    pub fn new(config_dir: &Path) -> Self {
+        let backend = fs::read_to_string(config_dir.join("secrets.json"))
+            .ok()
+            .and_then(|content| serde_json::from_str::<SecretSettings>(&content).ok())
+            .map(|settings| settings.backend)
+            .unwrap_or_default();
+        let manager = Self {
+            config_dir: config_dir.to_path_buf(),
+            backend: RwLock::new(backend),
+            store: RwLock::new(None),
+        };
+        // The keyring needs no unlocking.
+        if let Ok(store) = manager.keyring_store(backend) {
+            *manager.store.write().unwrap() = store;
+        }
+        manager
+    }
+
+    fn vault_path(&self) -> PathBuf {
+        self.config_dir.join("secrets.vault")
+    }
+
+    #[cfg(feature = "keyring")]
+    fn keyring_store(
+        &self,
+        backend: SecretBackend,
+    ) -> Result<Option<Arc<dyn SecretStore>>, String> {
+        Ok(match backend {
+            SecretBackend::Keyring => Some(Arc::new(KeyringStore)),
+            SecretBackend::Vault => None,
+        })
+    }
+
+    #[cfg(not(feature = "keyring"))]
+    fn keyring_store(
+        &self,
+        backend: SecretBackend,
+    ) -> Result<Option<Arc<dyn SecretStore>>, String> {
+        match backend {
+            SecretBackend::Keyring => {
+                Err("This build does not include OS keyring support.".to_string())
+            }
+            SecretBackend::Vault => Ok(None),
+        }
+    }
+
+    pub fn status(&self) -> SecretStoreStatus {
+        let backend = *self.backend.read().unwrap();
+        SecretStoreStatus {
+            backend,
+            initialized: backend == SecretBackend::Keyring || self.vault_path().exists(),
+            unlocked: self.is_unlocked(),
+            keyring_available: cfg!(feature = "keyring"),
+        }
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.store.read().unwrap().is_some()
+    }
+
+    /// Opens the vault, creating it with `master_password` on first use.
+    pub fn unlock(&self, master_password: &str) -> Result<(), String> {
+        let vault = self.open_or_create_vault(master_password)?;
+        *self.store.write().unwrap() = Some(Arc::new(vault));
+        Ok(())
+    }
+
+    fn open_or_create_vault(&self, master_password: &str) -> Result<Vault, String> {
+        let path = self.vault_path();
+        if path.exists() {
+            Vault::open(&path, master_password)
+        } else {
+            fs::create_dir_all(&self.config_dir).ok();
+            Vault::create(&path, master_password)
+        }
+    }
+
+    /// Forgets the vault key. No-op for the keyring backend.
+    pub fn lock(&self) {
+        if *self.backend.read().unwrap() == SecretBackend::Vault {
+            *self.store.write().unwrap() = None;
+        }
+    }
+
+    pub fn change_master_password(&self, current: &str, new_password: &str) -> Result<(), String> {
+        if *self.backend.read().unwrap() != SecretBackend::Vault {
+            return Err("The OS keyring has no master password.".to_string());
+        }
+        let vault = Vault::open(&self.vault_path(), current)?;
+        let vault = vault.change_password(new_password)?;
+        *self.store.write().unwrap() = Some(Arc::new(vault));
+        Ok(())
+    }
+
+    /// Switches backend, moving the secrets named by `keys` from the current
+    /// store into the new one. The current store must be unlocked, and
+    /// switching to the vault needs its master password.
+    pub fn set_backend(
+        &self,
+        backend: SecretBackend,
+        master_password: Option<&str>,
+        keys: &[String],
+    ) -> Result<(), String> {
+        if *self.backend.read().unwrap() == backend {
+            return Ok(());
+        }
+        let current = self.store.read().unwrap().clone();
+        let current = match (current, keys.is_empty()) {
+            (Some(store), _) => Some(store),
+            (None, true) => None,
+            (None, false) => return Err(LOCKED_ERROR.to_string()),
+        };
+        let next: Arc<dyn SecretStore> = match backend {
+            SecretBackend::Vault => {
+                let password =
+                    master_password.ok_or("A master password is required to use the vault.")?;
+                Arc::new(self.open_or_create_vault(password)?)
+            }
+            SecretBackend::Keyring => self
+                .keyring_store(backend)?
+                .ok_or("This build does not include OS keyring support.")?,
+        };
+        if let Some(current) = &current {
+            for key in keys {
+                if let Some(secret) = current.get(key)? {
+                    next.set(key, &secret)?;
+                }
+            }
+            for key in keys {
+                current.delete(key).ok();
+            }
+        }
+
+        let content = serde_json::to_string_pretty(&SecretSettings { backend })
+            .map_err(|e| format!("Failed to serialize secret settings: {}", e))?;
+        fs::write(self.config_dir.join("secrets.json"), content)
+            .map_err(|e| format!("Failed to write secret settings: {}", e))?;
+        *self.backend.write().unwrap() = backend;
+        *self.store.write().unwrap() = Some(next);
+        Ok(())
+    }
+
+    fn active(&self) -> Result<Arc<dyn SecretStore>, String> {
+        self.store
+            .read()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| LOCKED_ERROR.to_string())
+    }
+}
+
+impl SecretStore for SecretManager {
+    fn get(&self, key: &str) -> Result<Option<String>, String> {
+        self.active()?.get(key)
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), String> {
+        self.active()?.set(key, value)
+    }
+
+    fn delete(&self, key: &str) -> Result<(), String> {
+        self.active()?.delete(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cheap parameters; the real ones take seconds in an unoptimized build.
+    const TEST_KDF: KdfParams = KdfParams {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    fn temp_vault_path() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("dbui-secrets-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("secrets.vault")
+    }
+
+    #[test]
+    fn vault_round_trips_across_reopen() {
+        let path = temp_vault_path();
+        let vault = Vault::create_with(&path, "hunter2", TEST_KDF).unwrap();
+        vault.set("connection/a/password", "s3cret").unwrap();
+
+        let reopened = Vault::open(&path, "hunter2").unwrap();
+        assert_eq!(
+            reopened.get("connection/a/password").unwrap().as_deref(),
+            Some("s3cret")
+        );
+        assert_eq!(reopened.get("connection/b/password").unwrap(), None);
+    }
+
+    #[test]
+    fn vault_file_holds_no_plaintext() {
+        let path = temp_vault_path();
+        let vault = Vault::create_with(&path, "hunter2", TEST_KDF).unwrap();
+        vault
+            .set("connection/a/password", "very-distinctive-secret")
+            .unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("very-distinctive-secret"));
+        assert!(!content.contains("hunter2"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn vault_file_is_private_even_over_a_stale_temp_file() {
+        use std::os::unix::fs::PermissionsExt;
+        let path = temp_vault_path();
+        let tmp = path.with_extension("vault.tmp");
+        fs::write(&tmp, "").unwrap();
+        fs::set_permissions(&tmp, fs::Permissions::from_mode(0o644)).unwrap();
+
+        Vault::create_with(&path, "hunter2", TEST_KDF).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn wrong_master_password_is_rejected() {
+        let path = temp_vault_path();
+        Vault::create_with(&path, "right", TEST_KDF).unwrap();
+        let err = Vault::open(&path, "wrong").err().unwrap();
+        assert_eq!(err, "Incorrect master password.");
+    }
+
+    #[test]
+    fn swapped_ciphertexts_fail_to_decrypt() {
+        let path = temp_vault_path();
+        let vault = Vault::create_with(&path, "pw", TEST_KDF).unwrap();
+        vault.set("a", "first").unwrap();
+        vault.set("b", "second").unwrap();
+
+        let mut file: VaultFile =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let a = file.entries["a"].clone();
+        file.entries.insert("b".to_string(), a);
+        write_vault(&path, &file).unwrap();
+
+        let reopened = Vault::open(&path, "pw").unwrap();
+        assert!(reopened.get("b").is_err());
+        assert_eq!(reopened.get("a").unwrap().as_deref(), Some("first"));
+    }
+
+    #[test]
+    fn changing_the_master_password_keeps_secrets() {
+        let path = temp_vault_path();
+        let vault = Vault::create_with(&path, "old", TEST_KDF).unwrap();
+        vault.set("k", "v").unwrap();
+        vault.change_password("new").unwrap();
+
+        assert!(Vault::open(&path, "old").is_err());
+        let reopened = Vault::open(&path, "new").unwrap();
+        assert_eq!(reopened.get("k").unwrap().as_deref(), Some("v"));
+    }
+
+    #[test]
+    fn locked_manager_refuses_access() {
+        let dir = temp_vault_path().parent().unwrap().to_path_buf();
+        let manager = SecretManager::new(&dir);
+        assert!(!manager.is_unlocked());
+        assert_eq!(manager.get("k").unwrap_err(), LOCKED_ERROR);
+    }
+}
@@ -1,9 +1,15 @@
 // ABOUTME: SQL query analyzer using sqlparser-rs.
-// ABOUTME: Extracts table information from queries to determine if delete checkboxes should be shown.
+// ABOUTME: Traces result columns to their source tables and splits scripts into statements.
 
-use sqlparser::ast::{SetExpr, Statement, TableFactor};
-use sqlparser::dialect::{GenericDialect, MySqlDialect, PostgreSqlDialect, SQLiteDialect};
+use crate::db::ColumnInfo;
+use sqlparser::ast::{
+    Distinct, Expr, GroupByExpr, ObjectName, Select, SelectItem, SelectItemQualifiedWildcardKind,
+    SetExpr, Statement, TableFactor, WildcardAdditionalOptions,
+};
+use sqlparser::dialect::{Dialect, GenericDialect, MySqlDialect, PostgreSqlDialect, SQLiteDialect};
+use sqlparser::keywords::Keyword;
 use sqlparser::parser::Parser;
+use sqlparser::tokenizer::{Location, Token, TokenWithSpan, Tokenizer, Word};
 
 /// Result of analyzing a SQL query for its target table.
 #[derive(Debug, Clone, serde::Serialize)]
@@ -12,84 +18,531 @@ pub struct QueryTableInfo {
     pub table: String,
 }
 
-/// Attempt to extract a single table from a SQL query.
-/// Returns Some only if the query is a SELECT from exactly one real table
-/// (no joins, subqueries, CTEs, or UNIONs).
-pub fn extract_single_table(sql: &str, db_type: &str) -> Option<QueryTableInfo> {
-    let dialect: Box<dyn sqlparser::dialect::Dialect> = match db_type {
+pub(crate) fn dialect_for(db_type: &str) -> Box<dyn Dialect> {
+    match db_type {
         "postgres" => Box::new(PostgreSqlDialect {}),
         "mysql" => Box::new(MySqlDialect {}),
         "sqlite" => Box::new(SQLiteDialect {}),
         _ => Box::new(GenericDialect {}),
-    };
-
-    let statements = Parser::parse_sql(dialect.as_ref(), sql).ok()?;
+    }
+}
 
-    // Must be exactly one statement
+/// The SELECT of a query that is exactly one plain SELECT (no CTEs, UNIONs,
+/// or other statements).
+fn single_select(sql: &str, db_type: &str) -> Option<Box<Select>> {
+    let dialect = dialect_for(db_type);
+    let mut statements = Parser::parse_sql(dialect.as_ref(), sql).ok()?;
     if statements.len() != 1 {
         return None;
     }
+    let Statement::Query(query) = statements.pop()? else {
+        return None;
+    };
+    if query.with.is_some() {
+        return None;
+    }
+    match *query.body {
+        SetExpr::Select(select) => Some(select),
+        _ => None,
+    }
+}
 
-    let stmt = &statements[0];
-    match stmt {
-        Statement::Query(query) => {
-            // No CTEs allowed
-            if !query.with.is_none() {
-                return None;
+/// A table in a FROM clause, with the alias its columns are qualified by.
+#[derive(Debug, Clone)]
+struct TableRef {
+    info: QueryTableInfo,
+    alias: Option<String>,
+}
+
+impl TableRef {
+    /// Whether the qualifier of `q.column` names this table: its alias if it
+    /// has one, otherwise its (optionally schema-qualified) name.
+    fn matches(&self, qualifier: &[String]) -> bool {
+        let eq = |a: &str, b: &str| a.eq_ignore_ascii_case(b);
+        match (qualifier, &self.alias) {
+            ([name], Some(alias)) => eq(name, alias),
+            (_, Some(_)) => false,
+            ([name], None) => eq(name, &self.info.table),
+            ([.., schema, name], None) => {
+                eq(name, &self.info.table)
+                    && self.info.schema.as_deref().is_some_and(|s| eq(schema, s))
             }
+            _ => false,
+        }
+    }
+}
 
-            let select = match query.body.as_ref() {
-                SetExpr::Select(s) => s,
-                _ => return None, // UNION, INTERSECT, etc.
-            };
+fn ident_parts(name: &ObjectName) -> Option<Vec<String>> {
+    name.0
+        .iter()
+        .map(|part| part.as_ident().map(|id| id.value.clone()))
+        .collect()
+}
+
+/// A plain table reference. Table functions and aliases that rename columns
+/// (`AS t(a, b)`) don't count, since their columns aren't the table's.
+fn table_ref(relation: &TableFactor) -> Option<TableRef> {
+    let TableFactor::Table {
+        name,
+        alias,
+        args: None,
+        ..
+    } = relation
+    else {
+        return None;
+    };
+    if alias.as_ref().is_some_and(|a| !a.columns.is_empty()) {
+        return None;
+    }
+    let info = match ident_parts(name)?.as_slice() {
+        [table] => QueryTableInfo {
+            schema: None,
+            table: table.clone(),
+        },
+        // database.schema.table: use schema.table
+        [schema, table] | [_, schema, table] => QueryTableInfo {
+            schema: Some(schema.clone()),
+            table: table.clone(),
+        },
+        _ => return None,
+    };
+    Some(TableRef {
+        info,
+        alias: alias.as_ref().map(|a| a.name.value.clone()),
+    })
+}
 
-            // Exactly one FROM table, no joins
-            if select.from.len() != 1 {
-                return None;
+/// The tables a SELECT reads, in FROM order, and whether it also reads
+/// derived tables or functions whose columns cannot be traced.
+fn from_tables(select: &Select) -> (Vec<TableRef>, bool) {
+    let mut tables = Vec::new();
+    let mut opaque = false;
+    for from in &select.from {
+        let relations =
+            std::iter::once(&from.relation).chain(from.joins.iter().map(|j| &j.relation));
+        for relation in relations {
+            match table_ref(relation) {
+                Some(table) => tables.push(table),
+                None => opaque = true,
             }
+        }
+    }
+    (tables, opaque)
+}
+
+/// Attempt to extract a single table from a SQL query.
+/// Returns Some only if the query is a SELECT from exactly one real table
+/// (no joins, subqueries, CTEs, or UNIONs).
+pub fn extract_single_table(sql: &str, db_type: &str) -> Option<QueryTableInfo> {
+    let select = single_select(sql, db_type)?;
+    if select.from.len() != 1 || !select.from[0].joins.is_empty() {
+        return None;
+    }
+    match from_tables(&select) {
+        (mut tables, false) if tables.len() == 1 => tables.pop().map(|t| t.info),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum OutputItem {
+    /// `*` over every table, or `t.*` over the table at that index.
+    Wildcard(Option<usize>),
+    /// A column reference. An unqualified one has no table until column
+    /// metadata shows which table has it.
+    Column {
+        name: String,
+        table: Option<usize>,
+        column: String,
+    },
+    Computed {
+        name: String,
+    },
+}
+
+/// What a single SELECT reads and projects, before live column metadata is
+/// known. See [`SelectAnalysis::resolve`].
+#[derive(Debug, Clone)]
+pub struct SelectAnalysis {
+    /// Every real table in the FROM clause, joins included.
+    pub tables: Vec<QueryTableInfo>,
+    items: Vec<OutputItem>,
+    opaque_sources: bool,
+    /// DISTINCT, GROUP BY or HAVING: result rows are not table rows.
+    aggregated: bool,
+}
 
-            let from = &select.from[0];
+/// Where one result column comes from. `table` indexes [`SelectAnalysis::tables`].
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ColumnSource {
+    pub table: usize,
+    pub column: String,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ColumnProvenance {
+    pub name: String,
+    /// None for expressions, aggregates, columns of derived tables and
+    /// unqualified names more than one table has.
+    pub source: Option<ColumnSource>,
+    /// The column reads a table with a whole key also in the result, so an
+    /// edit can be written back to exactly one row.
+    pub editable: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedColumns {
+    /// One entry per result column, in result order.
+    pub columns: Vec<ColumnProvenance>,
+    /// Per table, the result positions of the first of its keys the result
+    /// holds whole. Empty when the query leaves part of every key out.
+    pub keys: Vec<Vec<usize>>,
+}
+
+/// What [`SelectAnalysis::resolve`] needs to know about a table it reads.
+#[derive(Debug, Clone, Default)]
+pub struct SourceTable {
+    /// Live columns, in table order.
+    pub columns: Vec<ColumnInfo>,
+    /// Column sets that identify a row, preferred first, as rows are
+    /// matched on when saved.
+    pub keys: Vec<Vec<String>>,
+}
+
+/// Analyzes a single SELECT, which may join several tables and mix column
+/// references with expressions. Returns None for anything else, and when a
+/// `*` covers a derived table (its width is unknown).
+pub fn analyze_select(sql: &str, db_type: &str) -> Option<SelectAnalysis> {
+    let select = single_select(sql, db_type)?;
+    let (tables, opaque_sources) = from_tables(&select);
+    let grouped = match &select.group_by {
+        GroupByExpr::All(_) => true,
+        GroupByExpr::Expressions(exprs, _) => !exprs.is_empty(),
+    };
+    let aggregated = grouped
+        || select.having.is_some()
+        || matches!(select.distinct, Some(Distinct::Distinct | Distinct::On(_)));
+    let items = select
+        .projection
+        .iter()
+        .map(|item| output_item(item, &tables, opaque_sources))
+        .collect::<Option<Vec<_>>>()?;
+    Some(SelectAnalysis {
+        tables: tables.into_iter().map(|t| t.info).collect(),
+        items,
+        opaque_sources,
+        aggregated,
+    })
+}
+
+fn output_item(item: &SelectItem, tables: &[TableRef], opaque: bool) -> Option<OutputItem> {
+    // EXCLUDE, EXCEPT, REPLACE and friends change which columns `*` yields.
+    let plain = |o: &WildcardAdditionalOptions| {
+        o.opt_ilike.is_none()
+            && o.opt_exclude.is_none()
+            && o.opt_except.is_none()
+            && o.opt_replace.is_none()
+            && o.opt_rename.is_none()
+    };
+    match item {
+        SelectItem::Wildcard(options) if plain(options) && !opaque => {
+            Some(OutputItem::Wildcard(None))
+        }
+        SelectItem::QualifiedWildcard(
+            SelectItemQualifiedWildcardKind::ObjectName(name),
+            options,
+        ) if plain(options) => {
+            let qualifier = ident_parts(name)?;
+            let table = tables.iter().position(|t| t.matches(&qualifier))?;
+            Some(OutputItem::Wildcard(Some(table)))
+        }
+        SelectItem::Wildcard(_) | SelectItem::QualifiedWildcard(..) => None,
+        SelectItem::UnnamedExpr(expr) => Some(column_item(expr, None, tables)),
+        SelectItem::ExprWithAlias { expr, alias } => {
+            Some(column_item(expr, Some(&alias.value), tables))
+        }
+    }
+}
 
-            // No joins
-            if !from.joins.is_empty() {
-                return None;
+fn column_item(expr: &Expr, alias: Option<&String>, tables: &[TableRef]) -> OutputItem {
+    let (qualifier, column) = match expr {
+        Expr::Identifier(ident) => (&[][..], ident),
+        Expr::CompoundIdentifier(parts) if parts.len() > 1 => {
+            let (column, qualifier) = parts.split_last().expect("compound identifier has parts");
+            (qualifier, column)
+        }
+        Expr::Nested(inner) => return column_item(inner, alias, tables),
+        _ => {
+            return OutputItem::Computed {
+                name: alias.cloned().unwrap_or_else(|| expr.to_string()),
             }
+        }
+    };
+    let name = alias.cloned().unwrap_or_else(|| column.value.clone());
+    let table = if qualifier.is_empty() {
+        None
+    } else {
+        let qualifier: Vec<String> = qualifier.iter().map(|i| i.value.clone()).collect();
+        match tables.iter().position(|t| t.matches(&qualifier)) {
+            Some(index) => Some(index),
+            // Qualified by a derived table or subquery alias.
+            None => return OutputItem::Computed { name },
+        }
+    };
+    OutputItem::Column {
+        name,
+        table,
+        column: column.value.clone(),
+    }
+}
 
-            // Must be a plain table reference
-            match &from.relation {
-                TableFactor::Table { name, alias: _, .. } => {
-                    let parts: Vec<String> = name
-                        .0
-                        .iter()
-                        .filter_map(|part| part.as_ident().map(|id| id.value.clone()))
-                        .collect();
-                    if parts.len() != name.0.len() {
-                        return None; // Contains non-identifier parts
-                    }
+/// Unquoted identifiers may differ in case from the catalog's spelling.
+fn find_column<'a>(columns: &'a [ColumnInfo], name: &str) -> Option<&'a ColumnInfo> {
+    columns
+        .iter()
+        .find(|c| c.name == name)
+        .or_else(|| columns.iter().find(|c| c.name.eq_ignore_ascii_case(name)))
+}
 
-                    match parts.len() {
-                        1 => Some(QueryTableInfo {
-                            schema: None,
-                            table: parts[0].clone(),
-                        }),
-                        2 => Some(QueryTableInfo {
-                            schema: Some(parts[0].clone()),
-                            table: parts[1].clone(),
-                        }),
-                        3 => {
-                            // database.schema.table — use schema.table
-                            Some(QueryTableInfo {
-                                schema: Some(parts[1].clone()),
-                                table: parts[2].clone(),
-                            })
+impl SelectAnalysis {
+    /// Maps each result column to the table column it reads. `sources` holds
+    /// what is known of each entry in `tables`, in the same order. Returns
+    /// None when a `*` covers a table with no known columns, since result
+    /// positions would no longer line up.
+    pub fn resolve(&self, sources: &[SourceTable]) -> Option<ResolvedColumns> {
+        let columns_of = |table: usize| sources.get(table).map_or(&[][..], |t| &t.columns);
+        let mut columns = Vec::new();
+        for item in &self.items {
+            let (name, source) = match item {
+                OutputItem::Wildcard(table) => {
+                    let covered = match table {
+                        Some(index) => *index..*index + 1,
+                        None => 0..self.tables.len(),
+                    };
+                    for table in covered {
+                        if columns_of(table).is_empty() {
+                            return None;
                         }
-                        _ => None,
+                        columns.extend(columns_of(table).iter().map(|c| ColumnProvenance {
+                            name: c.name.clone(),
+                            source: Some(ColumnSource {
+                                table,
+                                column: c.name.clone(),
+                            }),
+                            editable: false,
+                        }));
+                    }
+                    continue;
+                }
+                OutputItem::Column {
+                    name,
+                    table: Some(table),
+                    column,
+                } => (
+                    name,
+                    find_column(columns_of(*table), column).map(|c| (*table, c)),
+                ),
+                OutputItem::Column { name, column, .. } if !self.opaque_sources => {
+                    let mut hits = (0..self.tables.len()).filter_map(|table| {
+                        find_column(columns_of(table), column).map(|c| (table, c))
+                    });
+                    match (hits.next(), hits.next()) {
+                        (Some(hit), None) => (name, Some(hit)),
+                        _ => (name, None),
+                    }
+                }
+                OutputItem::Column { name, .. } | OutputItem::Computed { name } => (name, None),
+            };
+            columns.push(ColumnProvenance {
+                name: name.clone(),
+                source: source.map(|(table, c)| ColumnSource {
+                    table,
+                    column: c.name.clone(),
+                }),
+                editable: false,
+            });
+        }
+
+        let keys: Vec<Vec<usize>> = (0..self.tables.len())
+            .map(|table| {
+                if self.aggregated {
+                    return vec![];
+                }
+                let position = |name: &String| {
+                    columns.iter().position(|c| {
+                        c.source
+                            .as_ref()
+                            .is_some_and(|s| s.table == table && &s.column == name)
+                    })
+                };
+                sources
+                    .get(table)
+                    .into_iter()
+                    .flat_map(|t| &t.keys)
+                    .find_map(|key| key.iter().map(position).collect::<Option<Vec<_>>>())
+                    .unwrap_or_default()
+            })
+            .collect();
+        for column in &mut columns {
+            column.editable = column
+                .source
+                .as_ref()
+                .is_some_and(|s| !keys[s.table].is_empty());
+        }
+        Some(ResolvedColumns { columns, keys })
+    }
+}
+
+/// Splits a script into statements on top-level semicolons, keeping each
+/// statement's original text. The dialect's tokenizer handles quoting, comments
+/// and `$$` bodies; `BEGIN ... END` blocks inside `CREATE` statements (trigger
+/// and routine bodies) are kept whole. Only unquoted keywords outside
+/// parentheses count, so a column named `begin` opens no block. Comment-only
+/// fragments are dropped.
+/// If the script cannot be tokenized (e.g. an unterminated string) it is
+/// returned as one statement so the server reports the error.
+pub fn split_statements(sql: &str, db_type: &str) -> Vec<String> {
+    let dialect = dialect_for(db_type);
+    let tokens = match Tokenizer::new(dialect.as_ref(), sql).tokenize_with_location() {
+        Ok(tokens) => tokens,
+        Err(_) => return vec![sql.trim().to_string()],
+    };
+
+    let mut offsets = OffsetCursor::new(sql);
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut has_content = false;
+    let mut is_create = false;
+    let mut paren_depth = 0usize;
+    let mut block_depth = 0usize;
+    // The CASE of an `END CASE`, which closes a block rather than opening one.
+    let mut end_case = None;
+
+    for (i, tok) in tokens.iter().enumerate() {
+        match &tok.token {
+            Token::Whitespace(_) => continue,
+            Token::SemiColon if paren_depth == 0 && block_depth == 0 => {
+                let end = offsets.byte_offset(tok.span.start);
+                if has_content {
+                    statements.push(sql[start..end].trim().to_string());
+                }
+                start = end + 1;
+                has_content = false;
+                is_create = false;
+                continue;
+            }
+            Token::LParen => paren_depth += 1,
+            Token::RParen => paren_depth = paren_depth.saturating_sub(1),
+            Token::Word(word) => {
+                if !has_content {
+                    is_create = word.keyword == Keyword::CREATE;
+                } else if is_create
+                    && paren_depth == 0
+                    && word.quote_style.is_none()
+                    && end_case != Some(i)
+                {
+                    match word.keyword {
+                        Keyword::BEGIN | Keyword::CASE => block_depth += 1,
+                        Keyword::END => match next_word(&tokens, i + 1) {
+                            // END IF / END LOOP / ... close blocks we never counted.
+                            Some((_, next)) if closes_uncounted_block(next) => {}
+                            Some((at, next)) if next.keyword == Keyword::CASE => {
+                                end_case = Some(at);
+                                block_depth = block_depth.saturating_sub(1);
+                            }
+                            _ => block_depth = block_depth.saturating_sub(1),
+                        },
+                        _ => {}
                     }
                 }
-                _ => None, // Subquery, derived table, etc.
             }
+            _ => {}
         }
-        _ => None, // INSERT, UPDATE, DELETE, etc.
+        has_content = true;
+    }
+
+    if has_content {
+        statements.push(sql[start..].trim().to_string());
+    }
+    statements
+}
+
+/// Whether `sql` is a statement that can change the objects a schema lists
+/// (CREATE, ALTER, DROP, RENAME or COMMENT), judged by its leading keyword so
+/// engine-specific DDL the parser doesn't model still counts. Schema changes
+/// made inside functions or `DO` blocks are not detected.
+pub fn changes_schema(sql: &str, db_type: &str) -> bool {
+    let dialect = dialect_for(db_type);
+    let Ok(tokens) = Tokenizer::new(dialect.as_ref(), sql).tokenize() else {
+        return false;
+    };
+    let first_word = tokens.iter().find_map(|t| match t {
+        Token::Whitespace(_) | Token::LParen => None,
+        Token::Word(word) => Some(Some(word.keyword)),
+        _ => Some(None),
+    });
+    matches!(
+        first_word,
+        Some(Some(
+            Keyword::CREATE | Keyword::ALTER | Keyword::DROP | Keyword::RENAME | Keyword::COMMENT
+        ))
+    )
+}
+
+/// The first word at or after `from`, with its index, if nothing but
+/// whitespace comes before it.
+fn next_word(tokens: &[TokenWithSpan], from: usize) -> Option<(usize, &Word)> {
+    let (at, next) = tokens
+        .iter()
+        .enumerate()
+        .skip(from)
+        .find(|(_, t)| !matches!(t.token, Token::Whitespace(_)))?;
+    match &next.token {
+        Token::Word(word) => Some((at, word)),
+        _ => None,
+    }
+}
+
+fn closes_uncounted_block(next: &Word) -> bool {
+    ["IF", "LOOP", "WHILE", "REPEAT"]
+        .iter()
+        .any(|kw| next.value.eq_ignore_ascii_case(kw))
+}
+
+/// Converts the tokenizer's 1-based line/column (counted in chars) into byte
+/// offsets. Locations must be requested in increasing order, which keeps a
+/// whole script at one pass over the text.
+pub(crate) struct OffsetCursor<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+    line: u64,
+    column: u64,
+    len: usize,
+}
+
+impl<'a> OffsetCursor<'a> {
+    pub(crate) fn new(sql: &'a str) -> Self {
+        Self {
+            chars: sql.char_indices().peekable(),
+            line: 1,
+            column: 1,
+            len: sql.len(),
+        }
+    }
+
+    pub(crate) fn byte_offset(&mut self, target: Location) -> usize {
+        while let Some(&(index, c)) = self.chars.peek() {
+            if (self.line, self.column) >= (target.line, target.column) {
+                return index;
+            }
+            if c == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+            self.chars.next();
+        }
+        self.len
     }
 }
 
@@ -188,4 +641,238 @@ mod tests {
         );
         assert!(result.is_none());
     }
+
+    fn table(columns: &[&str], key: &[&str]) -> SourceTable {
+        SourceTable {
+            columns: columns
+                .iter()
+                .map(|name| ColumnInfo {
+                    name: name.to_string(),
+                    data_type: "text".to_string(),
+                    is_nullable: true,
+                    column_default: None,
+                    is_primary_key: key.contains(name),
+                })
+                .collect(),
+            keys: match key {
+                [] => vec![],
+                key => vec![key.iter().map(|k| k.to_string()).collect()],
+            },
+        }
+    }
+
+    fn sources(resolved: &ResolvedColumns) -> Vec<Option<(usize, &str)>> {
+        resolved
+            .columns
+            .iter()
+            .map(|c| c.source.as_ref().map(|s| (s.table, s.column.as_str())))
+            .collect()
+    }
+
+    #[test]
+    fn join_columns_trace_through_aliases() {
+        let analysis = analyze_select(
+            "SELECT u.id, u.name AS user_name, o.id AS order_id, o.total * 2 AS doubled, note \
+             FROM users u JOIN orders o ON o.user_id = u.id",
+            "postgres",
+        )
+        .unwrap();
+        assert_eq!(analysis.tables.len(), 2);
+        let resolved = analysis
+            .resolve(&[
+                table(&["id", "name"], &["id"]),
+                table(&["id", "user_id", "total", "note"], &["id"]),
+            ])
+            .unwrap();
+        assert_eq!(
+            sources(&resolved),
+            vec![
+                Some((0, "id")),
+                Some((0, "name")),
+                Some((1, "id")),
+                None,
+                Some((1, "note"))
+            ]
+        );
+        let names: Vec<&str> = resolved.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["id", "user_name", "order_id", "doubled", "note"]
+        );
+        let editable: Vec<bool> = resolved.columns.iter().map(|c| c.editable).collect();
+        assert_eq!(editable, vec![true, true, true, false, true]);
+        assert_eq!(resolved.keys, vec![vec![0], vec![2]]);
+    }
+
+    #[test]
+    fn wildcard_expands_from_metadata() {
+        let analysis = analyze_select("SELECT *, upper(name) FROM users", "postgres").unwrap();
+        let resolved = analysis
+            .resolve(&[table(&["id", "name"], &["id"])])
+            .unwrap();
+        assert_eq!(
+            sources(&resolved),
+            vec![Some((0, "id")), Some((0, "name")), None]
+        );
+        assert!(resolved.columns[1].editable);
+        assert!(!resolved.columns[2].editable);
+        assert!(analysis.resolve(&[SourceTable::default()]).is_none());
+    }
+
+    #[test]
+    fn missing_key_or_grouping_makes_columns_read_only() {
+        let users = [table(&["id", "name"], &["id"])];
+        let without_key = analyze_select("SELECT name FROM users", "postgres")
+            .unwrap()
+            .resolve(&users)
+            .unwrap();
+        assert_eq!(sources(&without_key), vec![Some((0, "name"))]);
+        assert!(!without_key.columns[0].editable);
+
+        let grouped = analyze_select("SELECT id, count(*) FROM users GROUP BY id", "postgres")
+            .unwrap()
+            .resolve(&users)
+            .unwrap();
+        assert!(grouped.columns.iter().all(|c| !c.editable));
+    }
+
+    #[test]
+    fn any_whole_key_in_the_result_makes_columns_editable() {
+        let mut users = table(&["id", "email", "name"], &["id"]);
+        users.keys.push(vec!["email".to_string()]);
+        let resolved = analyze_select("SELECT email, name FROM users", "postgres")
+            .unwrap()
+            .resolve(&[users])
+            .unwrap();
+        assert_eq!(resolved.keys, vec![vec![0]]);
+        assert!(resolved.columns.iter().all(|c| c.editable));
+    }
+
+    #[test]
+    fn ambiguous_and_derived_columns_have_no_source() {
+        let resolved = analyze_select(
+            "SELECT id, u.name FROM users u JOIN orders o ON o.user_id = u.id",
+            "postgres",
+        )
+        .unwrap()
+        .resolve(&[
+            table(&["id", "name"], &["id"]),
+            table(&["id", "user_id"], &["id"]),
+        ])
+        .unwrap();
+        assert_eq!(sources(&resolved), vec![None, Some((0, "name"))]);
+
+        let derived = analyze_select(
+            "SELECT s.total FROM (SELECT sum(total) AS total FROM orders) s",
+            "postgres",
+        )
+        .unwrap();
+        assert!(derived.tables.is_empty());
+        assert_eq!(sources(&derived.resolve(&[]).unwrap()), vec![None]);
+        assert!(analyze_select("SELECT * FROM (SELECT 1) s", "postgres").is_none());
+    }
+
+    #[test]
+    fn split_simple_script() {
+        let statements = split_statements(
+            "CREATE TABLE t (id int);\nINSERT INTO t VALUES (1);\nSELECT * FROM t;",
+            "postgres",
+        );
+        assert_eq!(
+            statements,
+            vec![
+                "CREATE TABLE t (id int)",
+                "INSERT INTO t VALUES (1)",
+                "SELECT * FROM t",
+            ]
+        );
+    }
+
+    #[test]
+    fn split_ignores_semicolons_in_strings_and_comments() {
+        let statements = split_statements(
+            "SELECT 'a;b', \"c;d\"; -- trailing; comment\nSELECT 2 /* x; y */",
+            "postgres",
+        );
+        assert_eq!(statements.len(), 2);
+        assert_eq!(statements[0], "SELECT 'a;b', \"c;d\"");
+        assert_eq!(statements[1], "-- trailing; comment\nSELECT 2 /* x; y */");
+    }
+
+    #[test]
+    fn split_keeps_dollar_quoted_bodies() {
+        let sql = "CREATE FUNCTION f() RETURNS int AS $$ BEGIN RETURN 1; END; $$ LANGUAGE plpgsql;\nSELECT f();";
+        let statements = split_statements(sql, "postgres");
+        assert_eq!(statements.len(), 2);
+        assert!(statements[0].ends_with("LANGUAGE plpgsql"));
+        assert_eq!(statements[1], "SELECT f()");
+    }
+
+    #[test]
+    fn split_keeps_trigger_bodies() {
+        let sql = "CREATE TRIGGER trg AFTER INSERT ON t BEGIN\n  UPDATE c SET n = n + 1;\n  DELETE FROM q;\nEND;\nSELECT 1;";
+        let statements = split_statements(sql, "sqlite");
+        assert_eq!(statements.len(), 2);
+        assert!(statements[0].ends_with("END"));
+    }
+
+    #[test]
+    fn split_keeps_mysql_procedure_with_nested_blocks() {
+        let sql = "CREATE PROCEDURE p() BEGIN IF 1 THEN SELECT 1; END IF; SELECT 2; END; CALL p();";
+        let statements = split_statements(sql, "mysql");
+        assert_eq!(statements.len(), 2);
+        assert_eq!(statements[1], "CALL p()");
+    }
+
+    #[test]
+    fn split_ignores_block_keywords_used_as_column_names() {
+        let sql =
+            "CREATE TABLE events (id int, begin timestamp, \"end\" timestamp); SELECT 1; SELECT 2;";
+        for db_type in ["postgres", "sqlite"] {
+            let statements = split_statements(sql, db_type);
+            assert_eq!(statements.len(), 3, "{}", db_type);
+            assert_eq!(statements[2], "SELECT 2");
+        }
+    }
+
+    #[test]
+    fn split_closes_mysql_case_statements_with_end_case() {
+        let sql = "CREATE PROCEDURE p(x INT) BEGIN CASE x WHEN 1 THEN SELECT 1; ELSE SELECT 2; END CASE; END; SELECT 3; SELECT 4;";
+        let statements = split_statements(sql, "mysql");
+        assert_eq!(statements.len(), 3);
+        assert!(statements[0].ends_with("END CASE; END"));
+        assert_eq!(statements[1], "SELECT 3");
+    }
+
+    #[test]
+    fn split_drops_empty_and_comment_only_fragments() {
+        let statements = split_statements(";;  -- nothing here\n; SELT 1 ;", "postgres");
+        assert_eq!(statements, vec!["SELT 1"]);
+    }
+
+    #[test]
+    fn split_handles_multibyte_text() {
+        let statements = split_statements("SELECT 'héllo'; SELECT 'wörld'", "postgres");
+        assert_eq!(statements, vec!["SELECT 'héllo'", "SELECT 'wörld'"]);
+    }
+
+    #[test]
+    fn split_untokenizable_script_is_one_statement() {
+        let statements = split_statements("SELECT 'unterminated; SELECT 2", "postgres");
+        assert_eq!(statements, vec!["SELECT 'unterminated; SELECT 2"]);
+    }
+
+    #[test]
+    fn schema_changes_are_detected_by_leading_keyword() {
+        assert!(changes_schema(
+            "-- add it\nCREATE TABLE t (id int)",
+            "postgres"
+        ));
+        assert!(changes_schema("alter table t add column c int", "mysql"));
+        assert!(changes_schema("DROP VIEW IF EXISTS v", "sqlite"));
+        assert!(changes_schema("RENAME TABLE a TO b", "mysql"));
+        assert!(!changes_schema("SELECT * FROM create_log", "postgres"));
+        assert!(!changes_schema("INSERT INTO t VALUES (1)", "postgres"));
+        assert!(!changes_schema("'CREATE", "postgres"));
+    }
 }
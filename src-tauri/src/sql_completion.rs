@@ -0,0 +1,566 @@
+// ABOUTME: Schema-aware SQL completion for the query editor.
+// ABOUTME: Works out the clause at the cursor from tokens, then ranks catalog names against it.
+
+use crate::db::ColumnInfo;
+use crate::sql_analyzer::{dialect_for, OffsetCursor};
+use serde::Serialize;
+use sqlparser::keywords::Keyword;
+use sqlparser::tokenizer::{Token, Tokenizer, Whitespace, Word};
+
+const MAX_SUGGESTIONS: usize = 100;
+
+const STATEMENT_KEYWORDS: &[&str] = &[
+    "SELECT",
+    "INSERT INTO",
+    "UPDATE",
+    "DELETE FROM",
+    "WITH",
+    "CREATE",
+    "ALTER",
+    "DROP",
+    "EXPLAIN",
+];
+
+const AFTER_TABLE_KEYWORDS: &[&str] = &[
+    "WHERE",
+    "JOIN",
+    "LEFT JOIN",
+    "INNER JOIN",
+    "ON",
+    "AS",
+    "GROUP BY",
+    "ORDER BY",
+    "LIMIT",
+    "SET",
+    "VALUES",
+];
+
+const EXPRESSION_KEYWORDS: &[&str] = &[
+    "AND", "OR", "NOT", "NULL", "IS", "IN", "LIKE", "BETWEEN", "EXISTS", "CASE", "WHEN", "THEN",
+    "ELSE", "END", "AS", "DISTINCT", "FROM", "WHERE", "GROUP BY", "ORDER BY", "HAVING", "LIMIT",
+    "ASC", "DESC", "TRUE", "FALSE",
+];
+
+const COMMON_FUNCTIONS: &[&str] = &[
+    "COUNT", "SUM", "AVG", "MIN", "MAX", "COALESCE", "NULLIF", "CAST", "LOWER", "UPPER", "LENGTH",
+    "SUBSTR", "TRIM", "REPLACE", "ROUND", "ABS",
+];
+
+fn engine_functions(db_type: &str) -> &'static [&'static str] {
+    match db_type {
+        "postgres" => &[
+            "NOW",
+            "DATE_TRUNC",
+            "STRING_AGG",
+            "ARRAY_AGG",
+            "JSONB_BUILD_OBJECT",
+            "TO_CHAR",
+            "GENERATE_SERIES",
+            "CONCAT",
+        ],
+        "mysql" => &[
+            "NOW",
+            "IFNULL",
+            "GROUP_CONCAT",
+            "DATE_FORMAT",
+            "JSON_EXTRACT",
+            "CONCAT",
+        ],
+        "sqlite" => &[
+            "IFNULL",
+            "DATETIME",
+            "STRFTIME",
+            "GROUP_CONCAT",
+            "JSON_EXTRACT",
+        ],
+        _ => &[],
+    }
+}
+
+/// A table the statement refers to, under the name its columns are
+/// qualified by.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScopeTable {
+    pub schema: Option<String>,
+    pub table: String,
+    pub alias: Option<String>,
+}
+
+impl ScopeTable {
+    pub fn label(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.table)
+    }
+
+    pub fn matches(&self, qualifier: &str) -> bool {
+        self.label().eq_ignore_ascii_case(qualifier) || self.table.eq_ignore_ascii_case(qualifier)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Clause {
+    /// Nothing typed yet in this statement.
+    Statement,
+    /// Where a table name goes: after FROM, JOIN, UPDATE or INTO.
+    Table,
+    /// After a table reference, where WHERE, JOIN or an alias can follow.
+    AfterTable,
+    /// An expression: select list, WHERE, ON, SET, ORDER BY and the like.
+    Expression,
+    /// After `qualifier.`, which names a table in scope or a schema.
+    Qualified(String),
+    /// Inside a string, comment or alias, where nothing is suggested.
+    None,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompletionContext {
+    pub clause: Clause,
+    /// The partial word before the cursor, and the byte offset it starts at.
+    pub prefix: String,
+    pub prefix_start: usize,
+    /// Tables of the statement around the cursor, including ones after it.
+    pub scope: Vec<ScopeTable>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SuggestionKind {
+    Column,
+    Table,
+    View,
+    Function,
+    Keyword,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Suggestion {
+    pub label: String,
+    pub kind: SuggestionKind,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Completion {
+    /// Where the word being completed starts, in UTF-16 units like the
+    /// editor's cursor offset.
+    pub from: usize,
+    pub suggestions: Vec<Suggestion>,
+}
+
+/// Names the suggestions are drawn from. Only what the clause needs is filled in.
+#[derive(Debug, Default)]
+pub struct CompletionCatalog {
+    pub tables: Vec<String>,
+    pub views: Vec<String>,
+    pub functions: Vec<String>,
+    /// Columns of tables in scope, under the name each table is qualified by.
+    pub columns: Vec<(String, Vec<ColumnInfo>)>,
+}
+
+/// Converts the editor's UTF-16 offset into a byte offset into `text`.
+pub fn utf16_to_byte(text: &str, offset: usize) -> usize {
+    let mut units = 0;
+    for (index, c) in text.char_indices() {
+        if units >= offset {
+            return index;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+pub fn byte_to_utf16(text: &str, offset: usize) -> usize {
+    text[..offset].chars().map(char::len_utf16).sum()
+}
+
+fn is_keyword(token: Option<&Token>, keyword: Keyword) -> bool {
+    matches!(token, Some(Token::Word(w)) if w.quote_style.is_none() && w.keyword == keyword)
+}
+
+fn significant(tokens: Vec<Token>) -> Vec<Token> {
+    tokens
+        .into_iter()
+        .filter(|t| !matches!(t, Token::Whitespace(_)))
+        .collect()
+}
+
+/// Works out what belongs at byte offset `cursor` in `text`.
+pub fn analyze(text: &str, cursor: usize, db_type: &str) -> CompletionContext {
+    let mut cursor = cursor.min(text.len());
+    while !text.is_char_boundary(cursor) {
+        cursor -= 1;
+    }
+    let dialect = dialect_for(db_type);
+    let mut context = CompletionContext {
+        clause: Clause::None,
+        prefix: String::new(),
+        prefix_start: cursor,
+        scope: vec![],
+    };
+
+    // Text before the cursor that doesn't tokenize ends inside a string,
+    // quoted identifier or block comment.
+    let Ok(mut before) = Tokenizer::new(dialect.as_ref(), &text[..cursor]).tokenize() else {
+        return context;
+    };
+    if let Some(Token::Whitespace(Whitespace::SingleLineComment { comment, .. })) = before.last() {
+        if !comment.ends_with('\n') {
+            return context;
+        }
+    }
+    if let Some(last_semicolon) = before.iter().rposition(|t| *t == Token::SemiColon) {
+        before.drain(..=last_semicolon);
+    }
+    if let Some(Token::Word(word)) = before.last() {
+        if word.quote_style.is_none() {
+            context.prefix = word.value.clone();
+            context.prefix_start = cursor - word.value.len();
+            before.pop();
+        }
+    }
+
+    context.scope = scope_tables(
+        &statement_tokens(text, cursor, db_type).unwrap_or_else(|| significant(before.clone())),
+    );
+    context.clause = clause_at(&significant(before));
+    context
+}
+
+/// Significant tokens of the whole statement the cursor is in, or None if
+/// the text doesn't tokenize (e.g. an unterminated string after the cursor).
+fn statement_tokens(text: &str, cursor: usize, db_type: &str) -> Option<Vec<Token>> {
+    let dialect = dialect_for(db_type);
+    let tokens = Tokenizer::new(dialect.as_ref(), text)
+        .tokenize_with_location()
+        .ok()?;
+    let mut offsets = OffsetCursor::new(text);
+    let mut statement = Vec::new();
+    for token in tokens {
+        let start = offsets.byte_offset(token.span.start);
+        match token.token {
+            Token::SemiColon if start < cursor => statement.clear(),
+            Token::SemiColon => break,
+            Token::Whitespace(_) => {}
+            other => statement.push(other),
+        }
+    }
+    Some(statement)
+}
+
+/// Reads `FROM a [AS] x, b y`, `JOIN c`, `UPDATE d` and `INTO e` references.
+fn scope_tables(tokens: &[Token]) -> Vec<ScopeTable> {
+    let mut scope = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let starts_list = [Keyword::FROM, Keyword::JOIN, Keyword::UPDATE, Keyword::INTO]
+            .into_iter()
+            .any(|k| is_keyword(tokens.get(i), k));
+        i += 1;
+        if !starts_list {
+            continue;
+        }
+        while let Some((parts, next)) = object_name(tokens, i) {
+            i = next;
+            if is_keyword(tokens.get(i), Keyword::AS) {
+                i += 1;
+            }
+            let alias = match tokens.get(i) {
+                Some(Token::Word(w))
+                    if w.quote_style.is_some() || w.keyword == Keyword::NoKeyword =>
+                {
+                    i += 1;
+                    Some(w.value.clone())
+                }
+                _ => None,
+            };
+            let (schema, table) = match parts.as_slice() {
+                [.., schema, table] => (Some(schema.clone()), table.clone()),
+                [table] => (None, table.clone()),
+                [] => unreachable!("object names have at least one part"),
+            };
+            scope.push(ScopeTable {
+                schema,
+                table,
+                alias,
+            });
+            if tokens.get(i) != Some(&Token::Comma) {
+                break;
+            }
+            i += 1;
+        }
+    }
+    scope
+}
+
+/// A dotted name starting at `i`, and the index after it.
+fn object_name(tokens: &[Token], mut i: usize) -> Option<(Vec<String>, usize)> {
+    let mut parts = Vec::new();
+    while let Some(Token::Word(Word { value, .. })) = tokens.get(i) {
+        parts.push(value.clone());
+        i += 1;
+        if tokens.get(i) != Some(&Token::Period) {
+            return Some((parts, i));
+        }
+        i += 1;
+    }
+    None
+}
+
+/// The clause the cursor is in, from the significant tokens before it.
+fn clause_at(tokens: &[Token]) -> Clause {
+    if let [.., Token::Word(qualifier), Token::Period] = tokens {
+        return Clause::Qualified(qualifier.value.clone());
+    }
+    if tokens.is_empty() {
+        return Clause::Statement;
+    }
+    // Walk back to the keyword that opened the current clause, skipping
+    // anything nested in parentheses.
+    let mut depth = 0usize;
+    for (i, token) in tokens.iter().enumerate().rev() {
+        let word = match token {
+            Token::RParen => {
+                depth += 1;
+                continue;
+            }
+            Token::LParen if depth == 0 => return Clause::Expression,
+            Token::LParen => {
+                depth -= 1;
+                continue;
+            }
+            Token::Word(w) if depth == 0 && w.quote_style.is_none() => w,
+            _ => continue,
+        };
+        match word.keyword {
+            Keyword::FROM | Keyword::JOIN | Keyword::UPDATE | Keyword::INTO | Keyword::TABLE => {
+                let after = &tokens[i + 1..];
+                return match after.last() {
+                    None | Some(Token::Comma) => Clause::Table,
+                    Some(_) => Clause::AfterTable,
+                };
+            }
+            Keyword::SELECT
+            | Keyword::WHERE
+            | Keyword::ON
+            | Keyword::BY
+            | Keyword::HAVING
+            | Keyword::SET
+            | Keyword::AND
+            | Keyword::OR
+            | Keyword::NOT
+            | Keyword::CASE
+            | Keyword::WHEN
+            | Keyword::THEN
+            | Keyword::ELSE
+            | Keyword::RETURNING
+            | Keyword::DISTINCT
+            | Keyword::VALUES => return Clause::Expression,
+            // An alias is being named.
+            Keyword::AS => return Clause::None,
+            Keyword::EXPLAIN | Keyword::ANALYZE | Keyword::WITH if i + 1 == tokens.len() => {
+                return Clause::Statement
+            }
+            _ => {}
+        }
+    }
+    Clause::Expression
+}
+
+/// Suggestions for `context`, best first: prefix matches before substring
+/// matches, then by kind in the order of [`SuggestionKind`], then shortest.
+pub fn suggest(
+    context: &CompletionContext,
+    catalog: &CompletionCatalog,
+    db_type: &str,
+) -> Vec<Suggestion> {
+    let names = |names: &[String], kind| {
+        names
+            .iter()
+            .map(|n| Suggestion {
+                label: n.clone(),
+                kind,
+                detail: None,
+            })
+            .collect::<Vec<_>>()
+    };
+    let keywords = |words: &[&str]| {
+        words
+            .iter()
+            .map(|w| Suggestion {
+                label: w.to_string(),
+                kind: SuggestionKind::Keyword,
+                detail: None,
+            })
+            .collect::<Vec<_>>()
+    };
+    let columns = || {
+        catalog
+            .columns
+            .iter()
+            .flat_map(|(table, columns)| {
+                columns.iter().map(move |c| Suggestion {
+                    label: c.name.clone(),
+                    kind: SuggestionKind::Column,
+                    detail: Some(format!("{} · {}", table, c.data_type)),
+                })
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let candidates = match &context.clause {
+        Clause::None => vec![],
+        Clause::Statement => keywords(STATEMENT_KEYWORDS),
+        Clause::Table => [
+            names(&catalog.tables, SuggestionKind::Table),
+            names(&catalog.views, SuggestionKind::View),
+        ]
+        .concat(),
+        Clause::AfterTable => keywords(AFTER_TABLE_KEYWORDS),
+        Clause::Expression => {
+            let builtins = COMMON_FUNCTIONS.iter().chain(engine_functions(db_type));
+            let builtins: Vec<String> = builtins.map(|f| f.to_string()).collect();
+            [
+                columns(),
+                names(&catalog.functions, SuggestionKind::Function),
+                names(&builtins, SuggestionKind::Function),
+                keywords(EXPRESSION_KEYWORDS),
+            ]
+            .concat()
+        }
+        Clause::Qualified(_) => [
+            columns(),
+            names(&catalog.tables, SuggestionKind::Table),
+            names(&catalog.views, SuggestionKind::View),
+        ]
+        .concat(),
+    };
+    rank(candidates, &context.prefix)
+}
+
+fn rank(candidates: Vec<Suggestion>, prefix: &str) -> Vec<Suggestion> {
+    let prefix = prefix.to_lowercase();
+    let mut scored: Vec<(u8, Suggestion)> = candidates
+        .into_iter()
+        .filter_map(|s| {
+            let label = s.label.to_lowercase();
+            let quality = if label.starts_with(&prefix) {
+                0
+            } else if label.contains(&prefix) {
+                1
+            } else {
+                return None;
+            };
+            Some((quality, s))
+        })
+        .collect();
+    scored.sort_by(|(qa, a), (qb, b)| {
+        qa.cmp(qb)
+            .then(a.kind.cmp(&b.kind))
+            .then(a.label.len().cmp(&b.label.len()))
+            .then_with(|| a.label.cmp(&b.label))
+    });
+    scored.dedup_by(|(_, a), (_, b)| a.label.eq_ignore_ascii_case(&b.label) && a.kind == b.kind);
+    scored
+        .into_iter()
+        .map(|(_, s)| s)
+        .take(MAX_SUGGESTIONS)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context_at(sql_with_cursor: &str) -> CompletionContext {
+        let cursor = sql_with_cursor
+            .find('|')
+            .expect("test SQL marks the cursor with |");
+        let sql = sql_with_cursor.replace('|', "");
+        analyze(&sql, cursor, "postgres")
+    }
+
+    fn column(name: &str) -> ColumnInfo {
+        ColumnInfo {
+            name: name.to_string(),
+            data_type: "text".to_string(),
+            is_nullable: true,
+            column_default: None,
+            is_primary_key: false,
+        }
+    }
+
+    #[test]
+    fn clause_follows_the_last_keyword() {
+        assert_eq!(context_at("|").clause, Clause::Statement);
+        assert_eq!(context_at("SELECT * FROM us|").clause, Clause::Table);
+        assert_eq!(context_at("SELECT * FROM users, |").clause, Clause::Table);
+        assert_eq!(
+            context_at("SELECT * FROM users u |").clause,
+            Clause::AfterTable
+        );
+        assert_eq!(
+            context_at("SELECT * FROM users u JOIN orders o ON o.user_id = |").clause,
+            Clause::Expression
+        );
+        assert_eq!(context_at("SELECT count(|").clause, Clause::Expression);
+        assert_eq!(
+            context_at("SELECT * FROM users WHERE id IN (SELECT user_id FROM |)").clause,
+            Clause::Table
+        );
+        assert_eq!(
+            context_at("SELECT 1; UPDATE users SET na|").clause,
+            Clause::Expression
+        );
+        assert_eq!(context_at("SELECT 'abc|").clause, Clause::None);
+        assert_eq!(context_at("SELECT 1 -- note|").clause, Clause::None);
+    }
+
+    #[test]
+    fn qualifier_and_scope_include_text_after_the_cursor() {
+        let context = context_at("SELECT u.na| FROM public.users u JOIN orders ON true; SELECT 2");
+        assert_eq!(context.clause, Clause::Qualified("u".to_string()));
+        assert_eq!(context.prefix, "na");
+        assert_eq!(context.prefix_start, 9);
+        assert_eq!(
+            context.scope,
+            vec![
+                ScopeTable {
+                    schema: Some("public".to_string()),
+                    table: "users".to_string(),
+                    alias: Some("u".to_string()),
+                },
+                ScopeTable {
+                    schema: None,
+                    table: "orders".to_string(),
+                    alias: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn suggestions_rank_prefix_matches_and_columns_first() {
+        let context = context_at("SELECT na| FROM users");
+        let catalog = CompletionCatalog {
+            functions: vec!["normalize_name".to_string()],
+            columns: vec![(
+                "users".to_string(),
+                vec![column("id"), column("name"), column("nickname")],
+            )],
+            ..Default::default()
+        };
+        let labels: Vec<String> = suggest(&context, &catalog, "postgres")
+            .into_iter()
+            .map(|s| s.label)
+            .collect();
+        assert_eq!(labels, vec!["name", "nickname", "normalize_name"]);
+    }
+
+    #[test]
+    fn utf16_offsets_round_trip() {
+        let text = "SELECT 'é😀' FROM t";
+        let byte = utf16_to_byte(text, 12);
+        assert_eq!(&text[byte..], " FROM t");
+        assert_eq!(byte_to_utf16(text, byte), 12);
+    }
+}
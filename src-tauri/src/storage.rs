@@ -1,6 +1,7 @@
 // ABOUTME: Handles persistence of database connection configurations and categories.
 // ABOUTME: Stores connections and categories as JSON in the user's config directory.
 
+use crate::secrets::SecretStore;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -44,20 +45,67 @@ pub enum SshAuthMethod {
     Agent,
     PrivateKey {
         path: String,
-        #[serde(default)]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
         passphrase: Option<String>,
     },
     Password {
+        #[serde(default, skip_serializing_if = "String::is_empty")]
         password: String,
     },
 }
 
+/// The bastion that forwards to the database. Any `jump_hosts` are connected
+/// through first, in order, like ssh's `ProxyJump`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SshTunnelConfig {
     pub host: String,
     pub port: u16,
     pub username: String,
     pub auth: SshAuthMethod,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub jump_hosts: Vec<SshHop>,
+    /// A `Host` alias from `~/.ssh/config`. When set, the host, port and jump
+    /// hosts come from there, and `username` only overrides its `User`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_config_host: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SshHop {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub auth: SshAuthMethod,
+    /// Keys from ssh_config's `IdentityFile`, tried after the agent.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub identity_files: Vec<String>,
+    /// ssh_config's `IdentitiesOnly`: only offer agent keys that match
+    /// `identity_files`.
+    #[serde(default)]
+    pub identities_only: bool,
+}
+
+impl SshHop {
+    /// `user@host:port`, which names the hop in secret keys and messages.
+    pub fn label(&self) -> String {
+        format!("{}@{}:{}", self.username, self.host, self.port)
+    }
+}
+
+impl SshTunnelConfig {
+    /// Every SSH server on the way to the database, in connection order.
+    pub fn hops(&self) -> Vec<SshHop> {
+        let mut hops = self.jump_hosts.clone();
+        hops.push(SshHop {
+            host: self.host.clone(),
+            port: self.port,
+            username: self.username.clone(),
+            auth: self.auth.clone(),
+            identity_files: Vec::new(),
+            identities_only: false,
+        });
+        hops
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -68,6 +116,9 @@ pub struct ConnectionConfig {
     pub host: String,
     pub port: u16,
     pub username: String,
+    /// Only set in memory: on disk the password lives in the secret store and
+    /// `secrets` names it.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub password: String,
     pub database: Option<String>,
     #[serde(default)]
@@ -76,19 +127,145 @@ pub struct ConnectionConfig {
     pub visible_databases: Option<u16>,
     #[serde(default = "default_ssl_mode")]
     pub ssl_mode: SslMode,
+    /// PEM file of the CA certificates to verify the server against, instead
+    /// of the system's trust store.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssl_root_cert: Option<String>,
+    /// PEM client certificate and key, for servers that require mutual TLS.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssl_client_cert: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssl_client_key: Option<String>,
+    #[serde(default, skip_serializing_if = "ConnectionOptions::is_default")]
+    pub options: ConnectionOptions,
+    #[serde(default, skip_serializing_if = "RedisTopology::is_standalone")]
+    pub redis_topology: RedisTopology,
     #[serde(default)]
     pub last_selected: Option<Vec<LastSelected>>,
     #[serde(default)]
     pub ssh_tunnel: Option<SshTunnelConfig>,
+    #[serde(default, skip_serializing_if = "SecretRefs::is_empty")]
+    pub secrets: SecretRefs,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-#[serde(rename_all = "lowercase")]
+/// Secret store keys for a connection's passwords.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SecretRefs {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_password: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_passphrase: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub jump_hosts: Vec<HopSecretRefs>,
+}
+
+/// Secret store keys for a jump host, matched to it by [`SshHop::label`] so
+/// reordering or removing hops can't hand one hop another's password.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HopSecretRefs {
+    pub hop: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_password: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_passphrase: Option<String>,
+}
+
+impl SecretRefs {
+    pub fn is_empty(&self) -> bool {
+        self.password.is_none()
+            && self.ssh_password.is_none()
+            && self.ssh_passphrase.is_none()
+            && self.jump_hosts.is_empty()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        [&self.password, &self.ssh_password, &self.ssh_passphrase]
+            .into_iter()
+            .chain(
+                self.jump_hosts
+                    .iter()
+                    .flat_map(|h| [&h.ssh_password, &h.ssh_passphrase]),
+            )
+            .flatten()
+    }
+}
+
+/// Driver settings beyond where to connect and how to log in. Unset fields
+/// keep the driver's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConnectionOptions {
+    /// Postgres `application_name`, shown in `pg_stat_activity`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub application_name: Option<String>,
+    /// Postgres `statement_timeout`; MySQL `max_execution_time` (which only
+    /// limits SELECTs) or MariaDB `max_statement_time`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub statement_timeout_ms: Option<u64>,
+    /// Postgres `search_path`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search_path: Option<String>,
+    /// MySQL connection character set, `utf8mb4` by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub charset: Option<String>,
+    /// Most connections the pool keeps open; Redis always uses one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_connections: Option<u32>,
+    /// Pooled connections left unused this long are closed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idle_timeout_secs: Option<u64>,
+    /// Separates a Redis key's namespace from the rest, `:` by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redis_key_delimiter: Option<String>,
+}
+
+impl ConnectionOptions {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// How a Redis connection finds its server. For a cluster or Sentinel,
+/// `host` and `port` name the first seed node or sentinel, and the lists hold
+/// any others as `host:port`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum RedisTopology {
+    #[default]
+    Standalone,
+    Cluster {
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        seeds: Vec<String>,
+    },
+    /// The primary that the sentinels monitor as `master_name`.
+    Sentinel {
+        master_name: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        sentinels: Vec<String>,
+    },
+}
+
+impl RedisTopology {
+    pub fn is_standalone(&self) -> bool {
+        matches!(self, Self::Standalone)
+    }
+}
+
+/// Named after libpq's `sslmode`. Only the verify modes check the server's
+/// certificate; Prefer and Require just encrypt.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
 pub enum SslMode {
     #[default]
     Disable,
     Prefer,
     Require,
+    /// The certificate must chain to a trusted CA.
+    VerifyCa,
+    /// As VerifyCa, and the certificate must name the host.
+    VerifyFull,
 }
 
 fn default_ssl_mode() -> SslMode {
@@ -118,10 +295,273 @@ impl ConnectionConfig {
             category_id,
             visible_databases: None,
             ssl_mode: SslMode::default(),
+            ssl_root_cert: None,
+            ssl_client_cert: None,
+            ssl_client_key: None,
+            options: ConnectionOptions::default(),
+            redis_topology: RedisTopology::default(),
             last_selected: None,
             ssh_tunnel: None,
+            secrets: SecretRefs::default(),
+        }
+    }
+
+    /// The client certificate and key for mutual TLS, which only work as a pair.
+    pub fn ssl_client_identity(&self) -> Result<Option<(&str, &str)>, String> {
+        let cert = self.ssl_client_cert.as_deref().filter(|p| !p.is_empty());
+        let key = self.ssl_client_key.as_deref().filter(|p| !p.is_empty());
+        match (cert, key) {
+            (Some(cert), Some(key)) => Ok(Some((cert, key))),
+            (None, None) => Ok(None),
+            (Some(_), None) => Err("A client certificate needs a client key".to_string()),
+            (None, Some(_)) => Err("A client key needs a client certificate".to_string()),
+        }
+    }
+
+    /// Through an SSH tunnel the client connects to the tunnel's local end,
+    /// and the TLS libraries check the certificate's host name against that
+    /// address, not the server's name, so host name verification can never
+    /// pass. Refused up front instead of failing as a certificate error.
+    pub fn check_tunnel_tls(&self) -> Result<(), String> {
+        if self.ssh_tunnel.is_none() {
+            return Ok(());
+        }
+        match (&self.db_type, &self.ssl_mode) {
+            (DatabaseType::Postgres | DatabaseType::Mysql, SslMode::VerifyFull) => Err(
+                "Verify CA and host name can't be used through an SSH tunnel: the host name would \
+                 be checked against the tunnel's local address. Use Verify CA instead."
+                    .to_string(),
+            ),
+            // rustls checks the host name whenever Redis verifies the server.
+            (DatabaseType::Redis, SslMode::VerifyCa | SslMode::VerifyFull) => Err(
+                "Redis certificate verification can't be used through an SSH tunnel: it always \
+                 checks the host name, which would be the tunnel's local address. Use Require \
+                 instead."
+                    .to_string(),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether any password would be plaintext if written as-is.
+    pub fn has_plaintext_secrets(&self) -> bool {
+        let ssh = self.ssh_tunnel.iter().flat_map(|t| {
+            std::iter::once(&t.auth).chain(t.jump_hosts.iter().map(|h| &h.auth))
+        });
+        !self.password.is_empty() || ssh.filter_map(auth_secret).any(|s| !s.is_empty())
+    }
+
+    /// Blanks any plaintext passwords, for handing a config that older
+    /// versions saved to the UI while the secret store is locked.
+    pub fn strip_plaintext_secrets(&mut self) {
+        self.password.clear();
+        if let Some(tunnel) = self.ssh_tunnel.as_mut() {
+            take_auth_secrets(Some(&mut tunnel.auth));
+            for hop in tunnel.jump_hosts.iter_mut() {
+                take_auth_secrets(Some(&mut hop.auth));
+            }
+        }
+    }
+}
+
+fn secret_key(id: &str, name: &str) -> String {
+    format!("connection/{}/{}", id, name)
+}
+
+fn auth_secret(auth: &SshAuthMethod) -> Option<&String> {
+    match auth {
+        SshAuthMethod::Password { password } => Some(password),
+        SshAuthMethod::PrivateKey { passphrase, .. } => passphrase.as_ref(),
+        SshAuthMethod::Agent => None,
+    }
+}
+
+/// Takes the plaintext password and passphrase out of `auth`, in the shape
+/// [`update_ref`] expects: `None` for a secret the method doesn't use.
+fn take_auth_secrets(auth: Option<&mut SshAuthMethod>) -> (Option<String>, Option<String>) {
+    match auth {
+        Some(SshAuthMethod::Password { password }) => (Some(std::mem::take(password)), None),
+        Some(SshAuthMethod::PrivateKey { passphrase, .. }) => {
+            (None, Some(passphrase.take().unwrap_or_default()))
+        }
+        _ => (None, None),
+    }
+}
+
+fn fill_auth_secrets(auth: &mut SshAuthMethod, password: Option<String>, passphrase: Option<String>) {
+    match auth {
+        SshAuthMethod::Password { password: p } if p.is_empty() && password.is_some() => {
+            *p = password.unwrap_or_default();
+        }
+        SshAuthMethod::PrivateKey { passphrase: p, .. }
+            if p.as_deref().unwrap_or_default().is_empty() && passphrase.is_some() =>
+        {
+            *p = passphrase;
+        }
+        _ => {}
+    }
+}
+
+/// Moves the config's plaintext passwords into `store` and replaces them with
+/// references. An empty password keeps the secret already referenced, so the
+/// edit form doesn't need to know it; secrets that no longer apply (the SSH
+/// auth method changed, the tunnel or a jump host was removed) are deleted.
+pub fn store_secrets(store: &dyn SecretStore, config: &mut ConnectionConfig) -> Result<(), String> {
+    let id = config.id.clone();
+    let mut refs = std::mem::take(&mut config.secrets);
+
+    if !config.password.is_empty() {
+        let key = secret_key(&id, "password");
+        store.set(&key, &std::mem::take(&mut config.password))?;
+        refs.password = Some(key);
+    }
+
+    let (ssh_password, ssh_passphrase) =
+        take_auth_secrets(config.ssh_tunnel.as_mut().map(|t| &mut t.auth));
+    refs.ssh_password = update_ref(store, &id, "ssh_password", refs.ssh_password, ssh_password)?;
+    refs.ssh_passphrase =
+        update_ref(store, &id, "ssh_passphrase", refs.ssh_passphrase, ssh_passphrase)?;
+
+    let mut old_hops = std::mem::take(&mut refs.jump_hosts);
+    let jump_hosts = match config.ssh_tunnel.as_mut() {
+        Some(tunnel) => tunnel.jump_hosts.as_mut_slice(),
+        None => &mut [],
+    };
+    for hop in jump_hosts {
+        let label = hop.label();
+        let existing = old_hops
+            .iter()
+            .position(|h| h.hop == label)
+            .map(|i| old_hops.remove(i))
+            .unwrap_or_default();
+        let (password, passphrase) = take_auth_secrets(Some(&mut hop.auth));
+        let prefix = format!("jump/{}", label);
+        let hop_refs = HopSecretRefs {
+            ssh_password: update_ref(
+                store,
+                &id,
+                &format!("{}/ssh_password", prefix),
+                existing.ssh_password,
+                password,
+            )?,
+            ssh_passphrase: update_ref(
+                store,
+                &id,
+                &format!("{}/ssh_passphrase", prefix),
+                existing.ssh_passphrase,
+                passphrase,
+            )?,
+            hop: label,
+        };
+        if hop_refs.ssh_password.is_some() || hop_refs.ssh_passphrase.is_some() {
+            refs.jump_hosts.push(hop_refs);
+        }
+    }
+    // Hops that are gone
+    for hop in old_hops {
+        for key in [hop.ssh_password, hop.ssh_passphrase].into_iter().flatten() {
+            store.delete(&key)?;
+        }
+    }
+
+    config.secrets = refs;
+    Ok(())
+}
+
+/// `value` is `None` when the secret no longer applies, and empty when the
+/// user left it unchanged.
+fn update_ref(
+    store: &dyn SecretStore,
+    id: &str,
+    name: &str,
+    existing: Option<String>,
+    value: Option<String>,
+) -> Result<Option<String>, String> {
+    match value {
+        Some(value) if !value.is_empty() => {
+            let key = secret_key(id, name);
+            store.set(&key, &value)?;
+            Ok(Some(key))
+        }
+        Some(_) => Ok(existing),
+        None => {
+            if let Some(key) = existing {
+                store.delete(&key)?;
+            }
+            Ok(None)
+        }
+    }
+}
+
+/// Fills the config's blank passwords in from `store`: for connecting, and for
+/// testing an edited connection whose unchanged passwords the form left blank.
+pub fn resolve_secrets(store: &dyn SecretStore, config: &mut ConnectionConfig) -> Result<(), String> {
+    let lookup = |key: &Option<String>| -> Result<Option<String>, String> {
+        match key {
+            Some(key) => store.get(key),
+            None => Ok(None),
+        }
+    };
+    if config.password.is_empty() {
+        if let Some(password) = lookup(&config.secrets.password)? {
+            config.password = password;
+        }
+    }
+    let Some(tunnel) = config.ssh_tunnel.as_mut() else {
+        return Ok(());
+    };
+    fill_auth_secrets(
+        &mut tunnel.auth,
+        lookup(&config.secrets.ssh_password)?,
+        lookup(&config.secrets.ssh_passphrase)?,
+    );
+    for hop in tunnel.jump_hosts.iter_mut() {
+        let label = hop.label();
+        if let Some(refs) = config.secrets.jump_hosts.iter().find(|h| h.hop == label) {
+            fill_auth_secrets(
+                &mut hop.auth,
+                lookup(&refs.ssh_password)?,
+                lookup(&refs.ssh_passphrase)?,
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Deletes the connection's stored main password, so a cleared password field
+/// can be told apart from one left unchanged.
+pub fn clear_password(
+    store: &dyn SecretStore,
+    config: &mut ConnectionConfig,
+) -> Result<(), String> {
+    if let Some(key) = config.secrets.password.take() {
+        store.delete(&key)?;
+    }
+    Ok(())
+}
+
+pub fn delete_secrets(store: &dyn SecretStore, config: &ConnectionConfig) -> Result<(), String> {
+    for key in config.secrets.keys() {
+        store.delete(key)?;
+    }
+    Ok(())
+}
+
+/// Moves plaintext passwords left in connections.json by older versions into
+/// `store`. Returns how many connections were migrated.
+pub fn migrate_plaintext_secrets(config_dir: &Path, store: &dyn SecretStore) -> Result<usize, String> {
+    let mut connections = load_connections(config_dir);
+    let mut migrated = 0;
+    for config in connections.iter_mut() {
+        if config.has_plaintext_secrets() {
+            store_secrets(store, config)?;
+            migrated += 1;
         }
     }
+    if migrated > 0 {
+        save_connections(config_dir, &connections)?;
+    }
+    Ok(migrated)
 }
 
 fn connections_file_path(config_dir: &Path) -> PathBuf {
@@ -256,3 +696,210 @@ pub fn remove_category(config_dir: &Path, id: &str) -> Result<(), String> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MemoryStore(Mutex<HashMap<String, String>>);
+
+    impl SecretStore for MemoryStore {
+        fn get(&self, key: &str) -> Result<Option<String>, String> {
+            Ok(self.0.lock().unwrap().get(key).cloned())
+        }
+
+        fn set(&self, key: &str, value: &str) -> Result<(), String> {
+            self.0.lock().unwrap().insert(key.to_string(), value.to_string());
+            Ok(())
+        }
+
+        fn delete(&self, key: &str) -> Result<(), String> {
+            self.0.lock().unwrap().remove(key);
+            Ok(())
+        }
+    }
+
+    fn config_with_tunnel(auth: SshAuthMethod) -> ConnectionConfig {
+        let mut config = ConnectionConfig::new(
+            "db".into(),
+            DatabaseType::Postgres,
+            "localhost".into(),
+            5432,
+            "app".into(),
+            "pg-secret".into(),
+            None,
+            None,
+        );
+        config.ssh_tunnel = Some(SshTunnelConfig {
+            host: "bastion".into(),
+            port: 22,
+            username: "me".into(),
+            auth,
+            jump_hosts: Vec::new(),
+            ssh_config_host: None,
+        });
+        config
+    }
+
+    fn jump_host(host: &str, password: &str) -> SshHop {
+        SshHop {
+            host: host.into(),
+            port: 22,
+            username: "me".into(),
+            auth: SshAuthMethod::Password {
+                password: password.into(),
+            },
+            identity_files: Vec::new(),
+            identities_only: false,
+        }
+    }
+
+    #[test]
+    fn stored_config_serializes_only_references() {
+        let store = MemoryStore::default();
+        let mut config = config_with_tunnel(SshAuthMethod::Password {
+            password: "ssh-secret".into(),
+        });
+        store_secrets(&store, &mut config).unwrap();
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(!json.contains("pg-secret"));
+        assert!(!json.contains("ssh-secret"));
+
+        let mut loaded: ConnectionConfig = serde_json::from_str(&json).unwrap();
+        resolve_secrets(&store, &mut loaded).unwrap();
+        assert_eq!(loaded.password, "pg-secret");
+        assert!(matches!(
+            loaded.ssh_tunnel.unwrap().auth,
+            SshAuthMethod::Password { password } if password == "ssh-secret"
+        ));
+    }
+
+    #[test]
+    fn empty_password_keeps_the_stored_secret() {
+        let store = MemoryStore::default();
+        let mut config = config_with_tunnel(SshAuthMethod::Agent);
+        store_secrets(&store, &mut config).unwrap();
+
+        let mut edited = config.clone();
+        edited.host = "elsewhere".into();
+        store_secrets(&store, &mut edited).unwrap();
+        resolve_secrets(&store, &mut edited).unwrap();
+        assert_eq!(edited.password, "pg-secret");
+    }
+
+    #[test]
+    fn cleared_password_is_deleted() {
+        let store = MemoryStore::default();
+        let mut config = config_with_tunnel(SshAuthMethod::Agent);
+        store_secrets(&store, &mut config).unwrap();
+        let key = config.secrets.password.clone().unwrap();
+
+        clear_password(&store, &mut config).unwrap();
+        store_secrets(&store, &mut config).unwrap();
+        assert_eq!(config.secrets.password, None);
+        assert_eq!(store.get(&key).unwrap(), None);
+        resolve_secrets(&store, &mut config).unwrap();
+        assert_eq!(config.password, "");
+    }
+
+    #[test]
+    fn changing_ssh_auth_deletes_the_old_secret() {
+        let store = MemoryStore::default();
+        let mut config = config_with_tunnel(SshAuthMethod::PrivateKey {
+            path: "~/.ssh/id_ed25519".into(),
+            passphrase: Some("key-secret".into()),
+        });
+        store_secrets(&store, &mut config).unwrap();
+        let key = config.secrets.ssh_passphrase.clone().unwrap();
+        assert!(store.get(&key).unwrap().is_some());
+
+        config.ssh_tunnel.as_mut().unwrap().auth = SshAuthMethod::Agent;
+        store_secrets(&store, &mut config).unwrap();
+        assert_eq!(config.secrets.ssh_passphrase, None);
+        assert_eq!(store.get(&key).unwrap(), None);
+    }
+
+    #[test]
+    fn jump_host_secrets_follow_their_hop() {
+        let store = MemoryStore::default();
+        let mut config = config_with_tunnel(SshAuthMethod::Agent);
+        let tunnel = config.ssh_tunnel.as_mut().unwrap();
+        tunnel.jump_hosts = vec![jump_host("outer", "outer-pw"), jump_host("inner", "inner-pw")];
+        store_secrets(&store, &mut config).unwrap();
+        assert!(!serde_json::to_string(&config).unwrap().contains("-pw"));
+        let outer_key = config.secrets.jump_hosts[0].ssh_password.clone().unwrap();
+
+        // Dropping the first hop must not hand its password to the second.
+        let mut edited = config.clone();
+        edited.ssh_tunnel.as_mut().unwrap().jump_hosts.remove(0);
+        store_secrets(&store, &mut edited).unwrap();
+        assert_eq!(store.get(&outer_key).unwrap(), None);
+
+        resolve_secrets(&store, &mut edited).unwrap();
+        let hops = edited.ssh_tunnel.unwrap().hops();
+        assert_eq!(hops.len(), 2);
+        assert!(matches!(
+            &hops[0].auth,
+            SshAuthMethod::Password { password } if password == "inner-pw"
+        ));
+    }
+
+    #[test]
+    fn legacy_plaintext_configs_still_parse() {
+        let json = r#"{"id":"1","name":"old","db_type":"mysql","host":"h","port":3306,
+            "username":"u","password":"p","database":null}"#;
+        let config: ConnectionConfig = serde_json::from_str(json).unwrap();
+        assert!(config.has_plaintext_secrets());
+        assert!(config.secrets.is_empty());
+
+        let mut listed = config.clone();
+        listed.strip_plaintext_secrets();
+        assert!(!listed.has_plaintext_secrets());
+    }
+
+    #[test]
+    fn ssl_settings_round_trip() {
+        let json = r#"{"id":"1","name":"rds","db_type":"postgres","host":"h","port":5432,
+            "username":"u","database":null,"ssl_mode":"verify-full",
+            "ssl_root_cert":"/certs/rds-ca.pem"}"#;
+        let mut config: ConnectionConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.ssl_mode, SslMode::VerifyFull);
+        assert_eq!(config.ssl_client_identity(), Ok(None));
+
+        config.ssl_client_cert = Some("/certs/client.crt".to_string());
+        assert!(config.ssl_client_identity().is_err());
+        config.ssl_client_key = Some("/certs/client.key".to_string());
+        assert_eq!(
+            config.ssl_client_identity(),
+            Ok(Some(("/certs/client.crt", "/certs/client.key")))
+        );
+
+        let saved = serde_json::to_value(&config).unwrap();
+        assert_eq!(saved["ssl_mode"], "verify-full");
+        let legacy: ConnectionConfig =
+            serde_json::from_str(&json.replace("verify-full", "require")).unwrap();
+        assert_eq!(legacy.ssl_mode, SslMode::Require);
+    }
+
+    #[test]
+    fn host_name_verification_is_refused_through_a_tunnel() {
+        let mut config = config_with_tunnel(SshAuthMethod::Agent);
+        config.ssl_mode = SslMode::VerifyFull;
+        assert!(config.check_tunnel_tls().is_err());
+        config.ssl_mode = SslMode::VerifyCa;
+        assert_eq!(config.check_tunnel_tls(), Ok(()));
+
+        config.db_type = DatabaseType::Redis;
+        assert!(config.check_tunnel_tls().is_err());
+        config.ssl_mode = SslMode::Require;
+        assert_eq!(config.check_tunnel_tls(), Ok(()));
+
+        config.ssh_tunnel = None;
+        config.ssl_mode = SslMode::VerifyFull;
+        assert_eq!(config.check_tunnel_tls(), Ok(()));
+    }
+}
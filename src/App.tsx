@@ -2,12 +2,21 @@
 // ABOUTME: Orchestrates sidebar, query editor, and results display.
 
 import { Show, createSignal, onMount, onCleanup } from "solid-js";
-import { confirm } from "@tauri-apps/plugin-dialog";
+import { confirm, message } from "@tauri-apps/plugin-dialog";
 import { listen } from "@tauri-apps/api/event";
-import type { MetadataView, WorkingContext, QueryProgress } from "./lib/types";
+import type {
+  MetadataView,
+  WorkingContext,
+  QueryProgress,
+  HostKeyPrompt,
+  RowChange,
+  RedisBytes,
+  TableChanges,
+} from "./lib/types";
 import {
   executeQuery,
   extractQueryTable,
+  analyzeQueryColumns,
   listConnections,
   getFunctionDefinition,
   getViewDefinition,
@@ -18,7 +27,9 @@ import {
   listColumns,
   listIndexes,
   listConstraints,
-  cancelQueries,
+  applyRowChanges,
+  cancelQuery,
+  explainQuery,
 } from "./lib/tauri";
 import {
   generateDeleteQuery,
@@ -26,7 +37,7 @@ import {
   parseDeleteQuery,
 } from "./lib/deleteQueryGenerator";
 import { generateUpdateQuery, type RowEdit } from "./lib/updateQueryGenerator";
-import { StoreProvider, useStore } from "./lib/store";
+import { StoreProvider, useStore, type Tab } from "./lib/store";
 import { Sidebar } from "./components/Sidebar";
 import { QueryEditor } from "./components/QueryEditor";
 import { ResultsTable } from "./components/ResultsTable";
@@ -35,9 +46,12 @@ import { MetadataTable } from "./components/MetadataTable";
 import { FunctionViewer } from "./components/FunctionViewer";
 import { DependencyGraph } from "./components/DependencyGraph";
 import { UserViewer } from "./components/UserViewer";
+import { RedisKeyViewer } from "./components/RedisKeyViewer";
 import { ConnectionPath } from "./components/ConnectionPath";
 import { QueryHistory } from "./components/QueryHistory";
+import { PlanViewer } from "./components/PlanViewer";
 import { TabBar } from "./components/TabBar";
+import { HostKeyPromptDialog } from "./components/HostKeyPromptDialog";
 import "./styles/app.css";
 
 // Module-level map from query id to the tab id that launched the query, so
@@ -241,6 +255,7 @@ ORDER BY user;`;
   };
 
   const [grantsLoading, setGrantsLoading] = createSignal(false);
+  const [hostKeyPrompts, setHostKeyPrompts] = createSignal<HostKeyPrompt[]>([]);
 
   const handleUserSelect = async (username: string, host?: string) => {
     const t = activeTab();
@@ -521,6 +536,36 @@ ORDER BY user;`;
     }
   };
 
+  const handleRedisKeySelect = async (connectionId: string, database: string, key: RedisBytes) => {
+    const existing = store.tabs.find(
+      (t) =>
+        t.connectionId === connectionId &&
+        t.database === database &&
+        t.viewType === "redis-key" &&
+        JSON.stringify(t.redisKey) === JSON.stringify(key)
+    );
+    if (existing) {
+      setActiveTab(existing.id);
+      return;
+    }
+
+    const connections = await listConnections();
+    const conn = connections.find((c) => c.id === connectionId);
+    if (!conn) return;
+
+    createTab({
+      connectionId,
+      connectionName: conn.name,
+      dbType: conn.db_type,
+      categoryColor: null,
+      database,
+      schema: null,
+      table: null,
+      viewType: "redis-key",
+      redisKey: key,
+    });
+  };
+
   const handleShowDependencyGraph = async (ctx: WorkingContext) => {
     // Reuse existing dependency tab for this schema
     const existing = store.tabs.find(
@@ -565,6 +610,30 @@ ORDER BY user;`;
     updateActiveTab({ dependencies: deps });
   };
 
+  const handleExplain = async (queryToExplain: string, analyze: boolean) => {
+    const tab = activeTab();
+    if (!tab?.connectionId) {
+      updateActiveTab({ error: "No connection or database selected" });
+      return;
+    }
+
+    if (tab.hasPendingChanges) {
+      const confirmed = await confirm(
+        "You have pending edits that will be lost when the plan is shown. Continue?",
+        { title: "Pending Changes", kind: "warning" }
+      );
+      if (!confirmed) return;
+    }
+
+    updateActiveTab({ loading: true, error: null, plan: null, selectedCell: null });
+    try {
+      const plan = await explainQuery(tab.connectionId, queryToExplain, analyze);
+      updateActiveTab({ plan, loading: false });
+    } catch (err) {
+      updateActiveTab({ error: String(err), loading: false });
+    }
+  };
+
   const handleExecute = async (
     queryToExecute: string,
     preserveTableContext = false
@@ -615,10 +684,20 @@ ORDER BY user;`;
       }
     }
 
+    // Trace result columns to their tables, so joined results can be edited
+    // wherever each table's key is part of the result.
+    updateActiveTab({ queryColumns: null });
+    if (tab.dbType) {
+      analyzeQueryColumns(connId, db, sch, queryToExecute, tab.dbType)
+        .then((queryColumns) => updateActiveTab({ queryColumns }))
+        .catch(() => updateActiveTab({ queryColumns: null }));
+    }
+
     updateActiveTab({
       loading: true,
       error: null,
       result: null,
+      plan: null,
       selectedCell: null,
       metadataView: null,
       queryProgress: null,
@@ -628,7 +707,25 @@ ORDER BY user;`;
     queryIdToTabId.set(queryId, tab.id);
 
     try {
-      const [res, backendTime] = await executeQuery(queryId, connId, queryToExecute, db);
+      const [results, backendTime] = await executeQuery(
+        queryId,
+        connId,
+        queryToExecute,
+        db,
+        undefined,
+        undefined,
+        tab.id
+      );
+      const failedIndex = results.findIndex((r) => r.error !== null);
+      if (failedIndex !== -1) {
+        const prefix = results.length > 1 ? `Statement ${failedIndex + 1} failed: ` : "";
+        throw new Error(prefix + results[failedIndex].error);
+      }
+      // Show the last statement that returned rows, falling back to the last one.
+      const shown =
+        [...results].reverse().find((r) => r.result && r.result.columns.length > 0) ??
+        results[results.length - 1];
+      const res = shown.result!;
       updateActiveTab({ result: res, loading: false });
 
       const historyId = crypto.randomUUID();
@@ -674,8 +771,12 @@ ORDER BY user;`;
     const tab = activeTab();
     if (!tab?.connectionId) return;
 
-    // Attempt to cancel on the backend
-    cancelQueries(tab.connectionId).catch(console.error);
+    // Attempt to cancel this tab's query on the backend
+    for (const [queryId, tabId] of queryIdToTabId) {
+      if (tabId === tab.id) {
+        cancelQuery(tab.connectionId, queryId).catch(console.error);
+      }
+    }
 
     // Always reset the UI so it's no longer stuck
     updateActiveTab({
@@ -772,6 +873,100 @@ ORDER BY user;`;
     updateActiveTab({ query: updateQuery });
   };
 
+  /** Result columns traced to their tables, when they line up with the current result. */
+  const tracedColumns = (tab: Tab) => {
+    const qc = tab.queryColumns;
+    return qc && tab.result && qc.columns.length === tab.result.columns.length ? qc : null;
+  };
+
+  // Computed columns are never editable. A single-table result that leaves
+  // out the key still lets real columns be edited for Generate UPDATE, which
+  // falls back to matching on every column.
+  const editableColumns = (tab: Tab) =>
+    tracedColumns(tab)?.columns.map(
+      (c) => c.editable || (tab.tableContext !== null && c.source !== null)
+    );
+
+  const handleSaveChanges = async (edits: RowEdit[], deletions: number[]) => {
+    const tab = activeTab();
+    if (!tab || !tab.connectionId || !tab.result) return;
+
+    const res = tab.result;
+    const traced = tracedColumns(tab);
+    const batches: TableChanges[] = [];
+
+    if (traced && traced.tables.some((t) => t.key_columns.length > 0)) {
+      // One batch per source table, keyed by the columns the result holds its
+      // key in. All batches are saved in one transaction.
+      const sourceColumn = (i: number) => traced.columns[i].source!.column;
+      traced.tables.forEach((table, t) => {
+        if (table.key_columns.length === 0) return;
+        const keyOf = (row: unknown[]) =>
+          Object.fromEntries(table.key_columns.map((i) => [sourceColumn(i), row[i]]));
+        const changes: RowChange[] = edits.flatMap((edit): RowChange[] => {
+          const values = [...edit.changes]
+            .filter(([i]) => traced.columns[i].source?.table === t)
+            .map(([i, value]) => [sourceColumn(i), value]);
+          return values.length > 0
+            ? [
+                {
+                  kind: "update",
+                  key: keyOf(edit.originalRow),
+                  values: Object.fromEntries(values),
+                },
+              ]
+            : [];
+        });
+        // Which row a join result's deletion means is ambiguous, so only
+        // single-table results delete.
+        if (traced.tables.length === 1) {
+          changes.push(
+            ...deletions.map((i): RowChange => ({ kind: "delete", key: keyOf(res.rows[i]) }))
+          );
+        }
+        if (changes.length > 0) {
+          batches.push({
+            database: table.database,
+            schema: table.schema,
+            table: table.table,
+            key_columns: table.key_columns.map(sourceColumn),
+            changes,
+          });
+        }
+      });
+    } else if (tab.tableContext) {
+      const ctx = tab.tableContext;
+      const rowObject = (row: unknown[]) =>
+        Object.fromEntries(res.columns.map((col, i) => [col, row[i]]));
+      batches.push({
+        database: ctx.database,
+        schema: ctx.schema,
+        table: ctx.table,
+        key_columns: tab.primaryKeyColumns,
+        changes: [
+          ...edits.map((edit): RowChange => ({
+            kind: "update",
+            key: rowObject(edit.originalRow),
+            values: Object.fromEntries(
+              [...edit.changes].map(([colIndex, value]) => [res.columns[colIndex], value])
+            ),
+          })),
+          ...deletions.map((i): RowChange => ({ kind: "delete", key: rowObject(res.rows[i]) })),
+        ],
+      });
+    }
+
+    try {
+      await applyRowChanges(tab.connectionId, batches, tab.id);
+    } catch (err) {
+      await message(String(err), { title: "Changes Not Saved", kind: "error" });
+      return;
+    }
+
+    updateActiveTab({ hasPendingChanges: false });
+    handleExecute(tab.query, true);
+  };
+
   const handleGenerateKill = (rowIndices: number[]) => {
     const tab = activeTab();
     if (!tab || !tab.result) return;
@@ -906,6 +1101,14 @@ ORDER BY user;`;
     onCleanup(() => {
       unlistenPromise.then((fn) => fn()).catch(() => {});
     });
+
+    // SSH tunnels to unknown hosts wait for the user to trust the host key.
+    const unlistenHostKeys = listen<HostKeyPrompt>("ssh-host-key-prompt", (event) => {
+      setHostKeyPrompts((prompts) => [...prompts, event.payload]);
+    });
+    onCleanup(() => {
+      unlistenHostKeys.then((fn) => fn()).catch(() => {});
+    });
   });
 
   const tab = () => activeTab();
@@ -925,6 +1128,7 @@ ORDER BY user;`;
         onConnectionChange={handleConnectionChange}
         onDatabaseSwitch={handleDatabaseSwitch}
         onTableSelect={handleTableSelect}
+        onRedisKeySelect={handleRedisKeySelect}
         onQueryGenerate={handleQueryGenerate}
         onMetadataSelect={handleMetadataSelect}
         onFunctionSelect={handleFunctionSelect}
@@ -957,12 +1161,16 @@ ORDER BY user;`;
             onHistoryClick={() => setShowHistory(true)}
           />
 
-          <Show when={!tab()!.metadataView && !tab()!.functionInfo && !tab()!.dependencies && !tab()!.users}>
+          <Show when={!tab()!.metadataView && !tab()!.functionInfo && !tab()!.dependencies && !tab()!.users && !tab()!.redisKey}>
             <QueryEditor
               value={tab()!.query}
               onChange={(q) => updateActiveTab({ query: q })}
               onExecute={handleExecute}
+              onExplain={tab()!.dbType && tab()!.dbType !== "redis" ? handleExplain : undefined}
               dbType={tab()!.dbType}
+              connectionId={tab()!.connectionId}
+              database={tab()!.database}
+              schema={tab()!.schema}
               disabled={!tab()!.connectionId || tab()!.loading}
               canGoBack={canGoBack()}
               canGoForward={canGoForward()}
@@ -999,6 +1207,14 @@ ORDER BY user;`;
             />
           </Show>
 
+          <Show when={tab()!.redisKey && tab()!.connectionId && tab()!.database}>
+            <RedisKeyViewer
+              connectionId={tab()!.connectionId!}
+              database={tab()!.database!}
+              keyName={tab()!.redisKey!}
+            />
+          </Show>
+
           <Show when={tab()!.users}>
             <UserViewer
               users={tab()!.users!}
@@ -1013,29 +1229,41 @@ ORDER BY user;`;
             />
           </Show>
 
-          <Show when={!tab()!.metadataView && !tab()!.functionInfo && !tab()!.dependencies && !tab()!.users}>
+          <Show when={!tab()!.metadataView && !tab()!.functionInfo && !tab()!.dependencies && !tab()!.users && !tab()!.redisKey}>
             <div class="results-area">
               <div class="results-table-wrapper">
-                <ResultsTable
-                  result={tab()!.result}
-                  error={tab()!.error}
-                  loading={tab()!.loading}
-                  progress={tab()!.queryProgress}
-                  selectedCell={tab()!.selectedCell}
-                  onCellSelect={(sel) => updateActiveTab({ selectedCell: sel })}
-                  onRowDoubleClick={handleRowDoubleClick}
-                  tableContext={tab()!.tableContext}
-                  primaryKeyColumns={tab()!.primaryKeyColumns}
-                  onGenerateDelete={handleGenerateDelete}
-                  onGenerateUpdate={handleGenerateUpdate}
-                  onGenerateKill={isProcessListTab() ? handleGenerateKill : undefined}
-                  onFilterByValue={handleFilterByValue}
-                  onCancel={handleCancelQuery}
-                  onPendingChangesChange={(p) =>
-                    updateActiveTab({ hasPendingChanges: p })
+                <Show
+                  when={tab()!.plan && !tab()!.loading && !tab()!.error}
+                  fallback={
+                    <ResultsTable
+                      result={tab()!.result}
+                      error={tab()!.error}
+                      loading={tab()!.loading}
+                      progress={tab()!.queryProgress}
+                      selectedCell={tab()!.selectedCell}
+                      onCellSelect={(sel) => updateActiveTab({ selectedCell: sel })}
+                      onRowDoubleClick={handleRowDoubleClick}
+                      tableContext={tab()!.tableContext}
+                      primaryKeyColumns={tab()!.primaryKeyColumns}
+                      onGenerateDelete={handleGenerateDelete}
+                      onGenerateUpdate={handleGenerateUpdate}
+                      onSaveChanges={handleSaveChanges}
+                      editableColumns={editableColumns(tab()!)}
+                      onGenerateKill={isProcessListTab() ? handleGenerateKill : undefined}
+                      onFilterByValue={handleFilterByValue}
+                      onCancel={handleCancelQuery}
+                      onPendingChangesChange={(p) =>
+                        updateActiveTab({ hasPendingChanges: p })
+                      }
+                      dbType={tab()!.dbType}
+                    />
                   }
-                  dbType={tab()!.dbType}
-                />
+                >
+                  <PlanViewer
+                    plan={tab()!.plan!}
+                    onClose={() => updateActiveTab({ plan: null })}
+                  />
+                </Show>
               </div>
               <CellInspector
                 selection={tab()!.selectedCell}
@@ -1058,6 +1286,19 @@ ORDER BY user;`;
           />
         </Show>
       </main>
+
+      <Show when={hostKeyPrompts()[0]} keyed>
+        {(prompt) => (
+          <HostKeyPromptDialog
+            prompt={prompt}
+            onDone={() =>
+              setHostKeyPrompts((prompts) =>
+                prompts.filter((p) => p.request_id !== prompt.request_id)
+              )
+            }
+          />
+        )}
+      </Show>
     </div>
   );
 }
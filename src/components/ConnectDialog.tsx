@@ -3,16 +3,18 @@
 
 import { createSignal, For, Show, onMount, onCleanup } from "solid-js";
 import { Icon } from "./Icon";
-import type { ConnectionConfig, Category, WorkingContext, DatabaseType } from "../lib/types";
+import type { ConnectionConfig, Category, WorkingContext, DatabaseType, RedisDatabaseInfo } from "../lib/types";
 import {
   listConnections,
   listCategories,
   connect,
   listDatabases,
   listSchemas,
+  redisListDatabases,
   switchDatabase,
   saveLastSelected,
 } from "../lib/tauri";
+import { createUnlockPrompt } from "./UnlockVaultDialog";
 
 import xSvg from "@phosphor-icons/core/assets/regular/x.svg?raw";
 import arrowLeftSvg from "@phosphor-icons/core/assets/regular/arrow-left.svg?raw";
@@ -28,8 +30,18 @@ interface DiscoveredEntry {
   schema: string;
   checked: boolean;
   alreadyExists: boolean;
+  /** Shown next to the name, e.g. a Redis database's key count. */
+  detail?: string;
 }
 
+const formatRedisStats = (db: RedisDatabaseInfo) => {
+  if (db.keys === 0) return "empty";
+  const parts = [`${db.keys.toLocaleString()} keys`];
+  if (db.expires > 0) parts.push(`${db.expires.toLocaleString()} expiring`);
+  if (db.avg_ttl_ms !== null) parts.push(`avg TTL ${Math.round(db.avg_ttl_ms / 1000)}s`);
+  return parts.join(", ");
+};
+
 export function ConnectDialog(props: Props) {
   const [connections, setConnections] = createSignal<ConnectionConfig[]>([]);
   const [categories, setCategories] = createSignal<Category[]>([]);
@@ -85,7 +97,12 @@ export function ConnectDialog(props: Props) {
     return conn.last_selected ?? [];
   };
 
+  const unlockPrompt = createUnlockPrompt();
+
   const handlePickConnection = async (conn: ConnectionConfig) => {
+    const needsSecrets = Object.keys(conn.secrets ?? {}).length > 0;
+    if (needsSecrets && !(await unlockPrompt.ensureUnlocked())) return;
+
     setSelectedConnection(conn);
     setLoading(true);
     setError(null);
@@ -94,7 +111,7 @@ export function ConnectDialog(props: Props) {
       setLoadingStatus(`Connecting to ${conn.name}...`);
       await connect(conn.id);
       setLoadingStatus("Listing databases...");
-      const databases = await listDatabases(conn.id);
+      const databases = conn.db_type === "redis" ? [] : await listDatabases(conn.id);
 
       const discovered: DiscoveredEntry[] = [];
 
@@ -109,16 +126,19 @@ export function ConnectDialog(props: Props) {
           alreadyExists: props.existingContexts.some((c) => c.id === id),
         });
       } else if (conn.db_type === "redis") {
-        // Redis: each database index is a context
+        // Redis: each database index is a context. Databases past the
+        // visible count are still offered when they hold keys.
         const visibleCount = conn.visible_databases ?? 4;
-        const visibleDbs = databases.slice(0, visibleCount);
-        for (const db of visibleDbs) {
-          const id = makeContextId(conn.id, db, "");
+        const redisDbs = await redisListDatabases(conn.id);
+        for (const [i, db] of redisDbs.entries()) {
+          if (i >= visibleCount && db.keys === 0) continue;
+          const id = makeContextId(conn.id, db.name, "");
           discovered.push({
-            database: db,
+            database: db.name,
             schema: "",
             checked: false,
             alreadyExists: props.existingContexts.some((c) => c.id === id),
+            detail: formatRedisStats(db),
           });
         }
       } else if (conn.db_type === "postgres") {
@@ -450,6 +470,9 @@ export function ConnectDialog(props: Props) {
                           <Show when={entry.alreadyExists}>
                             <span class="connect-dialog-already">connected</span>
                           </Show>
+                          <Show when={!entry.alreadyExists && entry.detail}>
+                            <span class="connect-dialog-already">{entry.detail}</span>
+                          </Show>
                         </div>
                       )}
                     </For>
@@ -575,6 +598,7 @@ export function ConnectDialog(props: Props) {
             </button>
           </div>
         </Show>
+        {unlockPrompt.dialog()}
       </div>
     </div>
   );
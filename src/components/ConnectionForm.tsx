@@ -1,9 +1,48 @@
 // ABOUTME: Modal form for creating and editing database connections.
 // ABOUTME: Supports PostgreSQL, MySQL, and SQLite connection configuration.
 
-import { createSignal, createEffect, Show, For, onMount, onCleanup } from "solid-js";
-import type { DatabaseType, SslMode, SaveConnectionInput, UpdateConnectionInput, Category, ConnectionConfig, SshTunnelConfig, SshAuthMethod } from "../lib/types";
-import { saveConnection, updateConnection } from "../lib/tauri";
+import { createSignal, createEffect, Show, For, Index, onMount, onCleanup } from "solid-js";
+import type { DatabaseType, SslMode, SaveConnectionInput, UpdateConnectionInput, Category, ConnectionConfig, ConnectionOptions, RedisTopology, ConnectionTestReport, TestStepKind, SshTunnelConfig, SshAuthMethod, SshHop, SshConfigHost } from "../lib/types";
+import { saveConnection, updateConnection, listSshConfigHosts, testConnection } from "../lib/tauri";
+import { createUnlockPrompt } from "./UnlockVaultDialog";
+
+/** Form state for one jump host; secrets stay blank unless changed. */
+interface JumpHostDraft {
+  host: string;
+  port: number;
+  username: string;
+  authType: SshAuthMethod["type"];
+  keyPath: string;
+  passphrase: string;
+  password: string;
+}
+
+const buildAuth = (
+  type: SshAuthMethod["type"],
+  keyPath: string,
+  passphrase: string,
+  password: string
+): SshAuthMethod => {
+  if (type === "privatekey") {
+    return { type: "privatekey", path: keyPath, passphrase: passphrase || null };
+  } else if (type === "password") {
+    return { type: "password", password };
+  }
+  return { type: "agent" };
+};
+
+const STEP_NAMES: Record<TestStepKind, string> = {
+  ssh_tunnel: "SSH tunnel",
+  tcp: "TCP connect",
+  tls: "TLS",
+  auth: "Authentication",
+  query: "Query",
+};
+
+const STEP_ICONS = { ok: "✓", failed: "✗", skipped: "–" };
+
+const hopLabel = (hop: { username: string; host: string; port: number }) =>
+  `${hop.username}@${hop.host}:${hop.port}`;
 
 interface Props {
   categories: Category[];
@@ -19,12 +58,27 @@ export function ConnectionForm(props: Props) {
   const [port, setPort] = createSignal(5432);
   const [username, setUsername] = createSignal("");
   const [password, setPassword] = createSignal("");
+  const [passwordCleared, setPasswordCleared] = createSignal(false);
   const [database, setDatabase] = createSignal("");
   const [filePath, setFilePath] = createSignal("");
   const [categoryId, setCategoryId] = createSignal<string | null>(null);
   const [connectionUrl, setConnectionUrl] = createSignal("");
   const [visibleDatabases, setVisibleDatabases] = createSignal<number>(4);
   const [sslMode, setSslMode] = createSignal<SslMode>("disable");
+  const [sslRootCert, setSslRootCert] = createSignal("");
+  const [sslClientCert, setSslClientCert] = createSignal("");
+  const [sslClientKey, setSslClientKey] = createSignal("");
+  const [showAdvanced, setShowAdvanced] = createSignal(false);
+  const [applicationName, setApplicationName] = createSignal("");
+  const [statementTimeout, setStatementTimeout] = createSignal("");
+  const [searchPath, setSearchPath] = createSignal("");
+  const [charset, setCharset] = createSignal("");
+  const [maxConnections, setMaxConnections] = createSignal("");
+  const [idleTimeout, setIdleTimeout] = createSignal("");
+  const [keyDelimiter, setKeyDelimiter] = createSignal("");
+  const [redisMode, setRedisMode] = createSignal<RedisTopology["mode"]>("standalone");
+  const [redisNodes, setRedisNodes] = createSignal("");
+  const [redisMasterName, setRedisMasterName] = createSignal("");
   const [sshEnabled, setSshEnabled] = createSignal(false);
   const [sshHost, setSshHost] = createSignal("");
   const [sshPort, setSshPort] = createSignal(22);
@@ -33,14 +87,23 @@ export function ConnectionForm(props: Props) {
   const [sshKeyPath, setSshKeyPath] = createSignal("");
   const [sshKeyPassphrase, setSshKeyPassphrase] = createSignal("");
   const [sshPassword, setSshPassword] = createSignal("");
+  const [jumpHosts, setJumpHosts] = createSignal<JumpHostDraft[]>([]);
+  const [sshConfigHost, setSshConfigHost] = createSignal("");
+  const [sshConfigHosts, setSshConfigHosts] = createSignal<SshConfigHost[]>([]);
   const [error, setError] = createSignal<string | null>(null);
   const [saving, setSaving] = createSignal(false);
+  const [testing, setTesting] = createSignal(false);
+  const [testReport, setTestReport] = createSignal<ConnectionTestReport | null>(null);
   const [updatingFromUrl, setUpdatingFromUrl] = createSignal(false);
   const [updatingFromFields, setUpdatingFromFields] = createSignal(false);
 
   const isEditing = () => !!props.connection;
 
   onMount(() => {
+    listSshConfigHosts()
+      .then(setSshConfigHosts)
+      .catch(() => setSshConfigHosts([]));
+
     if (props.connection) {
       const conn = props.connection;
       setName(conn.name);
@@ -48,6 +111,27 @@ export function ConnectionForm(props: Props) {
       setCategoryId(conn.category_id);
       setVisibleDatabases(conn.visible_databases ?? 4);
       setSslMode(conn.ssl_mode ?? "disable");
+      setSslRootCert(conn.ssl_root_cert ?? "");
+      setSslClientCert(conn.ssl_client_cert ?? "");
+      setSslClientKey(conn.ssl_client_key ?? "");
+
+      const options = conn.options ?? {};
+      setApplicationName(options.application_name ?? "");
+      setStatementTimeout(options.statement_timeout_ms?.toString() ?? "");
+      setSearchPath(options.search_path ?? "");
+      setCharset(options.charset ?? "");
+      setMaxConnections(options.max_connections?.toString() ?? "");
+      setIdleTimeout(options.idle_timeout_secs?.toString() ?? "");
+      setKeyDelimiter(options.redis_key_delimiter ?? "");
+      setShowAdvanced(Object.values(options).some((v) => v != null));
+
+      const topology = conn.redis_topology ?? { mode: "standalone" };
+      setRedisMode(topology.mode);
+      if (topology.mode === "cluster") setRedisNodes((topology.seeds ?? []).join(", "));
+      if (topology.mode === "sentinel") {
+        setRedisMasterName(topology.master_name);
+        setRedisNodes((topology.sentinels ?? []).join(", "));
+      }
 
       if (conn.db_type === "sqlite") {
         setFilePath(conn.host);
@@ -55,7 +139,8 @@ export function ConnectionForm(props: Props) {
         setHost(conn.host);
         setPort(conn.port);
         setUsername(conn.username);
-        setPassword(conn.password);
+        setPassword(conn.password ?? "");
+        setPasswordCleared(false);
         setDatabase(conn.database || "");
       }
 
@@ -64,13 +149,25 @@ export function ConnectionForm(props: Props) {
         setSshHost(conn.ssh_tunnel.host);
         setSshPort(conn.ssh_tunnel.port);
         setSshUsername(conn.ssh_tunnel.username);
+        setSshConfigHost(conn.ssh_tunnel.ssh_config_host ?? "");
         setSshAuthType(conn.ssh_tunnel.auth.type);
         if (conn.ssh_tunnel.auth.type === "privatekey") {
           setSshKeyPath(conn.ssh_tunnel.auth.path);
           setSshKeyPassphrase(conn.ssh_tunnel.auth.passphrase ?? "");
         } else if (conn.ssh_tunnel.auth.type === "password") {
-          setSshPassword(conn.ssh_tunnel.auth.password);
+          setSshPassword(conn.ssh_tunnel.auth.password ?? "");
         }
+        setJumpHosts(
+          (conn.ssh_tunnel.jump_hosts ?? []).map((hop: SshHop) => ({
+            host: hop.host,
+            port: hop.port,
+            username: hop.username,
+            authType: hop.auth.type,
+            keyPath: hop.auth.type === "privatekey" ? hop.auth.path : "",
+            passphrase: "",
+            password: "",
+          }))
+        );
       }
     }
 
@@ -266,26 +363,141 @@ export function ConnectionForm(props: Props) {
     }
   };
 
+  const selectedSshConfigHost = () =>
+    sshConfigHosts().find((h) => h.alias === sshConfigHost());
+
   const buildSshConfig = (): SshTunnelConfig | null => {
-    if (!sshEnabled() || dbType() === "sqlite") return null;
-    let auth: SshAuthMethod;
-    if (sshAuthType() === "privatekey") {
-      auth = {
-        type: "privatekey",
-        path: sshKeyPath(),
-        passphrase: sshKeyPassphrase() || null,
-      };
-    } else if (sshAuthType() === "password") {
-      auth = { type: "password", password: sshPassword() };
-    } else {
-      auth = { type: "agent" };
-    }
+    if (!sshEnabled() || dbType() === "sqlite" || !redisSupportsTunnel()) return null;
+    // With an ssh_config alias the backend resolves host and port itself;
+    // they're kept here so the connection list has something to show.
+    const aliased = selectedSshConfigHost();
     return {
-      host: sshHost(),
-      port: sshPort(),
+      host: aliased?.host_name ?? sshHost(),
+      port: aliased?.port ?? sshPort(),
       username: sshUsername(),
-      auth,
+      ssh_config_host: sshConfigHost() || null,
+      auth: buildAuth(sshAuthType(), sshKeyPath(), sshKeyPassphrase(), sshPassword()),
+      jump_hosts: jumpHosts().map((hop) => ({
+        host: hop.host,
+        port: hop.port,
+        username: hop.username,
+        auth: buildAuth(hop.authType, hop.keyPath, hop.passphrase, hop.password),
+      })),
+    };
+  };
+
+  const addJumpHost = () =>
+    setJumpHosts((hops) => [
+      ...hops,
+      {
+        host: "",
+        port: 22,
+        username: sshUsername(),
+        authType: "agent",
+        keyPath: "",
+        passphrase: "",
+        password: "",
+      },
+    ]);
+
+  const updateJumpHost = (index: number, patch: Partial<JumpHostDraft>) =>
+    setJumpHosts((hops) => hops.map((hop, i) => (i === index ? { ...hop, ...patch } : hop)));
+
+  const removeJumpHost = (index: number) =>
+    setJumpHosts((hops) => hops.filter((_, i) => i !== index));
+
+  const unlockPrompt = createUnlockPrompt();
+
+  // Saved passwords are left blank when editing; leaving them blank keeps them.
+  // The main password can be cleared explicitly instead.
+  const storedSecrets = () => props.connection?.secrets ?? {};
+  const clearsPassword = () => passwordCleared() && !password();
+  const secretPlaceholder = (stored: string | undefined) =>
+    isEditing() && stored ? "Unchanged" : undefined;
+
+  const storedHopSecrets = (hop: JumpHostDraft) =>
+    storedSecrets().jump_hosts?.find((refs) => refs.hop === hopLabel(hop));
+
+  const hasNewSecrets = () => {
+    if (dbType() !== "sqlite" && (password() || clearsPassword())) return true;
+    if (!buildSshConfig()) return false;
+    if (jumpHosts().some((hop) => hop.password || hop.passphrase)) return true;
+    return sshAuthType() === "password" ? !!sshPassword() : !!sshKeyPassphrase();
+  };
+
+  // Only the options that apply to the chosen database; blank fields are left unset.
+  const buildOptions = (): ConnectionOptions => {
+    const text = (value: string) => value.trim() || null;
+    const number = (value: string) => {
+      const parsed = parseInt(value, 10);
+      return Number.isNaN(parsed) || parsed < 0 ? null : parsed;
     };
+    const type = dbType();
+    return {
+      application_name: type === "postgres" ? text(applicationName()) : null,
+      statement_timeout_ms: type === "postgres" || type === "mysql" ? number(statementTimeout()) : null,
+      search_path: type === "postgres" ? text(searchPath()) : null,
+      charset: type === "mysql" ? text(charset()) : null,
+      max_connections: type === "redis" ? null : number(maxConnections()),
+      idle_timeout_secs: type === "redis" ? null : number(idleTimeout()),
+      redis_key_delimiter: type === "redis" ? keyDelimiter() || null : null,
+    };
+  };
+
+  // The host and port fields hold the first seed node or sentinel; the rest are listed separately.
+  const buildRedisTopology = (): RedisTopology => {
+    if (dbType() !== "redis") return { mode: "standalone" };
+    const nodes = redisNodes()
+      .split(/[\s,]+/)
+      .filter((node) => node !== "");
+    switch (redisMode()) {
+      case "cluster":
+        return { mode: "cluster", seeds: nodes };
+      case "sentinel":
+        return { mode: "sentinel", master_name: redisMasterName().trim(), sentinels: nodes };
+      default:
+        return { mode: "standalone" };
+    }
+  };
+
+  const buildInput = (): SaveConnectionInput => ({
+    name: name(),
+    db_type: dbType(),
+    host: dbType() === "sqlite" ? filePath() : host(),
+    port: dbType() === "sqlite" ? 0 : port(),
+    username: dbType() === "sqlite" ? "" : username(),
+    password: dbType() === "sqlite" ? "" : password(),
+    database: dbType() === "sqlite" ? null : database() || null,
+    category_id: categoryId(),
+    visible_databases: dbType() === "redis" && redisMode() !== "cluster" ? visibleDatabases() : null,
+    ssl_mode: sslMode(),
+    ssl_root_cert: sslMode() !== "disable" ? sslRootCert() || null : null,
+    ssl_client_cert: sslMode() !== "disable" ? sslClientCert() || null : null,
+    ssl_client_key: sslMode() !== "disable" ? sslClientKey() || null : null,
+    options: buildOptions(),
+    redis_topology: buildRedisTopology(),
+    ssh_tunnel: buildSshConfig(),
+  });
+
+  const handleTest = async () => {
+    setError(null);
+    setTestReport(null);
+    setTesting(true);
+    try {
+      // Blank password fields of a saved connection are filled in from the
+      // secret store, which has to be open for that.
+      const hasStored = Object.keys(storedSecrets()).length > 0;
+      if (isEditing() && hasStored && !(await unlockPrompt.ensureUnlocked())) {
+        return;
+      }
+      setTestReport(
+        await testConnection({ id: props.connection?.id, clear_password: clearsPassword(), ...buildInput() }),
+      );
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+    } finally {
+      setTesting(false);
+    }
   };
 
   const handleSubmit = async (e: Event) => {
@@ -294,38 +506,18 @@ export function ConnectionForm(props: Props) {
     setSaving(true);
 
     try {
-      const ssh_tunnel = buildSshConfig();
+      if (hasNewSecrets() && !(await unlockPrompt.ensureUnlocked())) {
+        return;
+      }
       if (isEditing()) {
         const input: UpdateConnectionInput = {
           id: props.connection!.id,
-          name: name(),
-          db_type: dbType(),
-          host: dbType() === "sqlite" ? filePath() : host(),
-          port: dbType() === "sqlite" ? 0 : port(),
-          username: dbType() === "sqlite" ? "" : username(),
-          password: dbType() === "sqlite" ? "" : password(),
-          database: dbType() === "sqlite" ? null : database() || null,
-          category_id: categoryId(),
-          visible_databases: dbType() === "redis" ? visibleDatabases() : null,
-          ssl_mode: sslMode(),
-          ssh_tunnel,
+          clear_password: clearsPassword(),
+          ...buildInput(),
         };
         await updateConnection(input);
       } else {
-        const input: SaveConnectionInput = {
-          name: name(),
-          db_type: dbType(),
-          host: dbType() === "sqlite" ? filePath() : host(),
-          port: dbType() === "sqlite" ? 0 : port(),
-          username: dbType() === "sqlite" ? "" : username(),
-          password: dbType() === "sqlite" ? "" : password(),
-          database: dbType() === "sqlite" ? null : database() || null,
-          category_id: categoryId(),
-          visible_databases: dbType() === "redis" ? visibleDatabases() : null,
-          ssl_mode: sslMode(),
-          ssh_tunnel,
-        };
-        await saveConnection(input);
+        await saveConnection(buildInput());
       }
       props.onSaved();
       props.onClose();
@@ -338,6 +530,8 @@ export function ConnectionForm(props: Props) {
 
   const isServerBased = () => dbType() !== "sqlite";
   const isRedis = () => dbType() === "redis";
+  // Cluster and Sentinel clients connect to the nodes the servers report, past any tunnel.
+  const redisSupportsTunnel = () => !isRedis() || redisMode() === "standalone";
 
   const selectedCategory = () =>
     props.categories.find((c) => c.id === categoryId());
@@ -468,9 +662,30 @@ export function ConnectionForm(props: Props) {
           </Show>
 
           <Show when={isServerBased()}>
+            <Show when={isRedis()}>
+              <div class="form-group">
+                <label for="redisMode">Topology</label>
+                <select
+                  id="redisMode"
+                  value={redisMode()}
+                  onChange={(e) => setRedisMode(e.currentTarget.value as RedisTopology["mode"])}
+                >
+                  <option value="standalone">Single server</option>
+                  <option value="cluster">Cluster</option>
+                  <option value="sentinel">Sentinel</option>
+                </select>
+              </div>
+            </Show>
+
             <div class="form-row">
               <div class="form-group flex-1">
-                <label for="host">Host</label>
+                <label for="host">
+                  {isRedis() && redisMode() === "cluster"
+                    ? "Seed Node"
+                    : isRedis() && redisMode() === "sentinel"
+                      ? "Sentinel Host"
+                      : "Host"}
+                </label>
                 <input
                   id="host"
                   type="text"
@@ -494,6 +709,37 @@ export function ConnectionForm(props: Props) {
               </div>
             </div>
 
+            <Show when={isRedis() && redisMode() !== "standalone"}>
+              <Show when={redisMode() === "sentinel"}>
+                <div class="form-group">
+                  <label for="redisMasterName">Master Name</label>
+                  <input
+                    id="redisMasterName"
+                    type="text"
+                    value={redisMasterName()}
+                    onInput={(e) => setRedisMasterName(e.currentTarget.value)}
+                    placeholder="mymaster"
+                    required
+                  />
+                </div>
+              </Show>
+              <div class="form-group">
+                <label for="redisNodes">{redisMode() === "cluster" ? "More Seed Nodes" : "More Sentinels"}</label>
+                <input
+                  id="redisNodes"
+                  type="text"
+                  value={redisNodes()}
+                  onInput={(e) => setRedisNodes(e.currentTarget.value)}
+                  placeholder="host:port, host:port"
+                />
+                <span class="field-hint">
+                  {redisMode() === "cluster"
+                    ? "Any node will do; the rest of the cluster is discovered from it."
+                    : "Sentinels are contacted without the username and password above."}
+                </span>
+              </div>
+            </Show>
+
             <div class="form-row">
               <div class="form-group flex-1">
                 <label for="username">Username</label>
@@ -506,12 +752,27 @@ export function ConnectionForm(props: Props) {
               </div>
               <div class="form-group flex-1">
                 <label for="password">Password</label>
-                <input
-                  id="password"
-                  type="password"
-                  value={password()}
-                  onInput={(e) => setPassword(e.currentTarget.value)}
-                />
+                <div class="form-row">
+                  <input
+                    id="password"
+                    type="password"
+                    value={password()}
+                    onInput={(e) => setPassword(e.currentTarget.value)}
+                    placeholder={passwordCleared() ? undefined : secretPlaceholder(storedSecrets().password)}
+                  />
+                  <Show when={isEditing() && storedSecrets().password && !passwordCleared()}>
+                    <button
+                      type="button"
+                      onClick={() => {
+                        setPassword("");
+                        setPasswordCleared(true);
+                      }}
+                      title="Remove the saved password"
+                    >
+                      Clear
+                    </button>
+                  </Show>
+                </div>
               </div>
             </div>
 
@@ -528,77 +789,216 @@ export function ConnectionForm(props: Props) {
               </div>
             </Show>
 
-            <Show when={!isRedis()}>
+            <div class="form-group">
+              <label for="sslMode">SSL Mode</label>
+              <select
+                id="sslMode"
+                value={sslMode()}
+                onChange={(e) => setSslMode(e.currentTarget.value as SslMode)}
+              >
+                <option value="disable">Disable</option>
+                <option value="prefer">{isRedis() ? "Prefer (plaintext for Redis)" : "Prefer"}</option>
+                <option value="require">Require (no certificate check)</option>
+                <option value="verify-ca">Verify CA</option>
+                <option value="verify-full">Verify CA and host name</option>
+              </select>
+            </div>
+
+            <Show when={sslMode() !== "disable"}>
               <div class="form-group">
-                <label>SSL Mode</label>
-                <div class="radio-group">
-                  <label>
-                    <input
-                      type="radio"
-                      name="sslMode"
-                      checked={sslMode() === "disable"}
-                      onChange={() => setSslMode("disable")}
-                    />
-                    Disable
-                  </label>
-                  <label>
-                    <input
-                      type="radio"
-                      name="sslMode"
-                      checked={sslMode() === "prefer"}
-                      onChange={() => setSslMode("prefer")}
-                    />
-                    Prefer
-                  </label>
-                  <label>
-                    <input
-                      type="radio"
-                      name="sslMode"
-                      checked={sslMode() === "require"}
-                      onChange={() => setSslMode("require")}
-                    />
-                    Require
-                  </label>
+                <label for="sslRootCert">CA Certificate (optional)</label>
+                <input
+                  id="sslRootCert"
+                  type="text"
+                  value={sslRootCert()}
+                  onInput={(e) => setSslRootCert(e.currentTarget.value)}
+                  placeholder="System trust store"
+                />
+              </div>
+              <div class="form-row">
+                <div class="form-group flex-1">
+                  <label for="sslClientCert">Client Certificate (optional)</label>
+                  <input
+                    id="sslClientCert"
+                    type="text"
+                    value={sslClientCert()}
+                    onInput={(e) => setSslClientCert(e.currentTarget.value)}
+                    placeholder="client.crt"
+                    required={!!sslClientKey()}
+                  />
+                </div>
+                <div class="form-group flex-1">
+                  <label for="sslClientKey">Client Key</label>
+                  <input
+                    id="sslClientKey"
+                    type="text"
+                    value={sslClientKey()}
+                    onInput={(e) => setSslClientKey(e.currentTarget.value)}
+                    placeholder="client.key"
+                    required={!!sslClientCert()}
+                  />
                 </div>
               </div>
             </Show>
 
-            <div class="form-group">
-              <label>
-                <input
-                  type="checkbox"
-                  checked={sshEnabled()}
-                  onChange={(e) => setSshEnabled(e.currentTarget.checked)}
-                />
-                {" "}Connect via SSH tunnel
-              </label>
-            </div>
+            <Show when={redisSupportsTunnel()}>
+              <div class="form-group">
+                <label>
+                  <input
+                    type="checkbox"
+                    checked={sshEnabled()}
+                    onChange={(e) => setSshEnabled(e.currentTarget.checked)}
+                  />
+                  {" "}Connect via SSH tunnel
+                </label>
+              </div>
+            </Show>
 
-            <Show when={sshEnabled()}>
+            <Show when={sshEnabled() && redisSupportsTunnel()}>
               <div class="ssh-tunnel-section" style={{ "border-left": "2px solid var(--border, #444)", "padding-left": "12px", "margin-bottom": "12px" }}>
-                <div class="form-row">
-                  <div class="form-group flex-1">
-                    <label for="sshHost">SSH Host</label>
-                    <input
-                      id="sshHost"
-                      type="text"
-                      value={sshHost()}
-                      onInput={(e) => setSshHost(e.currentTarget.value)}
-                      placeholder="bastion.example.com"
-                      required
-                    />
+                <div class="form-group">
+                  <label>Jump Hosts</label>
+                  <Index each={jumpHosts()}>
+                    {(hop, i) => (
+                      <div class="jump-host" style={{ "margin-bottom": "8px" }}>
+                        <div class="form-row">
+                          <div class="form-group flex-1">
+                            <input
+                              type="text"
+                              value={hop().host}
+                              onInput={(e) => updateJumpHost(i, { host: e.currentTarget.value })}
+                              placeholder={`jump${i + 1}.example.com`}
+                              required
+                            />
+                          </div>
+                          <div class="form-group port-field">
+                            <input
+                              type="number"
+                              value={hop().port}
+                              onInput={(e) => updateJumpHost(i, { port: parseInt(e.currentTarget.value) || 22 })}
+                              required
+                            />
+                          </div>
+                          <div class="form-group flex-1">
+                            <input
+                              type="text"
+                              value={hop().username}
+                              onInput={(e) => updateJumpHost(i, { username: e.currentTarget.value })}
+                              placeholder="Username"
+                              required
+                            />
+                          </div>
+                          <div class="form-group">
+                            <select
+                              value={hop().authType}
+                              onChange={(e) =>
+                                updateJumpHost(i, { authType: e.currentTarget.value as SshAuthMethod["type"] })
+                              }
+                            >
+                              <option value="agent">SSH Agent</option>
+                              <option value="privatekey">Private Key</option>
+                              <option value="password">Password</option>
+                            </select>
+                          </div>
+                          <button type="button" onClick={() => removeJumpHost(i)} title="Remove jump host">
+                            Remove
+                          </button>
+                        </div>
+                        <Show when={hop().authType === "privatekey"}>
+                          <div class="form-row">
+                            <div class="form-group flex-1">
+                              <input
+                                type="text"
+                                value={hop().keyPath}
+                                onInput={(e) => updateJumpHost(i, { keyPath: e.currentTarget.value })}
+                                placeholder="~/.ssh/id_ed25519"
+                                required
+                              />
+                            </div>
+                            <div class="form-group flex-1">
+                              <input
+                                type="password"
+                                value={hop().passphrase}
+                                onInput={(e) => updateJumpHost(i, { passphrase: e.currentTarget.value })}
+                                placeholder={
+                                  secretPlaceholder(storedHopSecrets(hop())?.ssh_passphrase) ??
+                                  "Key passphrase (optional)"
+                                }
+                              />
+                            </div>
+                          </div>
+                        </Show>
+                        <Show when={hop().authType === "password"}>
+                          <div class="form-group">
+                            <input
+                              type="password"
+                              value={hop().password}
+                              onInput={(e) => updateJumpHost(i, { password: e.currentTarget.value })}
+                              placeholder={
+                                secretPlaceholder(storedHopSecrets(hop())?.ssh_password) ?? "Password"
+                              }
+                              required={!storedHopSecrets(hop())?.ssh_password}
+                            />
+                          </div>
+                        </Show>
+                      </div>
+                    )}
+                  </Index>
+                  <button type="button" onClick={addJumpHost}>
+                    Add Jump Host
+                  </button>
+                </div>
+
+                <Show when={sshConfigHosts().length > 0 || sshConfigHost()}>
+                  <div class="form-group">
+                    <label for="sshConfigHost">Host from ~/.ssh/config</label>
+                    <select
+                      id="sshConfigHost"
+                      value={sshConfigHost()}
+                      onChange={(e) => setSshConfigHost(e.currentTarget.value)}
+                    >
+                      <option value="">None (enter manually)</option>
+                      <Show when={sshConfigHost() && !selectedSshConfigHost()}>
+                        <option value={sshConfigHost()}>{sshConfigHost()} (not found)</option>
+                      </Show>
+                      <For each={sshConfigHosts()}>
+                        {(h) => (
+                          <option value={h.alias}>
+                            {h.alias}
+                            {h.alias !== h.host_name ? ` (${h.host_name})` : ""}
+                            {h.proxy_jump ? ` via ${h.proxy_jump}` : ""}
+                          </option>
+                        )}
+                      </For>
+                    </select>
                   </div>
-                  <div class="form-group port-field">
-                    <label for="sshPort">Port</label>
-                    <input
-                      id="sshPort"
-                      type="number"
-                      value={sshPort()}
-                      onInput={(e) => setSshPort(parseInt(e.currentTarget.value) || 22)}
-                      required
-                    />
+                </Show>
+
+                <Show when={!sshConfigHost()}>
+                  <div class="form-row">
+                    <div class="form-group flex-1">
+                      <label for="sshHost">SSH Host</label>
+                      <input
+                        id="sshHost"
+                        type="text"
+                        value={sshHost()}
+                        onInput={(e) => setSshHost(e.currentTarget.value)}
+                        placeholder="bastion.example.com"
+                        required
+                      />
+                    </div>
+                    <div class="form-group port-field">
+                      <label for="sshPort">Port</label>
+                      <input
+                        id="sshPort"
+                        type="number"
+                        value={sshPort()}
+                        onInput={(e) => setSshPort(parseInt(e.currentTarget.value) || 22)}
+                        required
+                      />
+                    </div>
                   </div>
-                </div>
+                </Show>
 
                 <div class="form-group">
                   <label for="sshUsername">SSH Username</label>
@@ -607,7 +1007,12 @@ export function ConnectionForm(props: Props) {
                     type="text"
                     value={sshUsername()}
                     onInput={(e) => setSshUsername(e.currentTarget.value)}
-                    required
+                    placeholder={
+                      sshConfigHost()
+                        ? selectedSshConfigHost()?.user ?? "From ~/.ssh/config"
+                        : undefined
+                    }
+                    required={!sshConfigHost()}
                   />
                 </div>
 
@@ -663,6 +1068,7 @@ export function ConnectionForm(props: Props) {
                       type="password"
                       value={sshKeyPassphrase()}
                       onInput={(e) => setSshKeyPassphrase(e.currentTarget.value)}
+                      placeholder={secretPlaceholder(storedSecrets().ssh_passphrase)}
                     />
                   </div>
                 </Show>
@@ -675,7 +1081,8 @@ export function ConnectionForm(props: Props) {
                       type="password"
                       value={sshPassword()}
                       onInput={(e) => setSshPassword(e.currentTarget.value)}
-                      required
+                      placeholder={secretPlaceholder(storedSecrets().ssh_password)}
+                      required={!storedSecrets().ssh_password}
                     />
                   </div>
                 </Show>
@@ -686,25 +1093,162 @@ export function ConnectionForm(props: Props) {
               </div>
             </Show>
 
-            <Show when={isRedis()}>
+            <Show when={isRedis() && redisMode() !== "cluster"}>
               <div class="form-group">
                 <label for="visibleDatabases">Visible Databases</label>
                 <input
                   id="visibleDatabases"
                   type="number"
                   min="1"
-                  max="16"
                   value={visibleDatabases()}
                   onInput={(e) => setVisibleDatabases(parseInt(e.currentTarget.value) || 4)}
                 />
-                <span class="field-hint">Number of Redis databases to show (1-16)</span>
+                <span class="field-hint">Number of Redis databases to show; ones holding keys are always shown</span>
               </div>
             </Show>
           </Show>
 
+          <div class="form-group">
+            <label>
+              <input
+                type="checkbox"
+                checked={showAdvanced()}
+                onChange={(e) => setShowAdvanced(e.currentTarget.checked)}
+              />
+              {" "}Advanced options
+            </label>
+          </div>
+
+          <Show when={showAdvanced()}>
+            <div class="advanced-options-section" style={{ "border-left": "2px solid var(--border, #444)", "padding-left": "12px", "margin-bottom": "12px" }}>
+              <Show when={dbType() === "postgres"}>
+                <div class="form-row">
+                  <div class="form-group flex-1">
+                    <label for="applicationName">Application Name</label>
+                    <input
+                      id="applicationName"
+                      type="text"
+                      value={applicationName()}
+                      onInput={(e) => setApplicationName(e.currentTarget.value)}
+                      placeholder="dbui"
+                    />
+                  </div>
+                  <div class="form-group flex-1">
+                    <label for="searchPath">Search Path</label>
+                    <input
+                      id="searchPath"
+                      type="text"
+                      value={searchPath()}
+                      onInput={(e) => setSearchPath(e.currentTarget.value)}
+                      placeholder='"$user", public'
+                    />
+                  </div>
+                </div>
+              </Show>
+
+              <Show when={dbType() === "mysql"}>
+                <div class="form-group">
+                  <label for="charset">Character Set</label>
+                  <input
+                    id="charset"
+                    type="text"
+                    value={charset()}
+                    onInput={(e) => setCharset(e.currentTarget.value)}
+                    placeholder="utf8mb4"
+                  />
+                </div>
+              </Show>
+
+              <Show when={dbType() === "postgres" || dbType() === "mysql"}>
+                <div class="form-group">
+                  <label for="statementTimeout">Statement Timeout (ms)</label>
+                  <input
+                    id="statementTimeout"
+                    type="number"
+                    min="0"
+                    value={statementTimeout()}
+                    onInput={(e) => setStatementTimeout(e.currentTarget.value)}
+                    placeholder="No limit"
+                  />
+                  <Show when={dbType() === "mysql"}>
+                    <span class="field-hint">MySQL only applies this to SELECT statements</span>
+                  </Show>
+                </div>
+              </Show>
+
+              <Show when={isRedis()}>
+                <div class="form-group">
+                  <label for="keyDelimiter">Key Namespace Delimiter</label>
+                  <input
+                    id="keyDelimiter"
+                    type="text"
+                    value={keyDelimiter()}
+                    onInput={(e) => setKeyDelimiter(e.currentTarget.value)}
+                    placeholder=":"
+                  />
+                  <span class="field-hint">Keys are grouped by the part before the first delimiter</span>
+                </div>
+              </Show>
+
+              <Show when={!isRedis()}>
+                <div class="form-row">
+                  <div class="form-group flex-1">
+                    <label for="maxConnections">Pool Size</label>
+                    <input
+                      id="maxConnections"
+                      type="number"
+                      min="1"
+                      value={maxConnections()}
+                      onInput={(e) => setMaxConnections(e.currentTarget.value)}
+                      placeholder="10"
+                    />
+                  </div>
+                  <div class="form-group flex-1">
+                    <label for="idleTimeout">Idle Timeout (s)</label>
+                    <input
+                      id="idleTimeout"
+                      type="number"
+                      min="0"
+                      value={idleTimeout()}
+                      onInput={(e) => setIdleTimeout(e.currentTarget.value)}
+                      placeholder="600"
+                    />
+                  </div>
+                </div>
+              </Show>
+            </div>
+          </Show>
+
           {error() && <div class="error">{error()}</div>}
 
+          <Show when={testReport()}>
+            {(report) => (
+              <div class="connection-test-report">
+                <For each={report().steps}>
+                  {(step) => (
+                    <div class={`connection-test-step ${step.status}`}>
+                      <span class="step-status">{STEP_ICONS[step.status]}</span>
+                      <span class="step-name">
+                        {step.kind === "auth" && dbType() === "sqlite" ? "Open file" : STEP_NAMES[step.kind]}
+                      </span>
+                      <span class="step-time">{step.status === "skipped" ? "" : `${step.duration_ms} ms`}</span>
+                      <span class="step-detail">{step.detail}</span>
+                    </div>
+                  )}
+                </For>
+                <div class="connection-test-summary">
+                  {report().success
+                    ? `Connection succeeded in ${report().total_ms} ms`
+                    : `Failed at ${STEP_NAMES[report().failed_step!]}`}
+                  {report().server_version ? ` · Server ${report().server_version}` : ""}
+                </div>
+              </div>
+            )}
+          </Show>
           <div class="form-actions">
+            <button type="button" onClick={handleTest} disabled={testing() || saving()}>
+              {testing() ? "Testing..." : "Test Connection"}
+            </button>
             <button type="button" onClick={() => props.onClose()}>
               Cancel
             </button>
@@ -713,6 +1257,7 @@ export function ConnectionForm(props: Props) {
             </button>
           </div>
         </form>
+        {unlockPrompt.dialog()}
       </div>
     </div>
   );
@@ -71,6 +71,7 @@ export function ConnectionPath(props: Props) {
   };
 
   const getViewLabel = (viewType: string) => {
+    if (viewType === "redis-key") return "Key";
     return viewType.charAt(0).toUpperCase() + viewType.slice(1);
   };
 
@@ -3,7 +3,7 @@
 
 import { For, Show } from "solid-js";
 import { Icon } from "./Icon";
-import type { WorkingContext, Category } from "../lib/types";
+import type { WorkingContext, Category, TunnelStatus } from "../lib/types";
 
 import ejectSvg from "@phosphor-icons/core/assets/regular/eject.svg?raw";
 import plusSvg from "@phosphor-icons/core/assets/regular/plus.svg?raw";
@@ -19,6 +19,8 @@ interface Props {
   onConnectClick: () => void;
   onShowProcesses: (ctx: WorkingContext) => void;
   onShowUsers: (ctx: WorkingContext) => void;
+  /** The latest SSH tunnel status per connection id, for tunneled connections. */
+  tunnelStatuses: Record<string, TunnelStatus>;
 }
 
 interface GroupedContexts {
@@ -61,6 +63,21 @@ export function DatabaseList(props: Props) {
     return `${prefix}${ctx.database}`;
   };
 
+  const tunnelProblem = (ctx: WorkingContext) => {
+    const status = props.tunnelStatuses[ctx.connectionId];
+    return status && status.state !== "connected" ? status : null;
+  };
+
+  const tunnelTitle = (status: TunnelStatus) => {
+    if (status.state === "reconnecting") {
+      return `SSH tunnel dropped, reconnecting (attempt ${status.attempt} of ${status.max_attempts})`;
+    }
+    if (status.state === "failed") {
+      return `SSH tunnel could not reconnect: ${status.error}. Disconnect and connect again.`;
+    }
+    return "";
+  };
+
   const grouped = (): GroupedContexts[] => {
     const categoryMap = new Map<string, Category>();
     for (const cat of props.categories) {
@@ -132,6 +149,13 @@ export function DatabaseList(props: Props) {
                     alt={ctx.dbType}
                   />
                   <span class="db-entry-label">{formatLabel(ctx)}</span>
+                  <Show when={tunnelProblem(ctx)}>
+                    {(status) => (
+                      <span class={`db-entry-tunnel ${status().state}`} title={tunnelTitle(status())}>
+                        {status().state === "failed" ? "tunnel down" : "reconnecting"}
+                      </span>
+                    )}
+                  </Show>
                   <div class="db-entry-actions">
                     <Show when={ctx.dbType === "postgres" || ctx.dbType === "mysql"}>
                       <button
@@ -0,0 +1,62 @@
+// ABOUTME: Asks whether to trust an SSH tunnel host that no known_hosts file records.
+// ABOUTME: Shows the server key's fingerprint; trusting it records the key for future connects.
+
+import { createSignal, onMount, onCleanup } from "solid-js";
+import type { HostKeyPrompt } from "../lib/types";
+import { answerHostKeyPrompt } from "../lib/tauri";
+
+interface Props {
+  prompt: HostKeyPrompt;
+  onDone: () => void;
+}
+
+export function HostKeyPromptDialog(props: Props) {
+  const [error, setError] = createSignal<string | null>(null);
+
+  const answer = async (trust: boolean) => {
+    try {
+      await answerHostKeyPrompt(props.prompt.request_id, trust);
+      props.onDone();
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+    }
+  };
+
+  onMount(() => {
+    const handleKeyDown = (e: KeyboardEvent) => {
+      if (e.key === "Escape") answer(false);
+    };
+    document.addEventListener("keydown", handleKeyDown);
+    onCleanup(() => document.removeEventListener("keydown", handleKeyDown));
+  });
+
+  const hostLabel = () =>
+    props.prompt.port === 22
+      ? props.prompt.host
+      : `${props.prompt.host}:${props.prompt.port}`;
+
+  return (
+    <div class="modal-overlay">
+      <div class="modal create-name-dialog">
+        <h2>Unknown SSH Host</h2>
+        <p>
+          The authenticity of <strong>{hostLabel()}</strong> can't be established.
+          Check the fingerprint with the server's administrator before trusting it.
+        </p>
+        <div class="form-group">
+          <label>{props.prompt.key_type} key fingerprint</label>
+          <code>{props.prompt.fingerprint}</code>
+        </div>
+        {error() && <div class="error">{error()}</div>}
+        <div class="form-actions">
+          <button type="button" onClick={() => answer(false)}>
+            Reject
+          </button>
+          <button type="button" class="primary" onClick={() => answer(true)}>
+            Trust and Connect
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+}
@@ -4,7 +4,14 @@
 import { createSignal, createEffect, For, Show, on, onCleanup } from "solid-js";
 import { Icon } from "./Icon";
 import { ContextMenu, type ContextMenuItem } from "./ContextMenu";
-import type { WorkingContext, ObjectTab, MetadataView } from "../lib/types";
+import type {
+  WorkingContext,
+  ObjectTab,
+  MetadataView,
+  RedisNamespace,
+  RedisKeyInfo,
+  RedisBytes,
+} from "../lib/types";
 import {
   listTables,
   listViews,
@@ -16,8 +23,12 @@ import {
   listColumns,
   listIndexes,
   listConstraints,
+  refreshMetadata,
   switchDatabase,
+  redisListNamespaces,
+  redisScanNamespace,
 } from "../lib/tauri";
+import { redisText } from "../lib/resultExporter";
 
 import tableSvg from "@phosphor-icons/core/assets/regular/table.svg?raw";
 import eyeSvg from "@phosphor-icons/core/assets/regular/eye.svg?raw";
@@ -37,6 +48,7 @@ import flowArrowSvg from "@phosphor-icons/core/assets/regular/flow-arrow.svg?raw
 interface Props {
   context: WorkingContext | null;
   onTableSelect: (connectionId: string, database: string, schema: string, table: string) => void;
+  onRedisKeySelect: (connectionId: string, database: string, key: RedisBytes) => void;
   onFunctionSelect: (connectionId: string, database: string, schema: string, functionName: string) => void;
   onViewDefinitionSelect: (connectionId: string, database: string, schema: string, viewName: string) => void;
   onShowDependencyGraph: (ctx: WorkingContext) => void;
@@ -60,6 +72,17 @@ const TAB_LABELS: Record<ObjectTab, string> = {
 const REDIS_TABS = ["keys", "lists", "hashes", "sets", "sorted-sets"] as const;
 type RedisTab = (typeof REDIS_TABS)[number];
 
+const REDIS_KEY_PAGE_SIZE = 100;
+
+const formatTtl = (ms: number | null) => {
+  if (ms === null) return "";
+  const secs = Math.round(ms / 1000);
+  if (secs < 60) return `${secs}s`;
+  if (secs < 3600) return `${Math.round(secs / 60)}m`;
+  if (secs < 86400) return `${Math.round(secs / 3600)}h`;
+  return `${Math.round(secs / 86400)}d`;
+};
+
 export function ObjectPanel(props: Props) {
   const [activeTab, setActiveTab] = createSignal<ObjectTab>("tables");
   const [tables, setTables] = createSignal<string[]>([]);
@@ -77,6 +100,11 @@ export function ObjectPanel(props: Props) {
     items: ContextMenuItem[];
   } | null>(null);
   const [redisTab, setRedisTab] = createSignal<RedisTab>("keys");
+  const [namespaces, setNamespaces] = createSignal<RedisNamespace[]>([]);
+  const [openNamespace, setOpenNamespace] = createSignal<RedisNamespace | null>(null);
+  const [namespaceKeys, setNamespaceKeys] = createSignal<RedisKeyInfo[]>([]);
+  const [keyCursor, setKeyCursor] = createSignal("0");
+  const [loadingKeys, setLoadingKeys] = createSignal(false);
   const [moreOpen, setMoreOpen] = createSignal(false);
 
   let moreRef: HTMLDivElement | undefined;
@@ -117,10 +145,17 @@ export function ObjectPanel(props: Props) {
     setSequences([]);
     setTriggers([]);
     setProcedures([]);
+    setNamespaces([]);
+    setOpenNamespace(null);
 
     try {
       await switchDatabase(ctx.connectionId, ctx.database);
 
+      if (ctx.dbType === "redis") {
+        setNamespaces(await redisListNamespaces(ctx.connectionId, ctx.database));
+        return;
+      }
+
       const [t, v, f, mv, seq, trg, proc] = await Promise.all([
         listTables(ctx.connectionId, ctx.database, ctx.schema),
         listViews(ctx.connectionId, ctx.database, ctx.schema),
@@ -145,9 +180,15 @@ export function ObjectPanel(props: Props) {
     }
   };
 
-  const handleReload = () => {
+  // Listings are cached by the backend; reloading drops this schema's
+  // entries first so the server is actually asked again.
+  const handleReload = async () => {
     const ctx = props.context;
-    if (ctx) loadObjects(ctx);
+    if (!ctx) return;
+    if (ctx.dbType !== "redis") {
+      await refreshMetadata(ctx.connectionId, ctx.database, ctx.schema).catch(() => {});
+    }
+    loadObjects(ctx);
   };
 
   // Reload objects when context changes
@@ -163,6 +204,8 @@ export function ObjectPanel(props: Props) {
           setSequences([]);
           setTriggers([]);
           setProcedures([]);
+          setNamespaces([]);
+          setOpenNamespace(null);
           return;
         }
         loadObjects(props.context);
@@ -236,6 +279,37 @@ export function ObjectPanel(props: Props) {
     props.onQueryGenerate(commands[tab]);
   };
 
+  const loadKeys = async (ns: RedisNamespace, cursor: string) => {
+    const ctx = props.context;
+    if (!ctx) return;
+    setLoadingKeys(true);
+    setError(null);
+    try {
+      const page = await redisScanNamespace(ctx.connectionId, ctx.database, ns.name, cursor, REDIS_KEY_PAGE_SIZE);
+      if (openNamespace() !== ns) return;
+      setNamespaceKeys((keys) => (cursor === "0" ? page.keys : [...keys, ...page.keys]));
+      setKeyCursor(page.cursor);
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+    } finally {
+      setLoadingKeys(false);
+    }
+  };
+
+  const handleNamespaceClick = (ns: RedisNamespace) => {
+    setOpenNamespace(ns);
+    setNamespaceKeys([]);
+    setKeyCursor("0");
+    loadKeys(ns, "0");
+  };
+
+  const handleKeyClick = (key: RedisKeyInfo) => {
+    const ctx = props.context;
+    if (ctx) props.onRedisKeySelect(ctx.connectionId, ctx.database, key.key);
+  };
+
+  const namespaceLabel = (ns: RedisNamespace) => ns.name || "(no namespace)";
+
   const getTabIcon = (tab: ObjectTab) => {
     switch (tab) {
       case "tables": return tableSvg;
@@ -259,7 +333,7 @@ export function ObjectPanel(props: Props) {
     <div class="object-panel">
       <div class="object-panel-header">
         <span class="object-panel-title">Objects</span>
-        <Show when={props.context && !isRedis()}>
+        <Show when={props.context}>
           <button class="object-reload-btn" onClick={handleReload} title="Reload">
             <Icon svg={arrowsClockwiseSvg} size={14} />
           </button>
@@ -405,9 +479,70 @@ export function ObjectPanel(props: Props) {
             </For>
           </div>
           <div class="object-list">
-            <div class="object-empty">
-              Click a tab to browse Redis data
-            </div>
+            <Show when={loading()}>
+              <div class="object-empty">Loading...</div>
+            </Show>
+            <Show when={error()}>
+              <div class="object-error">{error()}</div>
+            </Show>
+            <Show when={!loading()}>
+              <Show
+                when={openNamespace()}
+                fallback={
+                  <>
+                    <Show when={!error() && namespaces().length === 0}>
+                      <div class="object-empty">No keys</div>
+                    </Show>
+                    <For each={namespaces()}>
+                      {(ns) => (
+                        <div class="object-item" onClick={() => handleNamespaceClick(ns)} title={ns.pattern}>
+                          <span class="object-item-icon">
+                            <Icon svg={stackSvg} size={12} />
+                          </span>
+                          <span class="object-item-label">{namespaceLabel(ns)}</span>
+                          <span class="object-tab-count">
+                            {ns.approximate ? "~" : ""}{ns.key_count}
+                          </span>
+                        </div>
+                      )}
+                    </For>
+                  </>
+                }
+              >
+                {(ns) => (
+                  <>
+                    <div class="object-item redis-namespace-back" onClick={() => setOpenNamespace(null)}>
+                      <span class="object-item-label">← {namespaceLabel(ns())}</span>
+                    </div>
+                    <For each={namespaceKeys()}>
+                      {(key) => (
+                        <div class="object-item" onClick={() => handleKeyClick(key)} title={redisText(key.key)}>
+                          <span class="object-item-icon">
+                            <Icon svg={rowsSvg} size={12} />
+                          </span>
+                          <span class="object-item-label">{redisText(key.key)}</span>
+                          <span class="object-tab-count">
+                            {key.key_type}
+                            {key.ttl_ms !== null ? ` · ${formatTtl(key.ttl_ms)}` : ""}
+                          </span>
+                        </div>
+                      )}
+                    </For>
+                    <Show when={loadingKeys()}>
+                      <div class="object-empty">Loading...</div>
+                    </Show>
+                    <Show when={!loadingKeys() && keyCursor() !== "0"}>
+                      <button class="object-panel-footer-btn redis-load-more" onClick={() => loadKeys(ns(), keyCursor())}>
+                        Load more
+                      </button>
+                    </Show>
+                    <Show when={!loadingKeys() && keyCursor() === "0" && namespaceKeys().length === 0}>
+                      <div class="object-empty">No keys</div>
+                    </Show>
+                  </>
+                )}
+              </Show>
+            </Show>
           </div>
         </Show>
       </Show>
@@ -0,0 +1,110 @@
+// ABOUTME: Displays an EXPLAIN plan as an indented tree of operations.
+// ABOUTME: Shows estimated vs. actual rows, cost, timing and warnings per node.
+
+import { For, Show, createSignal } from "solid-js";
+import type { PlanNode, QueryPlan } from "../lib/types";
+import { Icon } from "./Icon";
+import xSvg from "@phosphor-icons/core/assets/regular/x.svg?raw";
+import warningSvg from "@phosphor-icons/core/assets/regular/warning.svg?raw";
+
+interface Props {
+  plan: QueryPlan;
+  onClose: () => void;
+}
+
+const formatRows = (rows: number) =>
+  rows >= 1000 ? Math.round(rows).toLocaleString() : String(Math.round(rows * 100) / 100);
+
+const formatMs = (ms: number) => (ms >= 100 ? `${Math.round(ms)} ms` : `${ms.toFixed(2)} ms`);
+
+function PlanNodeRow(props: { node: PlanNode }) {
+  const metrics = () => {
+    const n = props.node;
+    const parts: string[] = [];
+    if (n.estimated_rows !== null) {
+      parts.push(
+        n.actual_rows !== null
+          ? `rows ${formatRows(n.actual_rows)} of est. ${formatRows(n.estimated_rows)}`
+          : `est. rows ${formatRows(n.estimated_rows)}`
+      );
+    } else if (n.actual_rows !== null) {
+      parts.push(`rows ${formatRows(n.actual_rows)}`);
+    }
+    if (n.loops !== null && n.loops > 1) parts.push(`× ${formatRows(n.loops)} loops`);
+    if (n.cost !== null) parts.push(`cost ${n.cost.toFixed(2)}`);
+    if (n.time_ms !== null) parts.push(formatMs(n.time_ms));
+    if (n.buffers) parts.push(`buffers hit ${n.buffers.hit}, read ${n.buffers.read}`);
+    return parts.join(" · ");
+  };
+
+  return (
+    <li class="plan-node">
+      <div class="plan-node-line" classList={{ "has-warning": props.node.warnings.length > 0 }}>
+        <span class="plan-operation">{props.node.operation}</span>
+        <Show when={props.node.relation}>
+          <span class="plan-relation"> on {props.node.relation}</span>
+        </Show>
+        <Show when={props.node.index}>
+          <span class="plan-index"> using {props.node.index}</span>
+        </Show>
+        <Show when={metrics()}>
+          <span class="plan-metrics">{metrics()}</span>
+        </Show>
+      </div>
+      <Show when={props.node.detail}>
+        <div class="plan-detail">{props.node.detail}</div>
+      </Show>
+      <For each={props.node.warnings}>
+        {(warning) => (
+          <div class="plan-warning">
+            <Icon svg={warningSvg} size={12} />
+            {warning.message}
+          </div>
+        )}
+      </For>
+      <Show when={props.node.children.length > 0}>
+        <ul class="plan-children">
+          <For each={props.node.children}>{(child) => <PlanNodeRow node={child} />}</For>
+        </ul>
+      </Show>
+    </li>
+  );
+}
+
+export function PlanViewer(props: Props) {
+  const [showRaw, setShowRaw] = createSignal(false);
+
+  return (
+    <div class="plan-viewer">
+      <div class="plan-viewer-header">
+        <div class="plan-viewer-title">
+          <h3>{props.plan.analyzed ? "Query plan (analyzed)" : "Query plan"}</h3>
+          <Show when={props.plan.planning_time_ms !== null}>
+            <span class="plan-timing">planning {formatMs(props.plan.planning_time_ms!)}</span>
+          </Show>
+          <Show when={props.plan.execution_time_ms !== null}>
+            <span class="plan-timing">execution {formatMs(props.plan.execution_time_ms!)}</span>
+          </Show>
+        </div>
+        <div class="plan-viewer-actions">
+          <button class="plan-raw-toggle" onClick={() => setShowRaw(!showRaw())}>
+            {showRaw() ? "Tree" : "Raw output"}
+          </button>
+          <button class="refresh-button" onClick={props.onClose} title="Back to results">
+            <Icon svg={xSvg} size={14} />
+          </button>
+        </div>
+      </div>
+      <div class="plan-viewer-content">
+        <Show
+          when={!showRaw()}
+          fallback={<pre class="plan-raw">{props.plan.raw}</pre>}
+        >
+          <ul class="plan-tree">
+            <PlanNodeRow node={props.plan.root} />
+          </ul>
+        </Show>
+      </div>
+    </div>
+  );
+}
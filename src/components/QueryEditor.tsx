@@ -1,13 +1,19 @@
 // ABOUTME: SQL query editor using CodeMirror.
-// ABOUTME: Provides syntax highlighting and query execution.
+// ABOUTME: Provides syntax highlighting, schema-aware completion and query execution.
 
 import { onMount, onCleanup, createEffect, Show } from "solid-js";
 import { EditorState, StateEffect, StateField, Prec } from "@codemirror/state";
 import { EditorView, keymap, Decoration, DecorationSet } from "@codemirror/view";
 import { basicSetup } from "codemirror";
+import {
+  autocompletion,
+  type CompletionContext,
+  type CompletionResult,
+} from "@codemirror/autocomplete";
 import { sql, PostgreSQL, MySQL, SQLite } from "@codemirror/lang-sql";
 import { oneDark } from "@codemirror/theme-one-dark";
-import type { DatabaseType } from "../lib/types";
+import type { DatabaseType, SuggestionKind } from "../lib/types";
+import { completeSql } from "../lib/tauri";
 import { Icon } from "./Icon";
 import caretLeftSvg from "@phosphor-icons/core/assets/regular/caret-left.svg?raw";
 import caretRightSvg from "@phosphor-icons/core/assets/regular/caret-right.svg?raw";
@@ -16,7 +22,12 @@ interface Props {
   value: string;
   onChange: (value: string) => void;
   onExecute: (query: string) => void;
+  /** Shows the plan for the statement at the cursor; omitted where the engine has none. */
+  onExplain?: (query: string, analyze: boolean) => void;
   dbType: DatabaseType | null;
+  connectionId: string | null;
+  database: string | null;
+  schema: string | null;
   disabled: boolean;
   canGoBack?: boolean;
   canGoForward?: boolean;
@@ -187,12 +198,57 @@ export function QueryEditor(props: Props) {
     }
   };
 
+  const completionTypes: Record<SuggestionKind, string> = {
+    column: "property",
+    table: "type",
+    view: "interface",
+    function: "function",
+    keyword: "keyword",
+  };
+
+  // Suggestions come ranked from the backend, which knows the schema; the
+  // boost keeps CodeMirror from re-sorting them alphabetically.
+  const completeFromSchema = async (
+    context: CompletionContext
+  ): Promise<CompletionResult | null> => {
+    const connectionId = props.connectionId;
+    if (!connectionId || props.dbType === "redis") return null;
+    const word = context.matchBefore(/[\w$]*/);
+    const afterDot = context.state.sliceDoc(context.pos - 1, context.pos) === ".";
+    if (!context.explicit && !afterDot && (!word || word.from === word.to)) return null;
+
+    const completion = await completeSql(
+      connectionId,
+      props.database ?? "",
+      props.schema ?? "",
+      context.state.doc.toString(),
+      context.pos
+    ).catch(() => null);
+    if (!completion || context.aborted || completion.suggestions.length === 0) return null;
+
+    return {
+      from: completion.from,
+      filter: false,
+      options: completion.suggestions.map((s, i) => ({
+        label: s.label,
+        type: completionTypes[s.kind],
+        detail: s.detail ?? undefined,
+        boost: 99 - i,
+      })),
+    };
+  };
+
   const handleExecute = () => {
     if (props.disabled) return;
     const queryToRun = getQueryAtCursor();
     props.onExecute(queryToRun);
   };
 
+  const handleExplain = (analyze: boolean) => {
+    if (props.disabled) return;
+    props.onExplain?.(getQueryAtCursor(), analyze);
+  };
+
   onMount(() => {
     if (!containerRef) return;
 
@@ -208,6 +264,7 @@ export function QueryEditor(props: Props) {
       extensions: [
         basicSetup,
         sql({ dialect }),
+        autocompletion({ override: [completeFromSchema] }),
         oneDark,
         Prec.highest(
           keymap.of([
@@ -306,6 +363,24 @@ export function QueryEditor(props: Props) {
       </div>
       <div ref={containerRef} class="editor-container" />
       <div class="editor-footer">
+        <Show when={props.onExplain}>
+          <button
+            class="explain-btn"
+            onClick={() => handleExplain(false)}
+            disabled={props.disabled}
+            title="Show the estimated plan without running the query"
+          >
+            Explain
+          </button>
+          <button
+            class="explain-btn"
+            onClick={() => handleExplain(true)}
+            disabled={props.disabled}
+            title="Run the query inside a rolled-back transaction and show the measured plan"
+          >
+            Explain Analyze
+          </button>
+        </Show>
         <button
           class="run-btn"
           onClick={handleExecute}
@@ -0,0 +1,322 @@
+// ABOUTME: Type-aware viewer and editor for a single Redis key.
+// ABOUTME: Pages through large collections and edits values, members and TTL in place.
+
+import { createSignal, createEffect, on, For, Show } from "solid-js";
+import type { RedisBytes, RedisKeyValue, RedisValueData } from "../lib/types";
+import { isBinaryValue, redisText } from "../lib/resultExporter";
+import {
+  redisGetKey,
+  redisSetString,
+  redisSetHashFields,
+  redisSetListItem,
+  redisPushListItems,
+  redisSetSetMembers,
+  redisSetZsetMembers,
+  redisSetStreamEntry,
+  redisSetJson,
+  redisSetTtl,
+  redisDeleteMembers,
+} from "../lib/tauri";
+import { Icon } from "./Icon";
+import arrowsClockwiseSvg from "@phosphor-icons/core/assets/regular/arrows-clockwise.svg?raw";
+import trashSvg from "@phosphor-icons/core/assets/regular/trash.svg?raw";
+
+interface Props {
+  connectionId: string;
+  database: string;
+  keyName: RedisBytes;
+}
+
+const PAGE_SIZE = 200;
+
+type CollectionKind = "hash" | "list" | "set" | "zset" | "stream";
+
+/** A collection element as a table row; `id` is what delete_members removes. */
+interface Row {
+  id: RedisBytes;
+  cells: string[];
+  /** Values that fill the edit form when the row is clicked; null when they aren't text. */
+  form: [string, string] | null;
+}
+
+/** The form values for a row, unless one is bytes the text inputs can't hold. */
+const textForm = (a: RedisBytes, b: RedisBytes): [string, string] | null =>
+  isBinaryValue(a) || isBinaryValue(b) ? null : [a, b];
+
+/** Column headers, and the edit form's two inputs (an empty label hides the second). */
+const COLLECTIONS: Record<CollectionKind, { columns: string[]; inputs: [string, string] }> = {
+  hash: { columns: ["Field", "Value"], inputs: ["Field", "Value"] },
+  list: { columns: ["Index", "Value"], inputs: ["Index (empty to append)", "Value"] },
+  set: { columns: ["Member"], inputs: ["Member", ""] },
+  zset: { columns: ["Member", "Score"], inputs: ["Member", "Score"] },
+  stream: { columns: ["ID", "Fields"], inputs: ["Field", "Value"] },
+};
+
+function toRows(data: RedisValueData): Row[] {
+  switch (data.kind) {
+    case "hash":
+      return data.fields.map((f) => ({
+        id: f.field,
+        cells: [redisText(f.field), redisText(f.value)],
+        form: textForm(f.field, f.value),
+      }));
+    case "list":
+      return data.items.map((item, i) => {
+        const index = String(data.offset + i);
+        return { id: item, cells: [index, redisText(item)], form: textForm(index, item) };
+      });
+    case "set":
+      return data.members.map((m) => ({ id: m, cells: [redisText(m)], form: textForm(m, "") }));
+    case "zset":
+      return data.members.map((m) => ({
+        id: m.member,
+        cells: [redisText(m.member), String(m.score)],
+        form: textForm(m.member, String(m.score)),
+      }));
+    case "stream":
+      return data.entries.map((e) => ({
+        id: e.id,
+        cells: [e.id, e.fields.map((f) => `${redisText(f.field)}=${redisText(f.value)}`).join("  ")],
+        form: null,
+      }));
+    default:
+      return [];
+  }
+}
+
+const formatTtl = (ms: number | null) => (ms === null ? "No expiry" : `${(ms / 1000).toFixed(ms < 10_000 ? 1 : 0)}s`);
+
+export function RedisKeyViewer(props: Props) {
+  const [value, setValue] = createSignal<RedisKeyValue | null>(null);
+  const [rows, setRows] = createSignal<Row[]>([]);
+  const [loading, setLoading] = createSignal(false);
+  const [error, setError] = createSignal<string | null>(null);
+  const [text, setText] = createSignal("");
+  const [inputA, setInputA] = createSignal("");
+  const [inputB, setInputB] = createSignal("");
+  const [ttlInput, setTtlInput] = createSignal("");
+
+  const kind = () => value()?.data.kind;
+  const collection = () => {
+    const k = kind();
+    return k && k in COLLECTIONS ? COLLECTIONS[k as CollectionKind] : null;
+  };
+  /** Why the string can't be saved from the text box, or null when it can. */
+  const readOnlyReason = () => {
+    const data = value()?.data;
+    if (data?.kind !== "string") return null;
+    if (data.truncated) return "Showing the first 1 MiB; large values cannot be edited here.";
+    if (isBinaryValue(data.value)) return "This value isn't UTF-8 and is shown as hex; it cannot be edited here.";
+    return null;
+  };
+
+  const load = async (cursor: string | null = null) => {
+    setLoading(true);
+    setError(null);
+    try {
+      const page = await redisGetKey(props.connectionId, props.database, props.keyName, cursor, PAGE_SIZE);
+      setValue(page);
+      setRows((existing) => (cursor ? [...existing, ...toRows(page.data)] : toRows(page.data)));
+      if (!cursor) {
+        if (page.data.kind === "string") setText(redisText(page.data.value));
+        if (page.data.kind === "json") setText(JSON.stringify(page.data.value, null, 2));
+        setTtlInput(page.ttl_ms === null ? "" : String(Math.round(page.ttl_ms / 1000)));
+      }
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  createEffect(on(() => [props.connectionId, props.database, props.keyName], () => load()));
+
+  const run = async (action: () => Promise<unknown>) => {
+    setError(null);
+    try {
+      await action();
+      setInputA("");
+      setInputB("");
+      await load();
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+    }
+  };
+
+  const args = () => [props.connectionId, props.database, props.keyName] as const;
+
+  const saveText = () =>
+    run(async () => {
+      if (kind() === "json") {
+        let parsed: unknown;
+        try {
+          parsed = JSON.parse(text());
+        } catch (err) {
+          throw new Error(`Invalid JSON: ${err instanceof Error ? err.message : String(err)}`);
+        }
+        await redisSetJson(...args(), parsed);
+      } else {
+        await redisSetString(...args(), text());
+      }
+    });
+
+  const saveMember = () =>
+    run(async () => {
+      const a = inputA();
+      const b = inputB();
+      switch (kind()) {
+        case "hash":
+          return redisSetHashFields(...args(), [[a, b]]);
+        case "list":
+          return a.trim() === ""
+            ? redisPushListItems(...args(), [b], false)
+            : redisSetListItem(...args(), parseInt(a, 10), b);
+        case "set":
+          return redisSetSetMembers(...args(), [a]);
+        case "zset": {
+          const score = parseFloat(b);
+          if (Number.isNaN(score)) throw new Error("Score must be a number");
+          return redisSetZsetMembers(...args(), [{ member: a, score }]);
+        }
+        case "stream":
+          return redisSetStreamEntry(...args(), [[a, b]]);
+      }
+    });
+
+  const deleteRow = (row: Row) => run(() => redisDeleteMembers(...args(), [row.id]));
+
+  const saveTtl = () =>
+    run(() => {
+      const secs = parseFloat(ttlInput());
+      return redisSetTtl(...args(), ttlInput().trim() === "" || Number.isNaN(secs) ? null : Math.round(secs * 1000));
+    });
+
+  return (
+    <div class="redis-key-viewer">
+      <div class="function-viewer-header">
+        <div class="function-viewer-title">
+          <h3>{redisText(props.keyName)}</h3>
+          <Show when={value()}>
+            {(v) => (
+              <>
+                <span class="function-language">{v().key_type}</span>
+                <Show when={v().encoding}>
+                  <span class="function-return-type">{v().encoding}</span>
+                </Show>
+                <Show when={v().length !== null}>
+                  <span class="function-return-type">
+                    {v().length} {v().data.kind === "string" ? "bytes" : "items"}
+                  </span>
+                </Show>
+                <Show when={v().memory_bytes !== null}>
+                  <span class="function-return-type">{v().memory_bytes} B in memory</span>
+                </Show>
+              </>
+            )}
+          </Show>
+        </div>
+        <div class="redis-key-ttl">
+          <span class="function-return-type">{formatTtl(value()?.ttl_ms ?? null)}</span>
+          <input
+            type="number"
+            min="0"
+            value={ttlInput()}
+            onInput={(e) => setTtlInput(e.currentTarget.value)}
+            placeholder="TTL (s)"
+          />
+          <button class="btn-secondary" onClick={saveTtl}>
+            {ttlInput().trim() === "" ? "Persist" : "Set TTL"}
+          </button>
+          <button class="refresh-button" onClick={() => load()} disabled={loading()} title="Refresh">
+            <Icon svg={arrowsClockwiseSvg} size={14} />
+          </button>
+        </div>
+      </div>
+
+      <Show when={error()}>
+        <div class="object-error">{error()}</div>
+      </Show>
+
+      <div class="redis-key-content">
+        <Show when={kind() === "string" || kind() === "json"}>
+          <Show when={readOnlyReason()}>
+            <div class="field-hint">{readOnlyReason()}</div>
+          </Show>
+          <textarea
+            class="redis-key-text"
+            value={text()}
+            onInput={(e) => setText(e.currentTarget.value)}
+            spellcheck={false}
+          />
+          <div class="redis-key-actions">
+            <button class="btn-primary" onClick={saveText} disabled={readOnlyReason() !== null}>
+              Save
+            </button>
+          </div>
+        </Show>
+
+        <Show when={collection()}>
+          {(info) => (
+            <>
+              <table class="redis-key-table">
+                <thead>
+                  <tr>
+                    <For each={info().columns}>{(column) => <th>{column}</th>}</For>
+                    <th />
+                  </tr>
+                </thead>
+                <tbody>
+                  <For each={rows()}>
+                    {(row) => (
+                      <tr
+                        onClick={() => {
+                          if (!row.form) return;
+                          setInputA(row.form[0]);
+                          setInputB(row.form[1]);
+                        }}
+                      >
+                        <For each={row.cells}>{(cell) => <td>{cell}</td>}</For>
+                        <td class="redis-key-row-actions">
+                          <button
+                            class="refresh-button"
+                            title="Delete"
+                            onClick={(e) => {
+                              e.stopPropagation();
+                              deleteRow(row);
+                            }}
+                          >
+                            <Icon svg={trashSvg} size={12} />
+                          </button>
+                        </td>
+                      </tr>
+                    )}
+                  </For>
+                </tbody>
+              </table>
+              <Show when={value()?.cursor}>
+                {(cursor) => (
+                  <button class="object-panel-footer-btn redis-load-more" onClick={() => load(cursor())} disabled={loading()}>
+                    Load more
+                  </button>
+                )}
+              </Show>
+              <div class="redis-key-actions">
+                <input value={inputA()} onInput={(e) => setInputA(e.currentTarget.value)} placeholder={info().inputs[0]} />
+                <Show when={info().inputs[1]}>
+                  <input value={inputB()} onInput={(e) => setInputB(e.currentTarget.value)} placeholder={info().inputs[1]} />
+                </Show>
+                <button class="btn-primary" onClick={saveMember}>
+                  {kind() === "stream" ? "Add entry" : "Save"}
+                </button>
+              </div>
+            </>
+          )}
+        </Show>
+
+        <Show when={kind() === "unsupported"}>
+          <div class="object-empty">This key type cannot be shown here. Use the query editor instead.</div>
+        </Show>
+      </div>
+    </div>
+  );
+}
@@ -4,7 +4,13 @@
 import { createSignal, createEffect, onCleanup, For, Show } from "solid-js";
 import type { QueryResult, QueryProgress, CellSelection, TableContext, DatabaseType } from "../lib/types";
 import type { RowEdit } from "../lib/updateQueryGenerator";
-import { exportAsJson, exportAsSqlInsert } from "../lib/resultExporter";
+import {
+  exportAsJson,
+  exportAsSqlInsert,
+  isBinaryValue,
+  isRangeValue,
+  rangeLiteral,
+} from "../lib/resultExporter";
 import { readText } from "@tauri-apps/plugin-clipboard-manager";
 import { ContextMenu, type ContextMenuItem } from "./ContextMenu";
 
@@ -13,6 +19,7 @@ import { Icon } from "./Icon";
 import arrowRightSvg from "@phosphor-icons/core/assets/regular/arrow-right.svg?raw";
 import trashSvg from "@phosphor-icons/core/assets/regular/trash.svg?raw";
 import pencilSvg from "@phosphor-icons/core/assets/regular/pencil-simple.svg?raw";
+import floppyDiskSvg from "@phosphor-icons/core/assets/regular/floppy-disk.svg?raw";
 import exportSvg from "@phosphor-icons/core/assets/regular/export.svg?raw";
 
 interface Props {
@@ -27,6 +34,10 @@ interface Props {
   primaryKeyColumns?: string[];
   onGenerateDelete?: (rowIndices: number[]) => void;
   onGenerateUpdate?: (edits: RowEdit[]) => void;
+  /** Writes edits and deletions straight to the table instead of generating SQL. */
+  onSaveChanges?: (edits: RowEdit[], deletions: number[]) => void;
+  /** Per column, whether it traces back to an editable table column. Overrides tableContext. */
+  editableColumns?: boolean[];
   onGenerateKill?: (rowIndices: number[]) => void;
   onPendingChangesChange?: (hasPending: boolean) => void;
   onFilterByValue?: (columnName: string, value: unknown, mode: FilterMode) => void;
@@ -87,6 +98,9 @@ export function ResultsTable(props: Props) {
     props.tableContext !== null &&
     props.tableContext !== undefined;
 
+  const canEditColumn = (colIndex: number) =>
+    props.editableColumns ? props.editableColumns[colIndex] === true : canEdit();
+
   const canDelete = () =>
     (props.tableContext !== null && props.tableContext !== undefined) ||
     props.onGenerateKill !== undefined;
@@ -152,7 +166,7 @@ export function ResultsTable(props: Props) {
 
   const startEditing = (rowIndex: number, colIndex: number, currentValue: unknown) => {
     if (markedForDeletion().includes(rowIndex)) return;
-    if (!canEdit()) return;
+    if (!canEditColumn(colIndex)) return;
 
     const edited = editedCells().get(rowIndex)?.get(colIndex);
     const valueToEdit = edited !== undefined ? edited : currentValue;
@@ -208,24 +222,33 @@ export function ResultsTable(props: Props) {
     setEditValue("");
   };
 
-  const handleGenerateUpdate = () => {
-    const edits = editedCells();
-    if (edits.size === 0 || !props.onGenerateUpdate || !props.result) return;
-
+  const collectRowEdits = (): RowEdit[] => {
     const rowEdits: RowEdit[] = [];
-    edits.forEach((changes, rowIndex) => {
+    editedCells().forEach((changes, rowIndex) => {
       rowEdits.push({
         rowIndex,
         originalRow: props.result!.rows[rowIndex],
         changes,
       });
     });
+    return rowEdits;
+  };
 
-    props.onGenerateUpdate(rowEdits);
+  const handleGenerateUpdate = () => {
+    if (editedCells().size === 0 || !props.onGenerateUpdate || !props.result) return;
+
+    props.onGenerateUpdate(collectRowEdits());
     setEditedCells(new Map());
     setMarkedForDeletion([]);
   };
 
+  // Edits stay in place until the refreshed result arrives, so a failed save
+  // can be corrected and retried.
+  const handleSaveChanges = () => {
+    if (!props.onSaveChanges || !props.result) return;
+    props.onSaveChanges(collectRowEdits(), markedForDeletion());
+  };
+
   const getCellDisplayValue = (rowIndex: number, colIndex: number, originalValue: unknown): unknown => {
     const rowEdits = editedCells().get(rowIndex);
     if (rowEdits?.has(colIndex)) {
@@ -242,6 +265,12 @@ export function ResultsTable(props: Props) {
     if (value === null || value === undefined) {
       return "NULL";
     }
+    if (isBinaryValue(value)) {
+      return `0x${value.hex}`;
+    }
+    if (isRangeValue(value)) {
+      return rangeLiteral(value);
+    }
     if (typeof value === "object") {
       return JSON.stringify(value);
     }
@@ -424,6 +453,18 @@ export function ResultsTable(props: Props) {
               </div>
             </Show>
           </Show>
+          <Show
+            when={
+              props.onSaveChanges &&
+              (props.tableContext || props.editableColumns?.some((e) => e)) &&
+              editedCells().size + markedForDeletion().length > 0
+            }
+          >
+            <button class="generate-update-btn" onClick={handleSaveChanges}>
+              <Icon svg={floppyDiskSvg} size={14} />
+              Save changes ({editedCells().size + markedForDeletion().length})
+            </button>
+          </Show>
           <Show when={editedCells().size > 0}>
             <button class="generate-update-btn" onClick={handleGenerateUpdate}>
               <Icon svg={pencilSvg} size={14} />
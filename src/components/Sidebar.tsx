@@ -1,10 +1,12 @@
 // ABOUTME: Left sidebar with two sections: Databases list and Objects browser.
 // ABOUTME: Manages working contexts (connected databases) and object navigation.
 
-import { createSignal, Show, onMount } from "solid-js";
+import { createSignal, Show, onMount, onCleanup } from "solid-js";
+import { listen } from "@tauri-apps/api/event";
 import { Icon } from "./Icon";
-import type { WorkingContext, Category, MetadataView } from "../lib/types";
-import { disconnect, listCategories, switchDatabase } from "../lib/tauri";
+import type { WorkingContext, Category, MetadataView, TunnelStatus, RedisBytes } from "../lib/types";
+import { confirm } from "@tauri-apps/plugin-dialog";
+import { disconnect, listCategories, openTransactionCount, switchDatabase } from "../lib/tauri";
 import { DatabaseList } from "./DatabaseList";
 import { ObjectPanel } from "./ObjectPanel";
 import { ConnectDialog } from "./ConnectDialog";
@@ -20,6 +22,7 @@ interface Props {
   onConnectionChange: (id: string | null) => void;
   onDatabaseSwitch: (database: string, schema: string | null) => void;
   onTableSelect: (connectionId: string, database: string, schema: string, table: string) => void;
+  onRedisKeySelect: (connectionId: string, database: string, key: RedisBytes) => void;
   onQueryGenerate: (query: string) => void;
   onMetadataSelect: (view: MetadataView) => void;
   onFunctionSelect: (connectionId: string, database: string, schema: string, functionName: string) => void;
@@ -38,6 +41,8 @@ export function Sidebar(props: Props) {
   const [showConnectionManager, setShowConnectionManager] = createSignal(false);
   const [showCloudImport, setShowCloudImport] = createSignal(false);
   const [error, setError] = createSignal<string | null>(null);
+  const [tunnelStatuses, setTunnelStatuses] = createSignal<Record<string, TunnelStatus>>({});
+  const [notice, setNotice] = createSignal<string | null>(null);
 
   const loadCategories = async () => {
     try {
@@ -50,6 +55,26 @@ export function Sidebar(props: Props) {
 
   onMount(() => {
     loadCategories();
+
+    // SSH tunnels reconnect on their own; show it while it happens and say
+    // when it worked or gave up.
+    const unlistenTunnels = listen<TunnelStatus>("tunnel-status", (event) => {
+      const status = event.payload;
+      const previous = tunnelStatuses()[status.connection_id];
+      setTunnelStatuses((statuses) => ({ ...statuses, [status.connection_id]: status }));
+      const name =
+        contexts().find((c) => c.connectionId === status.connection_id)?.connectionName ?? "connection";
+      if (status.forward_error) {
+        setError(`SSH tunnel for ${name}: ${status.forward_error}`);
+      } else if (status.state === "failed") {
+        setError(`SSH tunnel for ${name} is down: ${status.error}`);
+      } else if (status.state === "connected" && previous?.state === "reconnecting") {
+        setNotice(`SSH tunnel for ${name} reconnected`);
+      }
+    });
+    onCleanup(() => {
+      unlistenTunnels.then((fn) => fn()).catch(() => {});
+    });
   });
 
   const activeContext = (): WorkingContext | null => {
@@ -79,8 +104,16 @@ export function Sidebar(props: Props) {
 
     // Only disconnect the backend connection if this is the last context for it
     if (remaining.length === 0) {
+      const openTransactions = await openTransactionCount(ctx.connectionId).catch(() => 0);
+      if (openTransactions > 0) {
+        const confirmed = await confirm(
+          `${openTransactions} open transaction(s) on this connection will be rolled back. Disconnect anyway?`,
+          { title: "Open Transactions", kind: "warning" }
+        );
+        if (!confirmed) return;
+      }
       try {
-        await disconnect(ctx.connectionId);
+        await disconnect(ctx.connectionId, true);
       } catch (err) {
         setError(err instanceof Error ? err.message : String(err));
       }
@@ -140,11 +173,15 @@ export function Sidebar(props: Props) {
       <Show when={error()}>
         <Toast message={error()!} type="error" onDismiss={() => setError(null)} />
       </Show>
+      <Show when={notice()}>
+        <Toast message={notice()!} type="info" onDismiss={() => setNotice(null)} />
+      </Show>
 
       <DatabaseList
         contexts={contexts()}
         categories={categories()}
         activeContextId={activeContextId()}
+        tunnelStatuses={tunnelStatuses()}
         onContextSelect={handleContextSelect}
         onDisconnect={handleDisconnect}
         onConnectClick={() => setShowConnectDialog(true)}
@@ -155,6 +192,7 @@ export function Sidebar(props: Props) {
       <ObjectPanel
         context={activeContext()}
         onTableSelect={props.onTableSelect}
+        onRedisKeySelect={props.onRedisKeySelect}
         onFunctionSelect={props.onFunctionSelect}
         onViewDefinitionSelect={props.onViewDefinitionSelect}
         onShowDependencyGraph={props.onShowDependencyGraph}
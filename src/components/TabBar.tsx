@@ -2,7 +2,9 @@
 // ABOUTME: Displays tab titles with close buttons and active tab highlighting.
 
 import { For, Show, createMemo } from "solid-js";
+import { confirm } from "@tauri-apps/plugin-dialog";
 import { useStore, type Tab } from "../lib/store";
+import { closeSession, transactionStatus } from "../lib/tauri";
 import { Icon } from "./Icon";
 import xSvg from "@phosphor-icons/core/assets/regular/x.svg?raw";
 import plusSvg from "@phosphor-icons/core/assets/regular/plus.svg?raw";
@@ -67,6 +69,16 @@ export function TabBar(props: Props) {
       if (!confirmed) return;
     }
 
+    const status = await transactionStatus(tabId).catch(() => null);
+    if (status?.active) {
+      const confirmed = await confirm(
+        "This tab has an open transaction. Closing it will roll the transaction back. Close anyway?",
+        { title: "Open Transaction", kind: "warning" }
+      );
+      if (!confirmed) return;
+    }
+    await closeSession(tabId, true).catch(console.error);
+
     closeTab(tabId);
   };
 
@@ -0,0 +1,146 @@
+// ABOUTME: Master password prompt for the encrypted secret vault.
+// ABOUTME: Creates the vault on first use, otherwise unlocks it for the session.
+
+import { createSignal, onMount, onCleanup, Show, type JSX } from "solid-js";
+import type { SecretStoreStatus } from "../lib/types";
+import { secretStoreStatus, unlockSecretStore } from "../lib/tauri";
+
+interface Props {
+  status: SecretStoreStatus;
+  onUnlocked: () => void;
+  onClose: () => void;
+}
+
+export function UnlockVaultDialog(props: Props) {
+  const [password, setPassword] = createSignal("");
+  const [confirm, setConfirm] = createSignal("");
+  const [error, setError] = createSignal<string | null>(null);
+  const [unlocking, setUnlocking] = createSignal(false);
+  let inputRef: HTMLInputElement | undefined;
+
+  const creating = () => !props.status.initialized;
+
+  onMount(() => {
+    inputRef?.focus();
+    // Capture phase, so Escape closes only this prompt and not the dialog
+    // that opened it.
+    const handleKeyDown = (e: KeyboardEvent) => {
+      if (e.key === "Escape") {
+        e.stopPropagation();
+        props.onClose();
+      }
+    };
+    document.addEventListener("keydown", handleKeyDown, true);
+    onCleanup(() => document.removeEventListener("keydown", handleKeyDown, true));
+  });
+
+  const handleSubmit = async (e: Event) => {
+    e.preventDefault();
+    if (!password()) return;
+    if (creating() && password() !== confirm()) {
+      setError("The passwords do not match.");
+      return;
+    }
+
+    setError(null);
+    setUnlocking(true);
+    try {
+      await unlockSecretStore(password());
+      props.onUnlocked();
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+    } finally {
+      setUnlocking(false);
+    }
+  };
+
+  return (
+    <div class="modal-overlay" onClick={() => props.onClose()}>
+      <div class="modal create-name-dialog" onClick={(e) => e.stopPropagation()}>
+        <h2>{creating() ? "Create Master Password" : "Unlock Saved Passwords"}</h2>
+        <form onSubmit={handleSubmit}>
+          <Show when={creating()}>
+            <p>
+              Connection passwords are stored encrypted with a master password.
+              It cannot be recovered if you forget it.
+            </p>
+          </Show>
+          <div class="form-group">
+            <label for="master-password">Master Password</label>
+            <input
+              ref={inputRef}
+              id="master-password"
+              type="password"
+              value={password()}
+              onInput={(e) => setPassword(e.currentTarget.value)}
+              required
+            />
+          </div>
+          <Show when={creating()}>
+            <div class="form-group">
+              <label for="master-password-confirm">Confirm Master Password</label>
+              <input
+                id="master-password-confirm"
+                type="password"
+                value={confirm()}
+                onInput={(e) => setConfirm(e.currentTarget.value)}
+                required
+              />
+            </div>
+          </Show>
+          {error() && <div class="error">{error()}</div>}
+          <div class="form-actions">
+            <button type="button" onClick={() => props.onClose()}>
+              Cancel
+            </button>
+            <button type="submit" class="primary" disabled={unlocking() || !password()}>
+              {unlocking() ? "Unlocking..." : creating() ? "Create" : "Unlock"}
+            </button>
+          </div>
+        </form>
+      </div>
+    </div>
+  );
+}
+
+interface PendingUnlock {
+  status: SecretStoreStatus;
+  resolve: (unlocked: boolean) => void;
+}
+
+/**
+ * Asks for the master password when a saved password is about to be read or
+ * written while the vault is locked. `ensureUnlocked` resolves to false if
+ * the user cancels; render `dialog` somewhere in the calling component.
+ */
+export function createUnlockPrompt(): {
+  ensureUnlocked: () => Promise<boolean>;
+  dialog: () => JSX.Element;
+} {
+  const [pending, setPending] = createSignal<PendingUnlock | null>(null);
+
+  const ensureUnlocked = async () => {
+    const status = await secretStoreStatus();
+    if (status.unlocked) return true;
+    return new Promise<boolean>((resolve) => setPending({ status, resolve }));
+  };
+
+  const finish = (unlocked: boolean) => {
+    pending()?.resolve(unlocked);
+    setPending(null);
+  };
+
+  const dialog = () => (
+    <Show when={pending()}>
+      {(p) => (
+        <UnlockVaultDialog
+          status={p().status}
+          onUnlocked={() => finish(true)}
+          onClose={() => finish(false)}
+        />
+      )}
+    </Show>
+  );
+
+  return { ensureUnlocked, dialog };
+}
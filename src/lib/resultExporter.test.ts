@@ -244,4 +244,67 @@ describe("exportAsSqlInsert", () => {
       "INSERT INTO users (id, name) VALUES\n  (1, 'Alice');"
     );
   });
+
+  it("leaves decimal columns unquoted", () => {
+    const result: QueryResult = {
+      columns: ["id", "price"],
+      column_meta: [
+        { name: "id", type_name: "INT4", nullable: false, logical_type: "integer" },
+        { name: "price", type_name: "NUMERIC", nullable: true, logical_type: "decimal" },
+      ],
+      rows: [[1, "12345678901234567890.01"]],
+      row_count: 1,
+      message: null,
+    };
+    expect(exportAsSqlInsert(result, "items", "sqlite")).toBe(
+      "INSERT INTO items (id, price) VALUES\n  (1, 12345678901234567890.01);"
+    );
+  });
+
+  it("writes binary values as hex literals", () => {
+    const result: QueryResult = {
+      columns: ["data"],
+      rows: [[{ $type: "binary", hex: "dead00", length: 3 }]],
+      row_count: 1,
+      message: null,
+    };
+    expect(exportAsSqlInsert(result, "blobs", "mysql")).toBe(
+      "INSERT IGNORE INTO blobs (data) VALUES\n  (X'dead00');"
+    );
+  });
+
+  it("writes postgres binary values as bytea escapes", () => {
+    const result: QueryResult = {
+      columns: ["data"],
+      rows: [[{ $type: "binary", hex: "dead00", length: 3 }]],
+      row_count: 1,
+      message: null,
+    };
+    expect(exportAsSqlInsert(result, "blobs", "postgres")).toBe(
+      "INSERT INTO blobs (data) VALUES\n  ('\\xdead00')\nON CONFLICT DO NOTHING;"
+    );
+  });
+
+  it("writes ranges as postgres range literals", () => {
+    const result: QueryResult = {
+      columns: ["during"],
+      rows: [
+        [
+          {
+            $type: "range",
+            empty: false,
+            lower: "2024-01-01 00:00:00+00",
+            upper: null,
+            lower_inclusive: true,
+            upper_inclusive: false,
+          },
+        ],
+      ],
+      row_count: 1,
+      message: null,
+    };
+    expect(exportAsSqlInsert(result, "bookings", "postgres")).toBe(
+      `INSERT INTO bookings (during) VALUES\n  ('["2024-01-01 00:00:00+00",)')\nON CONFLICT DO NOTHING;`
+    );
+  });
 });
@@ -1,7 +1,50 @@
 // ABOUTME: Exports query result sets as JSON or SQL INSERT statements.
 // ABOUTME: Handles various data types including NULL, strings, numbers, booleans, and objects.
 
-import type { QueryResult, DatabaseType } from "./types";
+import type {
+  QueryResult,
+  DatabaseType,
+  BinaryValue,
+  LogicalType,
+  RangeValue,
+  RedisBytes,
+} from "./types";
+
+export function isBinaryValue(value: unknown): value is BinaryValue {
+  return (
+    typeof value === "object" &&
+    value !== null &&
+    (value as { $type?: unknown }).$type === "binary"
+  );
+}
+
+// Shows Redis bytes that aren't UTF-8 as hex, the way the grid shows bytes.
+export function redisText(value: RedisBytes): string {
+  return isBinaryValue(value) ? `0x${value.hex}` : value;
+}
+
+export function isRangeValue(value: unknown): value is RangeValue {
+  return (
+    typeof value === "object" &&
+    value !== null &&
+    (value as { $type?: unknown }).$type === "range"
+  );
+}
+
+// Renders a range the way Postgres writes it, e.g. [1,10) or (,5].
+export function rangeLiteral(range: RangeValue): string {
+  if (range.empty) {
+    return "empty";
+  }
+  const bound = (v: unknown) => {
+    if (v === null || v === undefined) return "";
+    const text = String(v);
+    return /[,()[\]"\s]/.test(text) ? `"${text.replace(/["\\]/g, "\\$&")}"` : text;
+  };
+  return `${range.lower_inclusive ? "[" : "("}${bound(range.lower)},${bound(range.upper)}${
+    range.upper_inclusive ? "]" : ")"
+  }`;
+}
 
 export function exportAsJson(result: QueryResult): string {
   const rows = result.rows.map((row) => {
@@ -14,10 +57,24 @@ export function exportAsJson(result: QueryResult): string {
   return JSON.stringify(rows, null, 2);
 }
 
-function formatSqlValue(value: unknown): string {
+function formatSqlValue(
+  value: unknown,
+  logicalType?: LogicalType,
+  dbType?: DatabaseType | null
+): string {
   if (value === null || value === undefined) {
     return "NULL";
   }
+  if (isBinaryValue(value)) {
+    return dbType === "postgres" ? `'\\x${value.hex}'` : `X'${value.hex}'`;
+  }
+  if (isRangeValue(value)) {
+    return `'${rangeLiteral(value).replace(/'/g, "''")}'`;
+  }
+  // Decimals are strings to keep their precision, but belong unquoted in SQL.
+  if (logicalType === "decimal" && typeof value === "string" && /^-?\d+(\.\d+)?$/.test(value)) {
+    return value;
+  }
   if (typeof value === "string") {
     const escaped = value.replace(/'/g, "''");
     return `'${escaped}'`;
@@ -45,7 +102,11 @@ export function exportAsSqlInsert(
   const columns = result.columns.join(", ");
   const values = result.rows
     .map((row) => {
-      const formatted = row.map(formatSqlValue).join(", ");
+      const formatted = row
+        .map((value, index) =>
+          formatSqlValue(value, result.column_meta?.[index]?.logical_type, dbType)
+        )
+        .join(", ");
       return `  (${formatted})`;
     })
     .join(",\n");
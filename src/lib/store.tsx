@@ -14,7 +14,11 @@ import type {
   DatabaseUser,
   UserGrant,
   TableContext,
+  QueryColumns,
+  QueryPlan,
+  RedisBytes,
 } from "./types";
+import { redisText } from "./resultExporter";
 
 export interface Tab {
   id: string;
@@ -30,7 +34,7 @@ export interface Tab {
   database: string | null;
   schema: string | null;
   table: string | null;
-  viewType: "data" | "columns" | "indexes" | "constraints" | "function" | "dependencies" | "users" | null;
+  viewType: "data" | "columns" | "indexes" | "constraints" | "function" | "dependencies" | "users" | "redis-key" | null;
 
   // Query state
   query: string;
@@ -56,11 +60,16 @@ export interface Tab {
   selectedUser: string | null;
   selectedUserHost: string | null;
   userGrants: UserGrant[] | null;
+  redisKey: RedisBytes | null;
 
   // Edit context
   tableContext: TableContext | null;
   primaryKeyColumns: string[];
+  /** Source of each result column, when the query is a SELECT that could be traced. */
+  queryColumns: QueryColumns | null;
   hasPendingChanges: boolean;
+  /** Plan from the last Explain, shown in place of the results until the next run. */
+  plan: QueryPlan | null;
 }
 
 export interface AppStore {
@@ -98,9 +107,12 @@ function createDefaultTab(overrides: Partial<Tab> = {}): Tab {
     selectedUser: null,
     selectedUserHost: null,
     userGrants: null,
+    redisKey: null,
     tableContext: null,
     primaryKeyColumns: [],
+    queryColumns: null,
     hasPendingChanges: false,
+    plan: null,
     ...overrides,
   };
 }
@@ -112,6 +124,9 @@ export function generateTabTitle(tab: Partial<Tab>): string {
   if (tab.viewType === "dependencies") {
     return `Dependencies (${tab.schema || tab.database})`;
   }
+  if (tab.viewType === "redis-key" && tab.redisKey) {
+    return redisText(tab.redisKey);
+  }
   if (tab.functionInfo) {
     return tab.functionInfo.name;
   }
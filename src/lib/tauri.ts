@@ -12,11 +12,26 @@ import type {
   ColumnInfo,
   IndexInfo,
   ConstraintInfo,
+  TableChanges,
+  AppliedRowChanges,
+  QueryColumns,
+  Completion,
+  QueryPlan,
+  RedisDatabaseInfo,
+  RedisNamespace,
+  RedisKeyPage,
+  RedisBytes,
+  RedisKeyValue,
+  RedisScoredMember,
   FunctionInfo,
   ViewDependency,
   DatabaseUser,
+  DriverCapabilities,
   UserGrant,
-  QueryResult,
+  ResultPage,
+  StatementResult,
+  OnError,
+  TransactionStatus,
   QueryHistoryEntry,
   QueryHistoryFilter,
   AwsProfile,
@@ -28,6 +43,11 @@ import type {
   KubeSecretKey,
   ParsedConnection,
   LastSelected,
+  SecretBackend,
+  SecretStoreStatus,
+  SshConfigHost,
+  TestConnectionInput,
+  ConnectionTestReport,
 } from "./types";
 
 export async function saveConnection(
@@ -36,6 +56,12 @@ export async function saveConnection(
   return invoke("save_connection", { input });
 }
 
+export async function testConnection(
+  input: TestConnectionInput
+): Promise<ConnectionTestReport> {
+  return invoke("test_connection", { input });
+}
+
 export async function listConnections(): Promise<ConnectionConfig[]> {
   return invoke("list_connections");
 }
@@ -64,22 +90,63 @@ export async function saveLastSelected(
   return invoke("save_last_selected", { connectionId, selections });
 }
 
-export async function cancelQueries(connectionId: string): Promise<number> {
-  return invoke("cancel_queries", { connectionId });
+export async function secretStoreStatus(): Promise<SecretStoreStatus> {
+  return invoke("secret_store_status");
+}
+
+export async function unlockSecretStore(masterPassword: string): Promise<SecretStoreStatus> {
+  return invoke("unlock_secret_store", { masterPassword });
+}
+
+export async function lockSecretStore(): Promise<SecretStoreStatus> {
+  return invoke("lock_secret_store");
+}
+
+export async function changeMasterPassword(
+  currentPassword: string,
+  newPassword: string
+): Promise<void> {
+  return invoke("change_master_password", { currentPassword, newPassword });
+}
+
+export async function setSecretBackend(
+  backend: SecretBackend,
+  masterPassword?: string
+): Promise<SecretStoreStatus> {
+  return invoke("set_secret_backend", { backend, masterPassword });
+}
+
+export async function cancelQuery(
+  connectionId: string,
+  queryId: string
+): Promise<boolean> {
+  return invoke("cancel_query", { connectionId, queryId });
 }
 
 export async function connect(id: string): Promise<string> {
   return invoke("connect", { id });
 }
 
-export async function disconnect(connectionId: string): Promise<void> {
-  return invoke("disconnect", { connectionId });
+export async function listSshConfigHosts(): Promise<SshConfigHost[]> {
+  return invoke("list_ssh_config_hosts");
+}
+
+export async function answerHostKeyPrompt(requestId: string, trust: boolean): Promise<void> {
+  return invoke("answer_host_key_prompt", { requestId, trust });
+}
+
+export async function disconnect(connectionId: string, force?: boolean): Promise<void> {
+  return invoke("disconnect", { connectionId, force });
 }
 
 export async function switchDatabase(connectionId: string, database: string): Promise<void> {
   return invoke("switch_database", { connectionId, database });
 }
 
+export async function getCapabilities(connectionId: string): Promise<DriverCapabilities> {
+  return invoke("get_capabilities", { connectionId });
+}
+
 export async function listDatabases(connectionId: string): Promise<string[]> {
   return invoke("list_databases", { connectionId });
 }
@@ -105,6 +172,14 @@ export async function createSchema(
   return invoke("create_schema", { connectionId, name });
 }
 
+export async function refreshMetadata(
+  connectionId: string,
+  database: string | null = null,
+  schema: string | null = null
+): Promise<void> {
+  return invoke("refresh_metadata", { connectionId, database, schema });
+}
+
 export async function listTables(
   connectionId: string,
   database: string,
@@ -226,13 +301,204 @@ export async function listConstraints(
   return invoke("list_constraints", { connectionId, database, schema, table });
 }
 
+/** Runs inside the transaction open in `sessionId`'s tab, if there is one. */
+export async function applyRowChanges(
+  connectionId: string,
+  tables: TableChanges[],
+  sessionId?: string
+): Promise<AppliedRowChanges[]> {
+  return invoke("apply_row_changes", { connectionId, sessionId, tables });
+}
+
+export async function redisListDatabases(connectionId: string): Promise<RedisDatabaseInfo[]> {
+  return invoke("redis_list_databases", { connectionId });
+}
+
+export async function redisListNamespaces(
+  connectionId: string,
+  database: string
+): Promise<RedisNamespace[]> {
+  return invoke("redis_list_namespaces", { connectionId, database });
+}
+
+export async function redisScanNamespace(
+  connectionId: string,
+  database: string,
+  namespace: string,
+  cursor: string,
+  count?: number
+): Promise<RedisKeyPage> {
+  return invoke("redis_scan_namespace", { connectionId, database, namespace, cursor, count });
+}
+
+export async function redisGetKey(
+  connectionId: string,
+  database: string,
+  key: RedisBytes,
+  cursor?: string | null,
+  count?: number
+): Promise<RedisKeyValue> {
+  return invoke("redis_get_key", { connectionId, database, key, cursor, count });
+}
+
+export async function redisSetString(
+  connectionId: string,
+  database: string,
+  key: RedisBytes,
+  value: string
+): Promise<void> {
+  return invoke("redis_set_string", { connectionId, database, key, value });
+}
+
+export async function redisSetHashFields(
+  connectionId: string,
+  database: string,
+  key: RedisBytes,
+  fields: [string, string][]
+): Promise<void> {
+  return invoke("redis_set_hash_fields", { connectionId, database, key, fields });
+}
+
+export async function redisSetListItem(
+  connectionId: string,
+  database: string,
+  key: RedisBytes,
+  index: number,
+  value: string
+): Promise<void> {
+  return invoke("redis_set_list_item", { connectionId, database, key, index, value });
+}
+
+export async function redisPushListItems(
+  connectionId: string,
+  database: string,
+  key: RedisBytes,
+  values: string[],
+  head: boolean
+): Promise<void> {
+  return invoke("redis_push_list_items", { connectionId, database, key, values, head });
+}
+
+export async function redisSetSetMembers(
+  connectionId: string,
+  database: string,
+  key: RedisBytes,
+  members: string[]
+): Promise<void> {
+  return invoke("redis_set_set_members", { connectionId, database, key, members });
+}
+
+export async function redisSetZsetMembers(
+  connectionId: string,
+  database: string,
+  key: RedisBytes,
+  members: RedisScoredMember[]
+): Promise<void> {
+  return invoke("redis_set_zset_members", { connectionId, database, key, members });
+}
+
+export async function redisSetStreamEntry(
+  connectionId: string,
+  database: string,
+  key: RedisBytes,
+  fields: [string, string][]
+): Promise<void> {
+  return invoke("redis_set_stream_entry", { connectionId, database, key, fields });
+}
+
+export async function redisSetJson(
+  connectionId: string,
+  database: string,
+  key: RedisBytes,
+  value: unknown,
+  path?: string
+): Promise<void> {
+  return invoke("redis_set_json", { connectionId, database, key, path, value });
+}
+
+export async function redisSetTtl(
+  connectionId: string,
+  database: string,
+  key: RedisBytes,
+  ttlMs: number | null
+): Promise<void> {
+  return invoke("redis_set_ttl", { connectionId, database, key, ttlMs });
+}
+
+export async function redisDeleteMembers(
+  connectionId: string,
+  database: string,
+  key: RedisBytes,
+  members: RedisBytes[]
+): Promise<number> {
+  return invoke("redis_delete_members", { connectionId, database, key, members });
+}
+
 export async function executeQuery(
   queryId: string,
   connectionId: string,
   query: string,
-  database?: string
-): Promise<[QueryResult, number]> {
-  return invoke("execute_query", { queryId, connectionId, query, database });
+  database?: string,
+  maxRows?: number,
+  onError?: OnError,
+  sessionId?: string
+): Promise<[StatementResult[], number]> {
+  return invoke("execute_query", {
+    queryId,
+    connectionId,
+    sessionId,
+    query,
+    database,
+    maxRows,
+    onError,
+  });
+}
+
+export async function beginTransaction(
+  connectionId: string,
+  sessionId: string
+): Promise<void> {
+  return invoke("begin_transaction", { connectionId, sessionId });
+}
+
+export async function commit(sessionId: string): Promise<void> {
+  return invoke("commit", { sessionId });
+}
+
+export async function rollback(sessionId: string): Promise<void> {
+  return invoke("rollback", { sessionId });
+}
+
+export async function transactionStatus(sessionId: string): Promise<TransactionStatus> {
+  return invoke("transaction_status", { sessionId });
+}
+
+export async function openTransactionCount(connectionId: string): Promise<number> {
+  return invoke("open_transaction_count", { connectionId });
+}
+
+export async function closeSession(sessionId: string, force?: boolean): Promise<void> {
+  return invoke("close_session", { sessionId, force });
+}
+
+export async function openResult(
+  connectionId: string,
+  query: string,
+  maxRows?: number
+): Promise<string> {
+  return invoke("open_result", { connectionId, query, maxRows });
+}
+
+export async function fetchPage(
+  resultId: string,
+  offset: number,
+  limit: number
+): Promise<ResultPage> {
+  return invoke("fetch_page", { resultId, offset, limit });
+}
+
+export async function closeResult(resultId: string): Promise<void> {
+  return invoke("close_result", { resultId });
 }
 
 export async function saveQueryHistory(
@@ -362,3 +628,31 @@ export async function extractQueryTable(
 ): Promise<{ schema: string | null; table: string } | null> {
   return invoke("extract_query_table", { query, dbType });
 }
+
+export async function analyzeQueryColumns(
+  connectionId: string,
+  database: string,
+  schema: string,
+  query: string,
+  dbType: string
+): Promise<QueryColumns | null> {
+  return invoke("analyze_query_columns", { connectionId, database, schema, query, dbType });
+}
+
+export async function explainQuery(
+  connectionId: string,
+  query: string,
+  analyze: boolean
+): Promise<QueryPlan> {
+  return invoke("explain_query", { connectionId, query, analyze });
+}
+
+export async function completeSql(
+  connectionId: string,
+  database: string,
+  schema: string,
+  text: string,
+  cursorOffset: number
+): Promise<Completion> {
+  return invoke("complete_sql", { connectionId, database, schema, text, cursorOffset });
+}
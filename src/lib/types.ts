@@ -2,7 +2,7 @@
 // ABOUTME: Mirrors Rust structs for type-safe communication.
 
 export type DatabaseType = "postgres" | "mysql" | "sqlite" | "redis";
-export type SslMode = "disable" | "prefer" | "require";
+export type SslMode = "disable" | "prefer" | "require" | "verify-ca" | "verify-full";
 
 export interface Category {
   id: string;
@@ -17,16 +17,32 @@ export interface LastSelected {
 
 export type SshAuthMethod =
   | { type: "agent" }
-  | { type: "privatekey"; path: string; passphrase: string | null }
-  | { type: "password"; password: string };
+  | { type: "privatekey"; path: string; passphrase?: string | null }
+  | { type: "password"; password?: string };
 
-export interface SshTunnelConfig {
+export interface SshHop {
   host: string;
   port: number;
   username: string;
   auth: SshAuthMethod;
 }
 
+/** The bastion that forwards to the database, reached through `jump_hosts` in order. */
+export interface SshTunnelConfig extends SshHop {
+  jump_hosts?: SshHop[];
+  /** A `Host` alias from ~/.ssh/config; when set, the hosts come from there. */
+  ssh_config_host?: string | null;
+}
+
+/** A concrete `Host` alias from ~/.ssh/config. */
+export interface SshConfigHost {
+  alias: string;
+  host_name: string;
+  port: number;
+  user: string | null;
+  proxy_jump: string | null;
+}
+
 export interface ConnectionConfig {
   id: string;
   name: string;
@@ -34,13 +50,63 @@ export interface ConnectionConfig {
   host: string;
   port: number;
   username: string;
-  password: string;
+  /** Never sent by the backend; saved passwords are referenced by `secrets`. */
+  password?: string;
   database: string | null;
   category_id: string | null;
   visible_databases: number | null;
   ssl_mode: SslMode;
+  /** PEM CA bundle to verify the server with, instead of the system store. */
+  ssl_root_cert?: string | null;
+  /** PEM client certificate and key, for mutual TLS. */
+  ssl_client_cert?: string | null;
+  ssl_client_key?: string | null;
+  options?: ConnectionOptions;
+  redis_topology?: RedisTopology;
   last_selected: LastSelected[] | null;
   ssh_tunnel: SshTunnelConfig | null;
+  secrets?: SecretRefs;
+}
+
+/** Driver settings; unset ones keep the driver's defaults. */
+export interface ConnectionOptions {
+  application_name?: string | null;
+  statement_timeout_ms?: number | null;
+  search_path?: string | null;
+  charset?: string | null;
+  max_connections?: number | null;
+  idle_timeout_secs?: number | null;
+  redis_key_delimiter?: string | null;
+}
+
+/** For a cluster or Sentinel, `host`/`port` is the first seed or sentinel; the lists add more as `host:port`. */
+export type RedisTopology =
+  | { mode: "standalone" }
+  | { mode: "cluster"; seeds?: string[] }
+  | { mode: "sentinel"; master_name: string; sentinels?: string[] };
+
+/** Secret store keys for a connection's saved passwords. */
+export interface SecretRefs {
+  password?: string;
+  ssh_password?: string;
+  ssh_passphrase?: string;
+  jump_hosts?: HopSecretRefs[];
+}
+
+/** Secret keys for a jump host; `hop` is its `user@host:port`. */
+export interface HopSecretRefs {
+  hop: string;
+  ssh_password?: string;
+  ssh_passphrase?: string;
+}
+
+export type SecretBackend = "vault" | "keyring";
+
+export interface SecretStoreStatus {
+  backend: SecretBackend;
+  initialized: boolean;
+  unlocked: boolean;
+  keyring_available: boolean;
 }
 
 export interface SaveConnectionInput {
@@ -54,9 +120,39 @@ export interface SaveConnectionInput {
   category_id: string | null;
   visible_databases: number | null;
   ssl_mode: SslMode;
+  ssl_root_cert?: string | null;
+  ssl_client_cert?: string | null;
+  ssl_client_key?: string | null;
+  options?: ConnectionOptions;
+  redis_topology?: RedisTopology;
   ssh_tunnel: SshTunnelConfig | null;
 }
 
+/** The form's connection; `id` names the saved one whose stored passwords fill in blank fields. */
+export interface TestConnectionInput extends SaveConnectionInput {
+  id?: string;
+  /** Don't fill in the saved main password; the form cleared it. */
+  clear_password?: boolean;
+}
+
+export type TestStepKind = "ssh_tunnel" | "tcp" | "tls" | "auth" | "query";
+
+export interface TestStep {
+  kind: TestStepKind;
+  status: "ok" | "failed" | "skipped";
+  duration_ms: number;
+  detail: string | null;
+}
+
+export interface ConnectionTestReport {
+  success: boolean;
+  steps: TestStep[];
+  server_version: string | null;
+  failed_step: TestStepKind | null;
+  error: string | null;
+  total_ms: number;
+}
+
 export interface UpdateConnectionInput {
   id: string;
   name: string;
@@ -65,10 +161,17 @@ export interface UpdateConnectionInput {
   port: number;
   username: string;
   password: string;
+  /** Removes the saved main password; a blank `password` alone keeps it. */
+  clear_password?: boolean;
   database: string | null;
   category_id: string | null;
   visible_databases: number | null;
   ssl_mode: SslMode;
+  ssl_root_cert?: string | null;
+  ssl_client_cert?: string | null;
+  ssl_client_key?: string | null;
+  options?: ConnectionOptions;
+  redis_topology?: RedisTopology;
   ssh_tunnel: SshTunnelConfig | null;
 }
 
@@ -83,6 +186,25 @@ export interface UpdateCategoryInput {
   color: string;
 }
 
+export interface DriverCapabilities {
+  engine: string;
+  supports_schemas: boolean;
+  supports_create_database: boolean;
+  supports_create_schema: boolean;
+  supports_users: boolean;
+  supports_functions: boolean;
+  supports_procedures: boolean;
+  supports_materialized_views: boolean;
+  supports_sequences: boolean;
+  supports_triggers: boolean;
+  supports_view_dependencies: boolean;
+  supports_query_cancel: boolean;
+  supports_paged_results: boolean;
+  supports_transactions: boolean;
+  supports_query_plans: boolean;
+  switches_database_in_place: boolean;
+}
+
 export interface ColumnInfo {
   name: string;
   data_type: string;
@@ -106,6 +228,159 @@ export interface ConstraintInfo {
   foreign_columns: string[] | null;
 }
 
+/** One edit for apply_row_changes. `key` holds the row as it was read; only its key columns are used. */
+export type RowChange =
+  | { kind: "update"; key: Record<string, unknown>; values: Record<string, unknown> }
+  | { kind: "insert"; values: Record<string, unknown> }
+  | { kind: "delete"; key: Record<string, unknown> };
+
+/** One table's edits; an empty `key_columns` uses the table's own key. */
+export interface TableChanges {
+  database: string;
+  schema: string;
+  table: string;
+  key_columns: string[];
+  changes: RowChange[];
+}
+
+export interface AppliedRowChanges {
+  key_columns: string[];
+  inserted: number;
+  updated: number;
+  deleted: number;
+}
+
+/** Where a result column comes from; `source.table` indexes `QueryColumns.tables`. */
+export interface ColumnProvenance {
+  name: string;
+  source: { table: number; column: string } | null;
+  editable: boolean;
+}
+
+export interface QueryColumnTable {
+  database: string;
+  schema: string;
+  table: string;
+  /** Result positions of a primary or unique NOT NULL key; empty when the table's rows can't be edited. */
+  key_columns: number[];
+}
+
+export interface QueryColumns {
+  tables: QueryColumnTable[];
+  columns: ColumnProvenance[];
+}
+
+export type PlanWarningKind = "full_scan" | "row_estimate";
+
+export interface PlanWarning {
+  kind: PlanWarningKind;
+  message: string;
+}
+
+/** One step of a query plan. Row counts are per loop. */
+export interface PlanNode {
+  operation: string;
+  relation: string | null;
+  index: string | null;
+  detail: string | null;
+  estimated_rows: number | null;
+  actual_rows: number | null;
+  loops: number | null;
+  cost: number | null;
+  time_ms: number | null;
+  buffers: { hit: number; read: number } | null;
+  warnings: PlanWarning[];
+  children: PlanNode[];
+}
+
+export interface QueryPlan {
+  root: PlanNode;
+  analyzed: boolean;
+  planning_time_ms: number | null;
+  execution_time_ms: number | null;
+  raw: string;
+}
+
+export type SuggestionKind = "column" | "table" | "view" | "function" | "keyword";
+
+export interface Suggestion {
+  label: string;
+  kind: SuggestionKind;
+  detail: string | null;
+}
+
+/** Ranked suggestions for the word starting at `from` (UTF-16 offset). */
+export interface Completion {
+  from: number;
+  suggestions: Suggestion[];
+}
+
+/** A Redis logical database with its `INFO keyspace` figures. */
+export interface RedisDatabaseInfo {
+  name: string;
+  keys: number;
+  expires: number;
+  /** Redis's sampled estimate; null when no keys expire. */
+  avg_ttl_ms: number | null;
+}
+
+/** Redis keys sharing a prefix; the unnamed namespace holds keys without a delimiter. */
+export interface RedisNamespace {
+  name: string;
+  pattern: string;
+  key_count: number;
+  approximate: boolean;
+}
+
+/** A Redis key name, field, member or value; ones that aren't UTF-8 arrive as bytes. */
+export type RedisBytes = string | BinaryValue;
+
+export interface RedisKeyInfo {
+  key: RedisBytes;
+  key_type: string;
+  ttl_ms: number | null;
+  memory_bytes: number | null;
+}
+
+export interface RedisField {
+  field: RedisBytes;
+  value: RedisBytes;
+}
+
+export interface RedisScoredMember {
+  member: RedisBytes;
+  score: number;
+}
+
+export interface RedisStreamEntry {
+  id: string;
+  fields: RedisField[];
+}
+
+export type RedisValueData =
+  | { kind: "string"; value: RedisBytes; truncated: boolean }
+  | { kind: "hash"; fields: RedisField[] }
+  | { kind: "list"; items: RedisBytes[]; offset: number }
+  | { kind: "set"; members: RedisBytes[] }
+  | { kind: "zset"; members: RedisScoredMember[] }
+  | { kind: "stream"; entries: RedisStreamEntry[] }
+  | { kind: "json"; value: unknown }
+  | { kind: "unsupported" };
+
+/** One page of a key's value; `cursor` is null after the last page. */
+export interface RedisKeyValue extends RedisKeyInfo {
+  encoding: string | null;
+  length: number | null;
+  data: RedisValueData;
+  cursor: string | null;
+}
+
+/** `cursor` is "0" once the namespace has been scanned to the end. */
+export interface RedisKeyPage {
+  keys: RedisKeyInfo[];
+  cursor: string;
+}
+
 export interface FunctionInfo {
   name: string;
   definition: string;
@@ -119,14 +394,85 @@ export interface ViewDependency {
   depends_on_type: "table" | "view" | "materialized_view";
 }
 
+export type LogicalType =
+  | "boolean"
+  | "integer"
+  | "float"
+  | "decimal"
+  | "text"
+  | "binary"
+  | "date"
+  | "time"
+  | "timestamp"
+  | "timestamp_tz"
+  | "interval"
+  | "uuid"
+  | "json"
+  | "array"
+  | "network"
+  | "geometry"
+  | "enum"
+  | "bit"
+  | "unknown";
+
+export interface ColumnMeta {
+  name: string;
+  type_name: string;
+  nullable: boolean | null;
+  logical_type: LogicalType;
+}
+
+// Cell encoding for byte values; decimals arrive as plain strings.
+export interface BinaryValue {
+  $type: "binary";
+  hex: string;
+  length: number;
+}
+
+// Cell encoding for Postgres ranges; unbounded sides are null.
+export interface RangeValue {
+  $type: "range";
+  empty: boolean;
+  lower: unknown;
+  upper: unknown;
+  lower_inclusive: boolean;
+  upper_inclusive: boolean;
+}
+
 export interface QueryResult {
   columns: string[];
+  column_meta?: ColumnMeta[];
   rows: unknown[][];
   row_count: number;
   message: string | null;
   server_time_ms?: number | null;
   transfer_time_ms?: number | null;
   bytes_transferred?: number | null;
+  truncated?: boolean;
+}
+
+export type OnError = "stop" | "continue";
+
+export interface TransactionStatus {
+  active: boolean;
+  connection_id: string | null;
+  started_at: string | null;
+}
+
+export interface StatementResult {
+  statement: string;
+  result: QueryResult | null;
+  error: string | null;
+  elapsed_ms: number;
+}
+
+export interface ResultPage {
+  columns: string[];
+  column_meta: ColumnMeta[];
+  rows: unknown[][];
+  offset: number;
+  has_more: boolean;
+  truncated: boolean;
 }
 
 export interface QueryProgress {
@@ -139,6 +485,22 @@ export interface QueryProgress {
   bytes?: number | null;
 }
 
+/** Emitted as `ssh-host-key-prompt` when a tunnel's SSH host is not in known_hosts. */
+export interface HostKeyPrompt {
+  request_id: string;
+  host: string;
+  port: number;
+  key_type: string;
+  fingerprint: string;
+}
+
+/** Emitted as `tunnel-status` when an SSH tunnel changes state or one of its forwards fails. */
+export type TunnelStatus = { connection_id: string; forward_error?: string } & (
+  | { state: "connected" }
+  | { state: "reconnecting"; attempt: number; max_attempts: number }
+  | { state: "failed"; error: string }
+);
+
 export interface CellSelection {
   rowIndex: number;
   columnIndex: number;